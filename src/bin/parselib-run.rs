@@ -0,0 +1,357 @@
+//! Runs one of the built-in format parsers over a file or standard input and prints the output or a diagnostic.
+//!
+//! ```text
+//! parselib-run [--json] <format> [file]
+//! ```
+
+use parselib_rs::diagnostics::{ColorChoice, Diagnostic};
+use parselib_rs::formats::args::{ArgumentStyle, ParseArguments};
+use parselib_rs::formats::color::ParseColor;
+use parselib_rs::formats::cron::ParseCron;
+use parselib_rs::formats::duration::ParseDuration;
+use parselib_rs::formats::http::{ParseHeaders, ParseRequestLine};
+use parselib_rs::formats::template::{ParseTemplate, Segment};
+#[cfg(feature = "yaml")]
+use parselib_rs::formats::yaml::{ParseYaml, YamlValue};
+use parselib_rs::parsers::ParserExtensions;
+use parselib_rs::{ParseError, Parser, ParserState};
+use std::collections::BTreeSet;
+use std::fmt::{Debug, Display, Formatter};
+use std::io::{self, Read};
+use std::process::ExitCode;
+use std::{env, fs};
+
+/// The formats that can be parsed, with a short description of each.
+const FORMATS: &[(&str, &str)] = &[
+    ("args", "a POSIX shell command line"),
+    ("color", "a CSS color literal"),
+    ("cron", "a cron schedule"),
+    ("duration", "a duration like `1h30m`"),
+    ("request", "an HTTP request line and headers"),
+    ("template", "a template with `${NAME}` placeholders"),
+    #[cfg(feature = "yaml")]
+    ("yaml", "a subset of YAML"),
+];
+
+/// A JSON value to print.
+enum Json {
+    Null,
+    #[cfg_attr(not(feature = "yaml"), allow(dead_code))]
+    Bool(bool),
+
+    /// A number, already written as JSON.
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn number(number: impl Display) -> Self {
+        Self::Number(number.to_string())
+    }
+
+    fn string(text: &str) -> Self {
+        Self::String(String::from(text))
+    }
+
+    fn object<'a>(entries: impl IntoIterator<Item = (&'a str, Json)>) -> Self {
+        Self::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (String::from(key), value))
+                .collect(),
+        )
+    }
+
+    fn set(values: &BTreeSet<u8>) -> Self {
+        Self::Array(values.iter().map(Self::number).collect())
+    }
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fn quote(f: &mut Formatter<'_>, text: &str) -> std::fmt::Result {
+            write!(f, "\"")?;
+            for c in text.chars() {
+                match c {
+                    '"' => write!(f, "\\\"")?,
+                    '\\' => write!(f, "\\\\")?,
+                    '\n' => write!(f, "\\n")?,
+                    '\r' => write!(f, "\\r")?,
+                    '\t' => write!(f, "\\t")?,
+                    c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                    c => write!(f, "{}", c)?,
+                }
+            }
+            write!(f, "\"")
+        }
+
+        match self {
+            Self::Null => write!(f, "null"),
+            Self::Bool(value) => write!(f, "{}", value),
+            Self::Number(number) => write!(f, "{}", number),
+            Self::String(text) => quote(f, text),
+            Self::Array(values) => {
+                write!(f, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            Self::Object(entries) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    quote(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// The parsed output, ready to print as text or JSON.
+struct Output {
+    text: String,
+    json: Json,
+}
+
+/// Parse the whole input, converting the output with `to_json`.
+fn run<OutputType: Debug>(
+    parser: impl Parser<OutputType, ParseError>,
+    input: &str,
+    to_json: impl Fn(&OutputType) -> Json,
+) -> Result<Output, ParseError> {
+    let (output, _) = parser.all_consuming().parse(ParserState::new(input))?;
+    Ok(Output {
+        text: format!("{:#?}", output),
+        json: to_json(&output),
+    })
+}
+
+/// Parse the input in the named format, or return `None` if there is no such format.
+fn parse(format: &str, input: &str) -> Option<Result<Output, ParseError>> {
+    // Formats that describe a single line don't include its line ending.
+    let line = input.strip_suffix('\n').unwrap_or(input);
+    let line = line.strip_suffix('\r').unwrap_or(line);
+
+    Some(match format {
+        "args" => run(ParseArguments::new(ArgumentStyle::Posix), line, |args| {
+            Json::Array(args.iter().map(|arg| Json::string(arg)).collect())
+        }),
+        "color" => run(ParseColor, line, |color| {
+            Json::object([
+                ("r", Json::number(color.r)),
+                ("g", Json::number(color.g)),
+                ("b", Json::number(color.b)),
+                ("a", Json::number(color.a)),
+            ])
+        }),
+        "cron" => run(ParseCron, line, |schedule| {
+            Json::object([
+                (
+                    "seconds",
+                    schedule.seconds.as_ref().map_or(Json::Null, Json::set),
+                ),
+                ("minutes", Json::set(&schedule.minutes)),
+                ("hours", Json::set(&schedule.hours)),
+                ("days_of_month", Json::set(&schedule.days_of_month)),
+                ("months", Json::set(&schedule.months)),
+                ("days_of_week", Json::set(&schedule.days_of_week)),
+            ])
+        }),
+        "duration" => run(ParseDuration::default(), line, |duration| {
+            Json::object([("seconds", Json::number(duration.as_secs_f64()))])
+        }),
+        "request" => run(
+            ParseRequestLine.and(ParseHeaders),
+            input,
+            |(request, headers)| {
+                Json::object([
+                    ("method", Json::string(&request.method)),
+                    ("target", Json::string(&request.target)),
+                    (
+                        "version",
+                        Json::String(format!(
+                            "{}.{}",
+                            request.version.major, request.version.minor
+                        )),
+                    ),
+                    (
+                        "headers",
+                        Json::Array(
+                            headers
+                                .iter()
+                                .map(|header| {
+                                    Json::object([
+                                        ("name", Json::string(&header.name)),
+                                        ("value", Json::string(&header.value)),
+                                    ])
+                                })
+                                .collect(),
+                        ),
+                    ),
+                ])
+            },
+        ),
+        "template" => run(ParseTemplate, line, |segments| {
+            Json::Array(
+                segments
+                    .iter()
+                    .map(|segment| match segment {
+                        Segment::Literal(text) => Json::object([("literal", Json::string(text))]),
+                        Segment::Placeholder(name) => {
+                            Json::object([("placeholder", Json::string(name))])
+                        }
+                    })
+                    .collect(),
+            )
+        }),
+        #[cfg(feature = "yaml")]
+        "yaml" => run(ParseYaml, input, yaml_to_json),
+        _ => return None,
+    })
+}
+
+#[cfg(feature = "yaml")]
+fn yaml_to_json(value: &YamlValue) -> Json {
+    match value {
+        YamlValue::Null => Json::Null,
+        YamlValue::Bool(value) => Json::Bool(*value),
+        YamlValue::Int(value) => Json::number(value),
+        YamlValue::Float(value) if value.is_finite() => Json::number(value),
+
+        // JSON has no infinity or NaN.
+        YamlValue::Float(value) => Json::String(value.to_string()),
+        YamlValue::String(text) => Json::string(text),
+        YamlValue::Sequence(values) => Json::Array(values.iter().map(yaml_to_json).collect()),
+        YamlValue::Mapping(entries) => Json::Object(
+            entries
+                .iter()
+                .map(|(key, value)| (key.clone(), yaml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Convert an error into JSON, including everything the diagnostic would show.
+fn error_to_json(error: &ParseError, source: &str, name: &str) -> Json {
+    let strings =
+        |strings: &[String]| Json::Array(strings.iter().map(|s| Json::string(s)).collect());
+    let span = error.span();
+    Json::object([(
+        "error",
+        Json::object([
+            ("message", Json::string(error.kind().to_string().trim_end())),
+            ("start", Json::number(span.start)),
+            ("end", Json::number(span.end)),
+            (
+                "expected",
+                Json::Array(
+                    error
+                        .expected()
+                        .iter()
+                        .map(|expected| Json::String(expected.to_string()))
+                        .collect(),
+                ),
+            ),
+            (
+                "found",
+                error
+                    .found()
+                    .map_or(Json::Null, |found| Json::string(&found)),
+            ),
+            ("notes", strings(error.notes())),
+            ("help", strings(error.help())),
+            (
+                "report",
+                Json::String(Diagnostic::new(error, source).with_name(name).to_string()),
+            ),
+        ]),
+    )])
+}
+
+fn usage() -> String {
+    let mut usage = String::from("usage: parselib-run [--json] <format> [file]\n\nformats:\n");
+    for (name, description) in FORMATS {
+        usage.push_str(&format!("  {:10} {}\n", name, description));
+    }
+    usage
+}
+
+fn main() -> ExitCode {
+    let mut json = false;
+    let mut positional = Vec::new();
+    for argument in env::args().skip(1) {
+        match argument.as_str() {
+            "--json" => json = true,
+            "-h" | "--help" => {
+                print!("{}", usage());
+                return ExitCode::SUCCESS;
+            }
+            _ => positional.push(argument),
+        }
+    }
+
+    let (format, path) = match positional.as_slice() {
+        [format] => (format, None),
+        [format, path] => (format, Some(path)),
+        _ => {
+            eprint!("{}", usage());
+            return ExitCode::from(2);
+        }
+    };
+
+    // Check the format before waiting for input.
+    if !FORMATS.iter().any(|(name, _)| name == format) {
+        eprintln!("error: unknown format `{}`\n\n{}", format, usage());
+        return ExitCode::from(2);
+    }
+
+    // Read the whole input up front so that diagnostics can show it.
+    let (name, input) = match path {
+        Some(path) => (path.as_str(), fs::read_to_string(path)),
+        None => {
+            let mut input = String::new();
+            (
+                "<stdin>",
+                io::stdin().read_to_string(&mut input).map(|_| input),
+            )
+        }
+    };
+    let input = match input {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("error: could not read {}: {}", name, e);
+            return ExitCode::from(2);
+        }
+    };
+
+    match parse(format, &input) {
+        Some(Ok(output)) if json => println!("{}", output.json),
+        Some(Ok(output)) => println!("{}", output.text),
+        Some(Err(error)) => {
+            if json {
+                println!("{}", error_to_json(&error, &input, name));
+            } else {
+                eprint!(
+                    "{}",
+                    Diagnostic::new(&error, &input)
+                        .with_name(name)
+                        .with_color(ColorChoice::Auto)
+                );
+            }
+            return ExitCode::FAILURE;
+        }
+        None => unreachable!("every listed format can be parsed"),
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,377 @@
+use crate::ParseError;
+use std::marker::PhantomData;
+
+/// The current location within a binary input. The state borrows the input, so parsers can return slices of it
+/// without copying.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ByteState<'a> {
+    input: &'a [u8],
+    index: usize,
+}
+
+impl<'a> ByteState<'a> {
+    pub fn new(input: &'a [u8]) -> Self {
+        Self::new_offset(input, 0)
+    }
+
+    /// Create a state for an input that starts at `index` within some larger input.
+    pub const fn new_offset(input: &'a [u8], index: usize) -> Self {
+        Self { input, index }
+    }
+
+    /// Get the remaining input.
+    pub fn input(&self) -> &'a [u8] {
+        self.input
+    }
+
+    /// Get the offset of the remaining input within the original input.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Split off the next `count` bytes, if there are that many.
+    pub fn take(&self, count: usize) -> Option<(&'a [u8], Self)> {
+        if count > self.input.len() {
+            return None;
+        }
+
+        let (taken, rest) = self.input.split_at(count);
+        Some((
+            taken,
+            Self {
+                input: rest,
+                index: self.index + count,
+            },
+        ))
+    }
+}
+
+/// The result of a binary parser, which is either the output and the state after it, or an error.
+pub type ByteResult<'a, ErrorType, OutputType> = Result<(OutputType, ByteState<'a>), ErrorType>;
+
+/// A trait representing a parser for binary inputs, which may borrow from the input for its output.
+pub trait ByteParser<'a, OutputType, ErrorType> {
+    /// Attempt to parse the provided input.
+    fn parse(&self, byte_state: ByteState<'a>) -> ByteResult<'a, ErrorType, OutputType>;
+}
+
+impl<'a, OutputType, ErrorType, ParserType: ByteParser<'a, OutputType, ErrorType> + ?Sized>
+    ByteParser<'a, OutputType, ErrorType> for &ParserType
+{
+    fn parse(&self, byte_state: ByteState<'a>) -> ByteResult<'a, ErrorType, OutputType> {
+        (**self).parse(byte_state)
+    }
+}
+
+impl<'a, OutputType, ErrorType, ParserType: ByteParser<'a, OutputType, ErrorType> + ?Sized>
+    ByteParser<'a, OutputType, ErrorType> for Box<ParserType>
+{
+    fn parse(&self, byte_state: ByteState<'a>) -> ByteResult<'a, ErrorType, OutputType> {
+        (**self).parse(byte_state)
+    }
+}
+
+/// Format bytes as hex for error messages, like `7f 45 4c 46`.
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Parses a fixed number of bytes, returning a slice of the input.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ParseTake {
+    count: usize,
+}
+
+impl ParseTake {
+    pub const fn new(count: usize) -> Self {
+        Self { count }
+    }
+}
+
+impl<'a> ByteParser<'a, &'a [u8], ParseError> for ParseTake {
+    fn parse(&self, byte_state: ByteState<'a>) -> ByteResult<'a, ParseError, &'a [u8]> {
+        byte_state.take(self.count).ok_or_else(|| {
+            ParseError::wrong_count(
+                byte_state.index + byte_state.input.len(),
+                self.count,
+                self.count,
+                byte_state.input.len(),
+            )
+        })
+    }
+}
+
+/// Parses an exact sequence of bytes, returning the slice of the input that matched it.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ParseTag<'t> {
+    tag: &'t [u8],
+}
+
+impl<'t> ParseTag<'t> {
+    pub const fn new(tag: &'t [u8]) -> Self {
+        Self { tag }
+    }
+}
+
+impl<'a> ByteParser<'a, &'a [u8], ParseError> for ParseTag<'_> {
+    fn parse(&self, byte_state: ByteState<'a>) -> ByteResult<'a, ParseError, &'a [u8]> {
+        match byte_state.take(self.tag.len()) {
+            Some((taken, new_state)) if taken == self.tag => Ok((taken, new_state)),
+            _ => {
+                let found = hex(&byte_state.input[..self.tag.len().min(byte_state.input.len())]);
+                Err(ParseError::unexpected(
+                    byte_state.index,
+                    [hex(self.tag)],
+                    Some(found.as_str()).filter(|f| !f.is_empty()),
+                ))
+            }
+        }
+    }
+}
+
+/// Parses an element and returns the slice of the input it consumed instead of its output.
+pub struct ParseRecognize<
+    'a,
+    OutputType,
+    ErrorType,
+    ParserType: ByteParser<'a, OutputType, ErrorType>,
+> {
+    /// The parser whose consumed input should be returned.
+    parser: ParserType,
+
+    /* Phantom */
+    _phantom: PhantomData<(&'a (), OutputType, ErrorType)>,
+}
+
+impl<'a, OutputType, ErrorType, ParserType: ByteParser<'a, OutputType, ErrorType>>
+    ParseRecognize<'a, OutputType, ErrorType, ParserType>
+{
+    pub const fn new(parser: ParserType) -> Self {
+        Self {
+            parser,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, OutputType, ErrorType, ParserType: ByteParser<'a, OutputType, ErrorType>>
+    ByteParser<'a, &'a [u8], ErrorType> for ParseRecognize<'a, OutputType, ErrorType, ParserType>
+{
+    fn parse(&self, byte_state: ByteState<'a>) -> ByteResult<'a, ErrorType, &'a [u8]> {
+        let (_, new_state) = self.parser.parse(byte_state)?;
+
+        // Everything that isn't left over was consumed.
+        let consumed = byte_state.input.len() - new_state.input.len();
+        Ok((&byte_state.input[..consumed], new_state))
+    }
+}
+
+/// The order of the bytes in an integer.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Endian {
+    /// The most significant byte comes first, as in network protocols.
+    Big,
+
+    /// The least significant byte comes first.
+    Little,
+}
+
+/// An unsigned integer type that can be read from bytes.
+pub trait Unsigned: Copy + Into<u64> {
+    /// The number of bytes in the integer.
+    const SIZE: usize;
+
+    /// Convert exactly `SIZE` bytes into the integer.
+    fn from_bytes(bytes: &[u8], endian: Endian) -> Self;
+}
+
+macro_rules! impl_unsigned {
+    ($($int:ty),*) => {
+        $(
+            impl Unsigned for $int {
+                const SIZE: usize = std::mem::size_of::<$int>();
+
+                fn from_bytes(bytes: &[u8], endian: Endian) -> Self {
+                    let mut array = [0; std::mem::size_of::<$int>()];
+                    array.copy_from_slice(bytes);
+                    match endian {
+                        Endian::Big => <$int>::from_be_bytes(array),
+                        Endian::Little => <$int>::from_le_bytes(array),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_unsigned!(u8, u16, u32, u64);
+
+/// Parses an unsigned integer with the given byte order.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ParseUnsigned<IntType: Unsigned> {
+    endian: Endian,
+
+    /* Phantom */
+    _phantom: PhantomData<IntType>,
+}
+
+impl<IntType: Unsigned> ParseUnsigned<IntType> {
+    pub const fn new(endian: Endian) -> Self {
+        Self {
+            endian,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, IntType: Unsigned> ByteParser<'a, IntType, ParseError> for ParseUnsigned<IntType> {
+    fn parse(&self, byte_state: ByteState<'a>) -> ByteResult<'a, ParseError, IntType> {
+        let (bytes, new_state) = ParseTake::new(IntType::SIZE).parse(byte_state)?;
+        Ok((IntType::from_bytes(bytes, self.endian), new_state))
+    }
+}
+
+/// Parses an integer field and decodes the bits that are set into flags using a table.
+pub struct ParseFlags<
+    'a,
+    IntType: Unsigned,
+    FlagType: Clone,
+    ParserType: ByteParser<'a, IntType, ParseError>,
+> {
+    /// The parser for the field containing the flags.
+    parser: ParserType,
+
+    /// The mask for each flag, which is set if all of the mask's bits are set.
+    table: Vec<(u64, FlagType)>,
+
+    /// Whether bits that aren't in any mask are an error.
+    strict: bool,
+
+    /* Phantom */
+    _phantom: PhantomData<(&'a (), IntType)>,
+}
+
+impl<'a, IntType: Unsigned, FlagType: Clone, ParserType: ByteParser<'a, IntType, ParseError>>
+    ParseFlags<'a, IntType, FlagType, ParserType>
+{
+    /// Create a parser that decodes the flags in `table` from the field read by `parser`. Unknown bits are ignored
+    /// unless the parser is made [`strict`](Self::strict).
+    pub const fn new(parser: ParserType, table: Vec<(u64, FlagType)>) -> Self {
+        Self {
+            parser,
+            table,
+            strict: false,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Fail if the field has any bits set that aren't in the table.
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+}
+
+impl<'a, IntType: Unsigned, FlagType: Clone, ParserType: ByteParser<'a, IntType, ParseError>>
+    ByteParser<'a, Vec<FlagType>, ParseError> for ParseFlags<'a, IntType, FlagType, ParserType>
+{
+    fn parse(&self, byte_state: ByteState<'a>) -> ByteResult<'a, ParseError, Vec<FlagType>> {
+        let (bits, new_state) = self.parser.parse(byte_state)?;
+        let bits = bits.into();
+
+        let mut known = 0;
+        let mut flags = Vec::new();
+        for (mask, flag) in &self.table {
+            if bits & mask == *mask {
+                flags.push(flag.clone());
+            }
+            known |= mask;
+        }
+
+        if self.strict && bits & !known != 0 {
+            return Err(ParseError::invalid(
+                byte_state.index,
+                new_state.index,
+                &format!("unknown flag bits {:#x}", bits & !known),
+            ));
+        }
+        Ok((flags, new_state))
+    }
+}
+
+/// Create a parser that takes the next `count` bytes.
+pub const fn take(count: usize) -> ParseTake {
+    ParseTake::new(count)
+}
+
+/// Create a parser that matches exactly `bytes`.
+pub const fn tag(bytes: &[u8]) -> ParseTag<'_> {
+    ParseTag::new(bytes)
+}
+
+/// Create a parser for a single byte.
+pub const fn u8() -> ParseUnsigned<u8> {
+    ParseUnsigned::new(Endian::Big)
+}
+
+/// Create a parser for a big-endian `u16`.
+pub const fn be_u16() -> ParseUnsigned<u16> {
+    ParseUnsigned::new(Endian::Big)
+}
+
+/// Create a parser for a little-endian `u16`.
+pub const fn le_u16() -> ParseUnsigned<u16> {
+    ParseUnsigned::new(Endian::Little)
+}
+
+/// Create a parser for a big-endian `u32`.
+pub const fn be_u32() -> ParseUnsigned<u32> {
+    ParseUnsigned::new(Endian::Big)
+}
+
+/// Create a parser for a little-endian `u32`.
+pub const fn le_u32() -> ParseUnsigned<u32> {
+    ParseUnsigned::new(Endian::Little)
+}
+
+/// Create a parser for a big-endian `u64`.
+pub const fn be_u64() -> ParseUnsigned<u64> {
+    ParseUnsigned::new(Endian::Big)
+}
+
+/// Create a parser for a little-endian `u64`.
+pub const fn le_u64() -> ParseUnsigned<u64> {
+    ParseUnsigned::new(Endian::Little)
+}
+
+/// A trait to be added to binary parsers that allows easier parser combining.
+pub trait ByteParserExtensions<'a, OutputType, ErrorType>:
+    ByteParser<'a, OutputType, ErrorType>
+{
+    fn recognize(self) -> ParseRecognize<'a, OutputType, ErrorType, Self>
+    where
+        Self: Sized,
+    {
+        ParseRecognize::new(self)
+    }
+
+    fn flags<FlagType: Clone>(
+        self,
+        table: Vec<(u64, FlagType)>,
+    ) -> ParseFlags<'a, OutputType, FlagType, Self>
+    where
+        Self: Sized + ByteParser<'a, OutputType, ParseError>,
+        OutputType: Unsigned,
+    {
+        ParseFlags::new(self, table)
+    }
+}
+
+impl<'a, OutputType, ErrorType, ParserType: ByteParser<'a, OutputType, ErrorType>>
+    ByteParserExtensions<'a, OutputType, ErrorType> for ParserType
+{
+}
@@ -0,0 +1,191 @@
+use crate::diagnostics::Diagnostic;
+use crate::{ParseError, Parser, ParserState};
+use std::fmt::{Debug, Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The extension of the files holding the expected result for each input.
+pub const EXPECTED_EXTENSION: &str = "expected";
+
+/// A directory of input files, each with a `.expected` file next to it (like `list.txt` and `list.txt.expected`)
+/// holding a snapshot of what parsing the input should produce.
+///
+/// ```no_run
+/// use parselib_rs::corpus::Corpus;
+/// use parselib_rs::parsers::utf8::integer;
+///
+/// let report = Corpus::new("tests/corpus")
+///     .update(std::env::var_os("UPDATE_CORPUS").is_some())
+///     .run(&integer::<i64>())
+///     .expect("couldn't read the corpus");
+/// report.assert_passed();
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Corpus {
+    directory: PathBuf,
+    update: bool,
+}
+
+impl Corpus {
+    /// Create a corpus from the files in `directory`. Subdirectories aren't searched.
+    pub fn new(directory: impl AsRef<Path>) -> Self {
+        Self {
+            directory: directory.as_ref().to_path_buf(),
+            update: false,
+        }
+    }
+
+    /// Choose whether to overwrite the expectation files with the actual results instead of reporting mismatches.
+    /// Missing expectation files are always reported as mismatches unless this is set.
+    pub fn update(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Parse every input in the corpus and compare the results with their expectations, in file name order.
+    ///
+    /// Successful results are written as `ok` followed by the pretty-printed `Debug` output, and errors as `error`
+    /// followed by the rendered [`Diagnostic`].
+    pub fn run<OutputType: Debug, ParserType: Parser<OutputType, ParseError>>(
+        &self,
+        parser: &ParserType,
+    ) -> io::Result<CorpusReport> {
+        let mut inputs = fs::read_dir(&self.directory)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<PathBuf>>>()?;
+        inputs.retain(|path| {
+            path.is_file() && path.extension().is_none_or(|e| e != EXPECTED_EXTENSION)
+        });
+        inputs.sort();
+
+        let mut report = CorpusReport::default();
+        for input_path in inputs {
+            let input = fs::read_to_string(&input_path)?;
+            let actual = snapshot(parser, &input, &input_path);
+
+            let mut expected_path = input_path.clone().into_os_string();
+            expected_path.push(".");
+            expected_path.push(EXPECTED_EXTENSION);
+            let expected_path = PathBuf::from(expected_path);
+
+            let expected = match fs::read_to_string(&expected_path) {
+                Ok(expected) => Some(expected),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                Err(e) => return Err(e),
+            };
+
+            if expected.as_deref() == Some(actual.as_str()) {
+                report.passed.push(input_path);
+            } else if self.update {
+                fs::write(&expected_path, &actual)?;
+                report.updated.push(input_path);
+            } else {
+                report.failed.push(Mismatch {
+                    path: input_path,
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Describe the result of parsing an input in the form stored in expectation files.
+fn snapshot<OutputType: Debug, ParserType: Parser<OutputType, ParseError>>(
+    parser: &ParserType,
+    input: &str,
+    path: &Path,
+) -> String {
+    match parser.parse(ParserState::new(input)) {
+        Ok((output, _)) => format!("ok\n{:#?}\n", output),
+        Err(e) => {
+            let name = path.file_name().map(|name| name.to_string_lossy());
+            format!(
+                "error\n{}",
+                Diagnostic::new(&e, input).with_name(name.as_deref().unwrap_or("input"))
+            )
+        }
+    }
+}
+
+/// An input whose result didn't match its expectation.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Mismatch {
+    /// The input file.
+    pub path: PathBuf,
+
+    /// The contents of the expectation file, or `None` if it doesn't exist.
+    pub expected: Option<String>,
+
+    /// The actual result.
+    pub actual: String,
+}
+
+impl Display for Mismatch {
+    /// Writes the path followed by the lines that differ, marking expected lines with `-` and actual lines with `+`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let expected = match &self.expected {
+            Some(expected) => expected,
+            None => {
+                writeln!(f, "{}: no expectation file", self.path.display())?;
+                return Ok(());
+            }
+        };
+
+        writeln!(f, "{}:", self.path.display())?;
+        let expected_lines = expected.lines().collect::<Vec<&str>>();
+        let actual_lines = self.actual.lines().collect::<Vec<&str>>();
+        for index in 0..expected_lines.len().max(actual_lines.len()) {
+            match (expected_lines.get(index), actual_lines.get(index)) {
+                (Some(e), Some(a)) if e == a => writeln!(f, "  {}", e)?,
+                (e, a) => {
+                    if let Some(e) = e {
+                        writeln!(f, "- {}", e)?;
+                    }
+                    if let Some(a) = a {
+                        writeln!(f, "+ {}", a)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The results of running a [`Corpus`].
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct CorpusReport {
+    /// The inputs that matched their expectations.
+    pub passed: Vec<PathBuf>,
+
+    /// The inputs that didn't match their expectations.
+    pub failed: Vec<Mismatch>,
+
+    /// The inputs whose expectations were written because they didn't match, in update mode.
+    pub updated: Vec<PathBuf>,
+}
+
+impl CorpusReport {
+    /// Check whether every input matched its expectation or had its expectation updated.
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// Panic with a description of every mismatch if any input didn't match its expectation.
+    pub fn assert_passed(&self) {
+        if !self.is_success() {
+            let mut message = format!(
+                "{} of {} corpus inputs didn't match their expectations\n",
+                self.failed.len(),
+                self.passed.len() + self.failed.len() + self.updated.len()
+            );
+            for mismatch in &self.failed {
+                message.push_str(&mismatch.to_string());
+            }
+            panic!("{}", message);
+        }
+    }
+}
@@ -0,0 +1,421 @@
+use crate::parsers::utf8::ParseAllConsuming;
+use crate::{ParseError, Parser, ParserState};
+use serde::de::{
+    self, Deserialize, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, Unexpected,
+    Visitor,
+};
+use serde::forward_to_deserialize_any;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+
+/// An error produced while deserializing from the output of a parser.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum DeError {
+    /// The input didn't match the grammar.
+    Parse(ParseError),
+
+    /// The parser's output didn't have the shape the deserialized type needed.
+    Message(String),
+}
+
+impl Display for DeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse(e) => write!(f, "{}", e),
+            Self::Message(message) => writeln!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for DeError {}
+
+impl de::Error for DeError {
+    fn custom<MessageType: Display>(message: MessageType) -> Self {
+        Self::Message(message.to_string())
+    }
+}
+
+impl From<ParseError> for DeError {
+    fn from(e: ParseError) -> Self {
+        Self::Parse(e)
+    }
+}
+
+/// A piece of text from the input that is converted into whatever scalar type the deserialized value needs, so
+/// parsers can leave numbers and booleans as text. Numbers, booleans and characters are parsed with `FromStr`
+/// after trimming whitespace, empty text is `None` for optional values, and other text is the name of a unit enum
+/// variant.
+///
+/// Collections of text can be deserialized too: a `BTreeMap<String, Text>` fills in the fields of a struct whose
+/// fields have different types.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Text(pub String);
+
+impl From<String> for Text {
+    fn from(text: String) -> Self {
+        Self(text)
+    }
+}
+
+impl From<&str> for Text {
+    fn from(text: &str) -> Self {
+        Self(String::from(text))
+    }
+}
+
+impl<'de> IntoDeserializer<'de, DeError> for Text {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+/// Implement deserializer methods that parse the text with `FromStr`.
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<VisitorType: Visitor<'de>>(
+                self,
+                visitor: VisitorType,
+            ) -> Result<VisitorType::Value, DeError> {
+                match self.0.trim().parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => Err(de::Error::invalid_value(Unexpected::Str(&self.0), &visitor)),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Text {
+    type Error = DeError;
+
+    fn deserialize_any<VisitorType: Visitor<'de>>(
+        self,
+        visitor: VisitorType,
+    ) -> Result<VisitorType::Value, DeError> {
+        visitor.visit_string(self.0)
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_option<VisitorType: Visitor<'de>>(
+        self,
+        visitor: VisitorType,
+    ) -> Result<VisitorType::Value, DeError> {
+        if self.0.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<VisitorType: Visitor<'de>>(
+        self,
+        visitor: VisitorType,
+    ) -> Result<VisitorType::Value, DeError> {
+        if self.0.is_empty() {
+            visitor.visit_unit()
+        } else {
+            Err(de::Error::invalid_value(Unexpected::Str(&self.0), &visitor))
+        }
+    }
+
+    fn deserialize_newtype_struct<VisitorType: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: VisitorType,
+    ) -> Result<VisitorType::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<VisitorType: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: VisitorType,
+    ) -> Result<VisitorType::Value, DeError> {
+        IntoDeserializer::<DeError>::into_deserializer(self.0)
+            .deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// A serde `Deserializer` that runs a parser over the whole of an input and deserializes from its output, so a
+/// hand-written grammar can fill in `#[derive(Deserialize)]` types directly.
+///
+/// The parser's output can be anything serde can turn into a deserializer: strings, numbers, `Vec`s, `BTreeMap`s
+/// and [`Text`] for scalars that are still text, nested however the grammar produces them. The whole output is
+/// built before serde sees any of it; use [`EntriesDeserializer`] to hand entries to serde as they are parsed
+/// instead.
+pub struct ParserDeserializer<'a, OutputType, ParserType: Parser<OutputType, ParseError>> {
+    /// The parser to run.
+    parser: &'a ParserType,
+
+    /// The input to parse.
+    input: &'a str,
+
+    _phantom: PhantomData<OutputType>,
+}
+
+impl<'a, OutputType, ParserType: Parser<OutputType, ParseError>>
+    ParserDeserializer<'a, OutputType, ParserType>
+{
+    /// Create a deserializer that parses `input` with `parser`.
+    pub fn new(parser: &'a ParserType, input: &'a str) -> Self {
+        Self {
+            parser,
+            input,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Run the parser, which must consume the whole input.
+    fn output(&self) -> Result<OutputType, DeError> {
+        ParseAllConsuming::new(self.parser)
+            .parse(ParserState::new(self.input))
+            .map(|(output, _)| output)
+            .map_err(DeError::Parse)
+    }
+}
+
+/// Implement deserializer methods that parse the input and hand the output to its own deserializer.
+macro_rules! forward_to_output {
+    ($($method:ident($($argument:ident: $type:ty),*),)*) => {
+        $(
+            fn $method<VisitorType: Visitor<'de>>(
+                self,
+                $($argument: $type,)*
+                visitor: VisitorType,
+            ) -> Result<VisitorType::Value, DeError> {
+                self.output()?.into_deserializer().$method($($argument,)* visitor)
+            }
+        )*
+    };
+}
+
+impl<
+        'de,
+        OutputType: IntoDeserializer<'de, DeError>,
+        ParserType: Parser<OutputType, ParseError>,
+    > Deserializer<'de> for ParserDeserializer<'_, OutputType, ParserType>
+{
+    type Error = DeError;
+
+    forward_to_output! {
+        deserialize_any(),
+        deserialize_bool(),
+        deserialize_i8(),
+        deserialize_i16(),
+        deserialize_i32(),
+        deserialize_i64(),
+        deserialize_i128(),
+        deserialize_u8(),
+        deserialize_u16(),
+        deserialize_u32(),
+        deserialize_u64(),
+        deserialize_u128(),
+        deserialize_f32(),
+        deserialize_f64(),
+        deserialize_char(),
+        deserialize_str(),
+        deserialize_string(),
+        deserialize_bytes(),
+        deserialize_byte_buf(),
+        deserialize_option(),
+        deserialize_unit(),
+        deserialize_unit_struct(name: &'static str),
+        deserialize_newtype_struct(name: &'static str),
+        deserialize_seq(),
+        deserialize_tuple(len: usize),
+        deserialize_tuple_struct(name: &'static str, len: usize),
+        deserialize_map(),
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]),
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]),
+        deserialize_identifier(),
+        deserialize_ignored_any(),
+    }
+}
+
+/// Parse the whole of `input` with `parser` and deserialize a value from its output.
+pub fn from_str<
+    'de,
+    ValueType: Deserialize<'de>,
+    OutputType: IntoDeserializer<'de, DeError>,
+    ParserType: Parser<OutputType, ParseError>,
+>(
+    parser: &ParserType,
+    input: &str,
+) -> Result<ValueType, DeError> {
+    ValueType::deserialize(ParserDeserializer::new(parser, input))
+}
+
+/// A serde `Deserializer` for inputs made of key-value entries with separators between them, such as
+/// `name=server,port=8080`, which drives serde's visitor while it parses. Each entry is handed to serde as soon as
+/// it is parsed, so no map or other value is built for the input as a whole.
+///
+/// Values are [`Text`], so they become whatever type each field needs. Structs and maps can be deserialized.
+pub struct EntriesDeserializer<
+    'a,
+    EntryParserType: Parser<(String, Text), ParseError>,
+    SeparatorOutputType,
+    SeparatorParserType: Parser<SeparatorOutputType, ParseError>,
+> {
+    /// The parser for each entry.
+    entry: &'a EntryParserType,
+
+    /// The parser for the separator between entries.
+    separator: &'a SeparatorParserType,
+
+    /// The input to parse.
+    input: &'a str,
+
+    _phantom: PhantomData<SeparatorOutputType>,
+}
+
+impl<
+        'a,
+        EntryParserType: Parser<(String, Text), ParseError>,
+        SeparatorOutputType,
+        SeparatorParserType: Parser<SeparatorOutputType, ParseError>,
+    > EntriesDeserializer<'a, EntryParserType, SeparatorOutputType, SeparatorParserType>
+{
+    /// Create a deserializer that parses `input` as entries parsed by `entry`, separated by `separator`.
+    pub fn new(
+        entry: &'a EntryParserType,
+        separator: &'a SeparatorParserType,
+        input: &'a str,
+    ) -> Self {
+        Self {
+            entry,
+            separator,
+            input,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<
+        'de,
+        EntryParserType: Parser<(String, Text), ParseError>,
+        SeparatorOutputType,
+        SeparatorParserType: Parser<SeparatorOutputType, ParseError>,
+    > Deserializer<'de>
+    for EntriesDeserializer<'_, EntryParserType, SeparatorOutputType, SeparatorParserType>
+{
+    type Error = DeError;
+
+    fn deserialize_any<VisitorType: Visitor<'de>>(
+        self,
+        visitor: VisitorType,
+    ) -> Result<VisitorType::Value, DeError> {
+        visitor.visit_map(EntryAccess {
+            entry: self.entry,
+            separator: self.separator,
+            parser_state: ParserState::new(self.input),
+            first: true,
+            value: None,
+            _phantom: PhantomData,
+        })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option unit
+        unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Parses the entries of an [`EntriesDeserializer`] one at a time as serde asks for them.
+struct EntryAccess<'a, EntryParserType, SeparatorOutputType, SeparatorParserType> {
+    /// The parser for each entry.
+    entry: &'a EntryParserType,
+
+    /// The parser for the separator between entries.
+    separator: &'a SeparatorParserType,
+
+    /// The input after the entries parsed so far.
+    parser_state: ParserState,
+
+    /// Whether no entries have been parsed yet, so there is no separator before the next one.
+    first: bool,
+
+    /// The value of the entry whose key was just handed to serde.
+    value: Option<Text>,
+
+    _phantom: PhantomData<SeparatorOutputType>,
+}
+
+impl<
+        'de,
+        EntryParserType: Parser<(String, Text), ParseError>,
+        SeparatorOutputType,
+        SeparatorParserType: Parser<SeparatorOutputType, ParseError>,
+    > MapAccess<'de>
+    for EntryAccess<'_, EntryParserType, SeparatorOutputType, SeparatorParserType>
+{
+    type Error = DeError;
+
+    fn next_key_seed<SeedType: DeserializeSeed<'de>>(
+        &mut self,
+        seed: SeedType,
+    ) -> Result<Option<SeedType::Value>, DeError> {
+        if self.parser_state.input.is_empty() {
+            return Ok(None);
+        }
+
+        let mut parser_state = self.parser_state.clone();
+        if !self.first {
+            parser_state = self.separator.parse(parser_state)?.1;
+        }
+        let ((key, value), new_state) = self.entry.parse(parser_state)?;
+        self.parser_state = new_state;
+        self.first = false;
+        self.value = Some(value);
+        seed.deserialize(Text(key)).map(Some)
+    }
+
+    fn next_value_seed<SeedType: DeserializeSeed<'de>>(
+        &mut self,
+        seed: SeedType,
+    ) -> Result<SeedType::Value, DeError> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(value),
+            None => Err(de::Error::custom("value requested before its key")),
+        }
+    }
+}
+
+/// Parse `input` as entries separated by `separator` and deserialize a value from them as they are parsed.
+pub fn from_entries<
+    'de,
+    ValueType: Deserialize<'de>,
+    EntryParserType: Parser<(String, Text), ParseError>,
+    SeparatorOutputType,
+    SeparatorParserType: Parser<SeparatorOutputType, ParseError>,
+>(
+    entry: &EntryParserType,
+    separator: &SeparatorParserType,
+    input: &str,
+) -> Result<ValueType, DeError> {
+    ValueType::deserialize(EntriesDeserializer::new(entry, separator, input))
+}
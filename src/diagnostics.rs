@@ -0,0 +1,212 @@
+use crate::lines::LineIndex;
+use crate::messages::{DefaultMessages, MessageProvider, ReportLabel};
+use crate::ParseError;
+use std::env;
+use std::fmt::{Display, Formatter};
+use std::io::{self, IsTerminal};
+
+/// The ANSI escape code for red text.
+const RED: &str = "31";
+
+/// The ANSI escape code for bold red text.
+const BOLD_RED: &str = "1;31";
+
+/// The ANSI escape code for bold text.
+const BOLD: &str = "1";
+
+/// The ANSI escape code for dimmed text.
+const DIM: &str = "2";
+
+/// Whether a diagnostic should be rendered with ANSI colors.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ColorChoice {
+    /// Use colors when standard error is a terminal, unless the `NO_COLOR` environment variable is set.
+    Auto,
+
+    /// Always use colors.
+    Always,
+
+    /// Never use colors.
+    Never,
+}
+
+impl ColorChoice {
+    /// Decide whether colors should be used.
+    pub fn use_color(self) -> bool {
+        match self {
+            Self::Auto => {
+                // See https://no-color.org: any non-empty value disables color.
+                let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+                !no_color && io::stderr().is_terminal()
+            }
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+}
+
+/// A printable report of a parsing error that shows the line of the source input where the error occurred, with
+/// the location marked underneath it, followed by any notes and help attached to the error.
+#[derive(Debug, Clone)]
+pub struct Diagnostic<'a> {
+    /// The error to report.
+    error: &'a ParseError,
+
+    /// The complete input that was being parsed when the error occurred.
+    source: &'a str,
+
+    /// The lines of the source, if they were found before the report was created.
+    lines: Option<&'a LineIndex<'a>>,
+
+    /// The name of the input (such as a file name) to show in the report.
+    name: &'a str,
+
+    /// Whether to render the report with ANSI colors.
+    color: bool,
+
+    /// The wording of the report.
+    messages: &'a dyn MessageProvider,
+}
+
+impl<'a> Diagnostic<'a> {
+    /// Create a new report for an error produced while parsing `source`.
+    pub fn new(error: &'a ParseError, source: &'a str) -> Self {
+        Self {
+            error,
+            source,
+            lines: None,
+            name: "input",
+            color: false,
+            messages: &DefaultMessages,
+        }
+    }
+
+    /// Create a new report for an error produced while parsing the source of `lines`. Reusing the same line index
+    /// for every error in an input avoids scanning the input again for each report.
+    pub fn with_lines(error: &'a ParseError, lines: &'a LineIndex<'a>) -> Self {
+        Self {
+            lines: Some(lines),
+            ..Self::new(error, lines.source())
+        }
+    }
+
+    /// Set the name of the input (such as a file name) to show in the report.
+    pub fn with_name(mut self, name: &'a str) -> Self {
+        self.name = name;
+        self
+    }
+
+    /// Set whether the report should be rendered with ANSI colors. Reports are rendered without colors by default.
+    pub fn with_color(mut self, color: ColorChoice) -> Self {
+        self.color = color.use_color();
+        self
+    }
+
+    /// Set the wording of the report. Reports use the standard English messages by default.
+    pub fn with_messages(mut self, messages: &'a dyn MessageProvider) -> Self {
+        self.messages = messages;
+        self
+    }
+
+    /// Wrap the text in the provided ANSI style if colors are enabled.
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", style, text)
+        } else {
+            String::from(text)
+        }
+    }
+}
+
+impl Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // Make sure the offset is inside the source and on a character
+        // boundary so it can be used for slicing.
+        let mut offset = self.error.offset().min(self.source.len());
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        // Find the line containing the error, finding the lines of the
+        // source first if that wasn't done already.
+        let found_lines;
+        let lines = match self.lines {
+            Some(lines) => lines,
+            None => {
+                found_lines = LineIndex::new(self.source);
+                &found_lines
+            }
+        };
+        let (line_number, column) = lines.line_column(offset);
+        let line_span = lines.line_span(line_number).unwrap_or_default();
+        let (line_start, line_end) = (line_span.start, line_span.end);
+        let line = &self.source[line_start..line_end];
+
+        // Underline the whole span of the error (up to the end of the line),
+        // marking at least one position.
+        let end = self.error.span().end.min(line_end).max(offset);
+        let width = self
+            .source
+            .get(offset..end)
+            .map_or(1, |spanned| spanned.chars().count().max(1));
+
+        // Keep tabs before the marker so it lines up with the source line.
+        let padding = self.source[line_start..offset]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        let gutter = " ".repeat(line_number.to_string().len());
+
+        writeln!(
+            f,
+            "{}{}",
+            self.paint(BOLD_RED, &self.messages.label(ReportLabel::Error)),
+            self.paint(
+                BOLD,
+                &format!(": {}", self.messages.message(self.error.kind()))
+            )
+        )?;
+        writeln!(
+            f,
+            "{}",
+            self.paint(
+                DIM,
+                &format!("{}--> {}:{}:{}", gutter, self.name, line_number, column)
+            )
+        )?;
+        writeln!(f, "{}", self.paint(DIM, &format!("{} |", gutter)))?;
+        writeln!(
+            f,
+            "{} {}",
+            self.paint(DIM, &format!("{} |", line_number)),
+            line
+        )?;
+        writeln!(
+            f,
+            "{} {}{}",
+            self.paint(DIM, &format!("{} |", gutter)),
+            padding,
+            self.paint(RED, &"^".repeat(width))
+        )?;
+        for note in self.error.notes() {
+            writeln!(
+                f,
+                "{} {}: {}",
+                self.paint(DIM, &format!("{} =", gutter)),
+                self.paint(BOLD, &self.messages.label(ReportLabel::Note)),
+                note
+            )?;
+        }
+        for help in self.error.help() {
+            writeln!(
+                f,
+                "{} {}: {}",
+                self.paint(DIM, &format!("{} =", gutter)),
+                self.paint(BOLD, &self.messages.label(ReportLabel::Help)),
+                help
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,582 @@
+use crate::{Commit, ParseError, ParseErrorKind, Parser, ParserState};
+use std::marker::PhantomData;
+
+/// A text encoding that input may be stored in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+impl Encoding {
+    /// Detect the encoding from a byte order mark at the start of `bytes`, if there is one.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        // The UTF-32 marks must be checked first, since the little-endian one
+        // starts with the UTF-16 one.
+        match bytes {
+            [0xff, 0xfe, 0, 0, ..] => Some(Self::Utf32Le),
+            [0, 0, 0xfe, 0xff, ..] => Some(Self::Utf32Be),
+            [0xef, 0xbb, 0xbf, ..] => Some(Self::Utf8),
+            [0xff, 0xfe, ..] => Some(Self::Utf16Le),
+            [0xfe, 0xff, ..] => Some(Self::Utf16Be),
+            _ => None,
+        }
+    }
+
+    /// Get the size of each code unit in bytes.
+    fn unit_size(self) -> usize {
+        match self {
+            Self::Utf8 => 1,
+            Self::Utf16Le | Self::Utf16Be => 2,
+            Self::Utf32Le | Self::Utf32Be => 4,
+        }
+    }
+
+    /// Read the code unit at the start of `bytes`.
+    fn unit(self, bytes: &[u8]) -> u32 {
+        match self {
+            Self::Utf8 => u32::from(bytes[0]),
+            Self::Utf16Le => u32::from(u16::from_le_bytes([bytes[0], bytes[1]])),
+            Self::Utf16Be => u32::from(u16::from_be_bytes([bytes[0], bytes[1]])),
+            Self::Utf32Le => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            Self::Utf32Be => u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        }
+    }
+}
+
+/// The current location within text stored in an [`Encoding`]. Characters are decoded only as parsers reach them,
+/// so there is no separate transcoding pass, and offsets are byte offsets within the original input.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct EncodedState<'a> {
+    /// The whole encoded input.
+    bytes: &'a [u8],
+
+    /// The encoding of the input.
+    encoding: Encoding,
+
+    /// The offset of the next character within `bytes`.
+    index: usize,
+}
+
+impl<'a> EncodedState<'a> {
+    /// Create a state at the start of `bytes`, skipping a leading byte order mark.
+    pub fn new(bytes: &'a [u8], encoding: Encoding) -> Self {
+        let state = Self {
+            bytes,
+            encoding,
+            index: 0,
+        };
+        match state.next_char() {
+            Ok(Some(('\u{feff}', after))) => after,
+            _ => state,
+        }
+    }
+
+    /// Get the encoding of the input.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Get the offset of the next character within the original bytes.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Check whether the whole input has been consumed.
+    pub fn is_empty(&self) -> bool {
+        self.index >= self.bytes.len()
+    }
+
+    /// Decode the next character, returning it with the state after it, or `None` at the end of the input. Unpaired
+    /// surrogates, invalid characters, and incomplete code units are reported at their offset within the bytes.
+    pub fn next_char(&self) -> Result<Option<(char, Self)>, ParseError> {
+        let index = self.index;
+        let rest = &self.bytes[index.min(self.bytes.len())..];
+        if rest.is_empty() {
+            return Ok(None);
+        }
+
+        let (c, length) = if self.encoding == Encoding::Utf8 {
+            // Only look at as many bytes as the longest character needs.
+            let candidate = &rest[..rest.len().min(4)];
+            let valid = match std::str::from_utf8(candidate) {
+                Ok(valid) => valid,
+                Err(e) => std::str::from_utf8(&candidate[..e.valid_up_to()]).unwrap(),
+            };
+            let c = valid
+                .chars()
+                .next()
+                .ok_or_else(|| ParseError::invalid(index, index + 1, "invalid UTF-8"))?;
+            (c, c.len_utf8())
+        } else {
+            let size = self.encoding.unit_size();
+            if rest.len() < size {
+                return Err(ParseError::invalid(
+                    index,
+                    self.bytes.len(),
+                    "input ends partway through a character",
+                ));
+            }
+            let mut unit = self.encoding.unit(rest);
+            let mut length = size;
+
+            // A high surrogate must be followed by a low surrogate.
+            if size == 2 && (0xd800..0xdc00).contains(&unit) {
+                let low = rest
+                    .get(2..4)
+                    .map(|low| self.encoding.unit(low))
+                    .filter(|low| (0xdc00..0xe000).contains(low))
+                    .ok_or_else(|| {
+                        ParseError::invalid(index, index + 2, "unpaired high surrogate")
+                    })?;
+                unit = 0x10000 + ((unit - 0xd800) << 10) + (low - 0xdc00);
+                length = 4;
+            }
+
+            let c = char::from_u32(unit).ok_or_else(|| {
+                let message = if (0xd800..0xe000).contains(&unit) {
+                    "unpaired low surrogate"
+                } else {
+                    "invalid character"
+                };
+                ParseError::invalid(index, index + size, message)
+            })?;
+            (c, length)
+        };
+
+        Ok(Some((
+            c,
+            Self {
+                index: index + length,
+                ..*self
+            },
+        )))
+    }
+
+    /// Decode characters for as long as they match `predicate`, returning them with the state after them.
+    pub fn take_while(
+        &self,
+        predicate: impl Fn(char) -> bool,
+    ) -> Result<(String, Self), ParseError> {
+        let mut taken = String::new();
+        let mut state = *self;
+        while let Some((c, next_state)) = state.next_char()? {
+            if !predicate(c) {
+                break;
+            }
+            taken.push(c);
+            state = next_state;
+        }
+        Ok((taken, state))
+    }
+
+    /// Match the text `literal`, returning the state after it.
+    pub fn tag(&self, literal: &str) -> Result<Self, ParseError> {
+        let mut state = *self;
+        for expected in literal.chars() {
+            match state.next_char()? {
+                Some((c, next_state)) if c == expected => state = next_state,
+                found => {
+                    let found = found.map(|(c, _)| c.to_string());
+                    return Err(ParseError::unexpected(
+                        self.index,
+                        [literal],
+                        found.as_deref(),
+                    ));
+                }
+            }
+        }
+        Ok(state)
+    }
+}
+
+/// The result of a parser for encoded text, which is either the output and the state after it, or an error.
+pub type EncodedResult<'a, ErrorType, OutputType> =
+    Result<(OutputType, EncodedState<'a>), ErrorType>;
+
+/// A trait representing a parser that reads encoded text directly, decoding characters as it goes. Encoded parsers
+/// can be combined with [`EncodedParserExtensions`], which covers sequences, alternatives, and repetition. The rest
+/// of the combinators in [`parsers`](crate::parsers) only work on UTF-8 text, so grammars built from them need the
+/// input decoded up front with [`DecodedInput`] instead.
+pub trait EncodedParser<'a, OutputType, ErrorType> {
+    /// Attempt to parse the provided input.
+    fn parse(&self, encoded_state: EncodedState<'a>) -> EncodedResult<'a, ErrorType, OutputType>;
+}
+
+impl<'a, OutputType, ErrorType, FunctionType> EncodedParser<'a, OutputType, ErrorType>
+    for FunctionType
+where
+    FunctionType: Fn(EncodedState<'a>) -> EncodedResult<'a, ErrorType, OutputType>,
+{
+    fn parse(&self, encoded_state: EncodedState<'a>) -> EncodedResult<'a, ErrorType, OutputType> {
+        self(encoded_state)
+    }
+}
+
+/// Parses one element of encoded text and then another element.
+pub struct ParseAnd<
+    'a,
+    OutputTypeA,
+    OutputTypeB,
+    ErrorType,
+    ParserTypeA: EncodedParser<'a, OutputTypeA, ErrorType>,
+    ParserTypeB: EncodedParser<'a, OutputTypeB, ErrorType>,
+> {
+    /// The first parser to run.
+    parser_a: ParserTypeA,
+
+    /// The second parser to run.
+    parser_b: ParserTypeB,
+
+    /* Phantom */
+    _phantom: PhantomData<(&'a (), OutputTypeA, OutputTypeB, ErrorType)>,
+}
+
+impl<
+        'a,
+        OutputTypeA,
+        OutputTypeB,
+        ErrorType,
+        ParserTypeA: EncodedParser<'a, OutputTypeA, ErrorType>,
+        ParserTypeB: EncodedParser<'a, OutputTypeB, ErrorType>,
+    > ParseAnd<'a, OutputTypeA, OutputTypeB, ErrorType, ParserTypeA, ParserTypeB>
+{
+    pub const fn new(parser_a: ParserTypeA, parser_b: ParserTypeB) -> Self {
+        Self {
+            parser_a,
+            parser_b,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<
+        'a,
+        OutputTypeA,
+        OutputTypeB,
+        ErrorType,
+        ParserTypeA: EncodedParser<'a, OutputTypeA, ErrorType>,
+        ParserTypeB: EncodedParser<'a, OutputTypeB, ErrorType>,
+    > EncodedParser<'a, (OutputTypeA, OutputTypeB), ErrorType>
+    for ParseAnd<'a, OutputTypeA, OutputTypeB, ErrorType, ParserTypeA, ParserTypeB>
+{
+    fn parse(
+        &self,
+        encoded_state: EncodedState<'a>,
+    ) -> EncodedResult<'a, ErrorType, (OutputTypeA, OutputTypeB)> {
+        let (a, new_state) = self.parser_a.parse(encoded_state)?;
+        let (b, new_state) = self.parser_b.parse(new_state)?;
+        Ok(((a, b), new_state))
+    }
+}
+
+/// Parses one of two elements of encoded text, trying the second only if the first doesn't match.
+pub struct ParseOr<
+    'a,
+    OutputType,
+    ParserTypeA: EncodedParser<'a, OutputType, ParseError>,
+    ParserTypeB: EncodedParser<'a, OutputType, ParseError>,
+> {
+    /// The parser to try first.
+    parser_a: ParserTypeA,
+
+    /// The parser to try if the first one fails.
+    parser_b: ParserTypeB,
+
+    /* Phantom */
+    _phantom: PhantomData<(&'a (), OutputType)>,
+}
+
+impl<
+        'a,
+        OutputType,
+        ParserTypeA: EncodedParser<'a, OutputType, ParseError>,
+        ParserTypeB: EncodedParser<'a, OutputType, ParseError>,
+    > ParseOr<'a, OutputType, ParserTypeA, ParserTypeB>
+{
+    pub const fn new(parser_a: ParserTypeA, parser_b: ParserTypeB) -> Self {
+        Self {
+            parser_a,
+            parser_b,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<
+        'a,
+        OutputType,
+        ParserTypeA: EncodedParser<'a, OutputType, ParseError>,
+        ParserTypeB: EncodedParser<'a, OutputType, ParseError>,
+    > EncodedParser<'a, OutputType, ParseError>
+    for ParseOr<'a, OutputType, ParserTypeA, ParserTypeB>
+{
+    fn parse(&self, encoded_state: EncodedState<'a>) -> EncodedResult<'a, ParseError, OutputType> {
+        match self.parser_a.parse(encoded_state) {
+            Ok(result) => Ok(result),
+            Err(e_a) if e_a.is_committed() => Err(e_a),
+            Err(e_a) => self
+                .parser_b
+                .parse(encoded_state)
+                // If neither matches, report what both of them expected.
+                .map_err(|e_b| e_a.merge(e_b)),
+        }
+    }
+}
+
+/// Parses an element of encoded text and then converts its output with a function.
+pub struct ParseMap<
+    'a,
+    InputType,
+    OutputType,
+    ErrorType,
+    ParserType: EncodedParser<'a, InputType, ErrorType>,
+    MapperType: Fn(InputType) -> OutputType,
+> {
+    /// The parser whose output should be converted.
+    parser: ParserType,
+
+    /// The function used to convert the output.
+    mapper: MapperType,
+
+    /* Phantom */
+    _phantom: PhantomData<(&'a (), InputType, OutputType, ErrorType)>,
+}
+
+impl<
+        'a,
+        InputType,
+        OutputType,
+        ErrorType,
+        ParserType: EncodedParser<'a, InputType, ErrorType>,
+        MapperType: Fn(InputType) -> OutputType,
+    > ParseMap<'a, InputType, OutputType, ErrorType, ParserType, MapperType>
+{
+    pub const fn new(parser: ParserType, mapper: MapperType) -> Self {
+        Self {
+            parser,
+            mapper,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<
+        'a,
+        InputType,
+        OutputType,
+        ErrorType,
+        ParserType: EncodedParser<'a, InputType, ErrorType>,
+        MapperType: Fn(InputType) -> OutputType,
+    > EncodedParser<'a, OutputType, ErrorType>
+    for ParseMap<'a, InputType, OutputType, ErrorType, ParserType, MapperType>
+{
+    fn parse(&self, encoded_state: EncodedState<'a>) -> EncodedResult<'a, ErrorType, OutputType> {
+        let (output, new_state) = self.parser.parse(encoded_state)?;
+        Ok(((self.mapper)(output), new_state))
+    }
+}
+
+/// Parses an element of encoded text repeatedly, between a minimum and maximum number of times.
+pub struct ParseCount<
+    'a,
+    OutputType,
+    ErrorType,
+    ParserType: EncodedParser<'a, OutputType, ErrorType>,
+> {
+    /// The minimum number of elements.
+    min: usize,
+
+    /// The maximum number of elements.
+    max: usize,
+
+    /// The parser for each element.
+    parser: ParserType,
+
+    /* Phantom */
+    _phantom: PhantomData<(&'a (), OutputType, ErrorType)>,
+}
+
+impl<'a, OutputType, ErrorType, ParserType: EncodedParser<'a, OutputType, ErrorType>>
+    ParseCount<'a, OutputType, ErrorType, ParserType>
+{
+    pub const fn new(min: usize, max: usize, parser: ParserType) -> Self {
+        Self {
+            min,
+            max,
+            parser,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, OutputType, ErrorType, ParserType: EncodedParser<'a, OutputType, ErrorType>>
+    EncodedParser<'a, Vec<OutputType>, ParseError>
+    for ParseCount<'a, OutputType, ErrorType, ParserType>
+{
+    fn parse(
+        &self,
+        encoded_state: EncodedState<'a>,
+    ) -> EncodedResult<'a, ParseError, Vec<OutputType>> {
+        let mut new_state = encoded_state;
+        let mut output = Vec::with_capacity(self.min);
+        while output.len() < self.max {
+            match self.parser.parse(new_state) {
+                Ok((element, element_state)) => {
+                    new_state = element_state;
+                    output.push(element);
+                }
+                Err(_) => break,
+            }
+        }
+
+        if output.len() < self.min {
+            Err(ParseError::wrong_count(
+                new_state.index,
+                self.min,
+                self.max,
+                output.len(),
+            ))
+        } else {
+            Ok((output, new_state))
+        }
+    }
+}
+
+/// A trait to be added to encoded text parsers that allows easier parser combining.
+pub trait EncodedParserExtensions<'a, OutputType, ErrorType>:
+    EncodedParser<'a, OutputType, ErrorType>
+{
+    fn and<OutputTypeB, ParserTypeB: EncodedParser<'a, OutputTypeB, ErrorType>>(
+        self,
+        parser_b: ParserTypeB,
+    ) -> ParseAnd<'a, OutputType, OutputTypeB, ErrorType, Self, ParserTypeB>
+    where
+        Self: Sized,
+    {
+        ParseAnd::new(self, parser_b)
+    }
+
+    fn or<ParserTypeB: EncodedParser<'a, OutputType, ParseError>>(
+        self,
+        parser_b: ParserTypeB,
+    ) -> ParseOr<'a, OutputType, Self, ParserTypeB>
+    where
+        Self: Sized + EncodedParser<'a, OutputType, ParseError>,
+    {
+        ParseOr::new(self, parser_b)
+    }
+
+    fn map<NewOutputType, MapperType: Fn(OutputType) -> NewOutputType>(
+        self,
+        mapper: MapperType,
+    ) -> ParseMap<'a, OutputType, NewOutputType, ErrorType, Self, MapperType>
+    where
+        Self: Sized,
+    {
+        ParseMap::new(self, mapper)
+    }
+
+    fn between(self, min: usize, max: usize) -> ParseCount<'a, OutputType, ErrorType, Self>
+    where
+        Self: Sized,
+    {
+        ParseCount::new(min, max, self)
+    }
+
+    fn one_or_more(self) -> ParseCount<'a, OutputType, ErrorType, Self>
+    where
+        Self: Sized,
+    {
+        self.between(1, usize::MAX)
+    }
+}
+
+impl<'a, OutputType, ErrorType, ParserType: EncodedParser<'a, OutputType, ErrorType>>
+    EncodedParserExtensions<'a, OutputType, ErrorType> for ParserType
+{
+}
+
+/// Text decoded from another encoding up front, which remembers where each character came from so that offsets
+/// can be reported in terms of the original bytes. This lets the parsers for UTF-8 text be used on other
+/// encodings, at the cost of decoding the whole input first; parsers written for [`EncodedState`] avoid that.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct DecodedInput {
+    text: String,
+
+    /// The offset of each character in the text, paired with its offset in the original bytes.
+    offsets: Vec<(usize, usize)>,
+
+    /// The length of the original bytes.
+    source_length: usize,
+}
+
+impl DecodedInput {
+    /// Decode `bytes`, skipping a leading byte order mark. Unpaired surrogates, invalid characters, and incomplete
+    /// code units are reported at their offset within `bytes`.
+    pub fn decode(bytes: &[u8], encoding: Encoding) -> Result<Self, ParseError> {
+        let mut decoded = Self {
+            text: String::new(),
+            offsets: Vec::new(),
+            source_length: bytes.len(),
+        };
+
+        let mut state = EncodedState::new(bytes, encoding);
+        while let Some((c, next_state)) = state.next_char()? {
+            decoded.push(c, state.index);
+            state = next_state;
+        }
+
+        Ok(decoded)
+    }
+
+    /// Add a character that came from `source_offset`.
+    fn push(&mut self, c: char, source_offset: usize) {
+        self.offsets.push((self.text.len(), source_offset));
+        self.text.push(c);
+    }
+
+    /// Get the decoded text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Create a parser state for the decoded text.
+    pub fn state(&self) -> ParserState {
+        ParserState::new(&self.text)
+    }
+
+    /// Convert an offset in the decoded text into an offset in the original bytes.
+    pub fn source_offset(&self, text_offset: usize) -> usize {
+        // Offsets within a character are rounded to its start, and offsets
+        // after the last character are the end of the input.
+        let index = self.offsets.partition_point(|(t, _)| *t <= text_offset);
+        match index.checked_sub(1) {
+            Some(index) if text_offset < self.text.len() => self.offsets[index].1,
+            _ => self.source_length,
+        }
+    }
+
+    /// Convert the offsets in an error from the decoded text into offsets in the original bytes.
+    pub fn map_error(&self, mut error: ParseError) -> ParseError {
+        error.offset = self.source_offset(error.offset);
+        if let ParseErrorKind::Invalid { end, .. } = &mut error.kind {
+            *end = self.source_offset(*end);
+        }
+        error
+    }
+
+    /// Parse the whole decoded text, reporting any error with offsets in the original bytes.
+    pub fn parse<OutputType, ParserType: Parser<OutputType, ParseError>>(
+        &self,
+        parser: ParserType,
+    ) -> Result<OutputType, ParseError> {
+        parser
+            .parse(self.state())
+            .map(|(output, _)| output)
+            .map_err(|e| self.map_error(e))
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{ParseError, ParseResult, ParserState};
+
+/// Splitting command strings into arguments.
+pub mod args;
+
+/// Parsers for CSS-style color literals.
+pub mod color;
+
+/// Parsers for 5 and 6 field cron schedules.
+pub mod cron;
+
+/// Parsers for human-readable durations like `1h30m`.
+pub mod duration;
+
+/// Parsers for the request line, status line, and header fields of HTTP/1.x messages.
+pub mod http;
+
+/// Parsers for Ethernet, IPv4, UDP, and TCP headers.
+pub mod net;
+
+/// Parsers for numbers with digit separators and locale-specific grouping.
+pub mod numbers;
+
+/// Parsers for the protobuf wire format, without needing a schema.
+pub mod protobuf;
+
+/// Parsers for templates with `${name}` placeholders.
+pub mod template;
+
+/// Parsers for a pragmatic subset of YAML.
+#[cfg(feature = "yaml")]
+pub mod yaml;
+
+/// Consume characters while they match `predicate`, requiring at least `min` of them. `expected` describes the
+/// characters for errors.
+pub(crate) fn take_while(
+    parser_state: ParserState,
+    min: usize,
+    expected: &str,
+    predicate: impl Fn(char) -> bool,
+) -> ParseResult<ParseError, String> {
+    // Find the first character that doesn't match.
+    let (length, count) = parser_state
+        .input
+        .char_indices()
+        .enumerate()
+        .find(|(_, (_, c))| !predicate(*c))
+        .map_or_else(
+            || (parser_state.input.len(), parser_state.input.chars().count()),
+            |(count, (length, _))| (length, count),
+        );
+
+    let new_state = parser_state.advance(length);
+    if count < min {
+        // Report the error at the character that stopped the match.
+        Err(ParseError::expected_at(&new_state, expected))
+    } else {
+        Ok((String::from(&parser_state.input[..length]), new_state))
+    }
+}
+
+/// Consume exactly the text `literal`.
+pub(crate) fn tag(parser_state: ParserState, literal: &str) -> ParseResult<ParseError, ()> {
+    if parser_state.input.starts_with(literal) {
+        Ok(((), parser_state.advance(literal.len())))
+    } else {
+        Err(ParseError::expected_at(&parser_state, literal))
+    }
+}
+
+/// Parse an unsigned decimal number with an optional fractional part, such as `42` or `0.5`.
+pub(crate) fn decimal(parser_state: ParserState) -> ParseResult<ParseError, f64> {
+    let (whole, state) = take_while(parser_state.clone(), 0, "digit", |c| c.is_ascii_digit())?;
+    let (fraction, state) = match tag(state.clone(), ".") {
+        Ok(((), fraction_state)) => take_while(fraction_state, 1, "digit", |c| c.is_ascii_digit())?,
+        Err(_) => (String::new(), state),
+    };
+
+    if whole.is_empty() && fraction.is_empty() {
+        Err(ParseError::expected_at(&parser_state, "number"))
+    } else {
+        let number = format!("{}.{}", whole, fraction);
+        Ok((number.trim_end_matches('.').parse().unwrap_or(0.0), state))
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{ParseError, ParseResult, Parser, ParserState};
+
+/// The quoting rules used to split a command string.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ArgumentStyle {
+    /// Rules like a POSIX shell: single quotes are literal, double quotes allow escaping `\`, `"`, `$`, and `` ` ``,
+    /// and a backslash outside quotes escapes any character. No expansion is performed.
+    Posix,
+
+    /// Rules like `CommandLineToArgvW` on Windows: backslashes are only special before a double quote, and `""`
+    /// inside quotes is a literal quote. Every argument, including the first, is split the same way.
+    Windows,
+}
+
+/// Parses a whole command string into its arguments.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ParseArguments {
+    style: ArgumentStyle,
+}
+
+impl ParseArguments {
+    pub fn new(style: ArgumentStyle) -> Self {
+        Self { style }
+    }
+}
+
+impl Parser<Vec<String>, ParseError> for ParseArguments {
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, Vec<String>> {
+        let arguments = match self.style {
+            ArgumentStyle::Posix => split_posix(&parser_state)?,
+            ArgumentStyle::Windows => split_windows(&parser_state.input),
+        };
+
+        Ok((arguments, parser_state.advance(parser_state.input.len())))
+    }
+}
+
+/// Split an input with POSIX shell rules.
+fn split_posix(parser_state: &ParserState) -> Result<Vec<String>, ParseError> {
+    let mut arguments = Vec::new();
+    let mut current = None::<String>;
+    let mut chars = parser_state.input.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            ' ' | '\t' | '\n' => arguments.extend(current.take()),
+
+            '\\' => match chars.next() {
+                // An escaped line ending joins the lines.
+                Some((_, '\n')) => {}
+                Some((_, escaped)) => current.get_or_insert_with(String::new).push(escaped),
+                None => {
+                    return Err(ParseError::expected_at(
+                        &parser_state.advance(parser_state.input.len()),
+                        "escaped character",
+                    ))
+                }
+            },
+
+            '\'' => {
+                let argument = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some((_, '\'')) => break,
+                        Some((_, quoted)) => argument.push(quoted),
+                        None => return Err(unterminated(parser_state, index, '\'')),
+                    }
+                }
+            }
+
+            '"' => {
+                let argument = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.peek() {
+                            Some((_, '\n')) => {
+                                chars.next();
+                            }
+                            Some((_, escaped @ ('\\' | '"' | '$' | '`'))) => {
+                                argument.push(*escaped);
+                                chars.next();
+                            }
+                            _ => argument.push('\\'),
+                        },
+                        Some((_, quoted)) => argument.push(quoted),
+                        None => return Err(unterminated(parser_state, index, '"')),
+                    }
+                }
+            }
+
+            _ => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    arguments.extend(current);
+    Ok(arguments)
+}
+
+/// Create an error for a quote starting at `index` that never ends.
+fn unterminated(parser_state: &ParserState, index: usize, quote: char) -> ParseError {
+    let end = parser_state.advance(parser_state.input.len());
+    ParseError::expected_at(&end, quote.to_string()).with_note(&format!(
+        "the quoted argument starts at offset {}",
+        parser_state.index + index
+    ))
+}
+
+/// Split an input with Windows rules. Every input is valid, so this can't fail.
+fn split_windows(input: &str) -> Vec<String> {
+    let mut arguments = Vec::new();
+    let mut current = None::<String>;
+    let mut in_quotes = false;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' if !in_quotes => arguments.extend(current.take()),
+
+            '\\' => {
+                // Count the run of backslashes, which are only special before
+                // a quote.
+                let mut backslashes = 1;
+                while chars.peek() == Some(&'\\') {
+                    chars.next();
+                    backslashes += 1;
+                }
+
+                let argument = current.get_or_insert_with(String::new);
+                if chars.peek() == Some(&'"') {
+                    argument.push_str(&"\\".repeat(backslashes / 2));
+                    if backslashes % 2 == 1 {
+                        argument.push('"');
+                        chars.next();
+                    }
+                } else {
+                    argument.push_str(&"\\".repeat(backslashes));
+                }
+            }
+
+            '"' => {
+                let argument = current.get_or_insert_with(String::new);
+                if in_quotes && chars.peek() == Some(&'"') {
+                    argument.push('"');
+                    chars.next();
+                } else {
+                    in_quotes = !in_quotes;
+                }
+            }
+
+            _ => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    arguments.extend(current);
+    arguments
+}
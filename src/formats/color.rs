@@ -0,0 +1,236 @@
+use crate::formats::{decimal, tag, take_while};
+use crate::{ParseError, ParseResult, Parser, ParserState};
+
+/// A color with 8-bit red, green, blue, and alpha channels.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Rgba {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+
+    /// The opacity of the color, where 255 is fully opaque.
+    pub a: u8,
+}
+
+impl Rgba {
+    /// Create a fully opaque color.
+    pub fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 255 }
+    }
+
+    /// Create a color with the given opacity.
+    pub fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Create a color from a hue in degrees and saturation, lightness, and alpha between 0 and 1.
+    pub fn hsla(hue: f64, saturation: f64, lightness: f64, alpha: f64) -> Self {
+        // Convert using the algorithm from the CSS color specification.
+        let hue = hue.rem_euclid(360.0) / 30.0;
+        let amount = saturation * lightness.min(1.0 - lightness);
+        let channel = |n: f64| {
+            let k = (n + hue) % 12.0;
+            let value = lightness - amount * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0);
+            to_channel(value)
+        };
+
+        Self::new(channel(0.0), channel(8.0), channel(4.0), to_channel(alpha))
+    }
+}
+
+/// Convert a value between 0 and 1 into an 8-bit channel.
+fn to_channel(value: f64) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Parses a CSS-style color literal: `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb(..)`, `rgba(..)`, `hsl(..)`, or
+/// `hsla(..)`. Function names and hex digits are case-insensitive.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct ParseColor;
+
+impl Parser<Rgba, ParseError> for ParseColor {
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, Rgba> {
+        if parser_state.input.starts_with('#') {
+            return parse_hex(parser_state);
+        }
+
+        let (name, state) = take_while(parser_state.clone(), 1, "color", |c| {
+            c.is_ascii_alphabetic()
+        })?;
+        let is_hsl = match name.to_ascii_lowercase().as_str() {
+            "rgb" | "rgba" => false,
+            "hsl" | "hsla" => true,
+            _ => return Err(ParseError::unexpected(
+                parser_state.index,
+                ["#", "rgb", "hsl"],
+                Some(&name),
+            )
+            .with_help(
+                "colors are written like `#ff8800`, `rgb(255, 136, 0)`, or `hsl(32, 100%, 50%)`",
+            )),
+        };
+        let ((), state) = tag(state, "(")?;
+
+        // Read the three color arguments and the optional alpha.
+        let mut arguments = Vec::new();
+        let mut state = state;
+        for index in 0..4 {
+            let (_, argument_state) = whitespace(state)?;
+            let argument_state = if index == 0 {
+                argument_state
+            } else if index == 3 {
+                match tag(argument_state.clone(), ",") {
+                    Ok(((), comma_state)) => comma_state,
+                    Err(_) => {
+                        state = argument_state;
+                        break;
+                    }
+                }
+            } else {
+                tag(argument_state, ",")?.1
+            };
+            let (_, argument_state) = whitespace(argument_state)?;
+
+            let (argument, new_state) = argument(argument_state)?;
+            arguments.push(argument);
+            state = new_state;
+        }
+        let (_, state) = whitespace(state)?;
+        let ((), state) = tag(state, ")")?;
+
+        let alpha = arguments.get(3).map_or(Ok(1.0), |a| a.unit(1.0, "alpha"))?;
+        let color = if is_hsl {
+            Rgba::hsla(
+                arguments[0].degrees()?,
+                arguments[1].percentage("saturation")?,
+                arguments[2].percentage("lightness")?,
+                alpha,
+            )
+        } else {
+            Rgba::new(
+                to_channel(arguments[0].unit(255.0, "red")?),
+                to_channel(arguments[1].unit(255.0, "green")?),
+                to_channel(arguments[2].unit(255.0, "blue")?),
+                to_channel(alpha),
+            )
+        };
+
+        Ok((color, state))
+    }
+}
+
+/// Parse a `#` followed by three, four, six, or eight hex digits.
+fn parse_hex(parser_state: ParserState) -> ParseResult<ParseError, Rgba> {
+    let ((), state) = tag(parser_state.clone(), "#")?;
+    let (digits, new_state) = take_while(state, 1, "hex digit", |c| c.is_ascii_hexdigit())?;
+    let digits = digits
+        .chars()
+        .map(|c| c.to_digit(16).unwrap() as u8)
+        .collect::<Vec<u8>>();
+
+    let channels = match digits.len() {
+        // Short forms repeat each digit.
+        3 | 4 => digits.iter().map(|d| d * 17).collect::<Vec<u8>>(),
+        6 | 8 => digits.chunks(2).map(|d| d[0] * 16 + d[1]).collect(),
+        _ => {
+            return Err(ParseError::invalid(
+                parser_state.index,
+                new_state.index,
+                "hex colors must have 3, 4, 6, or 8 digits",
+            ))
+        }
+    };
+
+    Ok((
+        Rgba::new(
+            channels[0],
+            channels[1],
+            channels[2],
+            channels.get(3).copied().unwrap_or(255),
+        ),
+        new_state,
+    ))
+}
+
+/// Skip optional whitespace.
+fn whitespace(parser_state: ParserState) -> ParseResult<ParseError, String> {
+    take_while(parser_state, 0, "whitespace", char::is_whitespace)
+}
+
+/// A numeric argument to a color function, remembering where it was for errors.
+struct Argument {
+    value: f64,
+    is_percentage: bool,
+    start: usize,
+    end: usize,
+}
+
+impl Argument {
+    /// Get the argument as a fraction of 1, where plain numbers are out of `max`.
+    fn unit(&self, max: f64, name: &str) -> Result<f64, ParseError> {
+        if self.is_percentage {
+            self.in_range(self.value / 100.0, name)
+        } else {
+            self.in_range(self.value / max, name)
+        }
+    }
+
+    /// Get the argument as a fraction of 1, requiring it to be a percentage.
+    fn percentage(&self, name: &str) -> Result<f64, ParseError> {
+        if self.is_percentage {
+            self.in_range(self.value / 100.0, name)
+        } else {
+            Err(ParseError::invalid(
+                self.start,
+                self.end,
+                &format!("{} must be a percentage", name),
+            ))
+        }
+    }
+
+    /// Get the argument as an angle in degrees, requiring it not to be a percentage.
+    fn degrees(&self) -> Result<f64, ParseError> {
+        if self.is_percentage {
+            Err(ParseError::invalid(
+                self.start,
+                self.end,
+                "hue must be a number of degrees",
+            ))
+        } else {
+            Ok(self.value)
+        }
+    }
+
+    /// Require a fraction to be between 0 and 1.
+    fn in_range(&self, value: f64, name: &str) -> Result<f64, ParseError> {
+        if (0.0..=1.0).contains(&value) {
+            Ok(value)
+        } else {
+            Err(ParseError::invalid(
+                self.start,
+                self.end,
+                &format!("{} is out of range", name),
+            ))
+        }
+    }
+}
+
+/// Parse a number followed by an optional percent sign.
+fn argument(parser_state: ParserState) -> ParseResult<ParseError, Argument> {
+    let start = parser_state.index;
+    let (value, state) = decimal(parser_state)?;
+    let (is_percentage, state) = match tag(state.clone(), "%") {
+        Ok(((), percent_state)) => (true, percent_state),
+        Err(_) => (false, state),
+    };
+
+    Ok((
+        Argument {
+            value,
+            is_percentage,
+            start,
+            end: state.index,
+        },
+        state,
+    ))
+}
@@ -0,0 +1,259 @@
+use crate::formats::{tag, take_while};
+use crate::{ParseError, ParseResult, Parser, ParserState};
+use std::collections::BTreeSet;
+
+/// A parsed cron schedule, holding the set of values each field matches.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Schedule {
+    /// The seconds to run at, only present for 6 field schedules.
+    pub seconds: Option<BTreeSet<u8>>,
+    pub minutes: BTreeSet<u8>,
+    pub hours: BTreeSet<u8>,
+    pub days_of_month: BTreeSet<u8>,
+    pub months: BTreeSet<u8>,
+
+    /// The days of the week to run on, where both 0 and 7 are written as 0 (Sunday).
+    pub days_of_week: BTreeSet<u8>,
+}
+
+/// The valid values for one field of a schedule.
+struct FieldSpec {
+    name: &'static str,
+    min: u8,
+    max: u8,
+
+    /// Names for the values starting at `min`.
+    names: &'static [&'static str],
+}
+
+const SECONDS: FieldSpec = FieldSpec {
+    name: "second",
+    min: 0,
+    max: 59,
+    names: &[],
+};
+const MINUTES: FieldSpec = FieldSpec {
+    name: "minute",
+    min: 0,
+    max: 59,
+    names: &[],
+};
+const HOURS: FieldSpec = FieldSpec {
+    name: "hour",
+    min: 0,
+    max: 23,
+    names: &[],
+};
+const DAYS_OF_MONTH: FieldSpec = FieldSpec {
+    name: "day of month",
+    min: 1,
+    max: 31,
+    names: &[],
+};
+const MONTHS: FieldSpec = FieldSpec {
+    name: "month",
+    min: 1,
+    max: 12,
+    names: &[
+        "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+    ],
+};
+const DAYS_OF_WEEK: FieldSpec = FieldSpec {
+    name: "day of week",
+    min: 0,
+    max: 7,
+    names: &["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"],
+};
+
+/// Parses a cron schedule of 5 fields (minute, hour, day of month, month, day of week) or 6 fields (with a leading
+/// second). Each field may be `*`, `?`, a value, a range like `1-5`, any of those with a step like `*/15`, or a
+/// comma-separated list of them. Months and days of the week may also be written as names like `JAN` or `MON`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct ParseCron;
+
+impl Parser<Schedule, ParseError> for ParseCron {
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, Schedule> {
+        // Split the fields on whitespace first, so that the number of fields
+        // decides which field is which.
+        let mut fields = Vec::new();
+        let (_, mut state) = take_while(parser_state.clone(), 0, "whitespace", is_blank)?;
+        while !state.input.is_empty() && !state.input.starts_with('\n') {
+            let start = state.index;
+            let (field, field_state) = take_while(state, 1, "field", |c| !c.is_whitespace())?;
+            fields.push((field, start));
+            state = take_while(field_state, 0, "whitespace", is_blank)?.1;
+        }
+
+        let specs: &[FieldSpec] = match fields.len() {
+            5 => &[MINUTES, HOURS, DAYS_OF_MONTH, MONTHS, DAYS_OF_WEEK],
+            6 => &[SECONDS, MINUTES, HOURS, DAYS_OF_MONTH, MONTHS, DAYS_OF_WEEK],
+            found => {
+                return Err(
+                    ParseError::wrong_count(parser_state.index, 5, 6, found).with_note(
+                        "the fields are [second] minute hour day-of-month month day-of-week",
+                    ),
+                )
+            }
+        };
+
+        let mut values = fields
+            .iter()
+            .zip(specs)
+            .map(|((field, start), spec)| parse_field(field, *start, spec))
+            .collect::<Result<Vec<BTreeSet<u8>>, ParseError>>()?;
+
+        // Sunday may be written as either 0 or 7.
+        let mut days_of_week = values.pop().unwrap();
+        if days_of_week.remove(&7) {
+            days_of_week.insert(0);
+        }
+        let months = values.pop().unwrap();
+        let days_of_month = values.pop().unwrap();
+        let hours = values.pop().unwrap();
+        let minutes = values.pop().unwrap();
+
+        Ok((
+            Schedule {
+                seconds: values.pop(),
+                minutes,
+                hours,
+                days_of_month,
+                months,
+                days_of_week,
+            },
+            state,
+        ))
+    }
+}
+
+/// Check whether a character separates fields on a line.
+fn is_blank(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+/// Parse the whole text of one field, starting at `start` in the original input.
+fn parse_field(field: &str, start: usize, spec: &FieldSpec) -> Result<BTreeSet<u8>, ParseError> {
+    let mut values = BTreeSet::new();
+    let mut state = ParserState::new_offset(field, start);
+
+    loop {
+        let ((), new_state) = parse_item(state, spec, &mut values)?;
+        match tag(new_state.clone(), ",") {
+            Ok(((), next_state)) => state = next_state,
+            Err(_) if new_state.input.is_empty() => return Ok(values),
+            Err(_) => {
+                return Err(ParseError::expected_at(&new_state, ",").with_help(&format!(
+                    "{} fields are written like `1,5-10,*/15`",
+                    spec.name
+                )))
+            }
+        }
+    }
+}
+
+/// Parse a wildcard, value, or range with an optional step, adding the values it matches to `values`.
+fn parse_item(
+    parser_state: ParserState,
+    spec: &FieldSpec,
+    values: &mut BTreeSet<u8>,
+) -> ParseResult<ParseError, ()> {
+    let start = parser_state.index;
+
+    // Find the range of values the item covers.
+    let (first, last, is_range, state) =
+        match tag(parser_state.clone(), "*").or_else(|_| tag(parser_state.clone(), "?")) {
+            Ok(((), state)) => (spec.min, spec.max, true, state),
+            Err(_) => {
+                let (first, state) = parse_value(parser_state, spec)?;
+                match tag(state.clone(), "-") {
+                    Ok(((), range_state)) => {
+                        let (last, state) = parse_value(range_state, spec)?;
+                        if first > last {
+                            return Err(ParseError::invalid(
+                                start,
+                                state.index,
+                                &format!("{} range starts after it ends", spec.name),
+                            ));
+                        }
+                        (first, last, true, state)
+                    }
+                    Err(_) => (first, first, false, state),
+                }
+            }
+        };
+
+    // A step on a single value counts from that value to the end.
+    let (step, state) = match tag(state.clone(), "/") {
+        Ok(((), step_state)) => {
+            let step_start = step_state.index;
+            let (digits, state) = take_while(step_state, 1, "step", |c| c.is_ascii_digit())?;
+            match digits.parse::<u8>() {
+                Ok(step) if step > 0 => (step, state),
+                _ => {
+                    return Err(ParseError::invalid(
+                        step_start,
+                        state.index,
+                        &format!("{} step must be a number from 1 to 255", spec.name),
+                    ))
+                }
+            }
+        }
+        Err(_) => (1, state),
+    };
+    let last = if !is_range && step > 1 {
+        spec.max
+    } else {
+        last
+    };
+
+    values.extend((first..=last).step_by(step as usize));
+    Ok(((), state))
+}
+
+/// Parse a number or name within the field's range.
+fn parse_value(parser_state: ParserState, spec: &FieldSpec) -> ParseResult<ParseError, u8> {
+    let start = parser_state.index;
+
+    if let Ok((digits, state)) =
+        take_while(parser_state.clone(), 1, "value", |c| c.is_ascii_digit())
+    {
+        return match digits.parse::<u8>() {
+            Ok(value) if (spec.min..=spec.max).contains(&value) => Ok((value, state)),
+            _ => Err(ParseError::invalid(
+                start,
+                state.index,
+                &format!(
+                    "{} must be between {} and {}",
+                    spec.name, spec.min, spec.max
+                ),
+            )),
+        };
+    }
+
+    let (name, state) = take_while(parser_state, 1, spec.name, |c| c.is_ascii_alphabetic())
+        .map_err(|e| {
+            if spec.names.is_empty() {
+                e
+            } else {
+                e.with_help(&format!(
+                    "{}s may be numbers or names like `{}`",
+                    spec.name, spec.names[1]
+                ))
+            }
+        })?;
+    match spec
+        .names
+        .iter()
+        .position(|n| n.eq_ignore_ascii_case(&name))
+    {
+        Some(index) => Ok((spec.min + index as u8, state)),
+        None if spec.names.is_empty() => {
+            Err(ParseError::unexpected(start, ["number"], Some(&name)))
+        }
+        None => Err(ParseError::unexpected(
+            start,
+            spec.names.iter().copied(),
+            Some(&name),
+        )),
+    }
+}
@@ -0,0 +1,130 @@
+use crate::formats::{tag, take_while};
+use crate::{ParseError, ParseResult, Parser, ParserState};
+use std::convert::TryFrom;
+use std::time::Duration;
+
+/// The units that may follow each number in a duration.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct DurationUnits {
+    units: Vec<(String, Duration)>,
+}
+
+impl DurationUnits {
+    /// Create a table with no units.
+    pub fn new() -> Self {
+        Self { units: Vec::new() }
+    }
+
+    /// Add a unit named `name` that's worth `length` per whole number.
+    pub fn unit(mut self, name: &str, length: Duration) -> Self {
+        self.units.push((String::from(name), length));
+        self
+    }
+
+    /// Find the longest unit name at the start of `input`.
+    fn find(&self, input: &str) -> Option<&(String, Duration)> {
+        self.units
+            .iter()
+            .filter(|(name, _)| input.starts_with(name.as_str()))
+            .max_by_key(|(name, _)| name.len())
+    }
+}
+
+impl Default for DurationUnits {
+    /// The common units: `ns`, `us`, `µs`, `ms`, `s`, `m`, `h`, `d`, and `w`.
+    fn default() -> Self {
+        Self::new()
+            .unit("ns", Duration::from_nanos(1))
+            .unit("us", Duration::from_micros(1))
+            .unit("µs", Duration::from_micros(1))
+            .unit("ms", Duration::from_millis(1))
+            .unit("s", Duration::from_secs(1))
+            .unit("m", Duration::from_secs(60))
+            .unit("h", Duration::from_secs(60 * 60))
+            .unit("d", Duration::from_secs(24 * 60 * 60))
+            .unit("w", Duration::from_secs(7 * 24 * 60 * 60))
+    }
+}
+
+/// Parses a duration made of one or more numbers that are each followed by a unit, such as `1h30m`, `2d4h`, or
+/// `1.5s`. Numbers may have a fractional part.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Default)]
+pub struct ParseDuration {
+    units: DurationUnits,
+}
+
+impl ParseDuration {
+    pub fn new(units: DurationUnits) -> Self {
+        Self { units }
+    }
+}
+
+impl Parser<Duration, ParseError> for ParseDuration {
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, Duration> {
+        let mut total: u128 = 0;
+        let mut state = parser_state.clone();
+
+        loop {
+            let (whole, number_state) =
+                take_while(state.clone(), 1, "number", |c| c.is_ascii_digit())?;
+            let (fraction, number_state) = match tag(number_state.clone(), ".") {
+                Ok(((), fraction_state)) => {
+                    take_while(fraction_state, 1, "digit", |c| c.is_ascii_digit())?
+                }
+                Err(_) => (String::new(), number_state),
+            };
+
+            let (name, length) = self.units.find(&number_state.input).ok_or_else(|| {
+                ParseError::unexpected(
+                    number_state.index,
+                    self.units.units.iter().map(|(name, _)| name.as_str()),
+                    number_state
+                        .input
+                        .chars()
+                        .next()
+                        .map(String::from)
+                        .as_deref(),
+                )
+                .with_help("every number in a duration needs a unit, like `30s`")
+            })?;
+            let new_state = number_state.advance(name.len());
+
+            // Add up in nanoseconds, rounding off any fraction of a
+            // nanosecond.
+            let nanos = length.as_nanos();
+            let whole_nanos = whole
+                .parse::<u128>()
+                .ok()
+                .and_then(|w| w.checked_mul(nanos));
+            let fraction_nanos =
+                format!("0.{}", fraction).parse::<f64>().unwrap_or(0.0) * nanos as f64;
+            total = match whole_nanos
+                .and_then(|w| w.checked_add(total))
+                .and_then(|sum| sum.checked_add(fraction_nanos.round() as u128))
+            {
+                Some(sum) => sum,
+                None => {
+                    return Err(ParseError::invalid(
+                        parser_state.index,
+                        new_state.index,
+                        "duration is too long",
+                    ))
+                }
+            };
+            state = new_state;
+
+            // Keep going while another number follows.
+            if !state.input.starts_with(|c: char| c.is_ascii_digit()) {
+                break;
+            }
+        }
+
+        let seconds = u64::try_from(total / 1_000_000_000).map_err(|_| {
+            ParseError::invalid(parser_state.index, state.index, "duration is too long")
+        })?;
+        Ok((
+            Duration::new(seconds, (total % 1_000_000_000) as u32),
+            state,
+        ))
+    }
+}
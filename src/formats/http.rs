@@ -0,0 +1,286 @@
+use crate::formats::{tag, take_while};
+use crate::{ParseError, ParseResult, Parser, ParserState};
+
+/// The version of HTTP used by a message, such as `HTTP/1.1`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct HttpVersion {
+    /// The major version number.
+    pub major: u8,
+
+    /// The minor version number.
+    pub minor: u8,
+}
+
+/// The first line of an HTTP request, such as `GET /index.html HTTP/1.1`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct RequestLine {
+    /// The request method, such as `GET`.
+    pub method: String,
+
+    /// The request target, such as `/index.html`.
+    pub target: String,
+
+    /// The HTTP version of the request.
+    pub version: HttpVersion,
+}
+
+/// The first line of an HTTP response, such as `HTTP/1.1 404 Not Found`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct StatusLine {
+    /// The HTTP version of the response.
+    pub version: HttpVersion,
+
+    /// The three-digit status code.
+    pub status: u16,
+
+    /// The reason phrase, which may be empty.
+    pub reason: String,
+}
+
+/// A single header field, such as `Content-Length: 42`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Header {
+    /// The name of the field, as it appeared in the message.
+    pub name: String,
+
+    /// The value of the field, with surrounding whitespace removed and any folded lines joined by a single space.
+    pub value: String,
+}
+
+/// Check whether a character may appear in a token (RFC 7230 `tchar`).
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+}
+
+/// Check whether a character is visible (`VCHAR`) or is any non-ASCII character (`obs-text`).
+fn is_visible_char(c: char) -> bool {
+    c > ' ' && c != '\x7f'
+}
+
+/// Check whether a character is optional whitespace (`OWS`).
+fn is_whitespace(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+/// Parse a line ending. Both `\r\n` and a bare `\n` are accepted, as recommended for recipients.
+fn line_ending(parser_state: ParserState) -> ParseResult<ParseError, ()> {
+    tag(parser_state.clone(), "\r\n").or_else(|e| tag(parser_state, "\n").map_err(|_| e))
+}
+
+/// Parse a single digit as a number.
+fn digit(parser_state: ParserState) -> ParseResult<ParseError, u8> {
+    match parser_state.char(0) {
+        Some((new_state, c)) if c.is_ascii_digit() => Ok((c as u8 - b'0', new_state)),
+        _ => Err(ParseError::expected_at(&parser_state, "digit")),
+    }
+}
+
+/// Parses a token, such as a method or header name.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct ParseToken;
+
+impl Parser<String, ParseError> for ParseToken {
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, String> {
+        take_while(parser_state, 1, "token", is_token_char)
+    }
+}
+
+/// Parses a quoted string, such as `"a \"quoted\" value"`, returning its contents with escapes removed.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct ParseQuotedString;
+
+impl Parser<String, ParseError> for ParseQuotedString {
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, String> {
+        let ((), mut state) = tag(parser_state, "\"")?;
+        let mut output = String::new();
+
+        loop {
+            match state.char(0) {
+                // The closing quote ends the string.
+                Some((new_state, '"')) => return Ok((output, new_state)),
+
+                // A backslash escapes the next character.
+                Some((escape_state, '\\')) => match escape_state.char(0) {
+                    Some((new_state, c)) if is_whitespace(c) || is_visible_char(c) => {
+                        output.push(c);
+                        state = new_state;
+                    }
+                    _ => return Err(ParseError::expected_at(&escape_state, "escaped character")),
+                },
+
+                // Anything else that's printable is part of the string.
+                Some((new_state, c)) if is_whitespace(c) || is_visible_char(c) => {
+                    output.push(c);
+                    state = new_state;
+                }
+
+                _ => return Err(ParseError::expected_at(&state, "\"")),
+            }
+        }
+    }
+}
+
+/// Parses an HTTP version, such as `HTTP/1.1`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct ParseHttpVersion;
+
+impl Parser<HttpVersion, ParseError> for ParseHttpVersion {
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, HttpVersion> {
+        let ((), state) = tag(parser_state, "HTTP/")?;
+        let (major, state) = digit(state)?;
+        let ((), state) = tag(state, ".")?;
+        let (minor, state) = digit(state)?;
+        Ok((HttpVersion { major, minor }, state))
+    }
+}
+
+/// Parses a request line, including its line ending.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct ParseRequestLine;
+
+impl Parser<RequestLine, ParseError> for ParseRequestLine {
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, RequestLine> {
+        let (method, state) = ParseToken.parse(parser_state)?;
+        let ((), state) = tag(state, " ")?;
+        let (target, state) = take_while(state, 1, "request target", is_visible_char)?;
+        let ((), state) = tag(state, " ")?;
+        let (version, state) = ParseHttpVersion.parse(state)?;
+        let ((), state) = line_ending(state)?;
+
+        Ok((
+            RequestLine {
+                method,
+                target,
+                version,
+            },
+            state,
+        ))
+    }
+}
+
+/// Parses a status line, including its line ending.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct ParseStatusLine;
+
+impl Parser<StatusLine, ParseError> for ParseStatusLine {
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, StatusLine> {
+        let (version, state) = ParseHttpVersion.parse(parser_state)?;
+        let ((), state) = tag(state, " ")?;
+
+        // The status code is exactly three digits.
+        let mut status = 0;
+        let mut state = state;
+        for _ in 0..3 {
+            let (digit, new_state) = digit(state)?;
+            status = status * 10 + u16::from(digit);
+            state = new_state;
+        }
+
+        let ((), state) = tag(state, " ")?;
+        let (reason, state) = take_while(state, 0, "reason phrase", |c| {
+            is_whitespace(c) || is_visible_char(c)
+        })?;
+        let ((), state) = line_ending(state)?;
+
+        Ok((
+            StatusLine {
+                version,
+                status,
+                reason,
+            },
+            state,
+        ))
+    }
+}
+
+/// Parses a header field, including its line ending. Obsolete line folding (a line ending followed by whitespace)
+/// is replaced with a single space.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct ParseHeader;
+
+impl Parser<Header, ParseError> for ParseHeader {
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, Header> {
+        let (name, state) = ParseToken.parse(parser_state)?;
+        let ((), state) = tag(state, ":")?;
+        let (_, mut state) = take_while(state, 0, "whitespace", is_whitespace)?;
+        let mut value = String::new();
+
+        loop {
+            // Take the rest of the line.
+            let (content, new_state) = take_while(state, 0, "field value", |c| {
+                is_whitespace(c) || is_visible_char(c)
+            })?;
+            value.push_str(&content);
+            let ((), line_state) = line_ending(new_state)?;
+
+            // If the next line starts with whitespace, it continues this
+            // value.
+            match take_while(line_state.clone(), 1, "whitespace", is_whitespace) {
+                Ok((_, folded_state)) => {
+                    value.truncate(value.trim_end_matches(is_whitespace).len());
+                    value.push(' ');
+                    state = folded_state;
+                }
+                Err(_) => {
+                    value.truncate(value.trim_end_matches(is_whitespace).len());
+                    return Ok((Header { name, value }, line_state));
+                }
+            }
+        }
+    }
+}
+
+/// Parses a block of header fields ending with an empty line.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct ParseHeaders;
+
+impl Parser<Vec<Header>, ParseError> for ParseHeaders {
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, Vec<Header>> {
+        let mut headers = Vec::new();
+        let mut state = parser_state;
+
+        // Keep parsing headers until the empty line is reached.
+        loop {
+            match line_ending(state.clone()) {
+                Ok(((), new_state)) => return Ok((headers, new_state)),
+                Err(_) => {
+                    let (header, new_state) = ParseHeader.parse(state)?;
+                    headers.push(header);
+                    state = new_state;
+                }
+            }
+        }
+    }
+}
+
+/// Parses the parameters that follow some header values, such as `; charset="utf-8"; q=0.5`, returning each name
+/// and value.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct ParseParameters;
+
+impl Parser<Vec<(String, String)>, ParseError> for ParseParameters {
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, Vec<(String, String)>> {
+        let mut parameters = Vec::new();
+        let mut state = parser_state;
+
+        loop {
+            // Each parameter starts with a semicolon.
+            let (_, separator_state) = take_while(state.clone(), 0, "whitespace", is_whitespace)?;
+            let ((), parameter_state) = match tag(separator_state, ";") {
+                Ok(result) => result,
+                Err(_) => return Ok((parameters, state)),
+            };
+            let (_, parameter_state) = take_while(parameter_state, 0, "whitespace", is_whitespace)?;
+
+            // The value is either a token or a quoted string.
+            let (name, value_state) = ParseToken.parse(parameter_state)?;
+            let ((), value_state) = tag(value_state, "=")?;
+            let (value, new_state) = ParseToken
+                .parse(value_state.clone())
+                .or_else(|_| ParseQuotedString.parse(value_state))?;
+
+            parameters.push((name, value));
+            state = new_state;
+        }
+    }
+}
@@ -0,0 +1,342 @@
+use crate::bytes::{be_u16, be_u32, u8, ByteParser, ByteResult, ByteState, ParseTake};
+use crate::ParseError;
+use std::net::Ipv4Addr;
+
+/// The EtherType of an 802.1Q VLAN tag.
+const VLAN_ETHER_TYPE: u16 = 0x8100;
+
+/// The header of an Ethernet II frame.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct EthernetHeader {
+    pub destination: [u8; 6],
+    pub source: [u8; 6],
+
+    /// The tag control information of an 802.1Q VLAN tag, if the frame has one.
+    pub vlan: Option<u16>,
+
+    /// The protocol of the payload, such as `0x0800` for IPv4.
+    pub ether_type: u16,
+}
+
+/// The header of an IPv4 packet.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Ipv4Header<'a> {
+    /// The length of the header in 32-bit words.
+    pub header_length: u8,
+    pub dscp: u8,
+    pub ecn: u8,
+    pub total_length: u16,
+    pub identification: u16,
+    pub dont_fragment: bool,
+    pub more_fragments: bool,
+
+    /// The offset of this fragment in 8-byte units.
+    pub fragment_offset: u16,
+    pub ttl: u8,
+
+    /// The protocol of the payload, such as 6 for TCP or 17 for UDP.
+    pub protocol: u8,
+    pub checksum: u16,
+
+    /// Whether the checksum matches the rest of the header.
+    pub checksum_valid: bool,
+    pub source: Ipv4Addr,
+    pub destination: Ipv4Addr,
+
+    /// The raw bytes of any options.
+    pub options: &'a [u8],
+}
+
+/// The header of a UDP datagram.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct UdpHeader {
+    pub source_port: u16,
+    pub destination_port: u16,
+    pub length: u16,
+
+    /// The checksum, which covers a pseudo-header from the IP layer and so can't be checked here.
+    pub checksum: u16,
+}
+
+/// An option in a TCP header.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum TcpOption<'a> {
+    MaximumSegmentSize(u16),
+    WindowScale(u8),
+    SackPermitted,
+    Timestamps(u32, u32),
+
+    /// Any other option, with the bytes after its kind and length.
+    Other {
+        kind: u8,
+        data: &'a [u8],
+    },
+}
+
+/// The header of a TCP segment.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct TcpHeader<'a> {
+    pub source_port: u16,
+    pub destination_port: u16,
+    pub sequence: u32,
+    pub acknowledgement: u32,
+
+    /// The length of the header in 32-bit words.
+    pub data_offset: u8,
+
+    /// The control bits, which can be checked with constants like [`TcpHeader::SYN`].
+    pub flags: u16,
+    pub window: u16,
+
+    /// The checksum, which covers a pseudo-header from the IP layer and so can't be checked here.
+    pub checksum: u16,
+    pub urgent_pointer: u16,
+    pub options: Vec<TcpOption<'a>>,
+}
+
+impl TcpHeader<'_> {
+    pub const FIN: u16 = 0x001;
+    pub const SYN: u16 = 0x002;
+    pub const RST: u16 = 0x004;
+    pub const PSH: u16 = 0x008;
+    pub const ACK: u16 = 0x010;
+    pub const URG: u16 = 0x020;
+    pub const ECE: u16 = 0x040;
+    pub const CWR: u16 = 0x080;
+    pub const NS: u16 = 0x100;
+
+    /// Check whether all of the bits in `flag` are set.
+    pub fn has_flag(&self, flag: u16) -> bool {
+        self.flags & flag == flag
+    }
+}
+
+/// Compute the internet checksum (the ones' complement of the ones' complement sum of 16-bit words) of `data`. A
+/// header that includes its own correct checksum sums to zero.
+pub fn internet_checksum(data: &[u8]) -> u16 {
+    // Fold the carry back in after every word so that the sum can't
+    // overflow however long the data is.
+    let mut sum = data
+        .chunks(2)
+        .map(|word| u32::from(word[0]) << 8 | u32::from(*word.get(1).unwrap_or(&0)))
+        .fold(0u32, |sum, word| {
+            let sum = sum + word;
+            (sum & 0xffff) + (sum >> 16)
+        });
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// Read a MAC address.
+fn mac_address(byte_state: ByteState<'_>) -> ByteResult<'_, ParseError, [u8; 6]> {
+    let (bytes, new_state) = ParseTake::new(6).parse(byte_state)?;
+    let mut address = [0; 6];
+    address.copy_from_slice(bytes);
+    Ok((address, new_state))
+}
+
+/// Parses an Ethernet II header, including a single VLAN tag if there is one.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct ParseEthernet;
+
+impl<'a> ByteParser<'a, EthernetHeader, ParseError> for ParseEthernet {
+    fn parse(&self, byte_state: ByteState<'a>) -> ByteResult<'a, ParseError, EthernetHeader> {
+        let (destination, state) = mac_address(byte_state)?;
+        let (source, state) = mac_address(state)?;
+        let (ether_type, state) = be_u16().parse(state)?;
+
+        let (vlan, ether_type, state) = if ether_type == VLAN_ETHER_TYPE {
+            let (tag, state) = be_u16().parse(state)?;
+            let (ether_type, state) = be_u16().parse(state)?;
+            (Some(tag), ether_type, state)
+        } else {
+            (None, ether_type, state)
+        };
+
+        Ok((
+            EthernetHeader {
+                destination,
+                source,
+                vlan,
+                ether_type,
+            },
+            state,
+        ))
+    }
+}
+
+/// Parses an IPv4 header, including its options, and checks its checksum.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct ParseIpv4;
+
+impl<'a> ByteParser<'a, Ipv4Header<'a>, ParseError> for ParseIpv4 {
+    fn parse(&self, byte_state: ByteState<'a>) -> ByteResult<'a, ParseError, Ipv4Header<'a>> {
+        let start = byte_state.index();
+        let (version_length, _) = u8().parse(byte_state)?;
+        if version_length >> 4 != 4 {
+            return Err(ParseError::invalid(
+                start,
+                start + 1,
+                &format!("expected IP version 4 but found {}", version_length >> 4),
+            ));
+        }
+        let header_length = version_length & 0xf;
+        if header_length < 5 {
+            return Err(ParseError::invalid(
+                start,
+                start + 1,
+                "IPv4 header length must be at least 5 words",
+            ));
+        }
+
+        // Take the whole header first so that its checksum can be checked.
+        let (header, new_state) =
+            ParseTake::new(usize::from(header_length) * 4).parse(byte_state)?;
+        let state = ByteState::new_offset(header, start).take(1).unwrap().1;
+
+        let (dscp_ecn, state) = u8().parse(state)?;
+        let (total_length, state) = be_u16().parse(state)?;
+        let (identification, state) = be_u16().parse(state)?;
+        let (flags_offset, state) = be_u16().parse(state)?;
+        let (ttl, state) = u8().parse(state)?;
+        let (protocol, state) = u8().parse(state)?;
+        let (checksum, state) = be_u16().parse(state)?;
+        let (source, state) = be_u32().parse(state)?;
+        let (destination, state) = be_u32().parse(state)?;
+
+        Ok((
+            Ipv4Header {
+                header_length,
+                dscp: dscp_ecn >> 2,
+                ecn: dscp_ecn & 0x3,
+                total_length,
+                identification,
+                dont_fragment: flags_offset & 0x4000 != 0,
+                more_fragments: flags_offset & 0x2000 != 0,
+                fragment_offset: flags_offset & 0x1fff,
+                ttl,
+                protocol,
+                checksum,
+                checksum_valid: internet_checksum(header) == 0,
+                source: Ipv4Addr::from(source),
+                destination: Ipv4Addr::from(destination),
+                options: state.input(),
+            },
+            new_state,
+        ))
+    }
+}
+
+/// Parses a UDP header.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct ParseUdp;
+
+impl<'a> ByteParser<'a, UdpHeader, ParseError> for ParseUdp {
+    fn parse(&self, byte_state: ByteState<'a>) -> ByteResult<'a, ParseError, UdpHeader> {
+        let (source_port, state) = be_u16().parse(byte_state)?;
+        let (destination_port, state) = be_u16().parse(state)?;
+        let (length, state) = be_u16().parse(state)?;
+        let (checksum, state) = be_u16().parse(state)?;
+
+        Ok((
+            UdpHeader {
+                source_port,
+                destination_port,
+                length,
+                checksum,
+            },
+            state,
+        ))
+    }
+}
+
+/// Parses a TCP header, including its options.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct ParseTcp;
+
+impl<'a> ByteParser<'a, TcpHeader<'a>, ParseError> for ParseTcp {
+    fn parse(&self, byte_state: ByteState<'a>) -> ByteResult<'a, ParseError, TcpHeader<'a>> {
+        let (source_port, state) = be_u16().parse(byte_state)?;
+        let (destination_port, state) = be_u16().parse(state)?;
+        let (sequence, state) = be_u32().parse(state)?;
+        let (acknowledgement, state) = be_u32().parse(state)?;
+        let offset_index = state.index();
+        let (offset_flags, state) = be_u16().parse(state)?;
+        let (window, state) = be_u16().parse(state)?;
+        let (checksum, state) = be_u16().parse(state)?;
+        let (urgent_pointer, state) = be_u16().parse(state)?;
+
+        let data_offset = (offset_flags >> 12) as u8;
+        if data_offset < 5 {
+            return Err(ParseError::invalid(
+                offset_index,
+                offset_index + 1,
+                "TCP data offset must be at least 5 words",
+            ));
+        }
+        let (options, new_state) =
+            ParseTake::new((usize::from(data_offset) - 5) * 4).parse(state)?;
+
+        Ok((
+            TcpHeader {
+                source_port,
+                destination_port,
+                sequence,
+                acknowledgement,
+                data_offset,
+                flags: offset_flags & 0x1ff,
+                window,
+                checksum,
+                urgent_pointer,
+                options: tcp_options(ByteState::new_offset(options, state.index()))?,
+            },
+            new_state,
+        ))
+    }
+}
+
+/// Parse the options of a TCP header, stopping at the end of option list.
+fn tcp_options(byte_state: ByteState<'_>) -> Result<Vec<TcpOption<'_>>, ParseError> {
+    let mut options = Vec::new();
+    let mut state = byte_state;
+
+    while !state.input().is_empty() {
+        let start = state.index();
+        let (kind, kind_state) = u8().parse(state)?;
+        match kind {
+            0 => break,
+            1 => {
+                state = kind_state;
+                continue;
+            }
+            _ => {}
+        }
+
+        let (length, length_state) = u8().parse(kind_state)?;
+        if length < 2 {
+            return Err(ParseError::invalid(
+                start,
+                length_state.index(),
+                "TCP option length must be at least 2",
+            ));
+        }
+        let (data, new_state) = ParseTake::new(usize::from(length) - 2).parse(length_state)?;
+        let data_state = ByteState::new_offset(data, length_state.index());
+
+        options.push(match (kind, data.len()) {
+            (2, 2) => TcpOption::MaximumSegmentSize(be_u16().parse(data_state)?.0),
+            (3, 1) => TcpOption::WindowScale(data[0]),
+            (4, 0) => TcpOption::SackPermitted,
+            (8, 8) => {
+                let (value, echo_state) = be_u32().parse(data_state)?;
+                TcpOption::Timestamps(value, be_u32().parse(echo_state)?.0)
+            }
+            _ => TcpOption::Other { kind, data },
+        });
+        state = new_state;
+    }
+
+    Ok(options)
+}
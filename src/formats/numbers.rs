@@ -0,0 +1,193 @@
+use crate::formats::tag;
+use crate::{ParseError, ParseResult, Parser, ParserState};
+
+/// The separators allowed when writing a number.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct NumberFormat {
+    decimal: char,
+    grouping: Option<char>,
+    underscores: bool,
+}
+
+impl NumberFormat {
+    /// Create a format with a `.` decimal separator, no thousands grouping, and underscores allowed between digits.
+    pub fn new() -> Self {
+        Self {
+            decimal: '.',
+            grouping: None,
+            underscores: true,
+        }
+    }
+
+    /// Use `decimal` to separate the whole and fractional parts.
+    pub fn decimal(mut self, decimal: char) -> Self {
+        self.decimal = decimal;
+        self
+    }
+
+    /// Allow `grouping` to separate groups of three digits in the whole part, such as `1,234,567`.
+    pub fn grouping(mut self, grouping: char) -> Self {
+        self.grouping = Some(grouping);
+        self
+    }
+
+    /// Choose whether underscores may separate digits, such as `1_000_000`.
+    pub fn underscores(mut self, underscores: bool) -> Self {
+        self.underscores = underscores;
+        self
+    }
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parses a signed integer written in a [`NumberFormat`], stopping before any decimal separator.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ParseLocaleInt {
+    format: NumberFormat,
+}
+
+impl ParseLocaleInt {
+    pub fn new(format: NumberFormat) -> Self {
+        Self { format }
+    }
+}
+
+impl Parser<i64, ParseError> for ParseLocaleInt {
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, i64> {
+        let (number, new_state) = normalize(parser_state.clone(), &self.format, false)?;
+        match number.parse() {
+            Ok(number) => Ok((number, new_state)),
+            Err(_) => Err(ParseError::invalid(
+                parser_state.index,
+                new_state.index,
+                "integer is too large",
+            )),
+        }
+    }
+}
+
+/// Parses a signed decimal number written in a [`NumberFormat`], such as `-1.234,5` in many European locales.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ParseLocaleFloat {
+    format: NumberFormat,
+}
+
+impl ParseLocaleFloat {
+    pub fn new(format: NumberFormat) -> Self {
+        Self { format }
+    }
+}
+
+impl Parser<f64, ParseError> for ParseLocaleFloat {
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, f64> {
+        let (number, new_state) = normalize(parser_state, &self.format, true)?;
+
+        // The normalized number is always valid Rust syntax.
+        Ok((number.parse().unwrap(), new_state))
+    }
+}
+
+/// Parse a number, returning it without separators and with a `.` decimal separator.
+fn normalize(
+    parser_state: ParserState,
+    format: &NumberFormat,
+    fraction: bool,
+) -> ParseResult<ParseError, String> {
+    let mut number = String::new();
+    let mut state = parser_state;
+    if let Some(sign @ ('-' | '+')) = state.input.chars().next() {
+        number.push(sign);
+        state = state.advance(1);
+    }
+
+    // Read the whole part, checking that groups have three digits.
+    let (whole, mut state) = digits(state, format, format.grouping)?;
+    number.push_str(&whole);
+
+    if fraction {
+        if let Ok(((), fraction_state)) = tag(state.clone(), &format.decimal.to_string()) {
+            if fraction_state
+                .input
+                .starts_with(|c: char| c.is_ascii_digit())
+            {
+                let (fraction, fraction_state) = digits(fraction_state, format, None)?;
+                number.push('.');
+                number.push_str(&fraction);
+                state = fraction_state;
+            }
+        }
+    }
+
+    Ok((number, state))
+}
+
+/// Parse a run of digits with separators, returning just the digits.
+fn digits(
+    parser_state: ParserState,
+    format: &NumberFormat,
+    grouping: Option<char>,
+) -> ParseResult<ParseError, String> {
+    let start = parser_state.index;
+    let mut output = String::new();
+    let mut group_start = 0;
+    let mut grouped = false;
+    let mut state = parser_state;
+
+    loop {
+        match state.input.chars().next() {
+            Some(c) if c.is_ascii_digit() => {
+                output.push(c);
+                state = state.advance(1);
+            }
+
+            // Underscores must be followed by another digit.
+            Some('_') if format.underscores && !output.is_empty() => {
+                let next_state = state.advance(1);
+                if !next_state.input.starts_with(|c: char| c.is_ascii_digit()) {
+                    return Err(ParseError::invalid(
+                        state.index,
+                        next_state.index,
+                        "digit separators must be between digits",
+                    ));
+                }
+                state = next_state;
+            }
+
+            // Grouping separators only count when followed by a digit, so
+            // that a `,` can still end the number in a list.
+            Some(c) if Some(c) == grouping && !output.is_empty() => {
+                let next_state = state.advance(c.len_utf8());
+                if !next_state.input.starts_with(|c: char| c.is_ascii_digit()) {
+                    break;
+                }
+                let group_length = output.len() - group_start;
+                if (grouped && group_length != 3) || (!grouped && group_length > 3) {
+                    return Err(bad_group(start, state.index));
+                }
+                grouped = true;
+                group_start = output.len();
+                state = next_state;
+            }
+
+            _ => break,
+        }
+    }
+
+    if output.is_empty() {
+        Err(ParseError::expected_at(&state, "digit"))
+    } else if grouped && output.len() - group_start != 3 {
+        Err(bad_group(start, state.index))
+    } else {
+        Ok((output, state))
+    }
+}
+
+/// Create an error for digit groups of the wrong size between `start` and `end`.
+fn bad_group(start: usize, end: usize) -> ParseError {
+    ParseError::invalid(start, end, "digit groups must have three digits")
+        .with_help("only the first group may have fewer than three digits, like `12,345,678`")
+}
@@ -0,0 +1,171 @@
+use crate::bytes::{le_u32, le_u64, ByteParser, ByteResult, ByteState, ParseTake};
+use crate::ParseError;
+use std::convert::TryFrom;
+
+/// The encoding of a field's value.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum WireType {
+    Varint,
+    Fixed64,
+    LengthDelimited,
+    StartGroup,
+    EndGroup,
+    Fixed32,
+}
+
+/// The value of a field, which still needs a schema to be fully interpreted.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum FieldValue<'a> {
+    Varint(u64),
+    Fixed64(u64),
+
+    /// The bytes of a string, bytes field, embedded message, or packed repeated field.
+    LengthDelimited(&'a [u8]),
+    StartGroup,
+    EndGroup,
+    Fixed32(u32),
+}
+
+/// A single field of a message.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Field<'a> {
+    pub number: u32,
+    pub value: FieldValue<'a>,
+}
+
+/// Decode a zigzag-encoded varint, as used by `sint32` and `sint64` fields.
+pub fn zigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// Parses a base-128 varint of up to 10 bytes.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct ParseVarint;
+
+impl<'a> ByteParser<'a, u64, ParseError> for ParseVarint {
+    fn parse(&self, byte_state: ByteState<'a>) -> ByteResult<'a, ParseError, u64> {
+        let mut value = 0;
+        let mut state = byte_state;
+
+        for shift in (0..64).step_by(7) {
+            let (byte, new_state) = state
+                .take(1)
+                .ok_or_else(|| ParseError::unexpected(state.index(), ["varint byte"], None))?;
+            let byte = u64::from(byte[0]);
+            state = new_state;
+
+            // The tenth byte may only hold the last bit.
+            if shift == 63 && byte > 1 {
+                return Err(ParseError::invalid(
+                    byte_state.index(),
+                    state.index(),
+                    "varint is too large for 64 bits",
+                ));
+            }
+
+            value |= (byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok((value, state));
+            }
+        }
+
+        Err(ParseError::invalid(
+            byte_state.index(),
+            state.index(),
+            "varint is longer than 10 bytes",
+        ))
+    }
+}
+
+/// Parses the key of a field, which holds its number and wire type.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct ParseFieldKey;
+
+impl<'a> ByteParser<'a, (u32, WireType), ParseError> for ParseFieldKey {
+    fn parse(&self, byte_state: ByteState<'a>) -> ByteResult<'a, ParseError, (u32, WireType)> {
+        let (key, new_state) = ParseVarint.parse(byte_state)?;
+        let invalid = |message: &str| {
+            Err(ParseError::invalid(
+                byte_state.index(),
+                new_state.index(),
+                message,
+            ))
+        };
+
+        let wire_type = match key & 0x7 {
+            0 => WireType::Varint,
+            1 => WireType::Fixed64,
+            2 => WireType::LengthDelimited,
+            3 => WireType::StartGroup,
+            4 => WireType::EndGroup,
+            5 => WireType::Fixed32,
+            other => return invalid(&format!("unknown wire type {}", other)),
+        };
+        match u32::try_from(key >> 3) {
+            Ok(number) if number != 0 && number < 1 << 29 => Ok(((number, wire_type), new_state)),
+            _ => invalid("field numbers must be between 1 and 2^29 - 1"),
+        }
+    }
+}
+
+/// Parses a whole field: its key followed by a value of its wire type.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct ParseField;
+
+impl<'a> ByteParser<'a, Field<'a>, ParseError> for ParseField {
+    fn parse(&self, byte_state: ByteState<'a>) -> ByteResult<'a, ParseError, Field<'a>> {
+        let ((number, wire_type), state) = ParseFieldKey.parse(byte_state)?;
+
+        let (value, new_state) = match wire_type {
+            WireType::Varint => ParseVarint
+                .parse(state)
+                .map(|(v, s)| (FieldValue::Varint(v), s))?,
+            WireType::Fixed64 => le_u64()
+                .parse(state)
+                .map(|(v, s)| (FieldValue::Fixed64(v), s))?,
+            WireType::LengthDelimited => {
+                let (length, length_state) = ParseVarint.parse(state)?;
+                let length = usize::try_from(length).unwrap_or(usize::MAX);
+                ParseTake::new(length)
+                    .parse(length_state)
+                    .map(|(v, s)| (FieldValue::LengthDelimited(v), s))?
+            }
+            WireType::StartGroup => (FieldValue::StartGroup, state),
+            WireType::EndGroup => (FieldValue::EndGroup, state),
+            WireType::Fixed32 => le_u32()
+                .parse(state)
+                .map(|(v, s)| (FieldValue::Fixed32(v), s))?,
+        };
+
+        Ok((Field { number, value }, new_state))
+    }
+}
+
+/// An iterator over the fields of a message. It stops after the first error.
+#[derive(Debug, Copy, Clone)]
+pub struct Fields<'a> {
+    state: Option<ByteState<'a>>,
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Result<Field<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let state = self.state.take().filter(|s| !s.input().is_empty())?;
+        match ParseField.parse(state) {
+            Ok((field, new_state)) => {
+                self.state = Some(new_state);
+                Some(Ok(field))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// Iterate over the fields of an encoded message. Embedded messages can be read by calling this again on the bytes
+/// of a length-delimited field.
+pub fn fields(input: &[u8]) -> Fields<'_> {
+    Fields {
+        state: Some(ByteState::new(input)),
+    }
+}
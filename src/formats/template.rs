@@ -0,0 +1,82 @@
+use crate::formats::{tag, take_while};
+use crate::{ParseError, ParseResult, Parser, ParserState};
+
+/// A piece of a template.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Segment {
+    /// Text to be copied as-is, with any `$$` escapes already replaced by `$`.
+    Literal(String),
+
+    /// The name of a value to be substituted.
+    Placeholder(String),
+}
+
+/// Parses a whole template into literal text and placeholders written as `${name}` or `$name`. A `$$` is a literal
+/// `$`. Names start with a letter or underscore, followed by letters, digits, or underscores.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct ParseTemplate;
+
+impl Parser<Vec<Segment>, ParseError> for ParseTemplate {
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, Vec<Segment>> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut state = parser_state;
+
+        while !state.input.is_empty() {
+            let (text, dollar_state) = take_while(state, 0, "text", |c| c != '$')?;
+            literal.push_str(&text);
+            if dollar_state.input.is_empty() {
+                state = dollar_state;
+                break;
+            }
+
+            // Work out what follows the `$`.
+            let ((), after_state) = tag(dollar_state.clone(), "$")?;
+            if let Ok(((), escaped_state)) = tag(after_state.clone(), "$") {
+                literal.push('$');
+                state = escaped_state;
+                continue;
+            }
+            let (name, new_state) = match tag(after_state.clone(), "{") {
+                Ok(((), brace_state)) => {
+                    let (name, close_state) = name(brace_state)?;
+                    let ((), new_state) = tag(close_state, "}").map_err(|e| {
+                        e.with_note(&format!(
+                            "the placeholder starts at offset {}",
+                            dollar_state.index
+                        ))
+                    })?;
+                    (name, new_state)
+                }
+                Err(_) => {
+                    name(after_state).map_err(|e| e.with_help("write `$$` for a literal `$`"))?
+                }
+            };
+
+            if !literal.is_empty() {
+                segments.push(Segment::Literal(std::mem::take(&mut literal)));
+            }
+            segments.push(Segment::Placeholder(name));
+            state = new_state;
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok((segments, state))
+    }
+}
+
+/// Parse a placeholder name.
+fn name(parser_state: ParserState) -> ParseResult<ParseError, String> {
+    if !parser_state
+        .input
+        .starts_with(|c: char| c.is_alphabetic() || c == '_')
+    {
+        return Err(ParseError::expected_at(&parser_state, "placeholder name"));
+    }
+
+    take_while(parser_state, 1, "placeholder name", |c| {
+        c.is_alphanumeric() || c == '_'
+    })
+}
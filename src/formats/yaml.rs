@@ -0,0 +1,472 @@
+use crate::{ParseError, ParseResult, Parser, ParserState};
+
+/// A value in a YAML document.
+#[derive(Debug, Clone, PartialEq)]
+pub enum YamlValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Sequence(Vec<YamlValue>),
+
+    /// A mapping from keys to values, in the order they were written.
+    Mapping(Vec<(String, YamlValue)>),
+}
+
+impl YamlValue {
+    /// Get the value for `key` if this is a mapping that contains it.
+    pub fn get(&self, key: &str) -> Option<&YamlValue> {
+        match self {
+            Self::Mapping(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a whole YAML document made of block and flow sequences and mappings, plain and quoted scalars, and
+/// comments. Anchors, aliases, tags, multi-line scalars, and multiple documents aren't supported.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct ParseYaml;
+
+impl Parser<YamlValue, ParseError> for ParseYaml {
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, YamlValue> {
+        let mut block = Block {
+            lines: lines(&parser_state.input, parser_state.index)?,
+            position: 0,
+        };
+
+        let value = match block.lines.first() {
+            Some(line) => block.node(line.indent)?,
+            None => YamlValue::Null,
+        };
+        if let Some(line) = block.lines.get(block.position) {
+            return Err(unexpected_indentation(line));
+        }
+
+        Ok((value, parser_state.advance(parser_state.input.len())))
+    }
+}
+
+/// A line of a block, without its indentation or comments.
+#[derive(Debug, Copy, Clone)]
+struct Line<'a> {
+    indent: usize,
+    content: &'a str,
+
+    /// The offset of the content in the original input.
+    offset: usize,
+}
+
+/// Split an input into its non-empty lines.
+fn lines(input: &str, offset: usize) -> Result<Vec<Line<'_>>, ParseError> {
+    let mut lines = Vec::new();
+    let mut line_offset = offset;
+
+    for line in input.split('\n') {
+        let start = line_offset;
+        line_offset += line.len() + 1;
+
+        let content = strip_comment(line.trim_end_matches('\r')).trim_end();
+        let indent = content.len() - content.trim_start_matches(' ').len();
+        if content.trim_start().is_empty() || content == "---" {
+            continue;
+        }
+        if content[indent..].starts_with('\t') {
+            return Err(ParseError::invalid(
+                start + indent,
+                start + indent + 1,
+                "tabs can't be used for indentation",
+            ));
+        }
+
+        lines.push(Line {
+            indent,
+            content: &content[indent..],
+            offset: start + indent,
+        });
+    }
+
+    Ok(lines)
+}
+
+/// Remove a comment from the end of a line, ignoring `#` inside quotes or within a word. Quotes only start a quoted
+/// scalar at the start of a key, value, or flow item, so apostrophes within plain text like `it's` don't hide a
+/// comment after them.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+
+    // The last character before this one that isn't whitespace, which is
+    // `None` at the start of the line.
+    let mut previous_token = None;
+
+    for (index, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '#') if previous.is_whitespace() => return &line[..index],
+            (None, '"' | '\'')
+                if matches!(
+                    previous_token,
+                    None | Some(':' | '-' | '?' | ',' | '[' | '{')
+                ) =>
+            {
+                quote = Some(c)
+            }
+            (Some(q), _) if q == c => quote = None,
+            _ => {}
+        }
+        previous = c;
+        if !c.is_whitespace() {
+            previous_token = Some(c);
+        }
+    }
+
+    line
+}
+
+/// Check whether a line is an item of a block sequence.
+fn is_sequence_item(content: &str) -> bool {
+    content == "-" || content.starts_with("- ")
+}
+
+/// Create an error for a line that's indented where it shouldn't be.
+fn unexpected_indentation(line: &Line) -> ParseError {
+    ParseError::invalid(
+        line.offset,
+        line.offset + line.content.len(),
+        "unexpected indentation",
+    )
+}
+
+/// The lines of a document and how far through them the parser is.
+struct Block<'a> {
+    lines: Vec<Line<'a>>,
+    position: usize,
+}
+
+impl<'a> Block<'a> {
+    /// Parse the node starting at the current line, which is indented by `indent`.
+    fn node(&mut self, indent: usize) -> Result<YamlValue, ParseError> {
+        let line = self.lines[self.position];
+        if is_sequence_item(line.content) {
+            self.sequence(indent)
+        } else if split_key(line.content, line.offset)?.is_some() {
+            self.mapping(indent)
+        } else {
+            self.position += 1;
+            flow(line.content, line.offset)
+        }
+    }
+
+    /// Parse the node indented under a line that is indented by `indent`, if there is one.
+    fn child(&mut self, indent: usize) -> Result<YamlValue, ParseError> {
+        match self.lines.get(self.position) {
+            Some(line) if line.indent > indent => self.node(line.indent),
+            _ => Ok(YamlValue::Null),
+        }
+    }
+
+    /// Parse the items of a block sequence.
+    fn sequence(&mut self, indent: usize) -> Result<YamlValue, ParseError> {
+        let mut items = Vec::new();
+
+        while let Some(line) = self.lines.get(self.position).copied() {
+            if line.indent < indent || line.indent == indent && !is_sequence_item(line.content) {
+                break;
+            } else if line.indent > indent {
+                return Err(unexpected_indentation(&line));
+            }
+
+            let rest = &line.content[1..];
+            let item = rest.trim_start();
+            if item.is_empty() {
+                self.position += 1;
+                items.push(self.child(indent)?);
+            } else {
+                // Treat the rest of the line as if it were its own line, so
+                // that the following lines can continue it.
+                let skipped = line.content.len() - item.len();
+                self.lines[self.position] = Line {
+                    indent: indent + skipped,
+                    content: item,
+                    offset: line.offset + skipped,
+                };
+                items.push(self.node(indent + skipped)?);
+            }
+        }
+
+        Ok(YamlValue::Sequence(items))
+    }
+
+    /// Parse the entries of a block mapping.
+    fn mapping(&mut self, indent: usize) -> Result<YamlValue, ParseError> {
+        let mut entries = Vec::new();
+
+        while let Some(line) = self.lines.get(self.position).copied() {
+            if line.indent < indent {
+                break;
+            } else if line.indent > indent {
+                return Err(unexpected_indentation(&line));
+            }
+
+            let (key, rest, rest_offset) =
+                split_key(line.content, line.offset)?.ok_or_else(|| {
+                    ParseError::unexpected(line.offset, ["`key: value`"], Some(line.content))
+                })?;
+            self.position += 1;
+
+            let value = if !rest.is_empty() {
+                flow(rest, rest_offset)?
+            } else {
+                // A sequence may be nested at the same indentation as its key.
+                match self.lines.get(self.position) {
+                    Some(next) if next.indent == indent && is_sequence_item(next.content) => {
+                        self.sequence(indent)?
+                    }
+                    _ => self.child(indent)?,
+                }
+            };
+            entries.push((key, value));
+        }
+
+        Ok(YamlValue::Mapping(entries))
+    }
+}
+
+/// Split a `key: value` line into the key, the rest of the line, and the offset of the rest, or `None` if the line
+/// isn't a mapping entry.
+fn split_key(content: &str, offset: usize) -> Result<Option<(String, &str, usize)>, ParseError> {
+    let (key, after_key) = if content.starts_with('"') || content.starts_with('\'') {
+        let mut parser = Flow::new(content, offset);
+        let key = parser.quoted()?;
+        (key, parser.position)
+    } else if content.starts_with('[') || content.starts_with('{') {
+        return Ok(None);
+    } else {
+        match find_colon(content) {
+            Some(colon) => (String::from(content[..colon].trim_end()), colon),
+            None => return Ok(None),
+        }
+    };
+
+    let rest = &content[after_key..];
+    if rest == ":" || rest.starts_with(": ") {
+        let value = rest[1..].trim_start();
+        Ok(Some((key, value, offset + content.len() - value.len())))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Find a `:` followed by a space or the end of the text.
+fn find_colon(text: &str) -> Option<usize> {
+    text.char_indices()
+        .find(|(index, c)| {
+            *c == ':' && matches!(text[index + 1..].chars().next(), None | Some(' '))
+        })
+        .map(|(index, _)| index)
+}
+
+/// Parse a scalar or flow collection that makes up the rest of a line.
+fn flow(text: &str, offset: usize) -> Result<YamlValue, ParseError> {
+    let mut parser = Flow::new(text, offset);
+    let value = parser.value(false)?;
+    parser.skip_spaces();
+    if parser.position < text.len() {
+        return Err(ParseError::trailing_input(
+            offset + parser.position,
+            &text[parser.position..],
+        ));
+    }
+    Ok(value)
+}
+
+/// A parser for scalars and flow collections within one line.
+struct Flow<'a> {
+    text: &'a str,
+    position: usize,
+    offset: usize,
+}
+
+impl<'a> Flow<'a> {
+    fn new(text: &'a str, offset: usize) -> Self {
+        Self {
+            text,
+            position: 0,
+            offset,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek() == Some(' ') {
+            self.position += 1;
+        }
+    }
+
+    /// Consume `c`, or fail with an error expecting `expected`.
+    fn expect(&mut self, c: char, expected: &[&str]) -> Result<(), ParseError> {
+        if self.peek() == Some(c) {
+            self.position += 1;
+            Ok(())
+        } else {
+            let found = self.peek().map(String::from);
+            Err(ParseError::unexpected(
+                self.offset + self.position,
+                expected.iter().copied(),
+                found.as_deref(),
+            ))
+        }
+    }
+
+    /// Parse any value. Inside flow collections, plain scalars end at `,`, `]`, and `}`.
+    fn value(&mut self, in_collection: bool) -> Result<YamlValue, ParseError> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('[') => {
+                self.position += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_spaces();
+                    if self.peek() == Some(']') {
+                        self.position += 1;
+                        return Ok(YamlValue::Sequence(items));
+                    }
+                    items.push(self.value(true)?);
+                    self.skip_spaces();
+                    if self.peek() != Some(']') {
+                        self.expect(',', &["`,`", "`]`"])?;
+                    }
+                }
+            }
+            Some('{') => {
+                self.position += 1;
+                let mut entries = Vec::new();
+                loop {
+                    self.skip_spaces();
+                    if self.peek() == Some('}') {
+                        self.position += 1;
+                        return Ok(YamlValue::Mapping(entries));
+                    }
+                    let key = self.key()?;
+                    self.skip_spaces();
+                    self.expect(':', &["`:`"])?;
+                    entries.push((key, self.value(true)?));
+                    self.skip_spaces();
+                    if self.peek() != Some('}') {
+                        self.expect(',', &["`,`", "`}`"])?;
+                    }
+                }
+            }
+            Some('"' | '\'') => self.quoted().map(YamlValue::String),
+            _ => {
+                let rest = &self.text[self.position..];
+                let length = if in_collection {
+                    rest.find([',', ']', '}'])
+                        .into_iter()
+                        .chain(find_colon(rest))
+                        .min()
+                        .unwrap_or(rest.len())
+                } else {
+                    rest.len()
+                };
+                self.position += length;
+                Ok(plain(rest[..length].trim_end()))
+            }
+        }
+    }
+
+    /// Parse the key of a flow mapping entry, which is never converted to another type.
+    fn key(&mut self) -> Result<String, ParseError> {
+        match self.peek() {
+            Some('"' | '\'') => self.quoted(),
+            _ => {
+                let rest = &self.text[self.position..];
+                let length = rest
+                    .find([',', '}'])
+                    .into_iter()
+                    .chain(find_colon(rest))
+                    .min()
+                    .unwrap_or(rest.len());
+                self.position += length;
+                Ok(String::from(rest[..length].trim_end()))
+            }
+        }
+    }
+
+    /// Parse a single or double quoted string.
+    fn quoted(&mut self) -> Result<String, ParseError> {
+        let start = self.position;
+        let quote = self.peek().unwrap();
+        self.position += 1;
+        let mut output = String::new();
+
+        loop {
+            let c = self.peek().ok_or_else(|| {
+                ParseError::unexpected(self.offset + self.text.len(), [quote.to_string()], None)
+                    .with_note(&format!(
+                        "the string starts at offset {}",
+                        self.offset + start
+                    ))
+            })?;
+            self.position += c.len_utf8();
+
+            match (quote, c) {
+                // Single quoted strings escape quotes by doubling them.
+                ('\'', '\'') if self.peek() == Some('\'') => {
+                    self.position += 1;
+                    output.push('\'');
+                }
+                (q, c) if q == c => return Ok(output),
+                ('"', '\\') => {
+                    let escape_start = self.position - 1;
+                    let escaped = match self.peek() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('0') => '\0',
+                        Some(c @ ('"' | '\\' | '/' | ' ')) => c,
+                        _ => {
+                            return Err(ParseError::invalid(
+                                self.offset + escape_start,
+                                self.offset + self.position + self.peek().map_or(0, char::len_utf8),
+                                "unknown escape sequence",
+                            ))
+                        }
+                    };
+                    self.position += 1;
+                    output.push(escaped);
+                }
+                (_, c) => output.push(c),
+            }
+        }
+    }
+}
+
+/// Work out the type of a plain scalar.
+fn plain(text: &str) -> YamlValue {
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => YamlValue::Null,
+        "true" | "True" | "TRUE" => YamlValue::Bool(true),
+        "false" | "False" | "FALSE" => YamlValue::Bool(false),
+        ".inf" | "+.inf" => YamlValue::Float(f64::INFINITY),
+        "-.inf" => YamlValue::Float(f64::NEG_INFINITY),
+        ".nan" => YamlValue::Float(f64::NAN),
+        _ => {
+            if let Ok(int) = text.parse() {
+                YamlValue::Int(int)
+            } else if let Some(float) = text
+                .parse()
+                .ok()
+                .filter(|_| text.contains(|c: char| c.is_ascii_digit()))
+            {
+                YamlValue::Float(float)
+            } else {
+                YamlValue::String(String::from(text))
+            }
+        }
+    }
+}
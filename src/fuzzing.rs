@@ -0,0 +1,145 @@
+use crate::{ErrorVerbosity, ParseError, Parser, ParserState};
+use std::panic;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// A time limit for [`check_parser`] that is generous for parsers over short inputs.
+pub const DEFAULT_TIME_LIMIT: Duration = Duration::from_secs(1);
+
+/// Run a parser over arbitrary bytes and panic if it breaks any of the rules parsers should follow, so that it can
+/// be used directly as the body of a `cargo fuzz` target:
+///
+/// ```
+/// use parselib_rs::fuzzing::fuzz_parser;
+/// use parselib_rs::parsers::utf8::digit1;
+///
+/// // The body of `fuzz_target!(|data: &[u8]| ...)`.
+/// fn fuzz_digits(data: &[u8]) {
+///     fuzz_parser(&digit1(), data);
+/// }
+///
+/// fuzz_digits(b"12\xff34");
+/// ```
+///
+/// Invalid UTF-8 in `data` is replaced with `U+FFFD`. The parser is checked against the same rules as
+/// [`check_parser`], except for the time limit: it runs on the calling thread, so the fuzzer's own timeout reports
+/// a parser that hangs, and each run doesn't pay for starting threads.
+pub fn fuzz_parser<OutputType, ParserType: Parser<OutputType, ParseError>>(
+    parser: &ParserType,
+    data: &[u8],
+) {
+    let input = String::from_utf8_lossy(data);
+    let result = [ErrorVerbosity::Rich, ErrorVerbosity::Fast]
+        .iter()
+        .map(|&verbosity| check_result(parser, &input, verbosity))
+        .collect::<Result<Vec<_>, String>>()
+        .and_then(|ends| check_ends(&ends));
+    if let Err(message) = result {
+        panic!("{}", message);
+    }
+}
+
+/// Run a parser over `input` and check that:
+///
+/// * On success, the new state's index is within the input and the remaining input is exactly what follows it.
+/// * On failure, the error's span is within the input.
+/// * It finishes within `time_limit`, so it returns an error instead of hanging.
+/// * It succeeds or fails in the same way, and stops at the same place, whether it is building fast or rich errors.
+///
+/// The parser runs on a separate thread so that one that never finishes can be reported, which suits unit tests
+/// rather than fuzz targets. Threads can't be stopped, so a parser that hangs is left running in the background. A panic inside the parser is passed on to the caller,
+/// so the fuzzer reports it. Any other broken rule is returned as a message describing it.
+pub fn check_parser<OutputType: 'static, ParserType>(
+    parser: &Arc<ParserType>,
+    input: &str,
+    time_limit: Duration,
+) -> Result<(), String>
+where
+    ParserType: Parser<OutputType, ParseError> + Send + Sync + 'static,
+{
+    let mut ends = Vec::with_capacity(2);
+
+    for verbosity in [ErrorVerbosity::Rich, ErrorVerbosity::Fast] {
+        let (sender, receiver) = mpsc::channel();
+        let worker = {
+            let parser = Arc::clone(parser);
+            let input = String::from(input);
+            thread::spawn(move || {
+                // The receiver is gone if the parser took too long, which is
+                // already being reported.
+                let _ = sender.send(check_result(&*parser, &input, verbosity));
+            })
+        };
+
+        let end = match receiver.recv_timeout(time_limit) {
+            Ok(end) => end?,
+            Err(RecvTimeoutError::Timeout) => {
+                return Err(format!(
+                    "parser didn't finish within the limit of {:?} with {:?} errors",
+                    time_limit, verbosity
+                ))
+            }
+            // The worker stopped without sending anything, so it panicked.
+            Err(RecvTimeoutError::Disconnected) => match worker.join() {
+                Err(payload) => panic::resume_unwind(payload),
+                Ok(()) => unreachable!("the worker always sends a result"),
+            },
+        };
+        ends.push(end);
+    }
+
+    check_ends(&ends)
+}
+
+/// Check that a parser stopped at the same place with rich errors as with fast errors, given where it stopped with
+/// each of them in that order.
+fn check_ends(ends: &[Result<usize, usize>]) -> Result<(), String> {
+    if ends[0] != ends[1] {
+        return Err(format!(
+            "parser returned {:?} with rich errors but {:?} with fast errors",
+            ends[0], ends[1]
+        ));
+    }
+    Ok(())
+}
+
+/// Run a parser over `input` once, returning where it succeeded or failed, or a message describing a broken rule.
+fn check_result<OutputType, ParserType: Parser<OutputType, ParseError>>(
+    parser: &ParserType,
+    input: &str,
+    verbosity: ErrorVerbosity,
+) -> Result<Result<usize, usize>, String> {
+    match parser.parse(ParserState::new(input).with_verbosity(verbosity)) {
+        Ok((_, new_state)) => {
+            if new_state.index > input.len() {
+                return Err(format!(
+                    "parser finished at index {}, past the end of the {} byte input",
+                    new_state.index,
+                    input.len()
+                ));
+            }
+            if input.get(new_state.index..) != Some(new_state.input()) {
+                return Err(format!(
+                    "parser finished at index {} but the remaining input doesn't start there",
+                    new_state.index
+                ));
+            }
+            Ok(Ok(new_state.index))
+        }
+        Err(e) => {
+            let span = e.span();
+            if span.start > span.end || span.end > input.len() {
+                return Err(format!(
+                    "error spans {}..{}, outside the {} byte input: {}",
+                    span.start,
+                    span.end,
+                    input.len(),
+                    e
+                ));
+            }
+            Ok(Err(e.offset()))
+        }
+    }
+}
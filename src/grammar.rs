@@ -0,0 +1,195 @@
+use crate::parsers::utf8::ParseCharClass;
+use std::collections::BTreeSet;
+
+/// Finding alternatives that can start the same way.
+pub mod ambiguity;
+
+/// Reading grammars written for pest.
+#[cfg(feature = "pest")]
+pub mod pest;
+
+/// Generating human-readable documentation of the rules in a grammar.
+pub mod docs;
+
+/// Writing grammars as EBNF text.
+pub mod ebnf;
+
+/// Rendering grammars as railroad diagrams.
+pub mod railroad;
+
+/// Writing grammars as tree-sitter `grammar.js` files.
+pub mod tree_sitter;
+
+/// The description of a parser that accepts any single character.
+pub(crate) const ANY_CHARACTER: &str = "any character";
+
+/// Documentation attached to a named rule, such as with
+/// [`label_with_doc`](crate::parsers::ParserExtensions::label_with_doc).
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct RuleDoc {
+    /// What the rule matches and what it's for, written in prose.
+    pub description: Option<String>,
+
+    /// Examples of input that the rule matches.
+    pub examples: Vec<String>,
+}
+
+/// A description of the input that a parser accepts, built from the structure of the parser with
+/// [`Parser::describe`](crate::Parser::describe).
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Grammar {
+    /// A parser that doesn't describe itself, such as a parser written by hand.
+    Opaque,
+
+    /// Matches without consuming anything.
+    Empty,
+
+    /// Matches exactly this text.
+    Literal(String),
+
+    /// Matches a single character from a class, written in the syntax accepted by
+    /// [`char_class`](crate::parsers::utf8::char_class).
+    Class(String),
+
+    /// Matches something described in words, such as a position like `end of input`.
+    Special(String),
+
+    /// Matches each element in order.
+    Sequence(Vec<Grammar>),
+
+    /// Matches any one of the alternatives, which are tried in order.
+    Choice(Vec<Grammar>),
+
+    /// Matches the element between `min` and `max` times (inclusively), where a `max` of `usize::MAX` has no limit.
+    Repeat {
+        min: usize,
+        max: usize,
+        element: Box<Grammar>,
+    },
+
+    /// A named rule, such as one created with [`label`](crate::parsers::ParserExtensions::label), along with any
+    /// documentation attached to it.
+    Rule {
+        name: String,
+        body: Box<Grammar>,
+        doc: Option<RuleDoc>,
+    },
+
+    /// A rule that is defined elsewhere, referred to by name.
+    Reference(String),
+}
+
+impl Grammar {
+    /// Create a sequence, merging in the elements of any nested sequences and leaving out empty elements.
+    pub fn sequence(elements: impl IntoIterator<Item = Grammar>) -> Self {
+        let mut flattened = Vec::new();
+        for element in elements {
+            match element {
+                Self::Sequence(inner) => flattened.extend(inner),
+                Self::Empty => {}
+                element => flattened.push(element),
+            }
+        }
+
+        match flattened.len() {
+            0 => Self::Empty,
+            1 => flattened.pop().unwrap(),
+            _ => Self::Sequence(flattened),
+        }
+    }
+
+    /// Create a choice, merging in the alternatives of any nested choices.
+    pub fn choice(alternatives: impl IntoIterator<Item = Grammar>) -> Self {
+        let mut flattened = Vec::new();
+        for alternative in alternatives {
+            match alternative {
+                Self::Choice(inner) => flattened.extend(inner),
+                alternative => flattened.push(alternative),
+            }
+        }
+
+        if flattened.len() == 1 {
+            flattened.pop().unwrap()
+        } else {
+            Self::Choice(flattened)
+        }
+    }
+
+    /// Create a repetition of `element`.
+    pub fn repeat(min: usize, max: usize, element: Grammar) -> Self {
+        Self::Repeat {
+            min,
+            max,
+            element: Box::new(element),
+        }
+    }
+
+    /// Create a rule named `name`.
+    pub fn rule(name: &str, body: Grammar) -> Self {
+        Self::Rule {
+            name: String::from(name),
+            body: Box::new(body),
+            doc: None,
+        }
+    }
+
+    /// Create a rule named `name` with documentation describing it.
+    pub fn documented_rule(name: &str, body: Grammar, doc: RuleDoc) -> Self {
+        Self::Rule {
+            name: String::from(name),
+            body: Box::new(body),
+            doc: Some(doc),
+        }
+    }
+
+    /// Describe a single character from the range `start..=end`, where `None` leaves that side of the range open.
+    pub fn char_range(start: Option<char>, end: Option<char>) -> Self {
+        match (start, end) {
+            (Some(start), Some(end)) if start == end => Self::Literal(start.to_string()),
+            (None, None) => Self::Special(String::from(ANY_CHARACTER)),
+            (start, end) => Self::Class(
+                ParseCharClass::new(vec![(start.unwrap_or('\0'), end.unwrap_or(char::MAX))])
+                    .to_string(),
+            ),
+        }
+    }
+
+    /// Get every named rule within this grammar, including this grammar itself, in the order they are first found.
+    /// Rules with the same name as an earlier rule are left out.
+    pub fn rules(&self) -> Vec<(&str, &Grammar)> {
+        self.documented_rules()
+            .into_iter()
+            .map(|(name, _, body)| (name, body))
+            .collect()
+    }
+
+    /// Get every named rule within this grammar along with its documentation, in the same order as
+    /// [`rules`](Self::rules).
+    pub fn documented_rules(&self) -> Vec<(&str, Option<&RuleDoc>, &Grammar)> {
+        fn collect<'a>(
+            grammar: &'a Grammar,
+            names: &mut BTreeSet<&'a str>,
+            rules: &mut Vec<(&'a str, Option<&'a RuleDoc>, &'a Grammar)>,
+        ) {
+            match grammar {
+                Grammar::Rule { name, body, doc } => {
+                    if names.insert(name) {
+                        rules.push((name, doc.as_ref(), body));
+                    }
+                    collect(body, names, rules);
+                }
+                Grammar::Sequence(elements) | Grammar::Choice(elements) => {
+                    for element in elements {
+                        collect(element, names, rules);
+                    }
+                }
+                Grammar::Repeat { element, .. } => collect(element, names, rules),
+                _ => {}
+            }
+        }
+
+        let mut rules = Vec::new();
+        collect(self, &mut BTreeSet::new(), &mut rules);
+        rules
+    }
+}
@@ -0,0 +1,302 @@
+use crate::grammar::ebnf::expression_text;
+use crate::grammar::{Grammar, ANY_CHARACTER};
+use crate::parsers::utf8::{char_class, ParseCharClass};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+
+/// Why two alternatives of a choice might both match the same input.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum AmbiguityKind {
+    /// Both alternatives can start with any of these inclusive character ranges, so which one matches depends on
+    /// the order they are tried in.
+    SharedStart(Vec<(char, char)>),
+
+    /// The first alternative can match without consuming anything, so it always succeeds and the second
+    /// alternative is never tried.
+    Unreachable,
+}
+
+/// A pair of alternatives in a choice that might both match the same input.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Ambiguity {
+    /// The name of the innermost rule containing the choice, if there is one.
+    pub rule: Option<String>,
+
+    /// The positions of the two alternatives within the choice, where `first` comes before `second`.
+    pub first: usize,
+    pub second: usize,
+
+    /// The two alternatives, written as EBNF.
+    pub alternatives: (String, String),
+
+    /// Why the alternatives overlap.
+    pub kind: AmbiguityKind,
+}
+
+impl Display for Ambiguity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(rule) = &self.rule {
+            write!(f, "in {}: ", rule)?;
+        }
+        match &self.kind {
+            AmbiguityKind::SharedStart(ranges) => write!(
+                f,
+                "`{}` and `{}` can both start with {}",
+                self.alternatives.0,
+                self.alternatives.1,
+                ParseCharClass::new(ranges.clone())
+            ),
+            AmbiguityKind::Unreachable => write!(
+                f,
+                "`{}` can match nothing, so `{}` is never tried",
+                self.alternatives.0, self.alternatives.1
+            ),
+        }
+    }
+}
+
+/// The characters a grammar can start with.
+#[derive(Debug, Clone, Default)]
+struct First {
+    /// Sorted, non-overlapping inclusive ranges.
+    ranges: Vec<(char, char)>,
+
+    /// Whether the grammar can match without consuming anything.
+    nullable: bool,
+
+    /// Whether part of the grammar couldn't be analyzed, so the ranges may be incomplete.
+    unknown: bool,
+}
+
+impl First {
+    fn of_ranges(ranges: Vec<(char, char)>) -> Self {
+        let mut first = Self::default();
+        first.add(&ranges);
+        first
+    }
+
+    fn nullable() -> Self {
+        Self {
+            nullable: true,
+            ..Self::default()
+        }
+    }
+
+    fn unknown() -> Self {
+        Self {
+            unknown: true,
+            ..Self::default()
+        }
+    }
+
+    /// Add ranges, keeping them sorted and merging any that touch.
+    fn add(&mut self, ranges: &[(char, char)]) {
+        self.ranges.extend_from_slice(ranges);
+        self.ranges.sort();
+        let mut merged: Vec<(char, char)> = Vec::with_capacity(self.ranges.len());
+        for (start, end) in self.ranges.drain(..) {
+            match merged.last_mut() {
+                Some(last) if start as u32 <= last.1 as u32 + 1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        self.ranges = merged;
+    }
+
+    /// Get the ranges of characters that both sets start with.
+    fn intersection(&self, other: &First) -> Vec<(char, char)> {
+        let mut shared = Vec::new();
+        for (start_a, end_a) in &self.ranges {
+            for (start_b, end_b) in &other.ranges {
+                let (start, end) = (*start_a.max(start_b), *end_a.min(end_b));
+                if start <= end {
+                    shared.push((start, end));
+                }
+            }
+        }
+        shared
+    }
+}
+
+/// Find the pairs of alternatives in every choice of a grammar that might both match the same input, by comparing
+/// the characters each alternative can start with. Ordered choice always picks the first alternative that
+/// matches, so these are places where reordering the alternatives would change what is parsed.
+///
+/// This is an approximation: it only looks at the first character, so it can report alternatives that differ
+/// later on, and it can't see inside parsers that don't describe themselves.
+pub fn ambiguities(grammar: &Grammar) -> Vec<Ambiguity> {
+    let rules = grammar
+        .rules()
+        .into_iter()
+        .collect::<BTreeMap<&str, &Grammar>>();
+    let mut found = Vec::new();
+    search(grammar, None, &rules, &mut BTreeSet::new(), &mut found);
+    found
+}
+
+/// Look for ambiguities in every choice within a grammar, checking the body of each rule only once.
+fn search<'a>(
+    grammar: &'a Grammar,
+    rule: Option<&'a str>,
+    rules: &BTreeMap<&'a str, &'a Grammar>,
+    searched: &mut BTreeSet<&'a str>,
+    found: &mut Vec<Ambiguity>,
+) {
+    match grammar {
+        Grammar::Rule { name, body, .. } if searched.insert(name) => {
+            search(body, Some(name), rules, searched, found)
+        }
+        Grammar::Sequence(elements) => {
+            for element in elements {
+                search(element, rule, rules, searched, found);
+            }
+        }
+        Grammar::Repeat { element, .. } => search(element, rule, rules, searched, found),
+        Grammar::Choice(alternatives) => {
+            let firsts = alternatives
+                .iter()
+                .map(|alternative| first(alternative, rules, &mut BTreeSet::new()))
+                .collect::<Vec<First>>();
+
+            for (index_a, first_a) in firsts.iter().enumerate() {
+                for (index_b, first_b) in firsts.iter().enumerate().skip(index_a + 1) {
+                    let kind = if first_a.nullable && !first_a.unknown {
+                        AmbiguityKind::Unreachable
+                    } else {
+                        let shared = first_a.intersection(first_b);
+                        if shared.is_empty() {
+                            continue;
+                        }
+                        AmbiguityKind::SharedStart(shared)
+                    };
+
+                    found.push(Ambiguity {
+                        rule: rule.map(String::from),
+                        first: index_a,
+                        second: index_b,
+                        alternatives: (
+                            expression_text(&alternatives[index_a]),
+                            expression_text(&alternatives[index_b]),
+                        ),
+                        kind,
+                    });
+                }
+            }
+
+            for alternative in alternatives {
+                search(alternative, rule, rules, searched, found);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Work out the characters a grammar can start with, following references to other rules. Rules that are already
+/// being expanded are left-recursive, and are treated as unknown.
+fn first<'a>(
+    grammar: &'a Grammar,
+    rules: &BTreeMap<&'a str, &'a Grammar>,
+    expanding: &mut BTreeSet<&'a str>,
+) -> First {
+    match grammar {
+        Grammar::Opaque => First::unknown(),
+        Grammar::Empty => First::nullable(),
+        Grammar::Literal(text) => match text.chars().next() {
+            Some(c) => First::of_ranges(vec![(c, c)]),
+            None => First::nullable(),
+        },
+        Grammar::Class(spec) => match char_class(spec) {
+            Ok(class) if class.negated => First::of_ranges(complement(&class.ranges)),
+            Ok(class) => First::of_ranges(class.ranges),
+            Err(_) => First::unknown(),
+        },
+        Grammar::Special(description) if description == ANY_CHARACTER => {
+            First::of_ranges(vec![('\0', char::MAX)])
+        }
+
+        // Other special descriptions are positions, which don't consume anything.
+        Grammar::Special(_) => First::nullable(),
+
+        Grammar::Sequence(elements) => {
+            let mut total = First::nullable();
+            for element in elements {
+                let element = first(element, rules, expanding);
+                total.add(&element.ranges);
+                total.unknown |= element.unknown;
+                if !element.nullable {
+                    total.nullable = false;
+                    break;
+                }
+            }
+            total
+        }
+        Grammar::Choice(alternatives) => {
+            let mut total = First::default();
+            for alternative in alternatives {
+                let alternative = first(alternative, rules, expanding);
+                total.add(&alternative.ranges);
+                total.nullable |= alternative.nullable;
+                total.unknown |= alternative.unknown;
+            }
+            total
+        }
+        Grammar::Repeat { max: 0, .. } => First::nullable(),
+        Grammar::Repeat { min, element, .. } => {
+            let mut element = first(element, rules, expanding);
+            element.nullable |= *min == 0;
+            element
+        }
+        Grammar::Rule { name, body, .. } => {
+            if !expanding.insert(name) {
+                return First::unknown();
+            }
+            let body = first(body, rules, expanding);
+            expanding.remove(name.as_str());
+            body
+        }
+        Grammar::Reference(name) => match rules.get_key_value(name.as_str()) {
+            Some((name, body)) if expanding.insert(name) => {
+                let body = first(body, rules, expanding);
+                expanding.remove(name);
+                body
+            }
+            _ => First::unknown(),
+        },
+    }
+}
+
+/// Get the ranges of characters outside `ranges`.
+fn complement(ranges: &[(char, char)]) -> Vec<(char, char)> {
+    let mut sorted = First::of_ranges(ranges.to_vec()).ranges;
+    let mut outside = Vec::new();
+    let mut next = Some('\0');
+    for (start, end) in sorted.drain(..) {
+        if let Some(from) = next {
+            if from < start {
+                outside.push((from, previous_char(start)));
+            }
+        }
+        next = next_char(end);
+    }
+    if let Some(from) = next {
+        outside.push((from, char::MAX));
+    }
+    outside
+}
+
+/// Get the character before `c`, skipping surrogates.
+fn previous_char(c: char) -> char {
+    match c {
+        '\u{E000}' => '\u{D7FF}',
+        c => char::from_u32(c as u32 - 1).unwrap(),
+    }
+}
+
+/// Get the character after `c`, skipping surrogates, or `None` if `c` is the last character.
+fn next_char(c: char) -> Option<char> {
+    match c {
+        '\u{D7FF}' => Some('\u{E000}'),
+        char::MAX => None,
+        c => char::from_u32(c as u32 + 1),
+    }
+}
@@ -0,0 +1,108 @@
+use crate::grammar::ebnf::{expression_text, identifier};
+use crate::grammar::railroad::{escape, svg};
+use crate::grammar::{Grammar, RuleDoc};
+use std::fmt::Write;
+
+/// Write documentation of every named rule in a grammar as Markdown. Each rule gets a heading, its description, its
+/// syntax in EBNF, and its examples, using the documentation attached with
+/// [`label_with_doc`](crate::parsers::ParserExtensions::label_with_doc). If the grammar itself isn't a rule, its
+/// syntax is written first without a heading.
+pub fn markdown(grammar: &Grammar) -> String {
+    let mut out = String::from("# Grammar\n");
+
+    if !matches!(grammar, Grammar::Rule { .. }) {
+        writeln!(out, "\n```ebnf\nroot ::= {}\n```", expression_text(grammar)).unwrap();
+    }
+    for (name, doc, body) in grammar.documented_rules() {
+        writeln!(out, "\n## {}\n", name).unwrap();
+        if let Some(description) = doc.and_then(|doc| doc.description.as_ref()) {
+            writeln!(out, "{}\n", description).unwrap();
+        }
+        writeln!(
+            out,
+            "```ebnf\n{} ::= {}\n```",
+            identifier(name),
+            expression_text(body)
+        )
+        .unwrap();
+
+        let examples = examples(doc);
+        if !examples.is_empty() {
+            out.push_str("\nExamples:\n\n");
+            for example in examples {
+                writeln!(out, "- {}", code_span(example)).unwrap();
+            }
+        }
+    }
+    out
+}
+
+/// Write documentation of every named rule in a grammar as an HTML page. Each rule gets a heading, its
+/// description, a railroad diagram, its syntax in EBNF, and its examples. If the grammar itself isn't a rule, it is
+/// drawn first without a heading.
+pub fn html(grammar: &Grammar) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Grammar</title>\n</head>\n<body>\n<h1>Grammar</h1>\n",
+    );
+
+    if !matches!(grammar, Grammar::Rule { .. }) {
+        writeln!(
+            out,
+            "{}\n<pre><code>root ::= {}</code></pre>",
+            svg(grammar),
+            escape(&expression_text(grammar))
+        )
+        .unwrap();
+    }
+    for (name, doc, body) in grammar.documented_rules() {
+        writeln!(
+            out,
+            "<h2 id=\"{}\">{}</h2>",
+            escape(&identifier(name)),
+            escape(name)
+        )
+        .unwrap();
+        if let Some(description) = doc.and_then(|doc| doc.description.as_ref()) {
+            writeln!(out, "<p>{}</p>", escape(description)).unwrap();
+        }
+        writeln!(
+            out,
+            "{}\n<pre><code>{} ::= {}</code></pre>",
+            svg(body),
+            escape(&identifier(name)),
+            escape(&expression_text(body))
+        )
+        .unwrap();
+
+        let examples = examples(doc);
+        if !examples.is_empty() {
+            out.push_str("<p>Examples:</p>\n<ul>\n");
+            for example in examples {
+                writeln!(out, "<li><code>{}</code></li>", escape(example)).unwrap();
+            }
+            out.push_str("</ul>\n");
+        }
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Get the examples from a rule's documentation, if it has any.
+fn examples(doc: Option<&RuleDoc>) -> &[String] {
+    doc.map_or(&[], |doc| &doc.examples)
+}
+
+/// Write text as a Markdown code span, using enough backticks that any in the text don't end the span early.
+fn code_span(text: &str) -> String {
+    let longest_run = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run + 1);
+
+    // Code spans strip one space from each end, so pad text that starts or
+    // ends with a backtick to keep it from merging with the fence.
+    if text.starts_with('`') || text.ends_with('`') {
+        format!("{} {} {}", fence, text, fence)
+    } else {
+        format!("{}{}{}", fence, text, fence)
+    }
+}
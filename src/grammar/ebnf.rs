@@ -0,0 +1,120 @@
+use crate::grammar::Grammar;
+use std::fmt::Write;
+
+/// How tightly an expression binds, deciding whether it needs parentheses.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+enum Precedence {
+    Choice,
+    Sequence,
+    Repeat,
+}
+
+/// Write a grammar as EBNF text in the W3C style, with a line for each named rule, such as `number ::= "-"? digit+`.
+/// If the grammar itself isn't a rule, it is written first as a rule named `root`. Descriptions of things that
+/// can't be written in EBNF, like the end of the input or parsers that don't describe themselves, are written as
+/// special sequences like `? end of input ?`.
+pub fn text(grammar: &Grammar) -> String {
+    let mut rules = grammar
+        .rules()
+        .into_iter()
+        .map(|(name, body)| (identifier(name), body))
+        .collect::<Vec<(String, &Grammar)>>();
+    if !matches!(grammar, Grammar::Rule { .. }) {
+        rules.insert(0, (String::from("root"), grammar));
+    }
+
+    // Line up the definitions.
+    let width = rules
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut out = String::new();
+    for (name, body) in rules {
+        let mut definition = String::new();
+        expression(body, Precedence::Choice, &mut definition);
+        writeln!(out, "{:width$} ::= {}", name, definition, width = width).unwrap();
+    }
+    out
+}
+
+/// Write a single expression as EBNF text, naming any rules within it instead of writing out their bodies.
+pub(crate) fn expression_text(grammar: &Grammar) -> String {
+    let mut out = String::new();
+    expression(grammar, Precedence::Choice, &mut out);
+    out
+}
+
+/// Write an expression, wrapping it in parentheses if it binds more loosely than `precedence` allows.
+fn expression(grammar: &Grammar, precedence: Precedence, out: &mut String) {
+    let (own, separator, parts) = match grammar {
+        Grammar::Sequence(elements) => (Precedence::Sequence, " ", elements),
+        Grammar::Choice(alternatives) => (Precedence::Choice, " | ", alternatives),
+        Grammar::Repeat { min, max, element } => {
+            if *max == 0 {
+                out.push_str("()");
+                return;
+            }
+            expression(element, Precedence::Repeat, out);
+            match (*min, *max) {
+                (0, 1) => out.push('?'),
+                (0, usize::MAX) => out.push('*'),
+                (1, usize::MAX) => out.push('+'),
+                (1, 1) => {}
+                (min, usize::MAX) => write!(out, "{{{},}}", min).unwrap(),
+                (min, max) if min == max => write!(out, "{{{}}}", min).unwrap(),
+                (min, max) => write!(out, "{{{},{}}}", min, max).unwrap(),
+            }
+            return;
+        }
+        Grammar::Opaque => {
+            out.push_str("? unknown ?");
+            return;
+        }
+        Grammar::Empty => {
+            out.push_str("()");
+            return;
+        }
+        Grammar::Literal(text) => {
+            // Use whichever quote doesn't appear in the text, if either.
+            let quote = if text.contains('"') { '\'' } else { '"' };
+            write!(out, "{0}{1}{0}", quote, text).unwrap();
+            return;
+        }
+        Grammar::Class(class) => {
+            out.push_str(class);
+            return;
+        }
+        Grammar::Special(description) => {
+            write!(out, "? {} ?", description).unwrap();
+            return;
+        }
+        Grammar::Rule { name, .. } | Grammar::Reference(name) => {
+            out.push_str(&identifier(name));
+            return;
+        }
+    };
+
+    let parenthesize = own < precedence;
+    if parenthesize {
+        out.push('(');
+    }
+    for (index, part) in parts.iter().enumerate() {
+        if index > 0 {
+            out.push_str(separator);
+        }
+        // Parts of a sequence need parentheses around choices, and parts of a choice never need them.
+        expression(part, own, out);
+    }
+    if parenthesize {
+        out.push(')');
+    }
+}
+
+/// Turn a rule name into an EBNF identifier by replacing anything other than letters, digits, and underscores.
+pub(crate) fn identifier(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
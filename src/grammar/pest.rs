@@ -0,0 +1,978 @@
+use crate::formats::{tag, take_while};
+use crate::grammar::ebnf::expression_text;
+use crate::grammar::Grammar;
+use crate::parsers::utf8::ParseCharClass;
+use crate::span::Span;
+use crate::{Expected, ParseError, ParseResult, Parser, ParserState};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// How a rule's matches appear in the output and whether whitespace is skipped inside it, chosen by the modifier
+/// before its body.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum RuleKind {
+    /// `{ .. }`: produces a node, and skips implicit whitespace unless inside an atomic rule.
+    Normal,
+
+    /// `_{ .. }`: produces no node of its own, only the nodes of the rules inside it.
+    Silent,
+
+    /// `@{ .. }`: produces a node without any of the nodes inside it, and never skips implicit whitespace.
+    Atomic,
+
+    /// `${ .. }`: produces a node with the nodes inside it, but never skips implicit whitespace.
+    CompoundAtomic,
+
+    /// `!{ .. }`: like a normal rule, but skips implicit whitespace even inside an atomic rule.
+    NonAtomic,
+}
+
+/// An expression in the body of a rule.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+enum Expression {
+    Literal {
+        text: String,
+        insensitive: bool,
+    },
+
+    /// A single character within any of the ranges, with a name for errors.
+    Class {
+        name: String,
+        ranges: Vec<(char, char)>,
+    },
+    Any,
+    StartOfInput,
+    EndOfInput,
+    Rule(String),
+    Sequence(Vec<Expression>),
+    Choice(Vec<Expression>),
+    Repeat {
+        min: usize,
+        max: usize,
+        expression: Box<Expression>,
+    },
+    Lookahead {
+        negative: bool,
+        expression: Box<Expression>,
+    },
+}
+
+/// A match of a rule that isn't silent, like a pest `Pair`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct PestNode {
+    /// The name of the rule.
+    pub rule: String,
+
+    /// The region of the input that the rule matched.
+    pub span: Span,
+
+    /// The matches of the rules inside this one.
+    pub children: Vec<PestNode>,
+}
+
+/// A grammar read from a `.pest` file, whose rules can be used as parsers.
+///
+/// Rules support the usual modifiers, string and case-insensitive string literals, character ranges, sequences,
+/// ordered choice, repetition (including bounded repetition like `{2, 4}`), lookahead with `&` and `!`, implicit
+/// `WHITESPACE` and `COMMENT` rules, and the `ANY`, `SOI`, `EOI`, `NEWLINE`, and `ASCII_*` built-in rules. The
+/// stack operations (`PUSH`, `POP`, `PEEK`, and `DROP`) and node tags aren't supported.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PestGrammar {
+    /// The rules, in the order they were defined.
+    rules: Vec<(String, RuleKind, Expression)>,
+
+    /// The position of each rule in `rules`.
+    indices: BTreeMap<String, usize>,
+}
+
+impl PestGrammar {
+    /// Read a grammar from the text of a `.pest` file. Errors are reported at offsets within `source`.
+    pub fn new(source: &str) -> Result<Self, ParseError> {
+        let mut grammar = Self {
+            rules: Vec::new(),
+            indices: BTreeMap::new(),
+        };
+        let mut references = Vec::new();
+        let mut name_spans = Vec::new();
+
+        let mut state = skip_trivia(ParserState::new(source));
+        while !state.input.is_empty() {
+            let start = state.index;
+            let (name, new_state) = identifier(state)?;
+            let name_span = Span::new(start, new_state.index);
+            if grammar.indices.contains_key(&name) {
+                return Err(ParseError::invalid(
+                    start,
+                    new_state.index,
+                    &format!("rule `{}` is defined more than once", name),
+                ));
+            }
+            let ((), new_state) = tag(skip_trivia(new_state), "=")?;
+            let new_state = skip_trivia(new_state);
+
+            let (kind, new_state) = match new_state.input.chars().next() {
+                Some('_') => (RuleKind::Silent, new_state.advance(1)),
+                Some('@') => (RuleKind::Atomic, new_state.advance(1)),
+                Some('$') => (RuleKind::CompoundAtomic, new_state.advance(1)),
+                Some('!') => (RuleKind::NonAtomic, new_state.advance(1)),
+                _ => (RuleKind::Normal, new_state),
+            };
+            let ((), new_state) = tag(new_state, "{")?;
+            let (body, new_state) = choice(skip_trivia(new_state), &mut references)?;
+            let ((), new_state) = tag(new_state, "}")?;
+
+            grammar.indices.insert(name.clone(), grammar.rules.len());
+            grammar.rules.push((name, kind, body));
+            name_spans.push(name_span);
+            state = skip_trivia(new_state);
+        }
+
+        // Rules may be used before they are defined, so references are
+        // only checked once every rule has been read.
+        for (name, start) in references {
+            if !grammar.indices.contains_key(&name) {
+                return Err(ParseError::invalid(
+                    start,
+                    start + name.len(),
+                    &format!("rule `{}` is not defined", name),
+                ));
+            }
+        }
+
+        // A rule that can reach itself without consuming anything would
+        // call itself forever, so reject it like pest does.
+        if let Some(cycle) = grammar.left_recursion() {
+            let span = name_spans[cycle[0]];
+            let path = cycle
+                .iter()
+                .map(|index| grammar.rules[*index].0.as_str())
+                .collect::<Vec<&str>>();
+            return Err(ParseError::invalid(
+                span.start,
+                span.end,
+                &format!(
+                    "rule `{}` is left-recursive ({})",
+                    path[0],
+                    path.join(" -> ")
+                ),
+            ));
+        }
+
+        Ok(grammar)
+    }
+
+    /// Find a cycle of rules that call each other without consuming any input, as the indices of the rules in the
+    /// order they are called, starting and ending with the same rule.
+    fn left_recursion(&self) -> Option<Vec<usize>> {
+        // Work out which rules can match without consuming anything,
+        // repeating until nothing changes since rules depend on each other.
+        let mut nullable = vec![false; self.rules.len()];
+        loop {
+            let mut changed = false;
+            for (index, (_, _, body)) in self.rules.iter().enumerate() {
+                if !nullable[index] && self.nullable(body, &nullable) {
+                    nullable[index] = true;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let calls = self
+            .rules
+            .iter()
+            .map(|(_, _, body)| {
+                let mut calls = Vec::new();
+                self.left_calls(body, &nullable, &mut calls);
+                calls
+            })
+            .collect::<Vec<Vec<usize>>>();
+
+        // Depth-first search for a rule that is already on the path.
+        fn visit(
+            index: usize,
+            calls: &[Vec<usize>],
+            path: &mut Vec<usize>,
+            done: &mut [bool],
+        ) -> Option<Vec<usize>> {
+            if let Some(position) = path.iter().position(|on_path| *on_path == index) {
+                let mut cycle = path[position..].to_vec();
+                cycle.push(index);
+                return Some(cycle);
+            }
+            if done[index] {
+                return None;
+            }
+
+            path.push(index);
+            for called in &calls[index] {
+                if let Some(cycle) = visit(*called, calls, path, done) {
+                    return Some(cycle);
+                }
+            }
+            path.pop();
+            done[index] = true;
+            None
+        }
+
+        let mut done = vec![false; self.rules.len()];
+        (0..self.rules.len()).find_map(|index| visit(index, &calls, &mut Vec::new(), &mut done))
+    }
+
+    /// Check whether an expression can match without consuming anything, given which rules can.
+    fn nullable(&self, expression: &Expression, nullable: &[bool]) -> bool {
+        match expression {
+            Expression::Literal { text, .. } => text.is_empty(),
+            Expression::Class { .. } | Expression::Any => false,
+            Expression::StartOfInput | Expression::EndOfInput | Expression::Lookahead { .. } => {
+                true
+            }
+            Expression::Rule(name) => nullable[self.indices[name]],
+            Expression::Sequence(elements) => elements
+                .iter()
+                .all(|element| self.nullable(element, nullable)),
+            Expression::Choice(alternatives) => alternatives
+                .iter()
+                .any(|alternative| self.nullable(alternative, nullable)),
+            Expression::Repeat {
+                min, expression, ..
+            } => *min == 0 || self.nullable(expression, nullable),
+        }
+    }
+
+    /// Find the rules an expression can call before it has consumed anything.
+    fn left_calls(&self, expression: &Expression, nullable: &[bool], calls: &mut Vec<usize>) {
+        match expression {
+            Expression::Rule(name) => calls.push(self.indices[name]),
+            Expression::Sequence(elements) => {
+                for element in elements {
+                    self.left_calls(element, nullable, calls);
+                    if !self.nullable(element, nullable) {
+                        break;
+                    }
+                }
+            }
+            Expression::Choice(alternatives) => {
+                for alternative in alternatives {
+                    self.left_calls(alternative, nullable, calls);
+                }
+            }
+            Expression::Repeat { expression, .. } | Expression::Lookahead { expression, .. } => {
+                self.left_calls(expression, nullable, calls)
+            }
+            _ => {}
+        }
+    }
+
+    /// Get the names of the rules, in the order they were defined.
+    pub fn rule_names(&self) -> impl Iterator<Item = &str> {
+        self.rules.iter().map(|(name, _, _)| name.as_str())
+    }
+
+    /// Get the kind of the rule named `name`.
+    pub fn rule_kind(&self, name: &str) -> Option<RuleKind> {
+        self.indices.get(name).map(|index| self.rules[*index].1)
+    }
+
+    /// Get a parser for the rule named `name`, which produces the rule's node (or the nodes inside it if the rule
+    /// is silent).
+    pub fn rule(&self, name: &str) -> Option<PestRule<'_>> {
+        self.indices.get(name).map(|index| PestRule {
+            grammar: self,
+            index: *index,
+        })
+    }
+
+    /// Run a rule at the current position, adding its nodes to `output`.
+    fn call(
+        &self,
+        index: usize,
+        parser_state: ParserState,
+        atomic: bool,
+        output: &mut Vec<PestNode>,
+        furthest: &mut Option<ParseError>,
+    ) -> ParseResult<ParseError, ()> {
+        let (name, kind, body) = &self.rules[index];
+        let atomic = match kind {
+            RuleKind::Atomic | RuleKind::CompoundAtomic => true,
+            RuleKind::NonAtomic => false,
+            RuleKind::Normal | RuleKind::Silent => atomic,
+        };
+
+        let start = parser_state.index;
+        let found = parser_state.input.chars().next();
+        let mut children = Vec::new();
+        let ((), new_state) = self
+            .evaluate(body, parser_state, atomic, &mut children, furthest)
+            .map_err(|e| {
+                // Name the rule that was expected if it failed right away.
+                if *kind != RuleKind::Silent && e.offset() == start {
+                    let found = found.map(String::from);
+                    ParseError::unexpected(start, [name.as_str()], found.as_deref())
+                } else {
+                    e
+                }
+            })?;
+
+        match kind {
+            RuleKind::Silent => output.extend(children),
+            RuleKind::Atomic => output.push(PestNode {
+                rule: name.clone(),
+                span: Span::new(start, new_state.index),
+                children: Vec::new(),
+            }),
+            _ => output.push(PestNode {
+                rule: name.clone(),
+                span: Span::new(start, new_state.index),
+                children,
+            }),
+        }
+        Ok(((), new_state))
+    }
+
+    /// Match an expression at the current position, adding the nodes of any rules it matched to `output`. Nodes
+    /// from alternatives that were abandoned are removed again.
+    fn evaluate(
+        &self,
+        expression: &Expression,
+        parser_state: ParserState,
+        atomic: bool,
+        output: &mut Vec<PestNode>,
+        furthest: &mut Option<ParseError>,
+    ) -> ParseResult<ParseError, ()> {
+        match expression {
+            Expression::Literal { text, insensitive } => {
+                let length = parser_state
+                    .input
+                    .char_indices()
+                    .nth(text.chars().count())
+                    .map_or(parser_state.input.len(), |(length, _)| length);
+                let candidate = &parser_state.input[..length];
+                let matched = if *insensitive {
+                    candidate.to_lowercase() == text.to_lowercase()
+                } else {
+                    candidate == text
+                };
+
+                if matched {
+                    Ok(((), parser_state.advance(length)))
+                } else {
+                    Err(ParseError::expected_at(&parser_state, text))
+                }
+            }
+            Expression::Class { name, ranges } => match parser_state.input.chars().next() {
+                Some(c)
+                    if ranges
+                        .iter()
+                        .any(|(start, end)| (*start..=*end).contains(&c)) =>
+                {
+                    Ok(((), parser_state.advance(c.len_utf8())))
+                }
+                _ if ranges.len() == 1 => Err(ParseError::expected_at(
+                    &parser_state,
+                    Expected::CharRange(Some(ranges[0].0), Some(ranges[0].1)),
+                )),
+                _ => Err(ParseError::expected_at(&parser_state, name)),
+            },
+            Expression::Any => match parser_state.char(0) {
+                Some((new_state, _)) => Ok(((), new_state)),
+                None => Err(ParseError::expected_at(&parser_state, "any character")),
+            },
+            Expression::StartOfInput if parser_state.previous.is_none() => Ok(((), parser_state)),
+            Expression::StartOfInput => {
+                Err(ParseError::expected_at(&parser_state, "start of input"))
+            }
+            Expression::EndOfInput if parser_state.input.is_empty() => Ok(((), parser_state)),
+            Expression::EndOfInput => Err(ParseError::expected_at(&parser_state, "end of input")),
+            Expression::Rule(name) => {
+                self.call(self.indices[name], parser_state, atomic, output, furthest)
+            }
+            Expression::Sequence(elements) => {
+                let mut state = parser_state;
+                for (index, element) in elements.iter().enumerate() {
+                    if index > 0 {
+                        state = self.skip(state, atomic, output);
+                    }
+                    state = self.evaluate(element, state, atomic, output, furthest)?.1;
+                }
+                Ok(((), state))
+            }
+            Expression::Choice(alternatives) => {
+                let length = output.len();
+                let mut error: Option<ParseError> = None;
+                for alternative in alternatives {
+                    match self.evaluate(alternative, parser_state.clone(), atomic, output, furthest)
+                    {
+                        Ok(result) => {
+                            if let Some(error) = error {
+                                record(furthest, error);
+                            }
+                            return Ok(result);
+                        }
+                        Err(e) => {
+                            output.truncate(length);
+                            error = Some(match error {
+                                Some(error) => error.merge(e),
+                                None => e,
+                            });
+                        }
+                    }
+                }
+                Err(error.unwrap())
+            }
+            Expression::Repeat {
+                min,
+                max,
+                expression,
+            } => {
+                let mut state = parser_state;
+                let mut count = 0;
+                while count < *max {
+                    let length = output.len();
+                    let start = if count > 0 {
+                        self.skip(state.clone(), atomic, output)
+                    } else {
+                        state.clone()
+                    };
+
+                    match self.evaluate(expression, start, atomic, output, furthest) {
+                        // Stop if nothing was consumed, otherwise this would
+                        // repeat forever.
+                        Ok(((), new_state)) if new_state.index == state.index => break,
+                        Ok(((), new_state)) => {
+                            state = new_state;
+                            count += 1;
+                        }
+                        Err(e) if count < *min => return Err(e),
+                        Err(e) => {
+                            output.truncate(length);
+                            record(furthest, e);
+                            break;
+                        }
+                    }
+                }
+
+                Ok(((), state))
+            }
+            Expression::Lookahead {
+                negative,
+                expression,
+            } => {
+                let matched = self
+                    .evaluate(
+                        expression,
+                        parser_state.clone(),
+                        atomic,
+                        &mut Vec::new(),
+                        &mut None,
+                    )
+                    .is_ok();
+                if matched != *negative {
+                    Ok(((), parser_state))
+                } else {
+                    let expected = format!(
+                        "{}{}",
+                        if *negative { "not " } else { "" },
+                        expression_text(
+                            &self.describe_expression(expression, &mut BTreeSet::new())
+                        )
+                    );
+                    Err(ParseError::expected_at(&parser_state, &expected))
+                }
+            }
+        }
+    }
+
+    /// Skip any implicit whitespace and comments, unless inside an atomic rule.
+    fn skip(
+        &self,
+        parser_state: ParserState,
+        atomic: bool,
+        output: &mut Vec<PestNode>,
+    ) -> ParserState {
+        if atomic {
+            return parser_state;
+        }
+
+        let implicit = ["WHITESPACE", "COMMENT"]
+            .iter()
+            .filter_map(|name| self.indices.get(*name).copied())
+            .collect::<Vec<usize>>();
+        let mut state = parser_state;
+        'skip: loop {
+            for index in &implicit {
+                let length = output.len();
+                match self.call(*index, state.clone(), true, output, &mut None) {
+                    Ok(((), new_state)) if new_state.index > state.index => {
+                        state = new_state;
+                        continue 'skip;
+                    }
+                    _ => output.truncate(length),
+                }
+            }
+            return state;
+        }
+    }
+
+    /// Describe an expression, writing out the body of each rule the first time it is seen and referring to it by
+    /// name after that.
+    fn describe_expression(&self, expression: &Expression, seen: &mut BTreeSet<String>) -> Grammar {
+        match expression {
+            Expression::Literal {
+                text,
+                insensitive: false,
+            } => Grammar::Literal(text.clone()),
+            Expression::Literal { text, .. } => Grammar::Special(format!("^{:?}", text)),
+            Expression::Class { ranges, .. } => {
+                Grammar::Class(ParseCharClass::new(ranges.clone()).to_string())
+            }
+            Expression::Any => Grammar::char_range(None, None),
+            Expression::StartOfInput => Grammar::Special(String::from("start of input")),
+            Expression::EndOfInput => Grammar::Special(String::from("end of input")),
+            Expression::Rule(name) => self.describe_rule(self.indices[name], seen),
+            Expression::Sequence(elements) => Grammar::sequence(
+                elements
+                    .iter()
+                    .map(|element| self.describe_expression(element, seen)),
+            ),
+            Expression::Choice(alternatives) => Grammar::choice(
+                alternatives
+                    .iter()
+                    .map(|alternative| self.describe_expression(alternative, seen)),
+            ),
+            Expression::Repeat {
+                min,
+                max,
+                expression,
+            } => Grammar::repeat(*min, *max, self.describe_expression(expression, seen)),
+            Expression::Lookahead {
+                negative,
+                expression,
+            } => Grammar::Special(format!(
+                "{} {}",
+                if *negative {
+                    "not followed by"
+                } else {
+                    "followed by"
+                },
+                expression_text(&self.describe_expression(expression, seen))
+            )),
+        }
+    }
+
+    /// Describe a rule, or refer to it by name if it has already been described.
+    fn describe_rule(&self, index: usize, seen: &mut BTreeSet<String>) -> Grammar {
+        let (name, _, body) = &self.rules[index];
+        if seen.insert(name.clone()) {
+            Grammar::rule(name, self.describe_expression(body, seen))
+        } else {
+            Grammar::Reference(name.clone())
+        }
+    }
+}
+
+/// Keep whichever of `error` and the furthest failure so far got further into the input.
+fn record(furthest: &mut Option<ParseError>, error: ParseError) {
+    *furthest = Some(match furthest.take() {
+        Some(furthest) => furthest.merge(error),
+        None => error,
+    });
+}
+
+/// A parser for one rule of a [`PestGrammar`].
+#[derive(Debug, Copy, Clone)]
+pub struct PestRule<'g> {
+    grammar: &'g PestGrammar,
+    index: usize,
+}
+
+impl Parser<Vec<PestNode>, ParseError> for PestRule<'_> {
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, Vec<PestNode>> {
+        let mut output = Vec::new();
+        let mut furthest = None;
+        match self
+            .grammar
+            .call(self.index, parser_state, false, &mut output, &mut furthest)
+        {
+            Ok(((), new_state)) => Ok((output, new_state)),
+
+            // Failures that were recovered from may have gotten further,
+            // which usually explains the problem better.
+            Err(e) => Err(match furthest {
+                Some(furthest) => e.merge(furthest),
+                None => e,
+            }),
+        }
+    }
+
+    fn describe(&self) -> Grammar {
+        self.grammar.describe_rule(self.index, &mut BTreeSet::new())
+    }
+}
+
+/// Skip whitespace and comments in a grammar file.
+fn skip_trivia(parser_state: ParserState) -> ParserState {
+    let mut state = parser_state;
+    loop {
+        let trimmed = state.input.trim_start();
+        state = state.advance(state.input.len() - trimmed.len());
+        if state.input.starts_with("//") {
+            let length = state.input.find('\n').unwrap_or(state.input.len());
+            state = state.advance(length);
+        } else if state.input.starts_with("/*") {
+            let length = state
+                .input
+                .find("*/")
+                .map_or(state.input.len(), |end| end + 2);
+            state = state.advance(length);
+        } else {
+            return state;
+        }
+    }
+}
+
+/// Read a rule name.
+fn identifier(parser_state: ParserState) -> ParseResult<ParseError, String> {
+    if !parser_state
+        .input
+        .starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+    {
+        return Err(ParseError::expected_at(&parser_state, "rule name"));
+    }
+    take_while(parser_state, 1, "rule name", |c| {
+        c.is_ascii_alphanumeric() || c == '_'
+    })
+}
+
+/// Read alternatives separated by `|`, allowing a leading `|`.
+fn choice(
+    parser_state: ParserState,
+    references: &mut Vec<(String, usize)>,
+) -> ParseResult<ParseError, Expression> {
+    let mut state = match tag(parser_state.clone(), "|") {
+        Ok(((), state)) => skip_trivia(state),
+        Err(_) => parser_state,
+    };
+
+    let mut alternatives = Vec::new();
+    loop {
+        let (alternative, new_state) = sequence(state, references)?;
+        alternatives.push(alternative);
+        match tag(new_state.clone(), "|") {
+            Ok(((), next_state)) => state = skip_trivia(next_state),
+            Err(_) => {
+                let expression = if alternatives.len() == 1 {
+                    alternatives.pop().unwrap()
+                } else {
+                    Expression::Choice(alternatives)
+                };
+                return Ok((expression, new_state));
+            }
+        }
+    }
+}
+
+/// Read terms separated by `~`.
+fn sequence(
+    parser_state: ParserState,
+    references: &mut Vec<(String, usize)>,
+) -> ParseResult<ParseError, Expression> {
+    let mut state = parser_state;
+    let mut elements = Vec::new();
+    loop {
+        let (element, new_state) = term(state, references)?;
+        elements.push(element);
+        match tag(new_state.clone(), "~") {
+            Ok(((), next_state)) => state = skip_trivia(next_state),
+            Err(_) => {
+                let expression = if elements.len() == 1 {
+                    elements.pop().unwrap()
+                } else {
+                    Expression::Sequence(elements)
+                };
+                return Ok((expression, new_state));
+            }
+        }
+    }
+}
+
+/// Read a term with any lookahead prefixes and repetition suffixes, skipping trivia after it.
+fn term(
+    parser_state: ParserState,
+    references: &mut Vec<(String, usize)>,
+) -> ParseResult<ParseError, Expression> {
+    // Prefixes apply from the innermost outwards.
+    let mut prefixes = Vec::new();
+    let mut state = parser_state;
+    while let Some(negative @ ('!' | '&')) = state.input.chars().next() {
+        prefixes.push(negative == '!');
+        state = skip_trivia(state.advance(1));
+    }
+
+    let (mut expression, mut state) = atom(state, references)?;
+    loop {
+        state = skip_trivia(state);
+        let (min, max, new_state) = match state.input.chars().next() {
+            Some('?') => (0, 1, state.advance(1)),
+            Some('*') => (0, usize::MAX, state.advance(1)),
+            Some('+') => (1, usize::MAX, state.advance(1)),
+            Some('{') => {
+                let ((min, max), new_state) = bounds(skip_trivia(state.advance(1)))?;
+                (min, max, new_state)
+            }
+            _ => break,
+        };
+        expression = Expression::Repeat {
+            min,
+            max,
+            expression: Box::new(expression),
+        };
+        state = new_state;
+    }
+
+    for negative in prefixes.into_iter().rev() {
+        expression = Expression::Lookahead {
+            negative,
+            expression: Box::new(expression),
+        };
+    }
+    Ok((expression, state))
+}
+
+/// Read the bounds of a repetition like `{2}`, `{2,}`, `{,4}`, or `{2,4}` after the `{`.
+fn bounds(parser_state: ParserState) -> ParseResult<ParseError, (usize, usize)> {
+    let number = |state: ParserState| -> ParseResult<ParseError, Option<usize>> {
+        let start = state.index;
+        let (digits, new_state) = take_while(state, 0, "number", |c| c.is_ascii_digit())?;
+        if digits.is_empty() {
+            return Ok((None, new_state));
+        }
+        match digits.parse() {
+            Ok(number) => Ok((Some(number), skip_trivia(new_state))),
+            Err(_) => Err(ParseError::invalid(
+                start,
+                new_state.index,
+                "repetition count is too large",
+            )),
+        }
+    };
+
+    let start = parser_state.index;
+    let (min, state) = number(parser_state)?;
+    let (max, state) = match tag(state.clone(), ",") {
+        Ok(((), state)) => {
+            let (max, state) = number(skip_trivia(state))?;
+            (max.unwrap_or(usize::MAX), state)
+        }
+        Err(_) if min.is_some() => (min.unwrap(), state),
+        Err(e) => return Err(e),
+    };
+    let ((), state) = tag(state, "}")?;
+
+    let min = min.unwrap_or(0);
+    if min > max {
+        return Err(ParseError::invalid(
+            start,
+            state.index,
+            "repetition minimum is larger than its maximum",
+        ));
+    }
+    Ok(((min, max), state))
+}
+
+/// Read a parenthesized expression, literal, character range, or rule name.
+fn atom(
+    parser_state: ParserState,
+    references: &mut Vec<(String, usize)>,
+) -> ParseResult<ParseError, Expression> {
+    let state = parser_state;
+    match state.input.chars().next() {
+        Some('(') => {
+            let (expression, new_state) = choice(skip_trivia(state.advance(1)), references)?;
+            let ((), new_state) = tag(new_state, ")")?;
+            Ok((expression, new_state))
+        }
+        Some('"') => {
+            let (text, new_state) = quoted(state, '"')?;
+            Ok((
+                Expression::Literal {
+                    text,
+                    insensitive: false,
+                },
+                new_state,
+            ))
+        }
+        Some('^') => {
+            let (text, new_state) = quoted(skip_trivia(state.advance(1)), '"')?;
+            Ok((
+                Expression::Literal {
+                    text,
+                    insensitive: true,
+                },
+                new_state,
+            ))
+        }
+        Some('\'') => {
+            let start = state.index;
+            let (first, new_state) = character(state)?;
+            let range_state = skip_trivia(new_state.clone());
+            match tag(range_state, "..") {
+                Ok(((), range_state)) => {
+                    let (last, new_state) = character(skip_trivia(range_state))?;
+                    if first > last {
+                        return Err(ParseError::invalid(
+                            start,
+                            new_state.index,
+                            "character range starts after it ends",
+                        ));
+                    }
+                    Ok((
+                        Expression::Class {
+                            name: format!("{:?}..{:?}", first, last),
+                            ranges: vec![(first, last)],
+                        },
+                        new_state,
+                    ))
+                }
+                Err(_) => Ok((
+                    Expression::Literal {
+                        text: first.to_string(),
+                        insensitive: false,
+                    },
+                    new_state,
+                )),
+            }
+        }
+        _ => {
+            let start = state.index;
+            let (name, new_state) = identifier(state)?;
+            let expression = match builtin(&name) {
+                Some(expression) => expression,
+                None if matches!(
+                    name.as_str(),
+                    "PUSH" | "POP" | "POP_ALL" | "PEEK" | "PEEK_ALL" | "DROP"
+                ) =>
+                {
+                    return Err(ParseError::invalid(
+                        start,
+                        new_state.index,
+                        "stack operations aren't supported",
+                    ));
+                }
+                None => {
+                    references.push((name.clone(), start));
+                    Expression::Rule(name)
+                }
+            };
+            Ok((expression, new_state))
+        }
+    }
+}
+
+/// Get the expression for a built-in rule.
+fn builtin(name: &str) -> Option<Expression> {
+    let ranges: &[(char, char)] = match name {
+        "ANY" => return Some(Expression::Any),
+        "SOI" => return Some(Expression::StartOfInput),
+        "EOI" => return Some(Expression::EndOfInput),
+        "NEWLINE" => {
+            return Some(Expression::Choice(
+                ["\n", "\r\n", "\r"]
+                    .iter()
+                    .map(|text| Expression::Literal {
+                        text: String::from(*text),
+                        insensitive: false,
+                    })
+                    .collect(),
+            ))
+        }
+        "ASCII_DIGIT" => &[('0', '9')],
+        "ASCII_NONZERO_DIGIT" => &[('1', '9')],
+        "ASCII_BIN_DIGIT" => &[('0', '1')],
+        "ASCII_OCT_DIGIT" => &[('0', '7')],
+        "ASCII_HEX_DIGIT" => &[('0', '9'), ('A', 'F'), ('a', 'f')],
+        "ASCII_ALPHA_LOWER" => &[('a', 'z')],
+        "ASCII_ALPHA_UPPER" => &[('A', 'Z')],
+        "ASCII_ALPHA" => &[('A', 'Z'), ('a', 'z')],
+        "ASCII_ALPHANUMERIC" => &[('0', '9'), ('A', 'Z'), ('a', 'z')],
+        "ASCII" => &[('\0', '\x7f')],
+        _ => return None,
+    };
+    Some(Expression::Class {
+        name: String::from(name),
+        ranges: ranges.to_vec(),
+    })
+}
+
+/// Read a single-quoted character.
+fn character(parser_state: ParserState) -> ParseResult<ParseError, char> {
+    let start = parser_state.index;
+    let (text, new_state) = quoted(parser_state, '\'')?;
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok((c, new_state)),
+        _ => Err(ParseError::invalid(
+            start,
+            new_state.index,
+            "character literals must hold exactly one character",
+        )),
+    }
+}
+
+/// Read text between `quote`s, replacing escapes.
+fn quoted(parser_state: ParserState, quote: char) -> ParseResult<ParseError, String> {
+    let ((), mut state) = tag(parser_state, &quote.to_string())?;
+    let mut text = String::new();
+    loop {
+        let escape_start = state.index;
+        let (c, new_state) = state
+            .char(0)
+            .map(|(new_state, c)| (c, new_state))
+            .ok_or_else(|| ParseError::expected_at(&state, quote))?;
+        state = new_state;
+
+        match c {
+            c if c == quote => return Ok((text, state)),
+            '\\' => {
+                let (escaped, new_state) = state
+                    .char(0)
+                    .map(|(new_state, c)| (c, new_state))
+                    .ok_or_else(|| ParseError::expected_at(&state, "escape"))?;
+                state = new_state;
+                text.push(match escaped {
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    '0' => '\0',
+                    'u' => {
+                        let ((), new_state) = tag(state, "{")?;
+                        let (digits, new_state) =
+                            take_while(new_state, 1, "hex digit", |c| c.is_ascii_hexdigit())?;
+                        let ((), new_state) = tag(new_state, "}")?;
+                        state = new_state;
+                        u32::from_str_radix(&digits, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| {
+                                ParseError::invalid(
+                                    escape_start,
+                                    state.index,
+                                    "escape is not a valid character",
+                                )
+                            })?
+                    }
+                    '\\' | '"' | '\'' => escaped,
+                    _ => {
+                        return Err(ParseError::invalid(
+                            escape_start,
+                            state.index,
+                            "unknown escape",
+                        ))
+                    }
+                });
+            }
+            c => text.push(c),
+        }
+    }
+}
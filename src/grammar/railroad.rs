@@ -0,0 +1,376 @@
+use crate::grammar::Grammar;
+use std::fmt::Write;
+
+/// The radius of the curves where lines branch and join.
+const ARC: i64 = 10;
+
+/// The approximate width of one character of text in a box.
+const CHAR_WIDTH: i64 = 8;
+
+/// The space between the text and the sides of a box.
+const BOX_PADDING: i64 = 10;
+
+/// The distance from the line running through a box to its top and bottom.
+const BOX_HALF_HEIGHT: i64 = 11;
+
+/// The space between elements of a sequence.
+const GAP: i64 = 10;
+
+/// The space between alternatives stacked on top of each other.
+const SPACING: i64 = 8;
+
+/// The height of the text under a loop saying how many times it repeats.
+const LABEL_HEIGHT: i64 = 14;
+
+/// The space around the whole diagram.
+const MARGIN: i64 = 20;
+
+/// The styles used by the diagrams.
+const STYLE: &str = "path { fill: none; stroke: #333; stroke-width: 1.5; } \
+rect { fill: #fff; stroke: #333; stroke-width: 1.5; } \
+.special rect { stroke-dasharray: 4 2; } \
+text { font: 13px monospace; text-anchor: middle; } \
+.label { font-size: 11px; fill: #666; }";
+
+/// The size of a diagram element, measured from where its line enters on the left.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct Size {
+    width: i64,
+
+    /// The height above the line.
+    up: i64,
+
+    /// The height below the line.
+    down: i64,
+}
+
+/// Render a grammar as a standalone SVG railroad diagram. If the grammar is a rule, its body is drawn; any other
+/// rules within it are drawn as boxes containing their names.
+pub fn svg(grammar: &Grammar) -> String {
+    let grammar = match grammar {
+        Grammar::Rule { body, .. } => body,
+        grammar => grammar,
+    };
+
+    // Leave room for the markers at the start and end of the line.
+    let size = measure(grammar);
+    let up = size.up.max(BOX_HALF_HEIGHT);
+    let width = size.width + 2 * GAP + 2 * MARGIN;
+    let height = up + size.down.max(BOX_HALF_HEIGHT) + 2 * MARGIN;
+    let y = MARGIN + up;
+
+    let mut out = String::new();
+    write!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\
+         <style>{2}</style>",
+        width, height, STYLE
+    )
+    .unwrap();
+    path(
+        &mut out,
+        &format!(
+            "M{0} {1}v{2}M{0} {3}h{4}M{5} {3}h{4}M{6} {1}v{2}",
+            MARGIN,
+            y - BOX_HALF_HEIGHT,
+            2 * BOX_HALF_HEIGHT,
+            y,
+            GAP,
+            MARGIN + GAP + size.width,
+            width - MARGIN,
+        ),
+    );
+    draw(grammar, MARGIN + GAP, y, &mut out);
+    out.push_str("</svg>");
+    out
+}
+
+/// Render every named rule in a grammar as an HTML page with a heading and railroad diagram for each rule. If the
+/// grammar itself isn't a rule, it is drawn first without a heading.
+pub fn html(grammar: &Grammar) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Grammar</title>\n</head>\n<body>\n",
+    );
+
+    if !matches!(grammar, Grammar::Rule { .. }) {
+        writeln!(out, "{}", svg(grammar)).unwrap();
+    }
+    for (name, body) in grammar.rules() {
+        writeln!(out, "<h2>{}</h2>\n{}", escape(name), svg(body)).unwrap();
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Measure the size of the diagram for a grammar.
+fn measure(grammar: &Grammar) -> Size {
+    match grammar {
+        Grammar::Empty | Grammar::Repeat { max: 0, .. } => Size {
+            width: 0,
+            up: 0,
+            down: 0,
+        },
+
+        Grammar::Sequence(elements) => elements
+            .iter()
+            .map(measure)
+            .fold(None, |total: Option<Size>, size| {
+                Some(match total {
+                    Some(total) => Size {
+                        width: total.width + GAP + size.width,
+                        up: total.up.max(size.up),
+                        down: total.down.max(size.down),
+                    },
+                    None => size,
+                })
+            })
+            .unwrap_or(Size {
+                width: 0,
+                up: 0,
+                down: 0,
+            }),
+
+        Grammar::Choice(alternatives) => {
+            let sizes = alternatives.iter().map(measure).collect::<Vec<Size>>();
+            let width = sizes.iter().map(|size| size.width).max().unwrap_or(0);
+            let offsets = choice_offsets(&sizes);
+            Size {
+                width: width + 4 * ARC,
+                up: sizes.first().map_or(0, |size| size.up),
+                down: offsets.last().copied().unwrap_or(0)
+                    + sizes.last().map_or(0, |size| size.down),
+            }
+        }
+
+        Grammar::Repeat { min, max, element } => {
+            let mut size = measure(element);
+            if *max > 1 {
+                size = Size {
+                    width: size.width + 4 * ARC,
+                    up: size.up,
+                    down: loop_offset(size) + repeat_label(*min, *max).map_or(0, |_| LABEL_HEIGHT),
+                };
+            }
+            if *min == 0 {
+                size = Size {
+                    width: size.width + 4 * ARC,
+                    up: skip_offset(size),
+                    down: size.down,
+                };
+            }
+            size
+        }
+
+        grammar => Size {
+            width: box_text(grammar).1.chars().count() as i64 * CHAR_WIDTH + 2 * BOX_PADDING,
+            up: BOX_HALF_HEIGHT,
+            down: BOX_HALF_HEIGHT,
+        },
+    }
+}
+
+/// Draw the diagram for a grammar with its line entering at `x` and `y`.
+fn draw(grammar: &Grammar, x: i64, y: i64, out: &mut String) {
+    match grammar {
+        Grammar::Empty | Grammar::Repeat { max: 0, .. } => {}
+
+        Grammar::Sequence(elements) => {
+            let mut x = x;
+            for (index, element) in elements.iter().enumerate() {
+                if index > 0 {
+                    path(out, &format!("M{} {}h{}", x, y, GAP));
+                    x += GAP;
+                }
+                draw(element, x, y, out);
+                x += measure(element).width;
+            }
+        }
+
+        Grammar::Choice(alternatives) => {
+            let sizes = alternatives.iter().map(measure).collect::<Vec<Size>>();
+            let end = x + measure(grammar).width;
+
+            for ((alternative, size), offset) in
+                alternatives.iter().zip(&sizes).zip(choice_offsets(&sizes))
+            {
+                if offset == 0 {
+                    // The first alternative stays on the main line.
+                    path(out, &format!("M{} {}h{}", x, y, 2 * ARC));
+                } else {
+                    // The others branch off below it and rejoin on the other side.
+                    path(
+                        out,
+                        &format!(
+                            "M{} {}a{2} {2} 0 0 1 {2} {2}V{3}a{2} {2} 0 0 0 {2} {2}",
+                            x,
+                            y,
+                            ARC,
+                            y + offset - ARC
+                        ),
+                    );
+                    path(
+                        out,
+                        &format!(
+                            "M{} {}H{}a{3} {3} 0 0 0 {3} -{3}V{4}a{3} {3} 0 0 1 {3} -{3}",
+                            x + 2 * ARC + size.width,
+                            y + offset,
+                            end - 2 * ARC,
+                            ARC,
+                            y + ARC
+                        ),
+                    );
+                }
+                draw(alternative, x + 2 * ARC, y + offset, out);
+                if offset == 0 {
+                    path(out, &format!("M{} {}H{}", x + 2 * ARC + size.width, y, end));
+                }
+            }
+        }
+
+        Grammar::Repeat { min, max, element } => {
+            let element_size = measure(element);
+            let mut x = x;
+
+            if *min == 0 {
+                // Draw a line that skips over the element.
+                let inner_width = measure(grammar).width - 4 * ARC;
+                let top = y - skip_offset(Size {
+                    width: inner_width,
+                    up: element_size.up,
+                    down: element_size.down,
+                });
+                path(
+                    out,
+                    &format!(
+                        "M{0} {1}h{2}M{3} {1}h{2}M{0} {1}a{4} {4} 0 0 0 {4} -{4}V{5}a{4} {4} 0 0 1 {4} -{4}\
+                         H{6}a{4} {4} 0 0 1 {4} {4}V{7}a{4} {4} 0 0 0 {4} {4}",
+                        x,
+                        y,
+                        2 * ARC,
+                        x + 2 * ARC + inner_width,
+                        ARC,
+                        top + ARC,
+                        x + 2 * ARC + inner_width,
+                        y - ARC,
+                    ),
+                );
+                x += 2 * ARC;
+            }
+
+            if *max > 1 {
+                // Draw a line that loops back from the end of the element to its start.
+                let bottom = y + loop_offset(element_size);
+                path(
+                    out,
+                    &format!(
+                        "M{0} {1}h{2}M{3} {1}h{2}M{3} {1}a{4} {4} 0 0 1 {4} {4}V{5}a{4} {4} 0 0 1 -{4} {4}\
+                         H{6}a{4} {4} 0 0 1 -{4} -{4}V{7}a{4} {4} 0 0 1 {4} -{4}",
+                        x,
+                        y,
+                        2 * ARC,
+                        x + 2 * ARC + element_size.width,
+                        ARC,
+                        bottom - ARC,
+                        x + 2 * ARC,
+                        y + ARC,
+                    ),
+                );
+                if let Some(label) = repeat_label(*min, *max) {
+                    write!(
+                        out,
+                        "<text class=\"label\" x=\"{}\" y=\"{}\">{}</text>",
+                        x + 2 * ARC + element_size.width / 2,
+                        bottom + LABEL_HEIGHT - 2,
+                        label
+                    )
+                    .unwrap();
+                }
+                x += 2 * ARC;
+            }
+
+            draw(element, x, y, out);
+        }
+
+        grammar => {
+            let (class, text) = box_text(grammar);
+            let width = measure(grammar).width;
+            write!(
+                out,
+                "<g class=\"{}\"><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\"/>\
+                 <text x=\"{}\" y=\"{}\">{}</text></g>",
+                class,
+                x,
+                y - BOX_HALF_HEIGHT,
+                width,
+                2 * BOX_HALF_HEIGHT,
+                if class == "terminal" { ARC } else { 0 },
+                x + width / 2,
+                y + 4,
+                escape(&text)
+            )
+            .unwrap();
+        }
+    }
+}
+
+/// Get the class and text of the box drawn for a grammar that isn't drawn with lines.
+fn box_text(grammar: &Grammar) -> (&'static str, String) {
+    match grammar {
+        Grammar::Literal(text) => ("terminal", format!("{:?}", text)),
+        Grammar::Class(class) => ("terminal", class.clone()),
+        Grammar::Rule { name, .. } | Grammar::Reference(name) => ("nonterminal", name.clone()),
+        Grammar::Special(description) => ("special", description.clone()),
+        _ => ("special", String::from("...")),
+    }
+}
+
+/// Get the distance below the main line of each alternative of a choice.
+fn choice_offsets(sizes: &[Size]) -> Vec<i64> {
+    let mut offsets = Vec::with_capacity(sizes.len());
+    let mut offset = 0;
+    for (index, size) in sizes.iter().enumerate() {
+        if index > 0 {
+            // Leave room for the curves even if the alternatives are thin.
+            offset += (sizes[index - 1].down + SPACING + size.up).max(2 * ARC);
+        }
+        offsets.push(offset);
+    }
+    offsets
+}
+
+/// Get the distance below the main line of the line looping back around an element.
+fn loop_offset(element: Size) -> i64 {
+    (element.down + SPACING).max(2 * ARC)
+}
+
+/// Get the distance above the main line of the line skipping over an element.
+fn skip_offset(element: Size) -> i64 {
+    (element.up + SPACING).max(2 * ARC)
+}
+
+/// Describe how many times a looping repetition repeats, unless the loop itself says enough.
+fn repeat_label(min: usize, max: usize) -> Option<String> {
+    if max == usize::MAX {
+        (min > 1).then(|| format!("at least {} times", min))
+    } else if min == max {
+        Some(format!("{} times", max))
+    } else if min <= 1 {
+        Some(format!("at most {} times", max))
+    } else {
+        Some(format!("{} to {} times", min, max))
+    }
+}
+
+/// Add a line to the diagram.
+fn path(out: &mut String, data: &str) {
+    write!(out, "<path d=\"{}\"/>", data).unwrap();
+}
+
+/// Escape text so that it can be included in SVG and HTML.
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,133 @@
+use crate::grammar::ebnf::identifier;
+use crate::grammar::{Grammar, ANY_CHARACTER};
+use crate::parsers::utf8::char_class;
+use std::fmt::Write;
+
+/// The largest number of optional copies written out for a bounded repetition. Repetitions allowing more than this
+/// are written as unbounded.
+const MAX_OPTIONAL_COPIES: usize = 8;
+
+/// Write a grammar as the `grammar.js` file of a tree-sitter grammar called `name`, with a rule for each named rule.
+/// If the grammar itself isn't a rule, it is written first as a rule named `root`, since tree-sitter starts from
+/// the first rule.
+///
+/// The result is a starting point rather than a finished grammar: tree-sitter skips whitespace between tokens by
+/// default, parsers that don't describe themselves become `blank()` with a comment to fill them in, and positions
+/// like the end of the input are left as comments.
+pub fn grammar_js(name: &str, grammar: &Grammar) -> String {
+    let mut rules = grammar
+        .rules()
+        .into_iter()
+        .map(|(name, body)| (identifier(name), body))
+        .collect::<Vec<(String, &Grammar)>>();
+    if !matches!(grammar, Grammar::Rule { .. }) {
+        rules.insert(0, (String::from("root"), grammar));
+    }
+
+    let mut out = format!(
+        "module.exports = grammar({{\n  name: '{}',\n\n  rules: {{\n",
+        identifier(name)
+    );
+    for (name, body) in rules {
+        let mut definition = String::new();
+        expression(body, &mut definition);
+        writeln!(out, "    {}: $ => {},\n", name, definition).unwrap();
+    }
+    out.truncate(out.len() - 1);
+    out.push_str("  }\n});\n");
+    out
+}
+
+/// Write an expression using the tree-sitter DSL functions.
+fn expression(grammar: &Grammar, out: &mut String) {
+    match grammar {
+        Grammar::Opaque => out.push_str("blank() /* TODO: describe this parser */"),
+        Grammar::Empty | Grammar::Repeat { max: 0, .. } => out.push_str("blank()"),
+        Grammar::Literal(text) => write!(out, "{:?}", text).unwrap(),
+        Grammar::Class(spec) => class(spec, out),
+        Grammar::Special(description) if description == ANY_CHARACTER => out.push_str("/[\\s\\S]/"),
+        Grammar::Special(description) => {
+            write!(out, "blank() /* {} */", description.replace("*/", "* /")).unwrap()
+        }
+        Grammar::Sequence(elements) => call("seq", elements.iter(), out),
+        Grammar::Choice(alternatives) => call("choice", alternatives.iter(), out),
+        Grammar::Repeat { min, max, element } => match (*min, *max) {
+            (0, 1) => call("optional", [&**element], out),
+            (0, usize::MAX) => call("repeat", [&**element], out),
+            (1, usize::MAX) => call("repeat1", [&**element], out),
+            (1, 1) => expression(element, out),
+
+            // tree-sitter has no bounded repetition, so write out the
+            // required copies followed by the optional ones.
+            (min, max) => {
+                out.push_str("seq(");
+                for index in 0..min {
+                    if index > 0 {
+                        out.push_str(", ");
+                    }
+                    expression(element, out);
+                }
+                let optional = max - min;
+                if min > 0 && optional > 0 {
+                    out.push_str(", ");
+                }
+                if optional > MAX_OPTIONAL_COPIES {
+                    call("repeat", [&**element], out);
+                } else {
+                    for index in 0..optional {
+                        if index > 0 {
+                            out.push_str(", ");
+                        }
+                        call("optional", [&**element], out);
+                    }
+                }
+                out.push(')');
+            }
+        },
+        Grammar::Rule { name, .. } | Grammar::Reference(name) => {
+            write!(out, "$.{}", identifier(name)).unwrap()
+        }
+    }
+}
+
+/// Write a call to one of the tree-sitter DSL functions.
+fn call<'a>(function: &str, arguments: impl IntoIterator<Item = &'a Grammar>, out: &mut String) {
+    write!(out, "{}(", function).unwrap();
+    for (index, argument) in arguments.into_iter().enumerate() {
+        if index > 0 {
+            out.push_str(", ");
+        }
+        expression(argument, out);
+    }
+    out.push(')');
+}
+
+/// Write a character class as a regular expression, escaping anything that isn't a letter or digit.
+fn class(spec: &str, out: &mut String) {
+    let class = match char_class(spec) {
+        Ok(class) => class,
+        Err(_) => {
+            out.push_str("blank() /* TODO: unreadable character class */");
+            return;
+        }
+    };
+
+    fn escape(c: char, out: &mut String) {
+        match c {
+            c if c.is_ascii_alphanumeric() => out.push(c),
+            c if c.is_ascii_graphic() => write!(out, "\\{}", c).unwrap(),
+            c if (c as u32) <= 0xFFFF => write!(out, "\\u{:04X}", c as u32).unwrap(),
+            c => write!(out, "\\u{{{:X}}}", c as u32).unwrap(),
+        }
+    }
+
+    out.push_str(if class.negated { "/[^" } else { "/[" });
+    for (start, end) in &class.ranges {
+        escape(*start, out);
+        if start != end {
+            out.push('-');
+            escape(*end, out);
+        }
+    }
+    out.push_str("]/");
+}
@@ -1,25 +1,92 @@
+use crate::grammar::Grammar;
+use crate::messages::{DefaultMessages, ErrorMessage, MessageProvider};
+use crate::span::Span;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// Default implementations for a few different types of parsers.
 pub mod parsers;
 
+/// Human-readable reports of parsing errors.
+pub mod diagnostics;
+
+/// The wording of error messages, which applications can replace to translate or rephrase them.
+pub mod messages;
+
+/// Types for tracking which region of the input a value came from.
+pub mod span;
+
+/// Parsing inputs one line at a time and finding the lines of positions in an input.
+pub mod lines;
+
+/// Converting between byte indices and the line and character positions used by language servers.
+pub mod lsp;
+
+/// Ready-made parsers for common text and binary formats.
+pub mod formats;
+
+/// Parsers for binary inputs that borrow from the input instead of copying it.
+pub mod bytes;
+
+/// Parsing text stored in encodings other than UTF-8.
+pub mod encoding;
+
+/// Describing the input parsers accept and exporting those descriptions.
+pub mod grammar;
+
+/// Harnesses for fuzzing parsers.
+pub mod fuzzing;
+
+/// Checking parsers against directories of inputs with stored expected results.
+pub mod corpus;
+
+/// Grammars assembled at runtime from parsers registered by name.
+pub mod registry;
+
+/// Deserializing values with serde straight from the output of parsers.
+#[cfg(feature = "serde")]
+pub mod deserialize;
+
 /// Parser testing utilities.
 #[cfg(test)]
 mod tests;
 
 /// A structure that contains all of the data for the current location and data for the parsing run.
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+///
+/// Parsers take the state they start from and return the state just past what they matched. A parser that matches
+/// without consuming anything, such as an anchor or a lookahead, returns the state it was given unchanged. A parser
+/// that fails returns no state at all, so whoever called it carries on from the state they already had.
+#[derive(Debug, Clone)]
 pub struct ParserState {
     input: String,
     index: usize,
+
+    /// The character just before the current position, or `None` at the start of the input.
+    previous: Option<char>,
+
+    /// How much information parsers should collect when building errors.
+    verbosity: ErrorVerbosity,
+
+    /// Identifies the parsing run, shared by every state reached from the same starting state. States from
+    /// different runs can have the same index but different input.
+    run: u64,
 }
 
+/// The identifier of the next parsing run to start.
+static NEXT_RUN: AtomicU64 = AtomicU64::new(1);
+
 impl ParserState {
     pub fn new_offset(input: &str, index: usize) -> Self {
         Self {
             input: String::from(input),
             index,
+            previous: None,
+            verbosity: ErrorVerbosity::Rich,
+            run: NEXT_RUN.fetch_add(1, Ordering::Relaxed),
         }
     }
 
@@ -31,49 +98,624 @@ impl ParserState {
         &self.input
     }
 
+    /// Set how much information parsers should collect when building errors from this state onwards.
+    pub fn with_verbosity(mut self, verbosity: ErrorVerbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    /// Get how much information parsers should collect when building errors.
+    pub fn verbosity(&self) -> ErrorVerbosity {
+        self.verbosity
+    }
+
     pub fn char(&self, offset: usize) -> Option<(Self, char)> {
-        if offset >= self.input.len() {
-            None
+        // Find the character by counting characters rather than bytes, so
+        // that multi-byte characters are handled.
+        let (start, char_at) = self.input.char_indices().nth(offset)?;
+        Some((self.advance(start + char_at.len_utf8()), char_at))
+    }
+
+    pub fn chars(&self, count: usize) -> Option<Vec<char>> {
+        let chars = self.input.chars().take(count).collect::<Vec<char>>();
+        if chars.len() == count {
+            Some(chars)
         } else {
-            Some((
-                Self {
-                    input: String::from(&self.input[(offset + 1)..]),
-                    index: self.index + offset + 1,
-                },
-                self.input.chars().take(offset + 1).last().unwrap(),
-            ))
+            None
         }
     }
 
-    pub fn chars(&self, count: usize) -> Option<Vec<char>> {
-        if self.input.len() >= count {
-            Some(self.input.chars().take(count).collect())
+    /// Get the state at the absolute `index`, or `None` if it is before the current position, past the end of the
+    /// input, or inside a character. This is how parsing resumes from where an error occurred.
+    pub fn at(&self, index: usize) -> Option<Self> {
+        let length = index.checked_sub(self.index)?;
+        if self.input.is_char_boundary(length) {
+            Some(self.advance(length))
         } else {
             None
         }
     }
+
+    /// Get the state after consuming `length` bytes of the input, which must end on a character boundary.
+    pub(crate) fn advance(&self, length: usize) -> Self {
+        Self {
+            input: String::from(&self.input[length..]),
+            index: self.index + length,
+            previous: self.input[..length].chars().next_back().or(self.previous),
+            verbosity: self.verbosity,
+            run: self.run,
+        }
+    }
+
+    /// Get the identifier of the parsing run this state belongs to, which is the same for every state reached from
+    /// the same starting state. Caches keyed by index must also check this, since the input can differ between runs.
+    pub(crate) fn run(&self) -> u64 {
+        self.run
+    }
+}
+
+// States are compared by where they are, not which run they came from.
+impl PartialEq for ParserState {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+            && self.previous == other.previous
+            && self.verbosity == other.verbosity
+            && self.input == other.input
+    }
+}
+
+impl Eq for ParserState {}
+
+impl Hash for ParserState {
+    fn hash<HasherType: Hasher>(&self, state: &mut HasherType) {
+        self.input.hash(state);
+        self.index.hash(state);
+        self.previous.hash(state);
+        self.verbosity.hash(state);
+    }
+}
+
+/// How much information parsers collect when they build errors.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ErrorVerbosity {
+    /// Only record the kind and position of errors, skipping the expected sets, found text, messages, notes, and
+    /// help. This is much cheaper when most errors are discarded, such as while trying alternatives.
+    Fast,
+
+    /// Record everything needed for a helpful error message.
+    Rich,
 }
 
 /// The type returned by parsers containing either the output and the new parser state or an error with more
 /// information.
 pub type ParseResult<ErrorType, OutputType> = Result<(OutputType, ParserState), ErrorType>;
 
+/// The type returned by `parse_partial`: the output or error, along with the state where parsing stopped. On
+/// success that is the state after the output, and on failure it is the state at which the error occurred, so the
+/// caller can see how far parsing got and resume from there.
+pub type PartialParseResult<ErrorType, OutputType> = (Result<OutputType, ErrorType>, ParserState);
+
 /// Represents a parser that will take the current parser state and try to transform it.
+///
+/// The built-in parsers are `Send + Sync` whenever the parsers and values inside them are, so a grammar can be
+/// built once and shared between threads with an `Arc`:
+///
+/// ```
+/// use parselib_rs::parsers::utf8::ParseChar;
+/// use parselib_rs::parsers::ParserExtensions;
+/// use parselib_rs::{Parser, ParserState};
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let word = Arc::new(ParseChar::from_range('a', 'z').one_or_more().memoized());
+/// let worker = {
+///     let word = Arc::clone(&word);
+///     thread::spawn(move || word.parse(ParserState::new("abc")).is_ok())
+/// };
+/// assert!(word.parse(ParserState::new("xyz")).is_ok());
+/// assert!(worker.join().unwrap());
+/// ```
 pub trait Parser<OutputType, ErrorType> {
     /// Try to parse a piece of the input and return a parser result based on whether that is successful.
     fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, OutputType>;
+
+    /// Parse a piece of the input every way it can be parsed, returning the output and new state of each
+    /// interpretation. Most parsers only match one way and return their single result, but
+    /// [`ParseAllOf`](parsers::utf8::ParseAllOf) returns every alternative that matched, and `and`, `map`, and
+    /// sequences return every combination of the interpretations of the parsers inside them. Other combinators
+    /// produce a single result.
+    fn parse_all(
+        &self,
+        parser_state: ParserState,
+    ) -> Result<Vec<(OutputType, ParserState)>, ErrorType> {
+        self.parse(parser_state).map(|result| vec![result])
+    }
+
+    /// Describe the input this parser accepts. Parsers that don't override this are described as
+    /// [`Grammar::Opaque`].
+    fn describe(&self) -> Grammar {
+        Grammar::Opaque
+    }
+}
+
+impl<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType> + ?Sized>
+    Parser<OutputType, ErrorType> for &ParserType
+{
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, OutputType> {
+        (**self).parse(parser_state)
+    }
+
+    fn parse_all(
+        &self,
+        parser_state: ParserState,
+    ) -> Result<Vec<(OutputType, ParserState)>, ErrorType> {
+        (**self).parse_all(parser_state)
+    }
+
+    fn describe(&self) -> Grammar {
+        (**self).describe()
+    }
+}
+
+/// A parser stored behind a pointer so that parsers of different types can be stored together, and so that parsers
+/// with long nested types can be stored in structs and returned from functions. Create one with
+/// [`boxed`](parsers::ParserExtensions::boxed). Boxed parsers must be `Send + Sync` so that the grammars built from
+/// them can be shared between threads.
+pub struct BoxedParser<OutputType, ErrorType>(Box<dyn Parser<OutputType, ErrorType> + Send + Sync>);
+
+impl<OutputType, ErrorType> BoxedParser<OutputType, ErrorType> {
+    /// Box the provided parser.
+    pub fn new(parser: impl Parser<OutputType, ErrorType> + Send + Sync + 'static) -> Self {
+        Self(Box::new(parser))
+    }
+}
+
+impl<OutputType, ErrorType> Parser<OutputType, ErrorType> for BoxedParser<OutputType, ErrorType> {
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, OutputType> {
+        self.0.parse(parser_state)
+    }
+
+    fn parse_all(
+        &self,
+        parser_state: ParserState,
+    ) -> Result<Vec<(OutputType, ParserState)>, ErrorType> {
+        self.0.parse_all(parser_state)
+    }
+
+    fn describe(&self) -> Grammar {
+        self.0.describe()
+    }
+}
+
+impl<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType> + ?Sized>
+    Parser<OutputType, ErrorType> for Box<ParserType>
+{
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, OutputType> {
+        (**self).parse(parser_state)
+    }
+
+    fn parse_all(
+        &self,
+        parser_state: ParserState,
+    ) -> Result<Vec<(OutputType, ParserState)>, ErrorType> {
+        (**self).parse_all(parser_state)
+    }
+
+    fn describe(&self) -> Grammar {
+        (**self).describe()
+    }
+}
+
+/// One way through a sequence of parsers while listing every interpretation of it: which interpretation of each
+/// element was taken so far, and the state after the last one.
+pub(crate) type SequencePath = (Vec<usize>, ParserState);
+
+/// Continue each path through a sequence with every interpretation of the next element. Paths that the element
+/// doesn't match end there, and the first of their errors is kept in `error` in case none of the paths continue.
+pub(crate) fn extend_paths<OutputType, ErrorType>(
+    parser: &impl Parser<OutputType, ErrorType>,
+    paths: Vec<SequencePath>,
+    error: &mut Option<ErrorType>,
+) -> Vec<SequencePath> {
+    let mut new_paths = Vec::new();
+    for (path, parser_state) in paths {
+        match parser.parse_all(parser_state) {
+            Ok(results) => {
+                for (choice, (_, new_state)) in results.into_iter().enumerate() {
+                    let mut path = path.clone();
+                    path.push(choice);
+                    new_paths.push((path, new_state));
+                }
+            }
+            Err(e) => {
+                error.get_or_insert(e);
+            }
+        }
+    }
+    new_paths
+}
+
+/// Implement [`Parser`] for tuples of parsers, which run each parser in order and produce a flat tuple of their
+/// outputs, so `(a, b, c)` produces `(A, B, C)` rather than the `((A, B), C)` of chained
+/// [`and`](parsers::ParserExtensions::and)s.
+macro_rules! impl_tuple_parser {
+    ($(($($index:tt $parser:ident $output:ident $value:ident),+)),+ $(,)?) => {
+        $(
+            impl<ErrorType, $($output, $parser: Parser<$output, ErrorType>),+>
+                Parser<($($output,)+), ErrorType> for ($($parser,)+)
+            {
+                fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, ($($output,)+)> {
+                    let new_state = parser_state;
+                    $(let ($value, new_state) = self.$index.parse(new_state)?;)+
+                    Ok((($($value,)+), new_state))
+                }
+
+                fn parse_all(
+                    &self,
+                    parser_state: ParserState,
+                ) -> Result<Vec<(($($output,)+), ParserState)>, ErrorType> {
+                    // Find every way through the sequence first, keeping only
+                    // the states, since outputs can't be copied into each of
+                    // the paths that share them.
+                    let mut paths = vec![(Vec::new(), parser_state.clone())];
+                    let mut error = None;
+                    $(paths = extend_paths(&self.$index, paths, &mut error);)+
+                    if let (true, Some(error)) = (paths.is_empty(), error) {
+                        return Err(error);
+                    }
+
+                    // Then build the outputs of each path by parsing along it
+                    // again.
+                    let mut results = Vec::new();
+                    'paths: for (path, _) in paths {
+                        let new_state = parser_state.clone();
+                        $(
+                            let ($value, new_state) =
+                                match self.$index.parse_all(new_state)?.into_iter().nth(path[$index]) {
+                                    Some(result) => result,
+                                    None => continue 'paths,
+                                };
+                        )+
+                        results.push((($($value,)+), new_state));
+                    }
+                    Ok(results)
+                }
+
+                fn describe(&self) -> Grammar {
+                    Grammar::sequence([$(self.$index.describe()),+])
+                }
+            }
+        )+
+    };
+}
+
+impl_tuple_parser!(
+    (0 ParserTypeA OutputTypeA a, 1 ParserTypeB OutputTypeB b),
+    (0 ParserTypeA OutputTypeA a, 1 ParserTypeB OutputTypeB b, 2 ParserTypeC OutputTypeC c),
+    (
+        0 ParserTypeA OutputTypeA a,
+        1 ParserTypeB OutputTypeB b,
+        2 ParserTypeC OutputTypeC c,
+        3 ParserTypeD OutputTypeD d
+    ),
+    (
+        0 ParserTypeA OutputTypeA a,
+        1 ParserTypeB OutputTypeB b,
+        2 ParserTypeC OutputTypeC c,
+        3 ParserTypeD OutputTypeD d,
+        4 ParserTypeE OutputTypeE e
+    ),
+    (
+        0 ParserTypeA OutputTypeA a,
+        1 ParserTypeB OutputTypeB b,
+        2 ParserTypeC OutputTypeC c,
+        3 ParserTypeD OutputTypeD d,
+        4 ParserTypeE OutputTypeE e,
+        5 ParserTypeF OutputTypeF f
+    ),
+    (
+        0 ParserTypeA OutputTypeA a,
+        1 ParserTypeB OutputTypeB b,
+        2 ParserTypeC OutputTypeC c,
+        3 ParserTypeD OutputTypeD d,
+        4 ParserTypeE OutputTypeE e,
+        5 ParserTypeF OutputTypeF f,
+        6 ParserTypeG OutputTypeG g
+    ),
+    (
+        0 ParserTypeA OutputTypeA a,
+        1 ParserTypeB OutputTypeB b,
+        2 ParserTypeC OutputTypeC c,
+        3 ParserTypeD OutputTypeD d,
+        4 ParserTypeE OutputTypeE e,
+        5 ParserTypeF OutputTypeF f,
+        6 ParserTypeG OutputTypeG g,
+        7 ParserTypeH OutputTypeH h
+    ),
+);
+
+/// Errors that can tell combinators to stop backtracking. Combinators that try alternatives or parse lists normally
+/// treat a failure as "this didn't match" and move on, but a committed error means the input did start the element
+/// and then went wrong, so they pass it on instead and the real problem gets reported.
+///
+/// The general-purpose repetitions and optional elements (such as `between`, `one_or_more`, `opt`, and `fold`) work
+/// with every error type, so they can't tell whether an error is committed and stop at any failure.
+pub trait Commit {
+    /// Check whether this error should be passed on instead of backtracking past it.
+    fn is_committed(&self) -> bool;
+
+    /// The index in the input at which the error occurred, if the error records one. Combinators use this to tell
+    /// an element that is missing from one that started and then went wrong.
+    fn offset(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// An error produced by the default provided parsers, containing what went wrong, where it went wrong, and any extra
+/// information attached to it while it was propagated.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseError {
+    /// The type of error that occurred.
+    kind: ParseErrorKind,
+
+    /// The index in the input at which the error occurred.
+    offset: usize,
+
+    /// Secondary notes that explain the error.
+    notes: Vec<String>,
+
+    /// Suggestions for how the input could be fixed.
+    help: Vec<String>,
+
+    /// Whether combinators should stop trying other alternatives and pass this error on.
+    committed: bool,
+}
+
+impl ParseError {
+    /// Create a new error of the provided kind that occurred at `offset` in the input.
+    pub fn new(kind: ParseErrorKind, offset: usize) -> Self {
+        Self {
+            kind,
+            offset,
+            notes: Vec::new(),
+            help: Vec::new(),
+            committed: false,
+        }
+    }
+
+    /// Create an error for input that wasn't expected, listing what would have been accepted instead.
+    pub fn unexpected<ExpectedType: Into<Expected>>(
+        offset: usize,
+        expected: impl IntoIterator<Item = ExpectedType>,
+        found: Option<&str>,
+    ) -> Self {
+        Self::new(
+            ParseErrorKind::Unexpected {
+                expected: expected.into_iter().map(Into::into).collect(),
+                found: found.map(Found::from),
+            },
+            offset,
+        )
+    }
+
+    /// Create an error for a repetition that found the wrong number of elements.
+    pub fn wrong_count(offset: usize, min: usize, max: usize, found: usize) -> Self {
+        Self::new(ParseErrorKind::WrongCount { min, max, found }, offset)
+    }
+
+    /// Create an error for input that matched more than one alternative.
+    pub fn ambiguous(offset: usize) -> Self {
+        Self::new(ParseErrorKind::Ambiguous, offset)
+    }
+
+    /// Create an error for input left over after parsing, starting at `offset`.
+    pub fn trailing_input(offset: usize, found: &str) -> Self {
+        Self::new(
+            ParseErrorKind::TrailingInput {
+                found: String::from(found),
+            },
+            offset,
+        )
+    }
+
+    /// Create an error for input between `start` and `end` that matched but isn't a valid value.
+    pub fn invalid(start: usize, end: usize, message: &str) -> Self {
+        Self::new(
+            ParseErrorKind::Invalid {
+                end,
+                message: String::from(message),
+            },
+            start,
+        )
+    }
+
+    /// Create an error for the input at the current position of `parser_state` not being `expected`, only recording
+    /// what was expected and found if the state asks for rich errors.
+    pub(crate) fn expected_at(parser_state: &ParserState, expected: impl Into<Expected>) -> Self {
+        let rich = parser_state.verbosity == ErrorVerbosity::Rich;
+        Self::new(
+            ParseErrorKind::Unexpected {
+                expected: if rich {
+                    BTreeSet::from([expected.into()])
+                } else {
+                    BTreeSet::new()
+                },
+                found: parser_state
+                    .input
+                    .chars()
+                    .next()
+                    .filter(|_| rich)
+                    .map(Found::Char),
+            },
+            parser_state.index,
+        )
+    }
+
+    /// Attach a secondary note that explains the error.
+    pub fn with_note(mut self, note: &str) -> Self {
+        self.notes.push(String::from(note));
+        self
+    }
+
+    /// Attach a suggestion for how the input could be fixed.
+    pub fn with_help(mut self, help: &str) -> Self {
+        self.help.push(String::from(help));
+        self
+    }
+
+    /// Get the type of error that occurred.
+    pub fn kind(&self) -> &ParseErrorKind {
+        &self.kind
+    }
+
+    /// Get the index in the input at which the error occurred.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Get the state at which this error occurred, given the state the failed parser started from. Offsets outside
+    /// the input that `started` covers are moved to its nearest end, and offsets inside a character are moved back
+    /// to the start of that character.
+    pub fn state(&self, started: &ParserState) -> ParserState {
+        let mut length = self
+            .offset
+            .saturating_sub(started.index)
+            .min(started.input.len());
+        while !started.input.is_char_boundary(length) {
+            length -= 1;
+        }
+        started.advance(length)
+    }
+
+    /// Get the full set of things that would have been accepted where this error occurred. This is empty if the
+    /// error wasn't caused by unexpected input.
+    pub fn expected(&self) -> &BTreeSet<Expected> {
+        // An empty set to borrow for errors that don't expect anything.
+        static NOTHING: BTreeSet<Expected> = BTreeSet::new();
+
+        match &self.kind {
+            ParseErrorKind::Unexpected { expected, .. } => expected,
+            _ => &NOTHING,
+        }
+    }
+
+    /// Get the region of the input the error refers to. This is only longer than one position for invalid values,
+    /// where it covers the whole value.
+    pub fn span(&self) -> Span {
+        match self.kind {
+            ParseErrorKind::Invalid { end, .. } if end > self.offset => Span::new(self.offset, end),
+            _ => Span::new(self.offset, self.offset),
+        }
+    }
+
+    /// Get the input that was found where the error occurred, if it was recorded.
+    pub fn found(&self) -> Option<Cow<'_, str>> {
+        match &self.kind {
+            ParseErrorKind::Unexpected {
+                found: Some(Found::Char(c)),
+                ..
+            } => Some(Cow::Owned(c.to_string())),
+            ParseErrorKind::Unexpected {
+                found: Some(Found::Text(found)),
+                ..
+            }
+            | ParseErrorKind::TrailingInput { found } => Some(Cow::Borrowed(found)),
+            _ => None,
+        }
+    }
+
+    /// Combine this error with an error from another alternative that was tried at the same position. The error
+    /// that got further into the input is kept, and if both stopped at the same place their expected sets are
+    /// merged so that the message lists everything that would have been accepted.
+    pub fn merge(mut self, other: ParseError) -> Self {
+        if other.offset > self.offset {
+            return other;
+        } else if other.offset < self.offset {
+            return self;
+        }
+
+        if let (
+            ParseErrorKind::Unexpected { expected, found },
+            ParseErrorKind::Unexpected {
+                expected: other_expected,
+                found: other_found,
+            },
+        ) = (&mut self.kind, other.kind)
+        {
+            expected.extend(other_expected);
+            if found.is_none() {
+                *found = other_found;
+            }
+        }
+        for note in other.notes {
+            if !self.notes.contains(&note) {
+                self.notes.push(note);
+            }
+        }
+        for help in other.help {
+            if !self.help.contains(&help) {
+                self.help.push(help);
+            }
+        }
+        self.committed |= other.committed;
+        self
+    }
+
+    /// Mark this error as committed, so that combinators pass it on instead of backtracking to try something
+    /// else. This is what [`cut`](parsers::ParserExtensions::cut) does to the errors of the parser it wraps.
+    pub fn commit(mut self) -> Self {
+        self.committed = true;
+        self
+    }
+
+    /// Get the secondary notes attached to this error.
+    pub fn notes(&self) -> &[String] {
+        &self.notes
+    }
+
+    /// Get the suggestions attached to this error.
+    pub fn help(&self) -> &[String] {
+        &self.help
+    }
+
+    /// Display this error using the wording of `messages` instead of the standard English messages.
+    pub fn display_with<'a>(&'a self, messages: &'a dyn MessageProvider) -> ErrorMessage<'a> {
+        ErrorMessage {
+            error: self,
+            messages,
+        }
+    }
 }
 
+impl Commit for ParseError {
+    fn is_committed(&self) -> bool {
+        self.committed
+    }
+
+    fn offset(&self) -> Option<usize> {
+        Some(self.offset)
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.display_with(&DefaultMessages))
+    }
+}
+
+impl Error for ParseError {}
+
 /// An enum of possible error types for the default provided parsers.
-#[derive(Debug)]
-pub enum ParseError {
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ParseErrorKind {
     /// TBD.
     Unknown,
 
     /// The parser received an input that it wasn't expecting.
     Unexpected {
-        expected: Option<String>,
-        found: Option<String>,
+        /// Everything that would have been accepted instead, deduplicated and sorted.
+        expected: BTreeSet<Expected>,
+        found: Option<Found>,
     },
 
     /// The parser didn't receive the number of elements that were expected.
@@ -82,30 +724,106 @@ pub enum ParseError {
         max: usize,
         found: usize,
     },
+
+    /// More than one alternative matched the input where exactly one was expected.
+    Ambiguous,
+
+    /// The parser finished but there was still input left over.
+    TrailingInput {
+        /// A preview of the leftover input.
+        found: String,
+    },
+
+    /// The parser matched but its output couldn't be converted into a valid value.
+    Invalid {
+        /// The index just past the end of the input that was matched (the error's offset is the start).
+        end: usize,
+
+        /// A description of why the value is invalid.
+        message: String,
+    },
 }
 
-impl Display for ParseError {
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", DefaultMessages.message(self))
+    }
+}
+
+/// Something that would have been accepted where an error occurred. Items are kept as structured data and only
+/// formatted when the error is displayed, so that failures which are immediately discarded stay cheap.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Expected {
+    /// A character within an inclusive range, where a missing bound means the range is unbounded on that side.
+    CharRange(Option<char>, Option<char>),
+
+    /// A literal, label, or other description.
+    Text(String),
+}
+
+impl From<char> for Expected {
+    fn from(c: char) -> Self {
+        Self::CharRange(Some(c), Some(c))
+    }
+}
+
+impl From<&str> for Expected {
+    fn from(text: &str) -> Self {
+        Self::Text(String::from(text))
+    }
+}
+
+impl From<String> for Expected {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<&String> for Expected {
+    fn from(text: &String) -> Self {
+        Self::Text(text.clone())
+    }
+}
+
+impl Display for Expected {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Unknown => writeln!(f, "unknown parsing error"),
-            Self::Unexpected { expected, found } => writeln!(
-                f,
-                "expected {} found {}",
-                expected.as_ref().map_or("nothing", |expected| expected),
-                found.as_ref().map_or("nothing", |found| found,)
-            ),
-            Self::WrongCount { min, max, found } => writeln!(
-                f,
-                "expected {} elements but found {}",
-                if min == max {
-                    min.to_string()
-                } else {
-                    format!("{}-{}", min, max)
-                },
-                found
-            ),
+            Self::CharRange(Some(start), Some(end)) if start == end => write!(f, "{}", start),
+            Self::CharRange(start, end) => {
+                write!(f, "{}..{}", start.unwrap_or('\0'), end.unwrap_or(char::MAX))
+            }
+            Self::Text(text) => write!(f, "{}", text),
         }
     }
 }
 
-impl Error for ParseError {}
+/// The input that was found where an error occurred.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Found {
+    /// A single character, which is stored without allocating.
+    Char(char),
+
+    /// Any longer text.
+    Text(String),
+}
+
+impl From<&str> for Found {
+    /// Text that is exactly one character is stored as [`Found::Char`], so that errors compare equal no matter how
+    /// they were constructed.
+    fn from(text: &str) -> Self {
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Self::Char(c),
+            _ => Self::Text(String::from(text)),
+        }
+    }
+}
+
+impl Display for Found {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Char(c) => write!(f, "{}", c),
+            Self::Text(text) => write!(f, "{}", text),
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use crate::parsers::utf8::ParseAllConsuming;
+use crate::span::Span;
+use crate::{ParseError, Parser, ParserState};
+
+/// Options controlling which lines are handed to the parser by `parse_lines`.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct LineOptions {
+    /// Whether to skip lines that are empty or only contain whitespace.
+    skip_blank: bool,
+
+    /// Lines starting with this prefix (after any indentation) are skipped as comments.
+    comment_prefix: Option<String>,
+}
+
+impl LineOptions {
+    /// Create options that parse every line.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Skip lines that are empty or only contain whitespace.
+    pub fn skip_blank(mut self) -> Self {
+        self.skip_blank = true;
+        self
+    }
+
+    /// Skip lines that start with `prefix` (after any indentation).
+    pub fn skip_comments(mut self, prefix: &str) -> Self {
+        self.comment_prefix = Some(String::from(prefix));
+        self
+    }
+
+    /// Check whether a line should be skipped.
+    fn skips(&self, line: &str) -> bool {
+        let trimmed = line.trim_start();
+        (self.skip_blank && trimmed.is_empty())
+            || self
+                .comment_prefix
+                .as_ref()
+                .is_some_and(|prefix| trimmed.starts_with(prefix.as_str()))
+    }
+}
+
+/// An iterator over the results of parsing each line of an input, created by `parse_lines`.
+pub struct ParseLines<'a, OutputType, ParserType: Parser<OutputType, ParseError>> {
+    /// The input that hasn't been split into lines yet.
+    input: &'a str,
+
+    /// The index of the start of the remaining input within the whole input.
+    offset: usize,
+
+    /// The number of the next line, starting from 1.
+    line_number: usize,
+
+    /// The parser to run on each line, which must consume the whole line.
+    parser: ParseAllConsuming<OutputType, ParserType>,
+
+    /// Which lines to skip.
+    options: LineOptions,
+}
+
+impl<OutputType, ParserType: Parser<OutputType, ParseError>> Iterator
+    for ParseLines<'_, OutputType, ParserType>
+{
+    type Item = Result<OutputType, (usize, ParseError)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.input.is_empty() {
+            // Split off the next line, including its line ending.
+            let length = self.input.find('\n').map_or(self.input.len(), |i| i + 1);
+            let (line_with_ending, rest) = self.input.split_at(length);
+            let line = line_with_ending
+                .strip_suffix('\n')
+                .map_or(line_with_ending, |line| {
+                    line.strip_suffix('\r').unwrap_or(line)
+                });
+
+            let line_number = self.line_number;
+            let offset = self.offset;
+            self.input = rest;
+            self.offset += length;
+            self.line_number += 1;
+
+            if !self.options.skips(line) {
+                // Parse the line with indices relative to the whole input so
+                // errors point to the right place.
+                return Some(
+                    self.parser
+                        .parse(ParserState::new_offset(line, offset))
+                        .map(|(output, _)| output)
+                        .map_err(|e| (line_number, e)),
+                );
+            }
+        }
+
+        None
+    }
+}
+
+/// Parse each line of `input` (split on `\n` or `\r\n`) with `parser`, which must consume the whole line. Each item
+/// is either the output for a line or the line number (starting from 1) and the error for that line.
+pub fn parse_lines<OutputType, ParserType: Parser<OutputType, ParseError>>(
+    parser: ParserType,
+    input: &str,
+    options: LineOptions,
+) -> ParseLines<'_, OutputType, ParserType> {
+    ParseLines {
+        input,
+        offset: 0,
+        line_number: 1,
+        parser: ParseAllConsuming::new(parser),
+        options,
+    }
+}
+
+/// The number of bytes between the character count checkpoints of a [`LineIndex`].
+const CHECKPOINT_SPACING: usize = 64;
+
+/// The positions of the lines in an input, found once up front so that converting between byte indices and line
+/// and column numbers takes a binary search instead of a scan from the start of the input. Lines are split on `\n`,
+/// and line and column numbers start from 1, with columns counted in characters.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct LineIndex<'a> {
+    /// The input the lines are in.
+    source: &'a str,
+
+    /// The index of the start of each line.
+    line_starts: Vec<usize>,
+
+    /// The byte index and number of characters before it about every [`CHECKPOINT_SPACING`] bytes, so that
+    /// counting characters doesn't need a scan from the start of a (possibly very long) line. Empty when the input
+    /// is ASCII, since then the two are the same.
+    checkpoints: Vec<(usize, usize)>,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Find the lines in `source`.
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+
+        let mut checkpoints = Vec::new();
+        if !source.is_ascii() {
+            let mut next = 0;
+            for (count, (index, _)) in source.char_indices().enumerate() {
+                if index >= next {
+                    checkpoints.push((index, count));
+                    next = index + CHECKPOINT_SPACING;
+                }
+            }
+        }
+
+        Self {
+            source,
+            line_starts,
+            checkpoints,
+        }
+    }
+
+    /// Count the characters before a byte index, which must be on a character boundary.
+    fn chars_before(&self, index: usize) -> usize {
+        if self.checkpoints.is_empty() {
+            return index;
+        }
+        let checkpoint = self
+            .checkpoints
+            .partition_point(|&(start, _)| start <= index);
+        let (start, count) = self.checkpoints[checkpoint - 1];
+        count + self.source[start..index].chars().count()
+    }
+
+    /// Get the byte index of the character with `count` characters before it, or the end of the input if that is
+    /// every character, or `None` if there aren't that many.
+    fn char_index(&self, count: usize) -> Option<usize> {
+        if self.checkpoints.is_empty() {
+            return Some(count).filter(|&count| count <= self.source.len());
+        }
+        let checkpoint = self
+            .checkpoints
+            .partition_point(|&(_, before)| before <= count);
+        let (start, before) = self.checkpoints[checkpoint - 1];
+        self.source[start..]
+            .char_indices()
+            .map(|(offset, _)| start + offset)
+            .chain([self.source.len()])
+            .nth(count - before)
+    }
+
+    /// Get the input the lines are in.
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    /// Get the number of lines, counting the (possibly empty) text after the last newline as a line.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Get the region of a line, not including its line ending (`\n` or `\r\n`), or `None` if there is no such
+    /// line.
+    pub fn line_span(&self, line_number: usize) -> Option<Span> {
+        let start = *self.line_starts.get(line_number.checked_sub(1)?)?;
+        let end = self
+            .line_starts
+            .get(line_number)
+            .map_or(self.source.len(), |next| next - 1);
+        let line = &self.source[start..end];
+        Some(Span::new(
+            start,
+            start + line.strip_suffix('\r').unwrap_or(line).len(),
+        ))
+    }
+
+    /// Get the text of a line, not including its line ending, or `None` if there is no such line.
+    pub fn line(&self, line_number: usize) -> Option<&'a str> {
+        self.line_span(line_number)
+            .map(|span| &self.source[span.start..span.end])
+    }
+
+    /// Get the line and column numbers of a byte index. Indices past the end of the input are treated as the end,
+    /// and indices inside a character are moved back to the start of that character.
+    pub fn line_column(&self, index: usize) -> (usize, usize) {
+        let mut index = index.min(self.source.len());
+        while !self.source.is_char_boundary(index) {
+            index -= 1;
+        }
+
+        let line_number = self.line_starts.partition_point(|&start| start <= index);
+        let line_start = self.line_starts[line_number - 1];
+        (
+            line_number,
+            self.chars_before(index) - self.chars_before(line_start) + 1,
+        )
+    }
+
+    /// Get the byte index of a line and column number, or `None` if the line doesn't exist or is too short. The
+    /// column just past the end of a line is its line ending.
+    pub fn index(&self, line_number: usize, column: usize) -> Option<usize> {
+        let span = self.line_span(line_number)?;
+        let index = self.char_index(self.chars_before(span.start) + column.checked_sub(1)?)?;
+        Some(index).filter(|&index| index <= span.end)
+    }
+}
@@ -0,0 +1,227 @@
+use crate::span::Span;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+
+#[cfg(feature = "lsp")]
+use crate::ParseError;
+
+/// A position in a text document in the form used by the Language Server Protocol: a line number and a character
+/// offset within that line, both starting from 0. The character offset counts UTF-16 code units, so characters
+/// outside the Basic Multilingual Plane (like most emoji) count as two.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
+pub struct Position {
+    /// The line number, starting from 0.
+    pub line: u32,
+
+    /// The number of UTF-16 code units before the position on its line.
+    pub character: u32,
+}
+
+impl Position {
+    /// Create a new position.
+    pub const fn new(line: u32, character: u32) -> Self {
+        Self { line, character }
+    }
+}
+
+impl Display for Position {
+    /// Writes the position as `line:character`, both starting from 0.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.character)
+    }
+}
+
+/// A region of a text document in the form used by the Language Server Protocol, from `start` up to (but not
+/// including) `end`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct Range {
+    /// The start of the region.
+    pub start: Position,
+
+    /// The position just past the end of the region.
+    pub end: Position,
+}
+
+impl Range {
+    /// Create a new range.
+    pub const fn new(start: Position, end: Position) -> Self {
+        Self { start, end }
+    }
+}
+
+impl Display for Range {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// Get the index just past each line ending in `source`. Like the Language Server Protocol, this treats `\n`,
+/// `\r\n` and a lone `\r` as line endings.
+fn line_ends(source: &str) -> impl Iterator<Item = usize> + '_ {
+    let bytes = source.as_bytes();
+    bytes
+        .iter()
+        .enumerate()
+        .filter_map(move |(index, byte)| match byte {
+            b'\n' => Some(index + 1),
+            b'\r' if bytes.get(index + 1) != Some(&b'\n') => Some(index + 1),
+            _ => None,
+        })
+}
+
+/// Convert a number to the `u32` used by the Language Server Protocol, saturating if it doesn't fit.
+fn to_u32(value: usize) -> u32 {
+    u32::try_from(value).unwrap_or(u32::MAX)
+}
+
+/// Convert a byte index in `source` into a line and UTF-16 character position. Indices past the end of the source
+/// are treated as the end, and indices inside a character are moved back to the start of that character.
+pub fn position(source: &str, index: usize) -> Position {
+    let mut index = index.min(source.len());
+    while !source.is_char_boundary(index) {
+        index -= 1;
+    }
+
+    let (line, line_start) = line_ends(source)
+        .take_while(|&end| end <= index)
+        .enumerate()
+        .last()
+        .map_or((0, 0), |(line, end)| (line + 1, end));
+    Position::new(
+        to_u32(line),
+        to_u32(source[line_start..index].encode_utf16().count()),
+    )
+}
+
+/// Convert a line and UTF-16 character position into a byte index in `source`. As the Language Server Protocol
+/// requires, characters past the end of a line mean the end of that line, and lines past the end of the source
+/// mean the end of the source. A position between the two halves of a surrogate pair is moved back to the start of
+/// that character.
+pub fn index(source: &str, position: Position) -> usize {
+    let line_start = match position.line as usize {
+        0 => 0,
+        line => match line_ends(source).nth(line - 1) {
+            Some(start) => start,
+            None => return source.len(),
+        },
+    };
+
+    let line = &source[line_start..];
+    let line = &line[..line.find(&['\r', '\n'][..]).unwrap_or(line.len())];
+    let mut units = 0;
+    for (offset, c) in line.char_indices() {
+        units += c.len_utf16();
+        if units > position.character as usize {
+            return line_start + offset;
+        }
+    }
+    line_start + line.len()
+}
+
+/// Convert a span of byte indices in `source` into a range of line and UTF-16 character positions.
+pub fn range(source: &str, span: Span) -> Range {
+    Range::new(position(source, span.start), position(source, span.end))
+}
+
+/// Convert a range of line and UTF-16 character positions into a span of byte indices in `source`.
+pub fn span(source: &str, range: Range) -> Span {
+    Span::new(index(source, range.start), index(source, range.end))
+}
+
+/// How serious a [`LspDiagnostic`] is, numbered as in the Language Server Protocol.
+#[cfg(feature = "lsp")]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum DiagnosticSeverity {
+    Error = 1,
+    Warning = 2,
+    Information = 3,
+    Hint = 4,
+}
+
+/// A parsing error in the shape of a Language Server Protocol `Diagnostic`, ready to be published to an editor.
+#[cfg(feature = "lsp")]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct LspDiagnostic {
+    /// The region of the document the diagnostic applies to.
+    pub range: Range,
+
+    /// How serious the diagnostic is.
+    pub severity: DiagnosticSeverity,
+
+    /// The name of the tool reporting the diagnostic, shown by editors next to the message.
+    pub source: Option<String>,
+
+    /// The error message, followed by any notes and help on their own lines.
+    pub message: String,
+}
+
+#[cfg(feature = "lsp")]
+impl LspDiagnostic {
+    /// Create a diagnostic for an error produced while parsing `source`.
+    pub fn new(error: &ParseError, source: &str) -> Self {
+        let mut message = error.kind().to_string().trim_end().to_string();
+        for note in error.notes() {
+            message.push_str("\nnote: ");
+            message.push_str(note);
+        }
+        for help in error.help() {
+            message.push_str("\nhelp: ");
+            message.push_str(help);
+        }
+
+        Self {
+            range: range(source, error.span()),
+            severity: DiagnosticSeverity::Error,
+            source: None,
+            message,
+        }
+    }
+
+    /// Set the name of the tool reporting the diagnostic.
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.source = Some(String::from(source));
+        self
+    }
+
+    /// Write the diagnostic as a JSON object, using the field names from the Language Server Protocol.
+    pub fn to_json(&self) -> String {
+        fn position(position: Position) -> String {
+            format!(
+                "{{\"line\":{},\"character\":{}}}",
+                position.line, position.character
+            )
+        }
+
+        fn quote(text: &str) -> String {
+            let mut quoted = String::from("\"");
+            for c in text.chars() {
+                match c {
+                    '"' => quoted.push_str("\\\""),
+                    '\\' => quoted.push_str("\\\\"),
+                    '\n' => quoted.push_str("\\n"),
+                    '\r' => quoted.push_str("\\r"),
+                    '\t' => quoted.push_str("\\t"),
+                    c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+                    c => quoted.push(c),
+                }
+            }
+            quoted.push('"');
+            quoted
+        }
+
+        let mut json = format!(
+            "{{\"range\":{{\"start\":{},\"end\":{}}},\"severity\":{}",
+            position(self.range.start),
+            position(self.range.end),
+            self.severity as u8
+        );
+        if let Some(source) = &self.source {
+            json.push_str(",\"source\":");
+            json.push_str(&quote(source));
+        }
+        json.push_str(",\"message\":");
+        json.push_str(&quote(&self.message));
+        json.push('}');
+        json
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{Expected, Found, ParseError, ParseErrorKind};
+use std::collections::BTreeSet;
+use std::fmt::{Debug, Display, Formatter};
+
+/// The largest number of expected items listed in an error message before the rest are summarized.
+const MAX_LISTED_EXPECTED: usize = 5;
+
+/// The words that introduce the parts of an error report.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ReportLabel {
+    /// Introduces the error itself.
+    Error,
+
+    /// Introduces extra information about the error.
+    Note,
+
+    /// Introduces a suggestion for fixing the error.
+    Help,
+}
+
+/// The wording used when errors are displayed. Every method has a default giving the standard English message, so
+/// an application can translate or rephrase just the messages it cares about:
+///
+/// ```
+/// use parselib_rs::diagnostics::Diagnostic;
+/// use parselib_rs::messages::MessageProvider;
+/// use parselib_rs::ParseError;
+///
+/// #[derive(Debug)]
+/// struct French;
+///
+/// impl MessageProvider for French {
+///     fn ambiguous(&self) -> String {
+///         String::from("l'entrée correspond à plusieurs alternatives")
+///     }
+/// }
+///
+/// let error = ParseError::ambiguous(0);
+/// let report = Diagnostic::new(&error, "a b").with_messages(&French).to_string();
+/// assert!(report.contains("l'entrée correspond à plusieurs alternatives"));
+/// ```
+///
+/// Labels given to parsers, notes, and help are written by the application already, so they are used as they are.
+pub trait MessageProvider: Debug + Sync {
+    /// Describe an error kind in a single line, without a trailing newline. This picks one of the other methods
+    /// based on the kind.
+    fn message(&self, kind: &ParseErrorKind) -> String {
+        match kind {
+            ParseErrorKind::Unknown => self.unknown(),
+            ParseErrorKind::Unexpected { expected, found } => {
+                self.unexpected(expected, found.as_ref())
+            }
+            ParseErrorKind::WrongCount { min, max, found } => self.wrong_count(*min, *max, *found),
+            ParseErrorKind::Ambiguous => self.ambiguous(),
+            ParseErrorKind::TrailingInput { found } => self.trailing_input(found),
+            ParseErrorKind::Invalid { message, .. } => self.invalid(message),
+        }
+    }
+
+    /// Describe an error with no more specific kind.
+    fn unknown(&self) -> String {
+        String::from("unknown parsing error")
+    }
+
+    /// Describe input that wasn't expected.
+    fn unexpected(&self, expected: &BTreeSet<Expected>, found: Option<&Found>) -> String {
+        let expected = ExpectedList {
+            items: expected,
+            messages: self,
+        };
+        match found {
+            Some(found) => format!("expected {} found {}", expected, found),
+            None => format!("expected {} found nothing", expected),
+        }
+    }
+
+    /// Describe one thing that would have been accepted, as used by the default list of expected items.
+    fn expected(&self, expected: &Expected) -> String {
+        expected.to_string()
+    }
+
+    /// Describe a repetition that found the wrong number of elements.
+    fn wrong_count(&self, min: usize, max: usize, found: usize) -> String {
+        let count = if min == max {
+            min.to_string()
+        } else {
+            format!("{}-{}", min, max)
+        };
+        format!("expected {} elements but found {}", count, found)
+    }
+
+    /// Describe input that matched more than one alternative.
+    fn ambiguous(&self) -> String {
+        String::from("input matched more than one alternative")
+    }
+
+    /// Describe input left over after parsing.
+    fn trailing_input(&self, found: &str) -> String {
+        format!("expected end of input but found \"{}\"", found)
+    }
+
+    /// Describe input that matched but isn't a valid value, where `message` explains why.
+    fn invalid(&self, message: &str) -> String {
+        format!("invalid input: {}", message)
+    }
+
+    /// Get the word that introduces a part of an error report.
+    fn label(&self, label: ReportLabel) -> String {
+        String::from(match label {
+            ReportLabel::Error => "error",
+            ReportLabel::Note => "note",
+            ReportLabel::Help => "help",
+        })
+    }
+}
+
+/// The standard English messages.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct DefaultMessages;
+
+impl MessageProvider for DefaultMessages {}
+
+/// Formats a set of expected items as a readable list, such as "one of `)`, `,`, or 12 more".
+struct ExpectedList<'a, MessagesType: MessageProvider + ?Sized> {
+    /// The items to list.
+    items: &'a BTreeSet<Expected>,
+
+    /// The wording for each item.
+    messages: &'a MessagesType,
+}
+
+impl<MessagesType: MessageProvider + ?Sized> Display for ExpectedList<'_, MessagesType> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut items = self.items.iter().map(|item| self.messages.expected(item));
+        match self.items.len() {
+            0 => write!(f, "nothing"),
+            1 => write!(f, "{}", items.next().unwrap()),
+            len => {
+                // List up to the limit and summarize the rest, unless there's
+                // only one item past the limit, which may as well be listed.
+                let listed = if len > MAX_LISTED_EXPECTED + 1 {
+                    MAX_LISTED_EXPECTED
+                } else {
+                    len - 1
+                };
+
+                write!(f, "one of ")?;
+                for (i, item) in items.by_ref().take(listed).enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "`{}`", item)?;
+                }
+
+                // Only use a serial comma when there are three or more entries.
+                let separator = if listed > 1 { ", or" } else { " or" };
+                if listed == len - 1 {
+                    write!(f, "{} `{}`", separator, items.next().unwrap())
+                } else {
+                    write!(f, "{} {} more", separator, len - listed)
+                }
+            }
+        }
+    }
+}
+
+/// An error displayed with the wording of a [`MessageProvider`], created by [`ParseError::display_with`]. It is
+/// written in the same form as the error's own `Display`: the message followed by any notes and help, each on its
+/// own line.
+#[derive(Debug, Clone)]
+pub struct ErrorMessage<'a> {
+    /// The error to display.
+    pub(crate) error: &'a ParseError,
+
+    /// The wording to use.
+    pub(crate) messages: &'a dyn MessageProvider,
+}
+
+impl Display for ErrorMessage<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.messages.message(self.error.kind()))?;
+        for note in self.error.notes() {
+            writeln!(f, "{}: {}", self.messages.label(ReportLabel::Note), note)?;
+        }
+        for help in self.error.help() {
+            writeln!(f, "{}: {}", self.messages.label(ReportLabel::Help), help)?;
+        }
+        Ok(())
+    }
+}
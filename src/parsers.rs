@@ -1,13 +1,39 @@
-use crate::parsers::utf8::{ParseAnd, ParseChar, ParseCount};
-use crate::Parser;
+use crate::parsers::utf8::{
+    Associativity, MemoCache, MemoKey, ParseAllConsuming, ParseAnd, ParseAndThen, ParseArray,
+    ParseChain, ParseChar, ParseCond, ParseCount, ParseCountOf, ParseCut, ParseDelimited,
+    ParseFold, ParseFoldWhile, ParseLabel, ParseManyTill, ParseMap, ParseMapErr, ParseMemoized,
+    ParseNot, ParseOpt, ParseOr, ParseOrValue, ParsePeek, ParsePreceded, ParseRecognize,
+    ParseReparse, ParseSepBy, ParseSpanned, ParseTerminated, ParseTo, ParseToValue, ParseTryMap,
+    ParseVerify, ParseWithSlice, ParseXor,
+};
+use crate::span::Spanned;
+use crate::{
+    BoxedParser, ErrorVerbosity, ParseError, ParseResult, Parser, ParserState, PartialParseResult,
+};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::ops::ControlFlow;
+use std::str::FromStr;
+
+/// Parsing expressions made of operands and prefix, infix, and postfix operators.
+pub mod expr;
 
 /// Parsers that specifically make use of the `char` type and can be used to parse strings.
 pub mod utf8 {
     use super::super::*;
+    use crate::grammar::{ebnf, RuleDoc};
+    use crate::span::{Span, Spanned};
+    use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+    use std::convert::TryFrom;
+    use std::fmt::Display;
     use std::marker::PhantomData;
+    use std::ops::ControlFlow;
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError, Weak};
+    use std::thread::{self, ThreadId};
 
     /// Parses a single character and optionally checks whether it is within a provided range.
-    #[derive(Debug, Clone, Eq, PartialEq)]
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
     pub struct ParseChar {
         /// The inclusive minimum bound of the character this parser is looking for, or `None` if it should accept
         /// characters from `0`.
@@ -20,7 +46,7 @@ pub mod utf8 {
 
     impl ParseChar {
         /// Create a character parser that will match characters between the inclusive range `start..end`.
-        pub fn from_range(start: char, end: char) -> Self {
+        pub const fn from_range(start: char, end: char) -> Self {
             Self {
                 start: Some(start),
                 end: Some(end),
@@ -28,7 +54,7 @@ pub mod utf8 {
         }
 
         /// Create a character parser that will match characters above and including the `start`.
-        pub fn from_start(start: char) -> Self {
+        pub const fn from_start(start: char) -> Self {
             Self {
                 start: Some(start),
                 end: None,
@@ -36,7 +62,7 @@ pub mod utf8 {
         }
 
         /// Create a character parser that will match characters below and including the `end`.
-        pub fn from_end(end: char) -> Self {
+        pub const fn from_end(end: char) -> Self {
             Self {
                 start: None,
                 end: Some(end),
@@ -44,7 +70,7 @@ pub mod utf8 {
         }
 
         /// Create a character parser that will match only the provided character.
-        pub fn from_char(c: char) -> Self {
+        pub const fn from_char(c: char) -> Self {
             Self {
                 start: Some(c),
                 end: Some(c),
@@ -52,7 +78,7 @@ pub mod utf8 {
         }
 
         /// Create a character parser that will match any character.
-        pub fn from_any() -> Self {
+        pub const fn from_any() -> Self {
             Self {
                 start: None,
                 end: None,
@@ -62,14 +88,16 @@ pub mod utf8 {
 
     impl Parser<char, ParseError> for ParseChar {
         fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, char> {
-            // Use a function to make this method neater when building an error.
-            fn expected_str_from_char_range(start: Option<char>, end: Option<char>) -> String {
-                format!(
-                    "{}..{}",
-                    if let Some(start) = start { start } else { '\0' },
-                    if let Some(end) = end { end } else { '\0' }
-                )
-            }
+            // Only describe the error if the state asks for it. The range is
+            // only formatted if the error is displayed.
+            let rich = parser_state.verbosity() == ErrorVerbosity::Rich;
+            let expected = || {
+                if rich {
+                    BTreeSet::from([Expected::CharRange(self.start, self.end)])
+                } else {
+                    BTreeSet::new()
+                }
+            };
 
             // Get the first character in the input
             if let Some((new_state, char_at)) = parser_state.char(0) {
@@ -88,19 +116,29 @@ pub mod utf8 {
                     Ok((char_at, new_state))
                 } else {
                     // Otherwise, the character found isn't within the provided range.
-                    Err(ParseError::Unexpected {
-                        expected: Some(expected_str_from_char_range(self.start, self.end)),
-                        found: Some(char_at.to_string()),
-                    })
+                    Err(ParseError::new(
+                        ParseErrorKind::Unexpected {
+                            expected: expected(),
+                            found: rich.then_some(Found::Char(char_at)),
+                        },
+                        parser_state.index,
+                    ))
                 }
             } else {
                 // There were no more characters to take from the input.
-                Err(ParseError::Unexpected {
-                    expected: Some(expected_str_from_char_range(self.start, self.end)),
-                    found: None,
-                })
+                Err(ParseError::new(
+                    ParseErrorKind::Unexpected {
+                        expected: expected(),
+                        found: None,
+                    },
+                    parser_state.index,
+                ))
             }
         }
+
+        fn describe(&self) -> Grammar {
+            Grammar::char_range(self.start, self.end)
+        }
     }
 
     /// Parses a variable number of elements.
@@ -122,7 +160,7 @@ pub mod utf8 {
         ParseCount<OutputType, ErrorType, ParserType>
     {
         /// Create a new count parser from the provided minimum and maximum counts.
-        pub fn new(min: usize, max: usize, parser: ParserType) -> Self {
+        pub const fn new(min: usize, max: usize, parser: ParserType) -> Self {
             Self {
                 min,
                 max,
@@ -164,16 +202,505 @@ pub mod utf8 {
             if output.len() < self.min {
                 // If there aren't the right number of elements, construct and
                 // return an error.
-                Err(ParseError::WrongCount {
-                    min: self.min,
-                    max: self.max,
-                    found: output.len(),
-                })
+                Err(ParseError::wrong_count(
+                    new_state.index,
+                    self.min,
+                    self.max,
+                    output.len(),
+                ))
             } else {
                 // Otherwise, return the new state and the outputs.
                 Ok((output, new_state))
             }
         }
+
+        fn describe(&self) -> Grammar {
+            Grammar::repeat(self.min, self.max, self.parser.describe())
+        }
+    }
+
+    /// Parses exactly `COUNT` elements into an array, such as the four hex digits of a unicode escape, without
+    /// allocating.
+    pub struct ParseArray<
+        OutputType,
+        ErrorType,
+        ParserType: Parser<OutputType, ErrorType>,
+        const COUNT: usize,
+    > {
+        /// The type of parser to run for each element.
+        parser: ParserType,
+
+        /* Phantom */
+        _phantom: PhantomData<(OutputType, ErrorType)>,
+    }
+
+    impl<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>, const COUNT: usize>
+        ParseArray<OutputType, ErrorType, ParserType, COUNT>
+    {
+        /// Create a new parser that runs the provided parser `COUNT` times.
+        pub const fn new(parser: ParserType) -> Self {
+            Self {
+                parser,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>, const COUNT: usize>
+        Parser<[OutputType; COUNT], ParseError>
+        for ParseArray<OutputType, ErrorType, ParserType, COUNT>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, [OutputType; COUNT]> {
+            let mut new_state = parser_state;
+            let mut count = 0;
+            let mut failed = false;
+
+            // Fill in the elements in order, leaving the rest empty once one
+            // of them fails.
+            let elements: [Option<OutputType>; COUNT] = std::array::from_fn(|_| {
+                if failed {
+                    return None;
+                }
+                match self.parser.parse(new_state.clone()) {
+                    Ok((output, parsed_new_state)) => {
+                        new_state = parsed_new_state;
+                        count += 1;
+                        Some(output)
+                    }
+                    Err(_) => {
+                        failed = true;
+                        None
+                    }
+                }
+            });
+
+            if count < COUNT {
+                Err(ParseError::wrong_count(
+                    new_state.index,
+                    COUNT,
+                    COUNT,
+                    count,
+                ))
+            } else {
+                Ok((elements.map(Option::unwrap), new_state))
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::repeat(COUNT, COUNT, self.parser.describe())
+        }
+    }
+
+    /// Parses a variable number of elements like [`ParseCount`], but only returns how many there were instead of
+    /// collecting their outputs.
+    pub struct ParseCountOf<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>> {
+        /// The minimum count of elements to parse.
+        min: usize,
+
+        /// The maximum count of elements to parse (inclusively).
+        max: usize,
+
+        /// The type of parser to run for each element.
+        parser: ParserType,
+
+        /* Phantom */
+        _phantom: PhantomData<(OutputType, ErrorType)>,
+    }
+
+    impl<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>>
+        ParseCountOf<OutputType, ErrorType, ParserType>
+    {
+        /// Create a new counting parser from the provided minimum and maximum counts.
+        pub const fn new(min: usize, max: usize, parser: ParserType) -> Self {
+            Self {
+                min,
+                max,
+                parser,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>> Parser<usize, ParseError>
+        for ParseCountOf<OutputType, ErrorType, ParserType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, usize> {
+            let mut new_state = parser_state;
+            let mut count = 0;
+
+            // Keep parsing until there are enough elements, an element fails,
+            // or an element doesn't consume anything (which would repeat
+            // forever).
+            while count < self.max {
+                match self.parser.parse(new_state.clone()) {
+                    Ok((_, parsed_new_state)) => {
+                        let consumed = parsed_new_state.index != new_state.index;
+                        new_state = parsed_new_state;
+                        count += 1;
+                        if !consumed {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                }
+            }
+
+            if count < self.min {
+                Err(ParseError::wrong_count(
+                    new_state.index,
+                    self.min,
+                    self.max,
+                    count,
+                ))
+            } else {
+                Ok((count, new_state))
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::repeat(self.min, self.max, self.parser.describe())
+        }
+    }
+
+    /// Parses a list of elements with a separator between each one, such as `a, b, c`, returning the elements.
+    pub struct ParseSepBy<
+        OutputType,
+        SeparatorOutputType,
+        ErrorType,
+        ParserType: Parser<OutputType, ErrorType>,
+        SeparatorParserType: Parser<SeparatorOutputType, ErrorType>,
+    > {
+        /// The parser for each element.
+        parser: ParserType,
+
+        /// The parser for the separator between elements.
+        separator: SeparatorParserType,
+
+        /// Whether at least one element is required.
+        required: bool,
+
+        /// Whether a separator may follow the last element.
+        trailing: bool,
+
+        /* Phantom */
+        _phantom: PhantomData<(OutputType, SeparatorOutputType, ErrorType)>,
+    }
+
+    impl<
+            OutputType,
+            SeparatorOutputType,
+            ErrorType,
+            ParserType: Parser<OutputType, ErrorType>,
+            SeparatorParserType: Parser<SeparatorOutputType, ErrorType>,
+        > ParseSepBy<OutputType, SeparatorOutputType, ErrorType, ParserType, SeparatorParserType>
+    {
+        /// Create a new parser for a list of elements separated by `separator`, which may be empty unless
+        /// `required` is set.
+        pub const fn new(
+            parser: ParserType,
+            separator: SeparatorParserType,
+            required: bool,
+        ) -> Self {
+            Self {
+                parser,
+                separator,
+                required,
+                trailing: false,
+                _phantom: PhantomData,
+            }
+        }
+
+        /// Allow (and consume) a separator after the last element, such as the last comma in `[1, 2, 3,]`.
+        pub const fn allow_trailing(mut self) -> Self {
+            self.trailing = true;
+            self
+        }
+    }
+
+    impl<
+            OutputType,
+            SeparatorOutputType,
+            ErrorType: Commit,
+            ParserType: Parser<OutputType, ErrorType>,
+            SeparatorParserType: Parser<SeparatorOutputType, ErrorType>,
+        > Parser<Vec<OutputType>, ErrorType>
+        for ParseSepBy<OutputType, SeparatorOutputType, ErrorType, ParserType, SeparatorParserType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, Vec<OutputType>> {
+            // Parse the first element, which is only an error if it's required.
+            let (first, mut new_state) = match self.parser.parse(parser_state.clone()) {
+                Ok(result) => result,
+                Err(e) if self.required || e.is_committed() => return Err(e),
+                Err(_) => return Ok((Vec::new(), parser_state)),
+            };
+            let mut output = vec![first];
+
+            // Parse a separator and an element until either is missing. A
+            // separator without an element after it is left unconsumed
+            // unless trailing separators are allowed.
+            loop {
+                let separator_state = match self.separator.parse(new_state.clone()) {
+                    Ok((_, separator_state)) => separator_state,
+                    Err(e) if e.is_committed() => return Err(e),
+                    Err(_) => break,
+                };
+                match self.parser.parse(separator_state.clone()) {
+                    Ok((element, element_state)) => {
+                        // Stop if nothing was consumed, which would repeat forever.
+                        let consumed = element_state.index != new_state.index;
+                        new_state = element_state;
+                        output.push(element);
+                        if !consumed {
+                            break;
+                        }
+                    }
+                    Err(e) if e.is_committed() => return Err(e),
+                    Err(_) => {
+                        if self.trailing {
+                            new_state = separator_state;
+                        }
+                        break;
+                    }
+                }
+            }
+
+            Ok((output, new_state))
+        }
+
+        fn describe(&self) -> Grammar {
+            let separator = self.separator.describe();
+            let mut list = vec![
+                self.parser.describe(),
+                Grammar::repeat(
+                    0,
+                    usize::MAX,
+                    Grammar::sequence([separator.clone(), self.parser.describe()]),
+                ),
+            ];
+            if self.trailing {
+                list.push(Grammar::repeat(0, 1, separator));
+            }
+
+            if self.required {
+                Grammar::sequence(list)
+            } else {
+                Grammar::repeat(0, 1, Grammar::sequence(list))
+            }
+        }
+    }
+
+    /// Which way a chain of binary operators groups, such as `a - b - c` meaning `(a - b) - c`.
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+    pub enum Associativity {
+        /// Operators group from the left, so `a - b - c` is `(a - b) - c`.
+        Left,
+
+        /// Operators group from the right, so `a ^ b ^ c` is `a ^ (b ^ c)`.
+        Right,
+    }
+
+    /// Parses operands separated by binary operators, such as `1 + 2 - 3`, combining the operands with the function
+    /// that each operator produces.
+    pub struct ParseChain<
+        OutputType,
+        OperatorType: Fn(OutputType, OutputType) -> OutputType,
+        ErrorType,
+        ParserType: Parser<OutputType, ErrorType>,
+        OperatorParserType: Parser<OperatorType, ErrorType>,
+    > {
+        /// The parser for each operand.
+        parser: ParserType,
+
+        /// The parser for the operators, which produces the function that combines the operands on either side.
+        operator: OperatorParserType,
+
+        /// Which way the operators group.
+        associativity: Associativity,
+
+        /* Phantom */
+        _phantom: PhantomData<(OutputType, OperatorType, ErrorType)>,
+    }
+
+    impl<
+            OutputType,
+            OperatorType: Fn(OutputType, OutputType) -> OutputType,
+            ErrorType,
+            ParserType: Parser<OutputType, ErrorType>,
+            OperatorParserType: Parser<OperatorType, ErrorType>,
+        > ParseChain<OutputType, OperatorType, ErrorType, ParserType, OperatorParserType>
+    {
+        /// Create a new parser for operands of `parser` separated by `operator`, grouped by `associativity`.
+        pub const fn new(
+            parser: ParserType,
+            operator: OperatorParserType,
+            associativity: Associativity,
+        ) -> Self {
+            Self {
+                parser,
+                operator,
+                associativity,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<
+            OutputType,
+            OperatorType: Fn(OutputType, OutputType) -> OutputType,
+            ErrorType: Commit,
+            ParserType: Parser<OutputType, ErrorType>,
+            OperatorParserType: Parser<OperatorType, ErrorType>,
+        > Parser<OutputType, ErrorType>
+        for ParseChain<OutputType, OperatorType, ErrorType, ParserType, OperatorParserType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, OutputType> {
+            let (first, mut new_state) = self.parser.parse(parser_state)?;
+
+            // Parse an operator and an operand until either is missing. An
+            // operator without an operand after it is left unconsumed.
+            let mut rest = Vec::new();
+            loop {
+                let (operator, operator_state) = match self.operator.parse(new_state.clone()) {
+                    Ok(result) => result,
+                    Err(e) if e.is_committed() => return Err(e),
+                    Err(_) => break,
+                };
+                match self.parser.parse(operator_state) {
+                    Ok((operand, operand_state)) => {
+                        // Stop if nothing was consumed, which would repeat forever.
+                        let consumed = operand_state.index != new_state.index;
+                        new_state = operand_state;
+                        rest.push((operator, operand));
+                        if !consumed {
+                            break;
+                        }
+                    }
+                    Err(e) if e.is_committed() => return Err(e),
+                    Err(_) => break,
+                }
+            }
+
+            let output = match self.associativity {
+                Associativity::Left => rest
+                    .into_iter()
+                    .fold(first, |left, (operator, right)| operator(left, right)),
+
+                // Combine from the end, where each operator joins the operand
+                // before it with everything after it.
+                Associativity::Right => {
+                    let mut operands = Vec::with_capacity(rest.len() + 1);
+                    let mut operators = Vec::with_capacity(rest.len());
+                    operands.push(first);
+                    for (operator, operand) in rest {
+                        operators.push(operator);
+                        operands.push(operand);
+                    }
+                    let mut right = operands.pop().unwrap();
+                    while let (Some(operator), Some(left)) = (operators.pop(), operands.pop()) {
+                        right = operator(left, right);
+                    }
+                    right
+                }
+            };
+            Ok((output, new_state))
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::sequence([
+                self.parser.describe(),
+                Grammar::repeat(
+                    0,
+                    usize::MAX,
+                    Grammar::sequence([self.operator.describe(), self.parser.describe()]),
+                ),
+            ])
+        }
+    }
+
+    /// Parses elements repeatedly until a terminator matches, such as the statements of a block ending with `end`,
+    /// returning the elements and the output of the terminator.
+    pub struct ParseManyTill<
+        OutputType,
+        TerminatorOutputType,
+        ParserType: Parser<OutputType, ParseError>,
+        TerminatorParserType: Parser<TerminatorOutputType, ParseError>,
+    > {
+        /// The parser for each element.
+        parser: ParserType,
+
+        /// The parser for the terminator, which is tried before each element.
+        terminator: TerminatorParserType,
+
+        /* Phantom */
+        _phantom: PhantomData<(OutputType, TerminatorOutputType)>,
+    }
+
+    impl<
+            OutputType,
+            TerminatorOutputType,
+            ParserType: Parser<OutputType, ParseError>,
+            TerminatorParserType: Parser<TerminatorOutputType, ParseError>,
+        > ParseManyTill<OutputType, TerminatorOutputType, ParserType, TerminatorParserType>
+    {
+        /// Create a new parser that repeats `parser` until `terminator` matches.
+        pub const fn new(parser: ParserType, terminator: TerminatorParserType) -> Self {
+            Self {
+                parser,
+                terminator,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<
+            OutputType,
+            TerminatorOutputType,
+            ParserType: Parser<OutputType, ParseError>,
+            TerminatorParserType: Parser<TerminatorOutputType, ParseError>,
+        > Parser<(Vec<OutputType>, TerminatorOutputType), ParseError>
+        for ParseManyTill<OutputType, TerminatorOutputType, ParserType, TerminatorParserType>
+    {
+        fn parse(
+            &self,
+            parser_state: ParserState,
+        ) -> ParseResult<ParseError, (Vec<OutputType>, TerminatorOutputType)> {
+            let mut new_state = parser_state;
+            let mut output = Vec::new();
+
+            loop {
+                // Stop as soon as the terminator matches.
+                let terminator_error = match self.terminator.parse(new_state.clone()) {
+                    Ok((terminator, terminator_state)) => {
+                        return Ok(((output, terminator), terminator_state))
+                    }
+                    Err(e) if e.is_committed() => return Err(e),
+                    Err(e) => e,
+                };
+
+                // Otherwise there must be another element. If there isn't,
+                // report what both of them expected.
+                match self.parser.parse(new_state.clone()) {
+                    Ok((element, element_state)) => {
+                        // An element that consumes nothing would repeat
+                        // forever, so the terminator is required instead.
+                        if element_state.index == new_state.index {
+                            return Err(terminator_error);
+                        }
+                        new_state = element_state;
+                        output.push(element);
+                    }
+                    Err(e) if e.is_committed() => return Err(e),
+                    Err(e) => return Err(terminator_error.merge(e)),
+                }
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::sequence([
+                Grammar::repeat(0, usize::MAX, self.parser.describe()),
+                self.terminator.describe(),
+            ])
+        }
     }
 
     /// Parses one element and then another element.
@@ -204,7 +731,7 @@ pub mod utf8 {
             ParserTypeB: Parser<OutputTypeB, ErrorTypeB>,
         > ParseAnd<OutputTypeA, ErrorTypeA, ParserTypeA, OutputTypeB, ErrorTypeB, ParserTypeB>
     {
-        pub fn new(parser_a: ParserTypeA, parser_b: ParserTypeB) -> Self {
+        pub const fn new(parser_a: ParserTypeA, parser_b: ParserTypeB) -> Self {
             Self {
                 parser_a,
                 parser_b,
@@ -235,50 +762,4517 @@ pub mod utf8 {
             // Return the values.
             Ok(((a, b), new_state))
         }
-    }
-}
 
-/// A trait to be added to other parsers that allows easier parser combining.
-pub trait ParserExtensions<OutputType, ErrorType>: Parser<OutputType, ErrorType> {
-    fn char(&self, character: char) -> ParseChar {
-        ParseChar::from_char(character)
+        fn parse_all(
+            &self,
+            parser_state: ParserState,
+        ) -> Result<Vec<((OutputTypeA, OutputTypeB), ParserState)>, ErrorType> {
+            // Find every pair of interpretations first, keeping only the
+            // states, since an output of the first parser can't be copied into
+            // each of the pairs that share it.
+            let mut error = None;
+            let paths = vec![(Vec::new(), parser_state.clone())];
+            let paths = extend_paths(&self.parser_a, paths, &mut error);
+            let paths = extend_paths(&self.parser_b, paths, &mut error);
+            if let (true, Some(error)) = (paths.is_empty(), error) {
+                return Err(error);
+            }
+
+            // Then build the outputs of each pair by parsing them again.
+            let mut results = Vec::new();
+            for (path, _) in paths {
+                let a = self
+                    .parser_a
+                    .parse_all(parser_state.clone())?
+                    .into_iter()
+                    .nth(path[0]);
+                if let Some((a, new_state)) = a {
+                    let b = self.parser_b.parse_all(new_state)?.into_iter().nth(path[1]);
+                    if let Some((b, new_state)) = b {
+                        results.push(((a, b), new_state));
+                    }
+                }
+            }
+            Ok(results)
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::sequence([self.parser_a.describe(), self.parser_b.describe()])
+        }
     }
 
-    fn char_range(&self, start: char, end: char) -> ParseChar {
-        ParseChar::from_range(start, end)
+    /// Parses one element and then another element, returning only the output of the second, such as a value
+    /// after a prefix.
+    pub struct ParsePreceded<
+        OutputTypeA,
+        OutputTypeB,
+        ErrorType,
+        ParserTypeA: Parser<OutputTypeA, ErrorType>,
+        ParserTypeB: Parser<OutputTypeB, ErrorType>,
+    > {
+        /// The first parser to run.
+        parser_a: ParserTypeA,
+
+        /// The second parser to run.
+        parser_b: ParserTypeB,
+
+        /* Phantom */
+        _phantom: PhantomData<(OutputTypeA, OutputTypeB, ErrorType)>,
     }
 
-    fn and<NextOutputType, NextErrorType, NextParserType: Parser<NextOutputType, NextErrorType>>(
-        self,
-        next: NextParserType,
-    ) -> ParseAnd<OutputType, ErrorType, Self, NextOutputType, NextErrorType, NextParserType>
-    where
-        Self: Sized,
+    impl<
+            OutputTypeA,
+            OutputTypeB,
+            ErrorType,
+            ParserTypeA: Parser<OutputTypeA, ErrorType>,
+            ParserTypeB: Parser<OutputTypeB, ErrorType>,
+        > ParsePreceded<OutputTypeA, OutputTypeB, ErrorType, ParserTypeA, ParserTypeB>
     {
-        ParseAnd::new(self, next)
+        /// Create a new parser that runs `parser_a` and then `parser_b`, keeping the output of the second.
+        pub const fn new(parser_a: ParserTypeA, parser_b: ParserTypeB) -> Self {
+            Self {
+                parser_a,
+                parser_b,
+                _phantom: PhantomData,
+            }
+        }
     }
 
-    fn between(self, min: usize, max: usize) -> ParseCount<OutputType, ErrorType, Self>
-    where
-        Self: Sized,
+    impl<
+            OutputTypeA,
+            OutputTypeB,
+            ErrorType,
+            ParserTypeA: Parser<OutputTypeA, ErrorType>,
+            ParserTypeB: Parser<OutputTypeB, ErrorType>,
+        > Parser<OutputTypeB, ErrorType>
+        for ParsePreceded<OutputTypeA, OutputTypeB, ErrorType, ParserTypeA, ParserTypeB>
     {
-        ParseCount::new(min, max, self)
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, OutputTypeB> {
+            let (_, new_state) = self.parser_a.parse(parser_state)?;
+            let (output, new_state) = self.parser_b.parse(new_state)?;
+            Ok((output, new_state))
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::sequence([self.parser_a.describe(), self.parser_b.describe()])
+        }
     }
 
-    fn at_least(self, min: usize) -> ParseCount<OutputType, ErrorType, Self>
-    where
-        Self: Sized,
-    {
-        self.between(min, usize::max_value())
+    /// Parses one element and then another element, returning only the output of the first, such as a statement
+    /// before a semicolon.
+    pub struct ParseTerminated<
+        OutputTypeA,
+        OutputTypeB,
+        ErrorType,
+        ParserTypeA: Parser<OutputTypeA, ErrorType>,
+        ParserTypeB: Parser<OutputTypeB, ErrorType>,
+    > {
+        /// The first parser to run.
+        parser_a: ParserTypeA,
+
+        /// The second parser to run.
+        parser_b: ParserTypeB,
+
+        /* Phantom */
+        _phantom: PhantomData<(OutputTypeA, OutputTypeB, ErrorType)>,
     }
 
-    fn no_more_than(self, max: usize) -> ParseCount<OutputType, ErrorType, Self>
-    where
-        Self: Sized,
-    {
+    impl<
+            OutputTypeA,
+            OutputTypeB,
+            ErrorType,
+            ParserTypeA: Parser<OutputTypeA, ErrorType>,
+            ParserTypeB: Parser<OutputTypeB, ErrorType>,
+        > ParseTerminated<OutputTypeA, OutputTypeB, ErrorType, ParserTypeA, ParserTypeB>
+    {
+        /// Create a new parser that runs `parser_a` and then `parser_b`, keeping the output of the first.
+        pub const fn new(parser_a: ParserTypeA, parser_b: ParserTypeB) -> Self {
+            Self {
+                parser_a,
+                parser_b,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<
+            OutputTypeA,
+            OutputTypeB,
+            ErrorType,
+            ParserTypeA: Parser<OutputTypeA, ErrorType>,
+            ParserTypeB: Parser<OutputTypeB, ErrorType>,
+        > Parser<OutputTypeA, ErrorType>
+        for ParseTerminated<OutputTypeA, OutputTypeB, ErrorType, ParserTypeA, ParserTypeB>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, OutputTypeA> {
+            let (output, new_state) = self.parser_a.parse(parser_state)?;
+            let (_, new_state) = self.parser_b.parse(new_state)?;
+            Ok((output, new_state))
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::sequence([self.parser_a.describe(), self.parser_b.describe()])
+        }
+    }
+
+    /// Parses an element between an opening and a closing element, such as the contents of brackets, returning only
+    /// the element in the middle.
+    pub struct ParseDelimited<
+        OpenOutputType,
+        OutputType,
+        CloseOutputType,
+        ErrorType,
+        OpenParserType: Parser<OpenOutputType, ErrorType>,
+        ParserType: Parser<OutputType, ErrorType>,
+        CloseParserType: Parser<CloseOutputType, ErrorType>,
+    > {
+        /// The parser for the opening element.
+        open: OpenParserType,
+
+        /// The parser for the element in the middle.
+        parser: ParserType,
+
+        /// The parser for the closing element.
+        close: CloseParserType,
+
+        /* Phantom */
+        _phantom: PhantomData<(OpenOutputType, OutputType, CloseOutputType, ErrorType)>,
+    }
+
+    impl<
+            OpenOutputType,
+            OutputType,
+            CloseOutputType,
+            ErrorType,
+            OpenParserType: Parser<OpenOutputType, ErrorType>,
+            ParserType: Parser<OutputType, ErrorType>,
+            CloseParserType: Parser<CloseOutputType, ErrorType>,
+        >
+        ParseDelimited<
+            OpenOutputType,
+            OutputType,
+            CloseOutputType,
+            ErrorType,
+            OpenParserType,
+            ParserType,
+            CloseParserType,
+        >
+    {
+        /// Create a new parser that runs `parser` between `open` and `close`.
+        pub const fn new(open: OpenParserType, parser: ParserType, close: CloseParserType) -> Self {
+            Self {
+                open,
+                parser,
+                close,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<
+            OpenOutputType,
+            OutputType,
+            CloseOutputType,
+            ErrorType,
+            OpenParserType: Parser<OpenOutputType, ErrorType>,
+            ParserType: Parser<OutputType, ErrorType>,
+            CloseParserType: Parser<CloseOutputType, ErrorType>,
+        > Parser<OutputType, ErrorType>
+        for ParseDelimited<
+            OpenOutputType,
+            OutputType,
+            CloseOutputType,
+            ErrorType,
+            OpenParserType,
+            ParserType,
+            CloseParserType,
+        >
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, OutputType> {
+            let (_, new_state) = self.open.parse(parser_state)?;
+            let (output, new_state) = self.parser.parse(new_state)?;
+            let (_, new_state) = self.close.parse(new_state)?;
+            Ok((output, new_state))
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::sequence([
+                self.open.describe(),
+                self.parser.describe(),
+                self.close.describe(),
+            ])
+        }
+    }
+
+    /// Parses one of two elements, failing if both of them match at the current position.
+    pub struct ParseXor<
+        OutputType,
+        ParserTypeA: Parser<OutputType, ParseError>,
+        ParserTypeB: Parser<OutputType, ParseError>,
+    > {
+        /// The first alternative to try.
+        parser_a: ParserTypeA,
+
+        /// The second alternative to try.
+        parser_b: ParserTypeB,
+
+        /* Phantom */
+        _phantom: PhantomData<OutputType>,
+    }
+
+    impl<
+            OutputType,
+            ParserTypeA: Parser<OutputType, ParseError>,
+            ParserTypeB: Parser<OutputType, ParseError>,
+        > ParseXor<OutputType, ParserTypeA, ParserTypeB>
+    {
+        /// Create a new exclusive-or parser from the two alternatives.
+        pub const fn new(parser_a: ParserTypeA, parser_b: ParserTypeB) -> Self {
+            Self {
+                parser_a,
+                parser_b,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<
+            OutputType,
+            ParserTypeA: Parser<OutputType, ParseError>,
+            ParserTypeB: Parser<OutputType, ParseError>,
+        > Parser<OutputType, ParseError> for ParseXor<OutputType, ParserTypeA, ParserTypeB>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, OutputType> {
+            // Run both alternatives from the same state.
+            let index = parser_state.index;
+            let result_a = self.parser_a.parse(parser_state.clone());
+            let result_b = self.parser_b.parse(parser_state);
+
+            match (result_a, result_b) {
+                // An ambiguity found inside either alternative (such as in a
+                // chain of `xor` calls), or an alternative that failed after
+                // committing, is always an error.
+                (Err(e), _) | (_, Err(e))
+                    if matches!(e.kind(), ParseErrorKind::Ambiguous) || e.is_committed() =>
+                {
+                    Err(e)
+                }
+
+                // If both alternatives match, the input is ambiguous.
+                (Ok(_), Ok(_)) => Err(ParseError::ambiguous(index)),
+
+                // If exactly one alternative matches, use its result.
+                (Ok(output), Err(_)) | (Err(_), Ok(output)) => Ok(output),
+
+                // If neither matches, report what both of them expected.
+                (Err(e_a), Err(e_b)) => Err(e_a.merge(e_b)),
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::choice([self.parser_a.describe(), self.parser_b.describe()])
+        }
+    }
+
+    /// Parses one of two elements, trying the second only if the first fails.
+    pub struct ParseOr<
+        OutputType,
+        ParserTypeA: Parser<OutputType, ParseError>,
+        ParserTypeB: Parser<OutputType, ParseError>,
+    > {
+        /// The alternative to try first.
+        parser_a: ParserTypeA,
+
+        /// The alternative to try if the first one fails.
+        parser_b: ParserTypeB,
+
+        /* Phantom */
+        _phantom: PhantomData<OutputType>,
+    }
+
+    impl<
+            OutputType,
+            ParserTypeA: Parser<OutputType, ParseError>,
+            ParserTypeB: Parser<OutputType, ParseError>,
+        > ParseOr<OutputType, ParserTypeA, ParserTypeB>
+    {
+        /// Create a new parser that tries `parser_a` and falls back to `parser_b`.
+        pub const fn new(parser_a: ParserTypeA, parser_b: ParserTypeB) -> Self {
+            Self {
+                parser_a,
+                parser_b,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<
+            OutputType,
+            ParserTypeA: Parser<OutputType, ParseError>,
+            ParserTypeB: Parser<OutputType, ParseError>,
+        > Parser<OutputType, ParseError> for ParseOr<OutputType, ParserTypeA, ParserTypeB>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, OutputType> {
+            match self.parser_a.parse(parser_state.clone()) {
+                Ok(result) => Ok(result),
+                Err(e_a) if e_a.is_committed() => Err(e_a),
+                Err(e_a) => self
+                    .parser_b
+                    .parse(parser_state)
+                    // If neither matches, report what both of them expected.
+                    .map_err(|e_b| e_a.merge(e_b)),
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::choice([self.parser_a.describe(), self.parser_b.describe()])
+        }
+    }
+
+    /// The number of characters of trailing input to include in errors from `ParseAllConsuming`.
+    const TRAILING_PREVIEW_LENGTH: usize = 16;
+
+    /// Parses an element and then requires that there is no input left over.
+    pub struct ParseAllConsuming<OutputType, ParserType: Parser<OutputType, ParseError>> {
+        /// The parser that should consume the rest of the input.
+        parser: ParserType,
+
+        /* Phantom */
+        _phantom: PhantomData<OutputType>,
+    }
+
+    impl<OutputType, ParserType: Parser<OutputType, ParseError>>
+        ParseAllConsuming<OutputType, ParserType>
+    {
+        /// Create a new parser that requires the provided parser to consume all of its input.
+        pub const fn new(parser: ParserType) -> Self {
+            Self {
+                parser,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<OutputType, ParserType: Parser<OutputType, ParseError>> Parser<OutputType, ParseError>
+        for ParseAllConsuming<OutputType, ParserType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, OutputType> {
+            // Run the inner parser.
+            let (output, new_state) = self.parser.parse(parser_state)?;
+
+            if new_state.input.is_empty() {
+                // If there's nothing left, the parse succeeded.
+                Ok((output, new_state))
+            } else {
+                // Otherwise, report where the leftover input starts and a
+                // short preview of what it contains.
+                let mut found = String::new();
+                if new_state.verbosity() == ErrorVerbosity::Rich {
+                    found.extend(new_state.input.chars().take(TRAILING_PREVIEW_LENGTH));
+                    if found.len() < new_state.input.len() {
+                        found.push_str("...");
+                    }
+                }
+
+                Err(ParseError::new(
+                    ParseErrorKind::TrailingInput { found },
+                    new_state.index,
+                ))
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::sequence([
+                self.parser.describe(),
+                Grammar::Special(String::from("end of input")),
+            ])
+        }
+    }
+
+    /// Parses an element if it's there, producing `None` without consuming any input if it isn't.
+    pub struct ParseOpt<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>> {
+        /// The parser for the optional element.
+        parser: ParserType,
+
+        /* Phantom */
+        _phantom: PhantomData<(OutputType, ErrorType)>,
+    }
+
+    impl<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>>
+        ParseOpt<OutputType, ErrorType, ParserType>
+    {
+        /// Create a new parser that makes the provided parser optional.
+        pub const fn new(parser: ParserType) -> Self {
+            Self {
+                parser,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>>
+        Parser<Option<OutputType>, ErrorType> for ParseOpt<OutputType, ErrorType, ParserType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, Option<OutputType>> {
+            match self.parser.parse(parser_state.clone()) {
+                Ok((output, new_state)) => Ok((Some(output), new_state)),
+                Err(_) => Ok((None, parser_state)),
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::repeat(0, 1, self.parser.describe())
+        }
+    }
+
+    /// Parses an element only when a condition holds, such as a flag for an optional dialect feature. The condition
+    /// is checked each time the parser is run, so it can change between runs.
+    pub struct ParseCond<
+        OutputType,
+        ErrorType,
+        ParserType: Parser<OutputType, ErrorType>,
+        ConditionType: Fn() -> bool,
+    > {
+        /// The parser for the element.
+        parser: ParserType,
+
+        /// The function that decides whether the element should be parsed.
+        condition: ConditionType,
+
+        /* Phantom */
+        _phantom: PhantomData<(OutputType, ErrorType)>,
+    }
+
+    impl<
+            OutputType,
+            ErrorType,
+            ParserType: Parser<OutputType, ErrorType>,
+            ConditionType: Fn() -> bool,
+        > ParseCond<OutputType, ErrorType, ParserType, ConditionType>
+    {
+        /// Create a new parser that only runs the provided parser when `condition` returns `true`.
+        pub const fn new(parser: ParserType, condition: ConditionType) -> Self {
+            Self {
+                parser,
+                condition,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<
+            OutputType,
+            ErrorType,
+            ParserType: Parser<OutputType, ErrorType>,
+            ConditionType: Fn() -> bool,
+        > Parser<Option<OutputType>, ErrorType>
+        for ParseCond<OutputType, ErrorType, ParserType, ConditionType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, Option<OutputType>> {
+            // When the condition holds the element is required, so its errors
+            // are passed on rather than treated as a missing element.
+            if (self.condition)() {
+                let (output, new_state) = self.parser.parse(parser_state)?;
+                Ok((Some(output), new_state))
+            } else {
+                Ok((None, parser_state))
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::repeat(0, 1, self.parser.describe())
+        }
+    }
+
+    /// Create a parser that only runs `parser` if `condition` is `true`, producing `None` otherwise.
+    pub fn cond<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>>(
+        condition: bool,
+        parser: ParserType,
+    ) -> ParseCond<OutputType, ErrorType, ParserType, impl Fn() -> bool> {
+        ParseCond::new(parser, move || condition)
+    }
+
+    /// Parses an element or, if it's missing, produces a fallback value without consuming any input. An element that
+    /// fails after its start is malformed rather than missing, so that error is returned instead.
+    pub struct ParseOrValue<OutputType: Clone, ErrorType, ParserType: Parser<OutputType, ErrorType>> {
+        /// The parser to try first.
+        parser: ParserType,
+
+        /// The value to produce if the parser fails.
+        value: OutputType,
+
+        /* Phantom */
+        _phantom: PhantomData<ErrorType>,
+    }
+
+    impl<OutputType: Clone, ErrorType, ParserType: Parser<OutputType, ErrorType>>
+        ParseOrValue<OutputType, ErrorType, ParserType>
+    {
+        /// Create a new parser that falls back to the provided value when the inner parser fails.
+        pub const fn new(parser: ParserType, value: OutputType) -> Self {
+            Self {
+                parser,
+                value,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<OutputType: Clone, ErrorType: Commit, ParserType: Parser<OutputType, ErrorType>>
+        Parser<OutputType, ErrorType> for ParseOrValue<OutputType, ErrorType, ParserType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, OutputType> {
+            match self.parser.parse(parser_state.clone()) {
+                Ok(result) => Ok(result),
+                // Only fall back when the element is missing. If it failed
+                // after the start, it was there but malformed.
+                Err(e) if e.is_committed() => Err(e),
+                Err(e) if Commit::offset(&e).is_some_and(|offset| offset != parser_state.index) => {
+                    Err(e)
+                }
+                Err(_) => Ok((self.value.clone(), parser_state)),
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::repeat(0, 1, self.parser.describe())
+        }
+    }
+
+    /// Parses an element and then converts its output with a function.
+    pub struct ParseMap<
+        InputType,
+        OutputType,
+        ErrorType,
+        ParserType: Parser<InputType, ErrorType>,
+        MapperType: Fn(InputType) -> OutputType,
+    > {
+        /// The parser whose output should be converted.
+        parser: ParserType,
+
+        /// The function used to convert the output.
+        mapper: MapperType,
+
+        /* Phantom */
+        _phantom: PhantomData<(InputType, OutputType, ErrorType)>,
+    }
+
+    impl<
+            InputType,
+            OutputType,
+            ErrorType,
+            ParserType: Parser<InputType, ErrorType>,
+            MapperType: Fn(InputType) -> OutputType,
+        > ParseMap<InputType, OutputType, ErrorType, ParserType, MapperType>
+    {
+        /// Create a new parser that converts the output of the provided parser with `mapper`.
+        pub const fn new(parser: ParserType, mapper: MapperType) -> Self {
+            Self {
+                parser,
+                mapper,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<
+            InputType,
+            OutputType,
+            ErrorType,
+            ParserType: Parser<InputType, ErrorType>,
+            MapperType: Fn(InputType) -> OutputType,
+        > Parser<OutputType, ErrorType>
+        for ParseMap<InputType, OutputType, ErrorType, ParserType, MapperType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, OutputType> {
+            let (output, new_state) = self.parser.parse(parser_state)?;
+            Ok(((self.mapper)(output), new_state))
+        }
+
+        fn parse_all(
+            &self,
+            parser_state: ParserState,
+        ) -> Result<Vec<(OutputType, ParserState)>, ErrorType> {
+            let results = self.parser.parse_all(parser_state)?;
+            Ok(results
+                .into_iter()
+                .map(|(output, new_state)| ((self.mapper)(output), new_state))
+                .collect())
+        }
+
+        fn describe(&self) -> Grammar {
+            self.parser.describe()
+        }
+    }
+
+    /// Parses an element and replaces its output with a fixed value, such as turning the keyword `true` into a
+    /// literal.
+    pub struct ParseToValue<
+        InputType,
+        OutputType: Clone,
+        ErrorType,
+        ParserType: Parser<InputType, ErrorType>,
+    > {
+        /// The parser whose output is replaced.
+        parser: ParserType,
+
+        /// The value to produce when the parser matches.
+        value: OutputType,
+
+        /* Phantom */
+        _phantom: PhantomData<(InputType, ErrorType)>,
+    }
+
+    impl<InputType, OutputType: Clone, ErrorType, ParserType: Parser<InputType, ErrorType>>
+        ParseToValue<InputType, OutputType, ErrorType, ParserType>
+    {
+        /// Create a new parser that produces `value` whenever the provided parser matches.
+        pub const fn new(parser: ParserType, value: OutputType) -> Self {
+            Self {
+                parser,
+                value,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<InputType, OutputType: Clone, ErrorType, ParserType: Parser<InputType, ErrorType>>
+        Parser<OutputType, ErrorType>
+        for ParseToValue<InputType, OutputType, ErrorType, ParserType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, OutputType> {
+            let (_, new_state) = self.parser.parse(parser_state)?;
+            Ok((self.value.clone(), new_state))
+        }
+
+        fn describe(&self) -> Grammar {
+            self.parser.describe()
+        }
+    }
+
+    /// Parses an element and converts any error it produces with a function, so parsers can be used with other
+    /// error types.
+    pub struct ParseMapErr<
+        OutputType,
+        ErrorType,
+        NextErrorType,
+        ParserType: Parser<OutputType, ErrorType>,
+        MapperType: Fn(ErrorType) -> NextErrorType,
+    > {
+        /// The parser whose errors should be converted.
+        parser: ParserType,
+
+        /// The function used to convert the errors.
+        mapper: MapperType,
+
+        /* Phantom */
+        _phantom: PhantomData<(OutputType, ErrorType, NextErrorType)>,
+    }
+
+    impl<
+            OutputType,
+            ErrorType,
+            NextErrorType,
+            ParserType: Parser<OutputType, ErrorType>,
+            MapperType: Fn(ErrorType) -> NextErrorType,
+        > ParseMapErr<OutputType, ErrorType, NextErrorType, ParserType, MapperType>
+    {
+        /// Create a new parser that converts the errors of the provided parser with `mapper`.
+        pub const fn new(parser: ParserType, mapper: MapperType) -> Self {
+            Self {
+                parser,
+                mapper,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<
+            OutputType,
+            ErrorType,
+            NextErrorType,
+            ParserType: Parser<OutputType, ErrorType>,
+            MapperType: Fn(ErrorType) -> NextErrorType,
+        > Parser<OutputType, NextErrorType>
+        for ParseMapErr<OutputType, ErrorType, NextErrorType, ParserType, MapperType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<NextErrorType, OutputType> {
+            self.parser.parse(parser_state).map_err(&self.mapper)
+        }
+
+        fn describe(&self) -> Grammar {
+            self.parser.describe()
+        }
+    }
+
+    /// Parses an element and then converts it with a function that may fail.
+    pub struct ParseTryMap<
+        InputType,
+        OutputType,
+        ConversionErrorType: Display,
+        ParserType: Parser<InputType, ParseError>,
+        MapperType: Fn(InputType) -> Result<OutputType, ConversionErrorType>,
+    > {
+        /// The parser whose output should be converted.
+        parser: ParserType,
+
+        /// The function used to convert the output.
+        mapper: MapperType,
+
+        /* Phantom */
+        _phantom: PhantomData<(InputType, OutputType, ConversionErrorType)>,
+    }
+
+    impl<
+            InputType,
+            OutputType,
+            ConversionErrorType: Display,
+            ParserType: Parser<InputType, ParseError>,
+            MapperType: Fn(InputType) -> Result<OutputType, ConversionErrorType>,
+        > ParseTryMap<InputType, OutputType, ConversionErrorType, ParserType, MapperType>
+    {
+        /// Create a new parser that converts the output of the provided parser with the fallible `mapper`.
+        pub const fn new(parser: ParserType, mapper: MapperType) -> Self {
+            Self {
+                parser,
+                mapper,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<
+            InputType,
+            OutputType,
+            ConversionErrorType: Display,
+            ParserType: Parser<InputType, ParseError>,
+            MapperType: Fn(InputType) -> Result<OutputType, ConversionErrorType>,
+        > Parser<OutputType, ParseError>
+        for ParseTryMap<InputType, OutputType, ConversionErrorType, ParserType, MapperType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, OutputType> {
+            // Run the inner parser, remembering where it started.
+            let start = parser_state.index;
+            let (output, new_state) = self.parser.parse(parser_state)?;
+
+            // Try to convert the output, attaching the span of the input that
+            // was parsed to the error if the conversion fails.
+            match (self.mapper)(output) {
+                Ok(output) => Ok((output, new_state)),
+                Err(e) => Err(ParseError::new(
+                    ParseErrorKind::Invalid {
+                        end: new_state.index,
+                        message: if new_state.verbosity() == ErrorVerbosity::Rich {
+                            e.to_string()
+                        } else {
+                            String::new()
+                        },
+                    },
+                    start,
+                )),
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            self.parser.describe()
+        }
+    }
+
+    /// Parses an element and only accepts its output if it passes a check, such as a number being within range or
+    /// an identifier not being a reserved word.
+    pub struct ParseVerify<
+        OutputType,
+        ParserType: Parser<OutputType, ParseError>,
+        PredicateType: Fn(&OutputType) -> bool,
+    > {
+        /// The parser whose output should be checked.
+        parser: ParserType,
+
+        /// A description of the outputs that pass, reported when the check fails.
+        expected: &'static str,
+
+        /// The function that decides whether the output is accepted.
+        predicate: PredicateType,
+
+        /* Phantom */
+        _phantom: PhantomData<OutputType>,
+    }
+
+    impl<
+            OutputType,
+            ParserType: Parser<OutputType, ParseError>,
+            PredicateType: Fn(&OutputType) -> bool,
+        > ParseVerify<OutputType, ParserType, PredicateType>
+    {
+        /// Create a new parser that only accepts the output of the provided parser if `predicate` returns `true`.
+        pub const fn new(parser: ParserType, predicate: PredicateType) -> Self {
+            Self {
+                parser,
+                expected: "valid value",
+                predicate,
+                _phantom: PhantomData,
+            }
+        }
+
+        /// Describe the outputs that pass the check, for errors.
+        pub const fn expecting(mut self, expected: &'static str) -> Self {
+            self.expected = expected;
+            self
+        }
+    }
+
+    impl<
+            OutputType,
+            ParserType: Parser<OutputType, ParseError>,
+            PredicateType: Fn(&OutputType) -> bool,
+        > Parser<OutputType, ParseError> for ParseVerify<OutputType, ParserType, PredicateType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, OutputType> {
+            // Keep the input so the rejected text can be reported.
+            let start = parser_state.index;
+            let input = parser_state.input.clone();
+            let (output, new_state) = self.parser.parse(parser_state)?;
+            if (self.predicate)(&output) {
+                return Ok((output, new_state));
+            }
+
+            // The error points at the start of the element that was rejected.
+            if new_state.verbosity() == ErrorVerbosity::Rich {
+                let matched = &input[..input.len() - new_state.input.len()];
+                Err(ParseError::unexpected(
+                    start,
+                    [self.expected],
+                    Some(matched),
+                ))
+            } else {
+                Err(ParseError::new(
+                    ParseErrorKind::Unexpected {
+                        expected: BTreeSet::new(),
+                        found: None,
+                    },
+                    start,
+                ))
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            self.parser.describe()
+        }
+    }
+
+    /// A store for the results of a memoized parser, keyed by the parsing run and the input position (and error
+    /// verbosity) that the parser was run from. The input can differ between runs, so results from one run are
+    /// never used for another.
+    pub trait MemoCache<OutputType, ErrorType> {
+        /// Get the stored result for the provided position, if there is one.
+        fn get(&self, key: MemoKey) -> Option<ParseResult<ErrorType, OutputType>>;
+
+        /// Store the result of running the parser from the provided position.
+        fn insert(&mut self, key: MemoKey, result: ParseResult<ErrorType, OutputType>);
+
+        /// Remove the stored results of the provided parsing run.
+        fn remove_run(&mut self, run: u64);
+
+        /// Remove all of the stored results.
+        fn clear(&mut self);
+    }
+
+    /// The position a memoized parser was run from: the parsing run, the index in the input, and how rich its
+    /// errors are.
+    pub type MemoKey = (u64, usize, ErrorVerbosity);
+
+    /// The number of parsing runs a memoized parser keeps results for at once, so that threads sharing the parser
+    /// don't throw away each other's results. Once there are more, the results of the oldest run are forgotten.
+    const MEMO_RUNS: usize = 16;
+
+    impl<OutputType: Clone, ErrorType: Clone> MemoCache<OutputType, ErrorType>
+        for HashMap<MemoKey, ParseResult<ErrorType, OutputType>>
+    {
+        fn get(&self, key: MemoKey) -> Option<ParseResult<ErrorType, OutputType>> {
+            HashMap::get(self, &key).cloned()
+        }
+
+        fn insert(&mut self, key: MemoKey, result: ParseResult<ErrorType, OutputType>) {
+            HashMap::insert(self, key, result);
+        }
+
+        fn remove_run(&mut self, run: u64) {
+            self.retain(|(key_run, _, _), _| *key_run != run);
+        }
+
+        fn clear(&mut self) {
+            HashMap::clear(self);
+        }
+    }
+
+    /// Parses an element, remembering the result for each position so that repeated attempts at the same position
+    /// don't run the inner parser again.
+    pub struct ParseMemoized<
+        OutputType,
+        ErrorType,
+        ParserType: Parser<OutputType, ErrorType>,
+        CacheType: MemoCache<OutputType, ErrorType>,
+    > {
+        /// The parser whose results should be remembered.
+        parser: ParserType,
+
+        /// The parsing runs with remembered results, oldest first, and the results that have been remembered so far,
+        /// behind a lock so the parser can be shared between threads.
+        cache: Mutex<(VecDeque<u64>, CacheType)>,
+
+        /* Phantom */
+        _phantom: PhantomData<(OutputType, ErrorType)>,
+    }
+
+    impl<OutputType: Clone, ErrorType: Clone, ParserType: Parser<OutputType, ErrorType>>
+        ParseMemoized<
+            OutputType,
+            ErrorType,
+            ParserType,
+            HashMap<MemoKey, ParseResult<ErrorType, OutputType>>,
+        >
+    {
+        /// Create a new memoizing parser that stores its results in a `HashMap`.
+        pub fn new(parser: ParserType) -> Self {
+            Self::with_cache(parser, HashMap::new())
+        }
+    }
+
+    impl<
+            OutputType,
+            ErrorType,
+            ParserType: Parser<OutputType, ErrorType>,
+            CacheType: MemoCache<OutputType, ErrorType>,
+        > ParseMemoized<OutputType, ErrorType, ParserType, CacheType>
+    {
+        /// Create a new memoizing parser that stores its results in the provided cache.
+        pub fn with_cache(parser: ParserType, cache: CacheType) -> Self {
+            Self {
+                parser,
+                cache: Mutex::new((VecDeque::new(), cache)),
+                _phantom: PhantomData,
+            }
+        }
+
+        /// Lock the cache. A panic while the cache was locked can't leave it half-updated, so a poisoned lock is
+        /// still used.
+        fn cache(&self) -> MutexGuard<'_, (VecDeque<u64>, CacheType)> {
+            self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+        }
+
+        /// Lock the cache for the run `parser_state` belongs to, making room for it if it's new by forgetting the
+        /// results of the oldest run.
+        fn cache_for(
+            &self,
+            parser_state: &ParserState,
+        ) -> MutexGuard<'_, (VecDeque<u64>, CacheType)> {
+            let mut cache = self.cache();
+            let run = parser_state.run();
+            if !cache.0.contains(&run) {
+                if cache.0.len() == MEMO_RUNS {
+                    if let Some(oldest) = cache.0.pop_front() {
+                        cache.1.remove_run(oldest);
+                    }
+                }
+                cache.0.push_back(run);
+            }
+            cache
+        }
+
+        /// Forget all of the results remembered so far.
+        pub fn clear(&self) {
+            let mut cache = self.cache();
+            cache.0.clear();
+            cache.1.clear();
+        }
+    }
+
+    impl<
+            OutputType: Clone,
+            ErrorType: Clone,
+            ParserType: Parser<OutputType, ErrorType>,
+            CacheType: MemoCache<OutputType, ErrorType>,
+        > Parser<OutputType, ErrorType>
+        for ParseMemoized<OutputType, ErrorType, ParserType, CacheType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, OutputType> {
+            // If this position has been parsed before, reuse the result.
+            let run = parser_state.run();
+            let key = (run, parser_state.index, parser_state.verbosity());
+            if let Some(result) = self.cache_for(&parser_state).1.get(key) {
+                return result;
+            }
+
+            // Otherwise, run the parser and remember the result, unless
+            // enough other runs started meanwhile that this one was
+            // forgotten. The cache isn't locked while the parser runs so that
+            // recursive uses of this parser don't deadlock.
+            let result = self.parser.parse(parser_state);
+            let mut cache = self.cache();
+            if cache.0.contains(&run) {
+                cache.1.insert(key, result.clone());
+            }
+            result
+        }
+
+        fn describe(&self) -> Grammar {
+            self.parser.describe()
+        }
+    }
+
+    /// Captures a region of text with one parser and then parses exactly that text with another. The capture
+    /// returns the text along with where it starts in the input, such as `body.recognize().spanned()` inside a pair
+    /// of delimiters, so that positions reported by the second parser refer to the original input. If the capture
+    /// transforms the text, such as by unescaping it, positions are counted from the start of its span.
+    pub struct ParseReparse<
+        OutputType,
+        ErrorType,
+        CaptureParserType: Parser<Spanned<String>, ErrorType>,
+        ParserType: Parser<OutputType, ErrorType>,
+    > {
+        /// The parser that captures the text to parse again.
+        capture: CaptureParserType,
+
+        /// The parser to run over the captured text.
+        parser: ParserType,
+
+        /* Phantom */
+        _phantom: PhantomData<(OutputType, ErrorType)>,
+    }
+
+    impl<
+            OutputType,
+            ErrorType,
+            CaptureParserType: Parser<Spanned<String>, ErrorType>,
+            ParserType: Parser<OutputType, ErrorType>,
+        > ParseReparse<OutputType, ErrorType, CaptureParserType, ParserType>
+    {
+        /// Create a new parser that runs `parser` over the text produced by `capture`.
+        pub const fn new(capture: CaptureParserType, parser: ParserType) -> Self {
+            Self {
+                capture,
+                parser,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<
+            OutputType,
+            ErrorType,
+            CaptureParserType: Parser<Spanned<String>, ErrorType>,
+            ParserType: Parser<OutputType, ErrorType>,
+        > Parser<OutputType, ErrorType>
+        for ParseReparse<OutputType, ErrorType, CaptureParserType, ParserType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, OutputType> {
+            let (captured, new_state) = self.capture.parse(parser_state)?;
+
+            // Run the inner parser over only the captured text, starting at
+            // its position in the original input, and continue from where
+            // the capture left off.
+            let (output, _) = self.parser.parse(
+                ParserState::new_offset(&captured.value, captured.span.start)
+                    .with_verbosity(new_state.verbosity()),
+            )?;
+            Ok((output, new_state))
+        }
+
+        fn describe(&self) -> Grammar {
+            self.capture.describe()
+        }
+    }
+
+    /// Parses an element and then parses the rest of the input with a parser chosen based on its output, such as
+    /// a payload whose length was given by the element.
+    pub struct ParseAndThen<
+        OutputType,
+        NextOutputType,
+        ErrorType,
+        ParserType: Parser<OutputType, ErrorType>,
+        NextParserType: Parser<NextOutputType, ErrorType>,
+        BinderType: Fn(OutputType) -> NextParserType,
+    > {
+        /// The parser whose output chooses the next parser.
+        parser: ParserType,
+
+        /// The function that creates the next parser from the output.
+        binder: BinderType,
+
+        /* Phantom */
+        _phantom: PhantomData<(OutputType, NextOutputType, ErrorType, NextParserType)>,
+    }
+
+    impl<
+            OutputType,
+            NextOutputType,
+            ErrorType,
+            ParserType: Parser<OutputType, ErrorType>,
+            NextParserType: Parser<NextOutputType, ErrorType>,
+            BinderType: Fn(OutputType) -> NextParserType,
+        >
+        ParseAndThen<OutputType, NextOutputType, ErrorType, ParserType, NextParserType, BinderType>
+    {
+        /// Create a new parser that runs `parser` and then the parser that `binder` creates from its output.
+        pub const fn new(parser: ParserType, binder: BinderType) -> Self {
+            Self {
+                parser,
+                binder,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<
+            OutputType,
+            NextOutputType,
+            ErrorType,
+            ParserType: Parser<OutputType, ErrorType>,
+            NextParserType: Parser<NextOutputType, ErrorType>,
+            BinderType: Fn(OutputType) -> NextParserType,
+        > Parser<NextOutputType, ErrorType>
+        for ParseAndThen<
+            OutputType,
+            NextOutputType,
+            ErrorType,
+            ParserType,
+            NextParserType,
+            BinderType,
+        >
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, NextOutputType> {
+            // Run the first parser, then build the next one from its output
+            // and continue from where the first left off.
+            let (output, new_state) = self.parser.parse(parser_state)?;
+            (self.binder)(output).parse(new_state)
+        }
+
+        fn describe(&self) -> Grammar {
+            // What follows depends on the input, so it can't be described.
+            Grammar::sequence([self.parser.describe(), Grammar::Opaque])
+        }
+    }
+
+    /// Zero-width parsers that check the position within the input without consuming anything.
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+    pub enum ParseAnchor {
+        /// Matches only at the very start of the input.
+        StartOfInput,
+
+        /// Matches at the start of the input or directly after a newline.
+        StartOfLine,
+
+        /// Matches only when there is no input left. When it doesn't match, the error reports all of the input that
+        /// was left over.
+        EndOfInput,
+
+        /// Matches at the end of the input or directly before a newline (`\n` or `\r\n`).
+        EndOfLine,
+    }
+
+    impl ParseAnchor {
+        /// Check whether this anchor matches at the provided state.
+        fn matches(&self, parser_state: &ParserState) -> bool {
+            match self {
+                Self::StartOfInput => parser_state.previous.is_none(),
+                Self::StartOfLine => matches!(parser_state.previous, None | Some('\n')),
+                Self::EndOfInput => parser_state.input.is_empty(),
+                Self::EndOfLine => {
+                    parser_state.input.is_empty()
+                        || parser_state.input.starts_with('\n')
+                        || parser_state.input.starts_with("\r\n")
+                }
+            }
+        }
+
+        /// Get a description of the position this anchor matches.
+        fn description(&self) -> &'static str {
+            match self {
+                Self::StartOfInput => "start of input",
+                Self::StartOfLine => "start of line",
+                Self::EndOfInput => "end of input",
+                Self::EndOfLine => "end of line",
+            }
+        }
+    }
+
+    impl Parser<(), ParseError> for ParseAnchor {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, ()> {
+            if self.matches(&parser_state) {
+                // Anchors never consume any input.
+                Ok(((), parser_state))
+            } else {
+                let rich = parser_state.verbosity() == ErrorVerbosity::Rich;
+                Err(ParseError::new(
+                    ParseErrorKind::Unexpected {
+                        expected: if rich {
+                            BTreeSet::from([Expected::from(self.description())])
+                        } else {
+                            BTreeSet::new()
+                        },
+                        // Input left over at the end is reported in full,
+                        // since all of it is what wasn't expected.
+                        found: match self {
+                            Self::EndOfInput => Some(Found::from(parser_state.input.as_str())),
+                            _ => parser_state.input.chars().next().map(Found::Char),
+                        }
+                        .filter(|_| rich),
+                    },
+                    parser_state.index,
+                ))
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::Special(String::from(self.description()))
+        }
+    }
+
+    /// Create a parser that only matches at the start of the input.
+    pub const fn start_of_input() -> ParseAnchor {
+        ParseAnchor::StartOfInput
+    }
+
+    /// Create a parser that only matches at the start of a line.
+    pub const fn start_of_line() -> ParseAnchor {
+        ParseAnchor::StartOfLine
+    }
+
+    /// Create a parser that only matches at the end of the input, for rejecting input with trailing text.
+    pub const fn end_of_input() -> ParseAnchor {
+        ParseAnchor::EndOfInput
+    }
+
+    /// Create a parser that only matches at the end of a line.
+    pub const fn end_of_line() -> ParseAnchor {
+        ParseAnchor::EndOfLine
+    }
+
+    /// Succeeds without consuming any input only if an element doesn't match at the current position, such as a
+    /// name that must not be followed by `(`.
+    pub struct ParseNot<OutputType, ParserType: Parser<OutputType, ParseError>> {
+        /// The parser that must not match.
+        parser: ParserType,
+
+        /* Phantom */
+        _phantom: PhantomData<OutputType>,
+    }
+
+    impl<OutputType, ParserType: Parser<OutputType, ParseError>> ParseNot<OutputType, ParserType> {
+        /// Create a new parser that succeeds only where the provided parser fails.
+        pub const fn new(parser: ParserType) -> Self {
+            Self {
+                parser,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<OutputType, ParserType: Parser<OutputType, ParseError>> Parser<(), ParseError>
+        for ParseNot<OutputType, ParserType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, ()> {
+            match self.parser.parse(parser_state.clone()) {
+                // Lookahead never consumes any input, so the state is
+                // returned unchanged.
+                Err(e) if e.is_committed() => Err(e),
+                Err(_) => Ok(((), parser_state)),
+
+                // Report the text that matched when it shouldn't have.
+                Ok((_, new_state)) => {
+                    let matched = &parser_state.input[..new_state.index - parser_state.index];
+                    let message = if parser_state.verbosity() == ErrorVerbosity::Rich {
+                        format!("\"{}\" is not allowed here", matched)
+                    } else {
+                        String::new()
+                    };
+                    Err(ParseError::invalid(
+                        parser_state.index,
+                        new_state.index,
+                        &message,
+                    ))
+                }
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::Special(format!(
+                "not {}",
+                ebnf::expression_text(&self.parser.describe())
+            ))
+        }
+    }
+
+    /// Parses an element and returns its output without consuming any input, so what follows can be checked
+    /// before committing to it.
+    pub struct ParsePeek<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>> {
+        /// The parser to look ahead with.
+        parser: ParserType,
+
+        /* Phantom */
+        _phantom: PhantomData<(OutputType, ErrorType)>,
+    }
+
+    impl<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>>
+        ParsePeek<OutputType, ErrorType, ParserType>
+    {
+        /// Create a new parser that runs the provided parser without consuming its input.
+        pub const fn new(parser: ParserType) -> Self {
+            Self {
+                parser,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>>
+        Parser<OutputType, ErrorType> for ParsePeek<OutputType, ErrorType, ParserType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, OutputType> {
+            // Keep the output but return the original state.
+            let (output, _) = self.parser.parse(parser_state.clone())?;
+            Ok((output, parser_state))
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::Special(format!(
+                "followed by {}",
+                ebnf::expression_text(&self.parser.describe())
+            ))
+        }
+    }
+
+    /// Parses an element and marks any error it produces as committed, so that alternatives and lists around it
+    /// report the error instead of backtracking past it (see [`Commit`] for which combinators do). Put it on the
+    /// part of a rule that comes after the input has clearly started that rule:
+    ///
+    /// ```
+    /// use parselib_rs::parsers::utf8::{alpha1, digit1, ParseStr};
+    /// use parselib_rs::parsers::ParserExtensions;
+    /// use parselib_rs::{Commit, Parser, ParserState};
+    ///
+    /// let statement = ParseStr::new("let ")
+    ///     .then_keep_right(alpha1().cut())
+    ///     .or(digit1());
+    /// assert_eq!(statement.parse(ParserState::new("let x")).unwrap().0, "x");
+    /// assert_eq!(statement.parse(ParserState::new("42")).unwrap().0, "42");
+    ///
+    /// let error = statement.parse(ParserState::new("let 42")).unwrap_err();
+    /// assert!(error.is_committed());
+    /// assert_eq!(error.offset(), 4);
+    /// ```
+    ///
+    /// Here a broken name after `let` is reported as it is, rather than as input that isn't a number.
+    pub struct ParseCut<OutputType, ParserType: Parser<OutputType, ParseError>> {
+        /// The parser whose errors are committed.
+        parser: ParserType,
+
+        /* Phantom */
+        _phantom: PhantomData<OutputType>,
+    }
+
+    impl<OutputType, ParserType: Parser<OutputType, ParseError>> ParseCut<OutputType, ParserType> {
+        /// Create a new parser that commits to any error from the provided parser.
+        pub const fn new(parser: ParserType) -> Self {
+            Self {
+                parser,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<OutputType, ParserType: Parser<OutputType, ParseError>> Parser<OutputType, ParseError>
+        for ParseCut<OutputType, ParserType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, OutputType> {
+            self.parser.parse(parser_state).map_err(ParseError::commit)
+        }
+
+        fn describe(&self) -> Grammar {
+            self.parser.describe()
+        }
+    }
+
+    /// Succeeds with a value without consuming any input.
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+    pub struct ParsePure<OutputType: Clone> {
+        /// The value to produce.
+        value: OutputType,
+    }
+
+    impl<OutputType: Clone> ParsePure<OutputType> {
+        /// Create a new parser that always produces the provided value.
+        pub const fn new(value: OutputType) -> Self {
+            Self { value }
+        }
+    }
+
+    impl<OutputType: Clone, ErrorType> Parser<OutputType, ErrorType> for ParsePure<OutputType> {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, OutputType> {
+            Ok((self.value.clone(), parser_state))
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::Empty
+        }
+    }
+
+    /// Fails with an error without consuming any input.
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    pub struct ParseFail {
+        /// The error to fail with, which is moved to wherever the parser is tried.
+        error: ParseError,
+    }
+
+    impl ParseFail {
+        /// Create a new parser that always fails with the provided error. The error is reported at the position the
+        /// parser is tried, whatever offset it was created with, and keeps the length of any input it spans.
+        pub const fn new(error: ParseError) -> Self {
+            Self { error }
+        }
+    }
+
+    impl<OutputType> Parser<OutputType, ParseError> for ParseFail {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, OutputType> {
+            // Move the end of an invalid value along with the offset, so
+            // that it still spans the same length of input.
+            let mut error = self.error.clone();
+            if let ParseErrorKind::Invalid { end, .. } = &mut error.kind {
+                *end = parser_state.index + end.saturating_sub(error.offset);
+            }
+            error.offset = parser_state.index;
+            Err(error)
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::Special(String::from("failure"))
+        }
+    }
+
+    /// Create a parser that always succeeds with `value` without consuming any input.
+    pub const fn pure<OutputType: Clone>(value: OutputType) -> ParsePure<OutputType> {
+        ParsePure::new(value)
+    }
+
+    /// Create a parser that always fails with an invalid value error carrying `message`.
+    pub fn fail(message: &str) -> ParseFail {
+        ParseFail::new(ParseError::invalid(0, 0, message))
+    }
+
+    /// Parses elements repeatedly and folds each one into an accumulator as it is parsed, instead of collecting them
+    /// into a `Vec`.
+    pub struct ParseFold<
+        OutputType,
+        ErrorType,
+        ParserType: Parser<OutputType, ErrorType>,
+        AccumulatorType: Clone,
+        FolderType: Fn(AccumulatorType, OutputType) -> AccumulatorType,
+    > {
+        /// The parser to run for each element.
+        parser: ParserType,
+
+        /// The initial value of the accumulator.
+        init: AccumulatorType,
+
+        /// The function that combines the accumulator with each element.
+        folder: FolderType,
+
+        /* Phantom */
+        _phantom: PhantomData<(OutputType, ErrorType)>,
+    }
+
+    impl<
+            OutputType,
+            ErrorType,
+            ParserType: Parser<OutputType, ErrorType>,
+            AccumulatorType: Clone,
+            FolderType: Fn(AccumulatorType, OutputType) -> AccumulatorType,
+        > ParseFold<OutputType, ErrorType, ParserType, AccumulatorType, FolderType>
+    {
+        /// Create a new folding parser that starts from `init` and combines each element using `folder`.
+        pub const fn new(parser: ParserType, init: AccumulatorType, folder: FolderType) -> Self {
+            Self {
+                parser,
+                init,
+                folder,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<
+            OutputType,
+            ErrorType,
+            ParserType: Parser<OutputType, ErrorType>,
+            AccumulatorType: Clone,
+            FolderType: Fn(AccumulatorType, OutputType) -> AccumulatorType,
+        > Parser<AccumulatorType, ErrorType>
+        for ParseFold<OutputType, ErrorType, ParserType, AccumulatorType, FolderType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, AccumulatorType> {
+            let mut new_state = parser_state;
+            let mut accumulator = self.init.clone();
+
+            // Keep parsing until the parser fails.
+            while let Ok((output, parsed_new_state)) = self.parser.parse(new_state.clone()) {
+                let consumed = parsed_new_state.index != new_state.index;
+                new_state = parsed_new_state;
+                accumulator = (self.folder)(accumulator, output);
+
+                // Stop if the element didn't consume anything, otherwise this
+                // would loop forever.
+                if !consumed {
+                    break;
+                }
+            }
+
+            Ok((accumulator, new_state))
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::repeat(0, usize::MAX, self.parser.describe())
+        }
+    }
+
+    /// Parses elements repeatedly and folds them into an accumulator until the parser fails or the folding function
+    /// signals that it should stop.
+    pub struct ParseFoldWhile<
+        OutputType,
+        ErrorType,
+        ParserType: Parser<OutputType, ErrorType>,
+        AccumulatorType: Clone,
+        FolderType: Fn(AccumulatorType, OutputType) -> ControlFlow<AccumulatorType, AccumulatorType>,
+    > {
+        /// The parser to run for each element.
+        parser: ParserType,
+
+        /// The initial value of the accumulator.
+        init: AccumulatorType,
+
+        /// The function that combines the accumulator with each element.
+        folder: FolderType,
+
+        /* Phantom */
+        _phantom: PhantomData<(OutputType, ErrorType)>,
+    }
+
+    impl<
+            OutputType,
+            ErrorType,
+            ParserType: Parser<OutputType, ErrorType>,
+            AccumulatorType: Clone,
+            FolderType: Fn(AccumulatorType, OutputType) -> ControlFlow<AccumulatorType, AccumulatorType>,
+        > ParseFoldWhile<OutputType, ErrorType, ParserType, AccumulatorType, FolderType>
+    {
+        /// Create a new folding parser that starts from `init` and combines each element using `folder`.
+        pub const fn new(parser: ParserType, init: AccumulatorType, folder: FolderType) -> Self {
+            Self {
+                parser,
+                init,
+                folder,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<
+            OutputType,
+            ErrorType,
+            ParserType: Parser<OutputType, ErrorType>,
+            AccumulatorType: Clone,
+            FolderType: Fn(AccumulatorType, OutputType) -> ControlFlow<AccumulatorType, AccumulatorType>,
+        > Parser<AccumulatorType, ErrorType>
+        for ParseFoldWhile<OutputType, ErrorType, ParserType, AccumulatorType, FolderType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, AccumulatorType> {
+            let mut new_state = parser_state;
+            let mut accumulator = self.init.clone();
+
+            // Keep parsing until the parser fails.
+            while let Ok((output, parsed_new_state)) = self.parser.parse(new_state.clone()) {
+                // Stop if the element didn't consume anything, otherwise this
+                // would loop forever.
+                let consumed = parsed_new_state.index != new_state.index;
+                new_state = parsed_new_state;
+
+                // Fold the element in, stopping early if the folder asks to.
+                // The element that caused the stop is still consumed.
+                match (self.folder)(accumulator, output) {
+                    ControlFlow::Continue(next) if consumed => accumulator = next,
+                    ControlFlow::Continue(next) | ControlFlow::Break(next) => {
+                        accumulator = next;
+                        break;
+                    }
+                }
+            }
+
+            Ok((accumulator, new_state))
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::repeat(0, usize::MAX, self.parser.describe())
+        }
+    }
+
+    /// Parses a list of elements in order using a list of parsers built at runtime.
+    pub struct ParseSequence<OutputType, ErrorType> {
+        /// The parsers to run, in order.
+        parsers: Vec<BoxedParser<OutputType, ErrorType>>,
+    }
+
+    impl<OutputType, ErrorType> ParseSequence<OutputType, ErrorType> {
+        /// Create a new sequence parser that runs each of the provided parsers in order.
+        pub const fn new(parsers: Vec<BoxedParser<OutputType, ErrorType>>) -> Self {
+            Self { parsers }
+        }
+    }
+
+    impl<OutputType, ErrorType> Parser<Vec<OutputType>, ErrorType>
+        for ParseSequence<OutputType, ErrorType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, Vec<OutputType>> {
+            let mut new_state = parser_state;
+            let mut output = Vec::with_capacity(self.parsers.len());
+
+            // Run each parser from where the previous one finished, failing
+            // as soon as any of them fails.
+            for parser in &self.parsers {
+                let (parsed_new_output, parsed_new_state) = parser.parse(new_state)?;
+                new_state = parsed_new_state;
+                output.push(parsed_new_output);
+            }
+
+            Ok((output, new_state))
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::sequence(self.parsers.iter().map(|parser| parser.describe()))
+        }
+    }
+
+    /// Parses one of a list of alternatives built at runtime, trying each in order and returning the first that
+    /// matches. If none of them match, the error lists what each alternative expected.
+    pub struct ParseChoice<OutputType> {
+        /// The alternatives to try, in order.
+        parsers: Vec<BoxedParser<OutputType, ParseError>>,
+    }
+
+    impl<OutputType> ParseChoice<OutputType> {
+        /// Create a new choice parser that tries each of the provided parsers in order.
+        pub const fn new(parsers: Vec<BoxedParser<OutputType, ParseError>>) -> Self {
+            Self { parsers }
+        }
+
+        /// Add an alternative to try after the existing ones.
+        pub fn alternative(
+            mut self,
+            parser: impl Parser<OutputType, ParseError> + Send + Sync + 'static,
+        ) -> Self {
+            self.parsers.push(BoxedParser::new(parser));
+            self
+        }
+    }
+
+    impl<OutputType> Parser<OutputType, ParseError> for ParseChoice<OutputType> {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, OutputType> {
+            let mut error = None::<ParseError>;
+
+            // Try each alternative from the same state, keeping the errors of
+            // the ones that fail.
+            for parser in &self.parsers {
+                match parser.parse(parser_state.clone()) {
+                    Ok(result) => return Ok(result),
+                    Err(e) if e.is_committed() => return Err(e),
+                    Err(e) => {
+                        error = Some(match error {
+                            Some(error) => error.merge(e),
+                            None => e,
+                        })
+                    }
+                }
+            }
+
+            // With no alternatives at all, there's nothing to report.
+            Err(error
+                .unwrap_or_else(|| ParseError::new(ParseErrorKind::Unknown, parser_state.index)))
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::choice(self.parsers.iter().map(|parser| parser.describe()))
+        }
+    }
+
+    /// Parses one of a list of alternatives built at runtime, trying all of them and returning the one that
+    /// consumed the most input, like a lexer choosing `>=` over `>`. When several match the same amount, the first
+    /// one added wins. If none of them match, the error lists what each alternative expected.
+    pub struct ParseLongestOf<OutputType> {
+        /// The alternatives to try, in order.
+        parsers: Vec<BoxedParser<OutputType, ParseError>>,
+    }
+
+    impl<OutputType> ParseLongestOf<OutputType> {
+        /// Create a new parser that tries all of the provided parsers and keeps the longest match.
+        pub const fn new(parsers: Vec<BoxedParser<OutputType, ParseError>>) -> Self {
+            Self { parsers }
+        }
+
+        /// Add an alternative, which wins ties against the existing ones only if they don't match.
+        pub fn alternative(
+            mut self,
+            parser: impl Parser<OutputType, ParseError> + Send + Sync + 'static,
+        ) -> Self {
+            self.parsers.push(BoxedParser::new(parser));
+            self
+        }
+    }
+
+    impl<OutputType> Parser<OutputType, ParseError> for ParseLongestOf<OutputType> {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, OutputType> {
+            let mut longest = None::<(OutputType, ParserState)>;
+            let mut error = None::<ParseError>;
+
+            // Every alternative starts from the same state. A later match
+            // only replaces the current one if it got strictly further.
+            for parser in &self.parsers {
+                match parser.parse(parser_state.clone()) {
+                    Ok((output, new_state)) => {
+                        if longest
+                            .as_ref()
+                            .is_none_or(|(_, longest_state)| new_state.index > longest_state.index)
+                        {
+                            longest = Some((output, new_state));
+                        }
+                    }
+                    Err(e) if e.is_committed() => return Err(e),
+                    Err(e) => {
+                        error = Some(match error {
+                            Some(error) => error.merge(e),
+                            None => e,
+                        })
+                    }
+                }
+            }
+
+            match longest {
+                Some(result) => Ok(result),
+                None => Err(error.unwrap_or_else(|| {
+                    ParseError::new(ParseErrorKind::Unknown, parser_state.index)
+                })),
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::choice(self.parsers.iter().map(|parser| parser.describe()))
+        }
+    }
+
+    /// Parses every one of a list of alternatives built at runtime, for grammars where input can genuinely mean
+    /// more than one thing. [`parse_all`](Parser::parse_all) returns every interpretation that matched, which is
+    /// also handy for finding out why a grammar is ambiguous.
+    ///
+    /// Used as an ordinary parser, it succeeds only when exactly one alternative matches, like a chain of
+    /// [`xor`](crate::parsers::ParserExtensions::xor)s, and reports an ambiguity when more than one does.
+    ///
+    /// Inside `and`, `map`, or a sequence, `parse_all` on the outer parser returns every combination of the
+    /// interpretations. Other combinators only take a single result, so a `ParseAllOf` that matches more than one
+    /// way inside them fails with an ambiguity.
+    pub struct ParseAllOf<OutputType> {
+        /// The alternatives to try, in order.
+        parsers: Vec<BoxedParser<OutputType, ParseError>>,
+    }
+
+    impl<OutputType> ParseAllOf<OutputType> {
+        /// Create a new parser that tries all of the provided parsers.
+        pub const fn new(parsers: Vec<BoxedParser<OutputType, ParseError>>) -> Self {
+            Self { parsers }
+        }
+
+        /// Add an alternative after the existing ones.
+        pub fn alternative(
+            mut self,
+            parser: impl Parser<OutputType, ParseError> + Send + Sync + 'static,
+        ) -> Self {
+            self.parsers.push(BoxedParser::new(parser));
+            self
+        }
+    }
+
+    impl<OutputType> Parser<OutputType, ParseError> for ParseAllOf<OutputType> {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, OutputType> {
+            let index = parser_state.index;
+            let mut results = self.parse_all(parser_state)?;
+            if results.len() == 1 {
+                Ok(results.remove(0))
+            } else {
+                Err(ParseError::ambiguous(index))
+            }
+        }
+
+        fn parse_all(
+            &self,
+            parser_state: ParserState,
+        ) -> Result<Vec<(OutputType, ParserState)>, ParseError> {
+            // Every alternative is run from the same state, and each of them
+            // may itself match more than one way.
+            let mut results = Vec::new();
+            let mut error = None::<ParseError>;
+            for parser in &self.parsers {
+                match parser.parse_all(parser_state.clone()) {
+                    Ok(alternative_results) => results.extend(alternative_results),
+                    Err(e) if e.is_committed() => return Err(e),
+                    Err(e) => {
+                        error = Some(match error {
+                            Some(error) => error.merge(e),
+                            None => e,
+                        })
+                    }
+                }
+            }
+
+            if results.is_empty() {
+                Err(error.unwrap_or_else(|| {
+                    ParseError::new(ParseErrorKind::Unknown, parser_state.index)
+                }))
+            } else {
+                Ok(results)
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::choice(self.parsers.iter().map(|parser| parser.describe()))
+        }
+    }
+
+    /// One of the elements of a [`ParsePermutation`].
+    struct PermutationElement<OutputType> {
+        /// The parser for the element.
+        parser: BoxedParser<OutputType, ParseError>,
+
+        /// Whether the permutation fails without this element.
+        required: bool,
+    }
+
+    /// Parses each of a set of elements exactly once, in any order, such as attributes or options that may be
+    /// written in whatever order the author likes. The output lists each element's value in the order the elements
+    /// were added, with `None` for optional elements that weren't found.
+    ///
+    /// At each position every element that hasn't been found yet is tried in the order they were added, and the
+    /// first one that matches is taken. Parsing stops when none of them match. If that leaves required elements
+    /// missing, the permutation backtracks and tries the next element that matched at an earlier position instead, so
+    /// `a` and `ab` find `aba` as `ab` followed by `a`. Since every order may be tried, permutations should be kept to
+    /// a handful of elements.
+    ///
+    /// If no order finds every required element, the error comes from the order that got furthest into the input and
+    /// lists what the missing elements expected, along with a note naming each of them.
+    pub struct ParsePermutation<OutputType> {
+        /// The elements to find, in the order they were added.
+        elements: Vec<PermutationElement<OutputType>>,
+    }
+
+    impl<OutputType> ParsePermutation<OutputType> {
+        /// Create a new permutation parser with no elements yet.
+        pub const fn new() -> Self {
+            Self {
+                elements: Vec::new(),
+            }
+        }
+
+        /// Add an element that must be found.
+        pub fn required(
+            mut self,
+            parser: impl Parser<OutputType, ParseError> + Send + Sync + 'static,
+        ) -> Self {
+            self.elements.push(PermutationElement {
+                parser: BoxedParser::new(parser),
+                required: true,
+            });
+            self
+        }
+
+        /// Add an element that may be left out.
+        pub fn optional(
+            mut self,
+            parser: impl Parser<OutputType, ParseError> + Send + Sync + 'static,
+        ) -> Self {
+            self.elements.push(PermutationElement {
+                parser: BoxedParser::new(parser),
+                required: false,
+            });
+            self
+        }
+    }
+
+    /// Where a [`ParsePermutation`] got furthest before running out of elements with required ones still missing.
+    struct PermutationFailure {
+        /// The position at which no more elements matched.
+        index: usize,
+
+        /// The errors from the required elements that didn't match there.
+        error: ParseError,
+
+        /// The indices of the required elements that were still missing.
+        missing: Vec<usize>,
+    }
+
+    impl<OutputType> ParsePermutation<OutputType> {
+        /// Find the remaining elements starting from `parser_state`, producing the state after the last one, or
+        /// `None` if no order of them finds every required element. The furthest dead end is kept in `failure`.
+        fn search(
+            &self,
+            parser_state: ParserState,
+            outputs: &mut [Option<OutputType>],
+            failure: &mut Option<PermutationFailure>,
+        ) -> Result<Option<ParserState>, ParseError> {
+            let mut error = None::<ParseError>;
+            for (index, element) in self.elements.iter().enumerate() {
+                if outputs[index].is_some() {
+                    continue;
+                }
+                match element.parser.parse(parser_state.clone()) {
+                    Ok((element_output, element_state)) => {
+                        outputs[index] = Some(element_output);
+                        if let Some(new_state) = self.search(element_state, outputs, failure)? {
+                            return Ok(Some(new_state));
+                        }
+                        // The rest couldn't be found after this element, so
+                        // try the next one here instead.
+                        outputs[index] = None;
+                    }
+                    Err(e) if e.is_committed() => return Err(e),
+                    Err(e) if element.required => {
+                        error = Some(match error {
+                            Some(error) => error.merge(e),
+                            None => e,
+                        })
+                    }
+                    Err(_) => {}
+                }
+            }
+
+            let missing = self
+                .elements
+                .iter()
+                .zip(outputs.iter())
+                .enumerate()
+                .filter(|(_, (element, output))| element.required && output.is_none())
+                .map(|(index, _)| index)
+                .collect::<Vec<usize>>();
+            if missing.is_empty() {
+                return Ok(Some(parser_state));
+            }
+
+            if failure
+                .as_ref()
+                .is_none_or(|failure| parser_state.index > failure.index)
+            {
+                *failure = Some(PermutationFailure {
+                    index: parser_state.index,
+                    error: error.unwrap_or_else(|| {
+                        ParseError::new(ParseErrorKind::Unknown, parser_state.index)
+                    }),
+                    missing,
+                });
+            }
+            Ok(None)
+        }
+    }
+
+    impl<OutputType> Default for ParsePermutation<OutputType> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<OutputType> Parser<Vec<Option<OutputType>>, ParseError> for ParsePermutation<OutputType> {
+        fn parse(
+            &self,
+            parser_state: ParserState,
+        ) -> ParseResult<ParseError, Vec<Option<OutputType>>> {
+            let mut outputs = self.elements.iter().map(|_| None).collect::<Vec<_>>();
+            let verbosity = parser_state.verbosity();
+            let mut failure = None;
+            if let Some(new_state) = self.search(parser_state, &mut outputs, &mut failure)? {
+                return Ok((outputs, new_state));
+            }
+
+            // Every dead end is recorded, so a failed search always has one.
+            let failure = failure.expect("a failed permutation records where it stopped");
+            if verbosity == ErrorVerbosity::Rich {
+                let missing = failure
+                    .missing
+                    .iter()
+                    .map(|&index| {
+                        format!(
+                            "`{}`",
+                            ebnf::expression_text(&self.elements[index].parser.describe())
+                        )
+                    })
+                    .collect::<Vec<String>>();
+                Err(failure
+                    .error
+                    .with_note(&format!("missing required {}", missing.join(", "))))
+            } else {
+                Err(failure.error)
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            let elements = self
+                .elements
+                .iter()
+                .map(|element| {
+                    let text = ebnf::expression_text(&element.parser.describe());
+                    if element.required {
+                        text
+                    } else {
+                        format!("[{}]", text)
+                    }
+                })
+                .collect::<Vec<String>>();
+            Grammar::Special(format!("any order of {}", elements.join(", ")))
+        }
+    }
+
+    /// A parser that can be declared before it is defined, so that grammars can refer to themselves, such as an
+    /// expression that contains parenthesized expressions:
+    ///
+    /// ```
+    /// use parselib_rs::parsers::utf8::{digit1, recursive, ParseChar};
+    /// use parselib_rs::parsers::ParserExtensions;
+    /// use parselib_rs::{Parser, ParserState};
+    ///
+    /// let expression = recursive("expression", |expression| {
+    ///     digit1().or(expression.delimited_by(ParseChar::from_char('('), ParseChar::from_char(')')))
+    /// });
+    /// assert_eq!(expression.parse(ParserState::new("((42))")).unwrap().0, "42");
+    /// assert!(expression.parse(ParserState::new("((42)")).is_err());
+    /// ```
+    ///
+    /// Mutually recursive parsers can be declared with [`ParseRecursive::declare`] and defined later with
+    /// [`ParseRecursive::define`]. Definitions should refer to recursive parsers through
+    /// [`reference`](ParseRecursive::reference), which doesn't keep the parser alive, so that the cycle doesn't
+    /// leak and describing the grammar names the rule instead of expanding it forever.
+    ///
+    /// Running a parser that hasn't been defined, or a reference to one that has been dropped, panics, since both
+    /// are mistakes in how the grammar was built rather than in the input.
+    pub struct ParseRecursive<OutputType, ErrorType> {
+        /// The name of the rule, used when describing the grammar.
+        name: String,
+
+        /// The definition, shared with the declaration and its other handles.
+        parser: RecursiveHandle<OutputType, ErrorType>,
+    }
+
+    /// The definition of a recursive parser, which is only set once.
+    type RecursiveDefinition<OutputType, ErrorType> = OnceLock<BoxedParser<OutputType, ErrorType>>;
+
+    /// How a [`ParseRecursive`] holds on to its definition.
+    enum RecursiveHandle<OutputType, ErrorType> {
+        /// Keeps the definition alive.
+        Strong(Arc<RecursiveDefinition<OutputType, ErrorType>>),
+
+        /// Refers to the definition without keeping it alive.
+        Weak(Weak<RecursiveDefinition<OutputType, ErrorType>>),
+    }
+
+    impl<OutputType, ErrorType> ParseRecursive<OutputType, ErrorType> {
+        /// Declare a recursive parser named `name` without defining it yet.
+        pub fn declare(name: &str) -> Self {
+            Self {
+                name: String::from(name),
+                parser: RecursiveHandle::Strong(Arc::new(OnceLock::new())),
+            }
+        }
+
+        /// Define what the parser parses. A parser can only be defined once, so defining it again panics.
+        pub fn define(&self, parser: impl Parser<OutputType, ErrorType> + Send + Sync + 'static) {
+            let defined = match &self.parser {
+                RecursiveHandle::Strong(definition) => {
+                    definition.set(BoxedParser::new(parser)).is_ok()
+                }
+                RecursiveHandle::Weak(definition) => definition
+                    .upgrade()
+                    .is_some_and(|definition| definition.set(BoxedParser::new(parser)).is_ok()),
+            };
+            assert!(
+                defined,
+                "recursive parser `{}` is already defined",
+                self.name
+            );
+        }
+
+        /// Create a handle to this parser for use inside definitions, which doesn't keep the parser alive.
+        pub fn reference(&self) -> Self {
+            Self {
+                name: self.name.clone(),
+                parser: RecursiveHandle::Weak(match &self.parser {
+                    RecursiveHandle::Strong(definition) => Arc::downgrade(definition),
+                    RecursiveHandle::Weak(definition) => Weak::clone(definition),
+                }),
+            }
+        }
+
+        /// Get the name of the rule this parser defines.
+        pub fn name(&self) -> &str {
+            &self.name
+        }
+    }
+
+    impl<OutputType, ErrorType> Clone for ParseRecursive<OutputType, ErrorType> {
+        fn clone(&self) -> Self {
+            Self {
+                name: self.name.clone(),
+                parser: match &self.parser {
+                    RecursiveHandle::Strong(definition) => {
+                        RecursiveHandle::Strong(Arc::clone(definition))
+                    }
+                    RecursiveHandle::Weak(definition) => {
+                        RecursiveHandle::Weak(Weak::clone(definition))
+                    }
+                },
+            }
+        }
+    }
+
+    impl<OutputType, ErrorType> Parser<OutputType, ErrorType>
+        for ParseRecursive<OutputType, ErrorType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, OutputType> {
+            let definition = match &self.parser {
+                RecursiveHandle::Strong(definition) => Arc::clone(definition),
+                RecursiveHandle::Weak(definition) => definition
+                    .upgrade()
+                    .unwrap_or_else(|| panic!("recursive parser `{}` was dropped", self.name)),
+            };
+            match definition.get() {
+                Some(parser) => parser.parse(parser_state),
+                None => panic!("recursive parser `{}` was never defined", self.name),
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            match &self.parser {
+                RecursiveHandle::Strong(definition) => match definition.get() {
+                    Some(parser) => Grammar::rule(&self.name, parser.describe()),
+                    None => Grammar::Reference(self.name.clone()),
+                },
+                RecursiveHandle::Weak(_) => Grammar::Reference(self.name.clone()),
+            }
+        }
+    }
+
+    /// Create a parser that refers to itself. `define` is given a reference to the parser being created and returns
+    /// its definition.
+    pub fn recursive<OutputType, ErrorType, ParserType>(
+        name: &str,
+        define: impl FnOnce(ParseRecursive<OutputType, ErrorType>) -> ParserType,
+    ) -> ParseRecursive<OutputType, ErrorType>
+    where
+        ParserType: Parser<OutputType, ErrorType> + Send + Sync + 'static,
+    {
+        let parser = ParseRecursive::declare(name);
+        parser.define(define(parser.reference()));
+        parser
+    }
+
+    /// Identifies a position a left-recursive parser is being parsed at: the thread parsing it and the parsing run,
+    /// so that separate parses don't see each other's results, and the index in the input.
+    type SeedKey = (ThreadId, u64, usize);
+
+    /// The results grown so far for each position a left-recursive parser is being parsed at.
+    type LeftRecursiveSeeds<OutputType> =
+        Mutex<HashMap<SeedKey, ParseResult<ParseError, OutputType>>>;
+
+    /// A recursive parser whose definition can start with itself, such as `expr ::= expr "-" term | term`. Plain
+    /// [`ParseRecursive`] parsers would call themselves forever on rules like this:
+    ///
+    /// ```
+    /// use parselib_rs::parsers::utf8::{integer, left_recursive, ParseChar};
+    /// use parselib_rs::parsers::ParserExtensions;
+    /// use parselib_rs::{Parser, ParserState};
+    ///
+    /// let number = integer::<i64>().unsigned();
+    /// let expression = left_recursive("expression", |expression| {
+    ///     expression
+    ///         .then_keep_left(ParseChar::from_char('-'))
+    ///         .and(number)
+    ///         .map(|(a, b)| a - b)
+    ///         .or(number)
+    /// });
+    /// assert_eq!(expression.parse(ParserState::new("5-2-1")).unwrap().0, 2);
+    /// ```
+    ///
+    /// This grows a result from a seed: when the parser reaches itself again at the position it started from, it
+    /// gets the previous result (a failure the first time), so the non-recursive alternative matches. The
+    /// definition is then run again with that result as the seed, as long as each run gets further into the
+    /// input. This makes operators defined this way group to the left, so `5-2-1` above is `(5-2)-1`.
+    ///
+    /// Only direct left recursion is supported, where the definition itself starts with a reference to the parser.
+    pub struct ParseLeftRecursive<OutputType> {
+        /// The recursive parser for the definition.
+        parser: ParseRecursive<OutputType, ParseError>,
+
+        /// The results being grown, shared with the declaration and its other handles.
+        seeds: Arc<LeftRecursiveSeeds<OutputType>>,
+    }
+
+    impl<OutputType> ParseLeftRecursive<OutputType> {
+        /// Declare a left-recursive parser named `name` without defining it yet.
+        pub fn declare(name: &str) -> Self {
+            Self {
+                parser: ParseRecursive::declare(name),
+                seeds: Arc::new(Mutex::new(HashMap::new())),
+            }
+        }
+
+        /// Define what the parser parses. A parser can only be defined once, so defining it again panics.
+        pub fn define(&self, parser: impl Parser<OutputType, ParseError> + Send + Sync + 'static) {
+            self.parser.define(parser);
+        }
+
+        /// Create a handle to this parser for use inside definitions, which doesn't keep the parser alive.
+        pub fn reference(&self) -> Self {
+            Self {
+                parser: self.parser.reference(),
+                seeds: Arc::clone(&self.seeds),
+            }
+        }
+
+        /// Get the name of the rule this parser defines.
+        pub fn name(&self) -> &str {
+            self.parser.name()
+        }
+
+        /// Lock the results being grown, ignoring poisoning since they are only ever replaced whole.
+        fn seeds(&self) -> MutexGuard<'_, HashMap<SeedKey, ParseResult<ParseError, OutputType>>> {
+            self.seeds.lock().unwrap_or_else(PoisonError::into_inner)
+        }
+    }
+
+    /// Removes the seed for a position when growing it ends, even if the definition panics, so that a later parse
+    /// at the same position doesn't pick up a stale seed.
+    struct SeedGuard<'a, OutputType> {
+        /// The parser the seed belongs to.
+        parser: &'a ParseLeftRecursive<OutputType>,
+
+        /// The position of the seed.
+        key: SeedKey,
+    }
+
+    impl<OutputType> Drop for SeedGuard<'_, OutputType> {
+        fn drop(&mut self) {
+            self.parser.seeds().remove(&self.key);
+        }
+    }
+
+    impl<OutputType> Clone for ParseLeftRecursive<OutputType> {
+        fn clone(&self) -> Self {
+            Self {
+                parser: self.parser.clone(),
+                seeds: Arc::clone(&self.seeds),
+            }
+        }
+    }
+
+    impl<OutputType: Clone> Parser<OutputType, ParseError> for ParseLeftRecursive<OutputType> {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, OutputType> {
+            let key = (
+                thread::current().id(),
+                parser_state.run(),
+                parser_state.index,
+            );
+
+            // Reaching the parser again at a position it's already growing a
+            // result for is the left recursion, which gets the current seed.
+            if let Some(seed) = self.seeds().get(&key) {
+                return seed.clone();
+            }
+
+            // The first seed is a failure that doesn't expect anything, so it
+            // doesn't add to the errors of the other alternatives.
+            self.seeds().insert(
+                key,
+                Err(ParseError::unexpected(
+                    parser_state.index,
+                    Vec::<Expected>::new(),
+                    None,
+                )),
+            );
+            let _guard = SeedGuard { parser: self, key };
+
+            // Keep running the definition while each run gets further than the
+            // last. The lock isn't held while parsing, since the definition
+            // needs it to get the seed.
+            loop {
+                let result = self.parser.parse(parser_state.clone());
+                let mut seeds = self.seeds();
+                let grew = match (&result, seeds.get(&key)) {
+                    (Ok(_), Some(Err(_))) => true,
+                    (Ok((_, new_state)), Some(Ok((_, seed_state)))) => {
+                        new_state.index > seed_state.index
+                    }
+                    _ => false,
+                };
+                if !grew {
+                    // Keep the longest result, or report why the definition
+                    // failed if it never matched at all.
+                    let seed = seeds.remove(&key).unwrap();
+                    return if seed.is_ok() { seed } else { result };
+                }
+                seeds.insert(key, result);
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            self.parser.describe()
+        }
+    }
+
+    /// Create a parser that refers to itself and may start with itself. `define` is given a reference to the parser
+    /// being created and returns its definition.
+    pub fn left_recursive<OutputType, ParserType>(
+        name: &str,
+        define: impl FnOnce(ParseLeftRecursive<OutputType>) -> ParserType,
+    ) -> ParseLeftRecursive<OutputType>
+    where
+        ParserType: Parser<OutputType, ParseError> + Send + Sync + 'static,
+    {
+        let parser = ParseLeftRecursive::declare(name);
+        parser.define(define(parser.reference()));
+        parser
+    }
+
+    /// Parses an element with a parser that is only built when it's first needed, so grammars spread across modules
+    /// don't have to be built in a particular order, and expensive parsers aren't built unless they're used. The
+    /// parser is built once and kept, unless [`uncached`](ParseLazy::uncached) asks for a new one every time.
+    pub struct ParseLazy<
+        OutputType,
+        ErrorType,
+        ParserType: Parser<OutputType, ErrorType>,
+        BuilderType: Fn() -> ParserType,
+    > {
+        /// The function that builds the parser.
+        build: BuilderType,
+
+        /// The parser once it has been built, if it is kept.
+        parser: OnceLock<ParserType>,
+
+        /// Whether the built parser is kept for later parses.
+        cached: bool,
+
+        /* Phantom */
+        _phantom: PhantomData<(OutputType, ErrorType)>,
+    }
+
+    impl<
+            OutputType,
+            ErrorType,
+            ParserType: Parser<OutputType, ErrorType>,
+            BuilderType: Fn() -> ParserType,
+        > ParseLazy<OutputType, ErrorType, ParserType, BuilderType>
+    {
+        /// Create a new parser that builds its parser with `build` the first time it is used.
+        pub const fn new(build: BuilderType) -> Self {
+            Self {
+                build,
+                parser: OnceLock::new(),
+                cached: true,
+                _phantom: PhantomData,
+            }
+        }
+
+        /// Build a new parser every time instead of keeping the first one.
+        pub const fn uncached(mut self) -> Self {
+            self.cached = false;
+            self
+        }
+    }
+
+    impl<
+            OutputType,
+            ErrorType,
+            ParserType: Parser<OutputType, ErrorType>,
+            BuilderType: Fn() -> ParserType,
+        > Parser<OutputType, ErrorType>
+        for ParseLazy<OutputType, ErrorType, ParserType, BuilderType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, OutputType> {
+            if self.cached {
+                self.parser.get_or_init(&self.build).parse(parser_state)
+            } else {
+                (self.build)().parse(parser_state)
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            if self.cached {
+                self.parser.get_or_init(&self.build).describe()
+            } else {
+                (self.build)().describe()
+            }
+        }
+    }
+
+    /// Create a parser that builds its parser with `build` the first time it is used.
+    pub const fn lazy<OutputType, ErrorType, ParserType, BuilderType>(
+        build: BuilderType,
+    ) -> ParseLazy<OutputType, ErrorType, ParserType, BuilderType>
+    where
+        ParserType: Parser<OutputType, ErrorType>,
+        BuilderType: Fn() -> ParserType,
+    {
+        ParseLazy::new(build)
+    }
+
+    /// Parses an element by looking at the next character and running the parser registered for it.
+    pub struct ParseDispatch<OutputType> {
+        /// The parser to run for each possible next character.
+        branches: HashMap<char, BoxedParser<OutputType, ParseError>>,
+
+        /// The parser to run when no branch is registered for the next character.
+        default: Option<BoxedParser<OutputType, ParseError>>,
+    }
+
+    impl<OutputType> ParseDispatch<OutputType> {
+        /// Create a new dispatch parser with no branches.
+        pub fn new() -> Self {
+            Self {
+                branches: HashMap::new(),
+                default: None,
+            }
+        }
+
+        /// Register the parser to run when the next character is `c`. The parser is run from the current position,
+        /// so it will see `c` itself.
+        pub fn on<ParserType: Parser<OutputType, ParseError> + Send + Sync + 'static>(
+            mut self,
+            c: char,
+            parser: ParserType,
+        ) -> Self {
+            self.branches.insert(c, BoxedParser::new(parser));
+            self
+        }
+
+        /// Register the parser to run when there is no branch for the next character (or no input is left).
+        pub fn otherwise<ParserType: Parser<OutputType, ParseError> + Send + Sync + 'static>(
+            mut self,
+            parser: ParserType,
+        ) -> Self {
+            self.default = Some(BoxedParser::new(parser));
+            self
+        }
+    }
+
+    impl<OutputType> Default for ParseDispatch<OutputType> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<OutputType> Parser<OutputType, ParseError> for ParseDispatch<OutputType> {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, OutputType> {
+            // Peek at the next character without consuming it and look up
+            // its branch.
+            let next = parser_state.input.chars().next();
+            let branch = next.and_then(|c| self.branches.get(&c));
+
+            match branch.or(self.default.as_ref()) {
+                Some(parser) => parser.parse(parser_state),
+                None => {
+                    // Nothing can handle this character, so list the
+                    // characters that could have been handled.
+                    let rich = parser_state.verbosity() == ErrorVerbosity::Rich;
+                    Err(ParseError::new(
+                        ParseErrorKind::Unexpected {
+                            expected: if rich {
+                                self.branches.keys().map(|c| Expected::from(*c)).collect()
+                            } else {
+                                BTreeSet::new()
+                            },
+                            found: next.filter(|_| rich).map(Found::Char),
+                        },
+                        parser_state.index,
+                    ))
+                }
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            // Sort the branches so that the description doesn't depend on the
+            // order of the map.
+            let mut branches = self.branches.iter().collect::<Vec<_>>();
+            branches.sort_by_key(|(c, _)| **c);
+            Grammar::choice(
+                branches
+                    .into_iter()
+                    .map(|(_, parser)| parser.describe())
+                    .chain(self.default.iter().map(|parser| parser.describe())),
+            )
+        }
+    }
+
+    /// Gives an element a name to use in errors and attaches extra notes and help to any error it produces.
+    pub struct ParseLabel<OutputType, ParserType: Parser<OutputType, ParseError>> {
+        /// The parser being labelled.
+        parser: ParserType,
+
+        /// The name of the element, reported as what was expected when it is missing.
+        label: String,
+
+        /// The notes to attach to errors.
+        notes: Vec<String>,
+
+        /// The suggestions to attach to errors.
+        help: Vec<String>,
+
+        /// The documentation to include when the element is described.
+        doc: RuleDoc,
+
+        /* Phantom */
+        _phantom: PhantomData<OutputType>,
+    }
+
+    impl<OutputType, ParserType: Parser<OutputType, ParseError>> ParseLabel<OutputType, ParserType> {
+        /// Create a new parser that reports failures of the provided parser using `label`.
+        pub fn new(parser: ParserType, label: &str) -> Self {
+            Self {
+                parser,
+                label: String::from(label),
+                notes: Vec::new(),
+                help: Vec::new(),
+                doc: RuleDoc {
+                    description: None,
+                    examples: Vec::new(),
+                },
+                _phantom: PhantomData,
+            }
+        }
+
+        /// Attach a note to any error produced by this parser.
+        pub fn note(mut self, note: &str) -> Self {
+            self.notes.push(String::from(note));
+            self
+        }
+
+        /// Attach a suggestion to any error produced by this parser.
+        pub fn help(mut self, help: &str) -> Self {
+            self.help.push(String::from(help));
+            self
+        }
+
+        /// Describe what the element matches, for generated documentation. This replaces any earlier description.
+        pub fn doc(mut self, description: &str) -> Self {
+            self.doc.description = Some(String::from(description));
+            self
+        }
+
+        /// Add an example of input the element matches, for generated documentation.
+        pub fn example(mut self, example: &str) -> Self {
+            self.doc.examples.push(String::from(example));
+            self
+        }
+    }
+
+    impl<OutputType, ParserType: Parser<OutputType, ParseError>> Parser<OutputType, ParseError>
+        for ParseLabel<OutputType, ParserType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, OutputType> {
+            let index = parser_state.index;
+            let found = parser_state.input.chars().next().map(Found::Char);
+
+            // Labels, notes, and help are only needed for rich errors.
+            if parser_state.verbosity() == ErrorVerbosity::Fast {
+                return self.parser.parse(parser_state);
+            }
+
+            self.parser.parse(parser_state).map_err(|mut e| {
+                // If the element failed right where it started, report it by
+                // name. Otherwise it failed part of the way through, which is
+                // more specific, so the original error is kept.
+                if e.offset == index {
+                    e.kind = ParseErrorKind::Unexpected {
+                        expected: BTreeSet::from([Expected::from(&self.label)]),
+                        found,
+                    };
+                }
+
+                // Attach the extra information.
+                e.notes.extend(self.notes.iter().cloned());
+                e.help.extend(self.help.iter().cloned());
+                e
+            })
+        }
+
+        fn describe(&self) -> Grammar {
+            if self.doc == RuleDoc::default() {
+                Grammar::rule(&self.label, self.parser.describe())
+            } else {
+                Grammar::documented_rule(&self.label, self.parser.describe(), self.doc.clone())
+            }
+        }
+    }
+
+    /// Parses an element and returns its output along with the exact text that it matched.
+    pub struct ParseWithSlice<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>> {
+        /// The parser whose matched text should be returned.
+        parser: ParserType,
+
+        /* Phantom */
+        _phantom: PhantomData<(OutputType, ErrorType)>,
+    }
+
+    impl<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>>
+        ParseWithSlice<OutputType, ErrorType, ParserType>
+    {
+        /// Create a new parser that returns the output of the provided parser along with the text it matched.
+        pub const fn new(parser: ParserType) -> Self {
+            Self {
+                parser,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>>
+        Parser<(OutputType, String), ErrorType>
+        for ParseWithSlice<OutputType, ErrorType, ParserType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, (OutputType, String)> {
+            // Keep the input so the matched part can be sliced out of it.
+            let input = parser_state.input.clone();
+            let (output, new_state) = self.parser.parse(parser_state)?;
+
+            // Everything that isn't left over was matched.
+            let matched = String::from(&input[..input.len() - new_state.input.len()]);
+            Ok(((output, matched), new_state))
+        }
+
+        fn describe(&self) -> Grammar {
+            self.parser.describe()
+        }
+    }
+
+    /// Parses an element only to consume input, returning the exact text that it matched instead of its output,
+    /// such as capturing an identifier as a string.
+    pub struct ParseRecognize<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>> {
+        /// The parser whose matched text should be returned.
+        parser: ParserType,
+
+        /* Phantom */
+        _phantom: PhantomData<(OutputType, ErrorType)>,
+    }
+
+    impl<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>>
+        ParseRecognize<OutputType, ErrorType, ParserType>
+    {
+        /// Create a new parser that returns the text matched by the provided parser.
+        pub const fn new(parser: ParserType) -> Self {
+            Self {
+                parser,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>> Parser<String, ErrorType>
+        for ParseRecognize<OutputType, ErrorType, ParserType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, String> {
+            // Keep the input so the matched part can be sliced out of it.
+            let input = parser_state.input.clone();
+            let (_, new_state) = self.parser.parse(parser_state)?;
+
+            // Everything that isn't left over was matched.
+            let matched = String::from(&input[..input.len() - new_state.input.len()]);
+            Ok((matched, new_state))
+        }
+
+        fn describe(&self) -> Grammar {
+            self.parser.describe()
+        }
+    }
+
+    /// Parses an element and converts the exact text that it matched into a value with `FromStr`.
+    pub struct ParseTo<InputType, OutputType: FromStr, ParserType: Parser<InputType, ParseError>> {
+        /// The parser whose matched text should be converted.
+        parser: ParserType,
+
+        /* Phantom */
+        _phantom: PhantomData<(InputType, OutputType)>,
+    }
+
+    impl<InputType, OutputType: FromStr, ParserType: Parser<InputType, ParseError>>
+        ParseTo<InputType, OutputType, ParserType>
+    {
+        /// Create a new parser that converts the text matched by the provided parser.
+        pub const fn new(parser: ParserType) -> Self {
+            Self {
+                parser,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<InputType, OutputType: FromStr, ParserType: Parser<InputType, ParseError>>
+        Parser<OutputType, ParseError> for ParseTo<InputType, OutputType, ParserType>
+    where
+        OutputType::Err: Display,
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, OutputType> {
+            let start = parser_state.index;
+            let input = parser_state.input.clone();
+            let (_, new_state) = self.parser.parse(parser_state)?;
+
+            // Convert everything that isn't left over, reporting a failed
+            // conversion over the whole of the matched text.
+            match input[..input.len() - new_state.input.len()].parse() {
+                Ok(output) => Ok((output, new_state)),
+                Err(e) => Err(ParseError::new(
+                    ParseErrorKind::Invalid {
+                        end: new_state.index,
+                        message: if new_state.verbosity() == ErrorVerbosity::Rich {
+                            e.to_string()
+                        } else {
+                            String::new()
+                        },
+                    },
+                    start,
+                )),
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            self.parser.describe()
+        }
+    }
+
+    /// Parses an element and records the region of the input that it was parsed from.
+    pub struct ParseSpanned<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>> {
+        /// The parser whose output should be spanned.
+        parser: ParserType,
+
+        /* Phantom */
+        _phantom: PhantomData<(OutputType, ErrorType)>,
+    }
+
+    impl<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>>
+        ParseSpanned<OutputType, ErrorType, ParserType>
+    {
+        /// Create a new parser that records the span of the output of the provided parser.
+        pub const fn new(parser: ParserType) -> Self {
+            Self {
+                parser,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<OutputType, ErrorType, ParserType: Parser<OutputType, ErrorType>>
+        Parser<Spanned<OutputType>, ErrorType> for ParseSpanned<OutputType, ErrorType, ParserType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ErrorType, Spanned<OutputType>> {
+            let start = parser_state.index;
+            let (output, new_state) = self.parser.parse(parser_state)?;
+            let span = Span::new(start, new_state.index);
+            Ok((Spanned::new(output, span), new_state))
+        }
+
+        fn describe(&self) -> Grammar {
+            self.parser.describe()
+        }
+    }
+
+    /// Parses a single character that is within any of a set of ranges, or outside all of them if the class is
+    /// negated.
+    #[derive(Debug, Clone, Eq, PartialEq, Hash)]
+    pub struct ParseCharClass {
+        /// The inclusive ranges of characters in the class.
+        pub ranges: Vec<(char, char)>,
+
+        /// Whether the class matches characters outside the ranges instead.
+        pub negated: bool,
+    }
+
+    impl ParseCharClass {
+        /// Create a class matching characters within any of the inclusive `ranges`.
+        pub const fn new(ranges: Vec<(char, char)>) -> Self {
+            Self {
+                ranges,
+                negated: false,
+            }
+        }
+
+        /// Match characters outside the ranges instead.
+        pub fn negate(mut self) -> Self {
+            self.negated = !self.negated;
+            self
+        }
+
+        /// Check whether a character is in the class.
+        pub fn contains(&self, c: char) -> bool {
+            self.ranges
+                .iter()
+                .any(|(start, end)| (*start..=*end).contains(&c))
+                != self.negated
+        }
+    }
+
+    impl Display for ParseCharClass {
+        /// Writes the class in the syntax accepted by [`char_class`].
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            fn escape(c: char) -> String {
+                match c {
+                    '\\' | ']' | '-' | '^' | '[' => format!("\\{}", c),
+                    '\n' => String::from("\\n"),
+                    '\r' => String::from("\\r"),
+                    '\t' => String::from("\\t"),
+                    c => c.to_string(),
+                }
+            }
+
+            write!(f, "[{}", if self.negated { "^" } else { "" })?;
+            for (start, end) in &self.ranges {
+                if start == end {
+                    write!(f, "{}", escape(*start))?;
+                } else {
+                    write!(f, "{}-{}", escape(*start), escape(*end))?;
+                }
+            }
+            write!(f, "]")
+        }
+    }
+
+    impl Parser<char, ParseError> for ParseCharClass {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, char> {
+            match parser_state.char(0) {
+                Some((new_state, char_at)) if self.contains(char_at) => Ok((char_at, new_state)),
+
+                // Only describe the class if the state asks for it.
+                _ if parser_state.verbosity() == ErrorVerbosity::Rich => {
+                    Err(ParseError::expected_at(&parser_state, self.to_string()))
+                }
+                _ => Err(ParseError::expected_at(&parser_state, "")),
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::Class(self.to_string())
+        }
+    }
+
+    /// Build a character class parser from a regex-style specification like `[a-zA-Z0-9_]` or `[^"\\]`.
+    ///
+    /// Inside the brackets, `a-z` is an inclusive range, a leading `^` negates the class, and a `-` at the start or
+    /// end is literal. A backslash escapes the next character, and `\n`, `\r`, `\t`, `\d`, `\w`, and `\s` have their
+    /// usual (ASCII) meanings. Errors are reported at offsets within `spec`.
+    pub fn char_class(spec: &str) -> Result<ParseCharClass, ParseError> {
+        /// An element of the specification: either one character or a shorthand class.
+        enum Item {
+            Char(char),
+            Class(&'static [(char, char)]),
+        }
+
+        let mut chars = spec.char_indices().peekable();
+        match chars.next() {
+            Some((_, '[')) => {}
+            found => {
+                let found = found.map(|(_, c)| c.to_string());
+                return Err(ParseError::unexpected(0, ["["], found.as_deref()));
+            }
+        }
+
+        let negated = chars.next_if(|(_, c)| *c == '^').is_some();
+        let mut ranges = Vec::new();
+        let mut previous = None::<(usize, char)>;
+        let mut in_range = None::<usize>;
+        let end = loop {
+            let (index, c) = chars
+                .next()
+                .ok_or_else(|| ParseError::unexpected(spec.len(), ["]"], None))?;
+
+            let item = match c {
+                ']' => break index,
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => Item::Char('\n'),
+                    Some((_, 'r')) => Item::Char('\r'),
+                    Some((_, 't')) => Item::Char('\t'),
+                    Some((_, 'd')) => Item::Class(&[('0', '9')]),
+                    Some((_, 'w')) => {
+                        Item::Class(&[('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')])
+                    }
+                    Some((_, 's')) => Item::Class(&[(' ', ' '), ('\t', '\r')]),
+                    Some((_, escaped)) => Item::Char(escaped),
+                    None => {
+                        return Err(ParseError::unexpected(
+                            spec.len(),
+                            ["escaped character"],
+                            None,
+                        ))
+                    }
+                },
+
+                // A dash between two characters makes a range.
+                '-' if previous.is_some() && chars.peek().is_some_and(|(_, c)| *c != ']') => {
+                    in_range = Some(index);
+                    continue;
+                }
+                c => Item::Char(c),
+            };
+
+            match (item, in_range.take()) {
+                (Item::Char(c), Some(_)) => {
+                    let (start_index, start) = previous.take().unwrap();
+                    if start > c {
+                        return Err(ParseError::invalid(
+                            start_index,
+                            chars.peek().map_or(spec.len(), |(i, _)| *i),
+                            "character range starts after it ends",
+                        ));
+                    }
+                    ranges.pop();
+                    ranges.push((start, c));
+                }
+                (Item::Class(_), Some(dash)) => {
+                    return Err(ParseError::invalid(
+                        dash,
+                        dash + 1,
+                        "ranges can't end with a shorthand class",
+                    ))
+                }
+                (Item::Char(c), None) => {
+                    ranges.push((c, c));
+                    previous = Some((index, c));
+                }
+                (Item::Class(class), None) => {
+                    ranges.extend_from_slice(class);
+                    previous = None;
+                }
+            }
+        };
+
+        if end + 1 < spec.len() {
+            return Err(ParseError::trailing_input(end + 1, &spec[end + 1..]));
+        }
+
+        Ok(ParseCharClass { ranges, negated })
+    }
+
+    /// Parses a run of characters that match a predicate, returning the text that matched. This is much faster than
+    /// repeating a [`ParseChar`] and can check any property of a character.
+    #[derive(Clone)]
+    pub struct ParseTakeWhile<PredicateType: Fn(char) -> bool> {
+        /// The minimum number of characters that must match.
+        min: usize,
+
+        /// A description of the characters that match, reported when there are too few of them.
+        expected: &'static str,
+
+        /// The function that decides whether each character matches.
+        predicate: PredicateType,
+    }
+
+    impl<PredicateType: Fn(char) -> bool> ParseTakeWhile<PredicateType> {
+        /// Create a new parser for a run of at least `min` characters that match `predicate`.
+        pub const fn new(min: usize, predicate: PredicateType) -> Self {
+            Self {
+                min,
+                expected: "matching character",
+                predicate,
+            }
+        }
+
+        /// Describe the characters that match, for errors and grammar descriptions.
+        pub const fn expecting(mut self, expected: &'static str) -> Self {
+            self.expected = expected;
+            self
+        }
+    }
+
+    impl<PredicateType: Fn(char) -> bool> Parser<String, ParseError> for ParseTakeWhile<PredicateType> {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, String> {
+            crate::formats::take_while(parser_state, self.min, self.expected, &self.predicate)
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::repeat(
+                self.min,
+                usize::MAX,
+                Grammar::Special(String::from(self.expected)),
+            )
+        }
+    }
+
+    /// Create a parser for a run of characters that match `predicate`, which may be empty.
+    pub const fn take_while<PredicateType: Fn(char) -> bool>(
+        predicate: PredicateType,
+    ) -> ParseTakeWhile<PredicateType> {
+        ParseTakeWhile::new(0, predicate)
+    }
+
+    /// Create a parser for a run of at least one character that matches `predicate`.
+    pub const fn take_while1<PredicateType: Fn(char) -> bool>(
+        predicate: PredicateType,
+    ) -> ParseTakeWhile<PredicateType> {
+        ParseTakeWhile::new(1, predicate)
+    }
+
+    /// Check whether a character is a space or a tab.
+    fn is_space(c: char) -> bool {
+        c == ' ' || c == '\t'
+    }
+
+    /// Create a parser for a run of spaces and tabs on one line, which may be empty.
+    pub const fn space0() -> ParseTakeWhile<fn(char) -> bool> {
+        ParseTakeWhile::new(0, is_space as fn(char) -> bool).expecting("space or tab")
+    }
+
+    /// Create a parser for a run of at least one space or tab on one line.
+    pub const fn space1() -> ParseTakeWhile<fn(char) -> bool> {
+        ParseTakeWhile::new(1, is_space as fn(char) -> bool).expecting("space or tab")
+    }
+
+    /// Create a parser for a run of any whitespace, including newlines, which may be empty.
+    pub const fn multispace0() -> ParseTakeWhile<fn(char) -> bool> {
+        ParseTakeWhile::new(0, char::is_whitespace as fn(char) -> bool).expecting("whitespace")
+    }
+
+    /// Create a parser for a run of at least one whitespace character, including newlines.
+    pub const fn multispace1() -> ParseTakeWhile<fn(char) -> bool> {
+        ParseTakeWhile::new(1, char::is_whitespace as fn(char) -> bool).expecting("whitespace")
+    }
+
+    /// Check whether a character is a decimal digit.
+    fn is_digit(c: char) -> bool {
+        c.is_ascii_digit()
+    }
+
+    /// Create a parser for a single decimal digit.
+    pub const fn digit() -> ParseChar {
+        ParseChar::from_range('0', '9')
+    }
+
+    /// Create a parser for a run of decimal digits, which may be empty.
+    pub const fn digit0() -> ParseTakeWhile<fn(char) -> bool> {
+        ParseTakeWhile::new(0, is_digit as fn(char) -> bool).expecting("digit")
+    }
+
+    /// Create a parser for a run of at least one decimal digit.
+    pub const fn digit1() -> ParseTakeWhile<fn(char) -> bool> {
+        ParseTakeWhile::new(1, is_digit as fn(char) -> bool).expecting("digit")
+    }
+
+    /// Create a parser for a run of letters from any script, which may be empty.
+    pub const fn alpha() -> ParseTakeWhile<fn(char) -> bool> {
+        ParseTakeWhile::new(0, char::is_alphabetic as fn(char) -> bool).expecting("letter")
+    }
+
+    /// Create a parser for a run of at least one letter from any script.
+    pub const fn alpha1() -> ParseTakeWhile<fn(char) -> bool> {
+        ParseTakeWhile::new(1, char::is_alphabetic as fn(char) -> bool).expecting("letter")
+    }
+
+    /// Create a parser for a run of letters and digits from any script, which may be empty.
+    pub const fn alphanumeric() -> ParseTakeWhile<fn(char) -> bool> {
+        ParseTakeWhile::new(0, char::is_alphanumeric as fn(char) -> bool)
+            .expecting("letter or digit")
+    }
+
+    /// Create a parser for a run of at least one letter or digit from any script.
+    pub const fn alphanumeric1() -> ParseTakeWhile<fn(char) -> bool> {
+        ParseTakeWhile::new(1, char::is_alphanumeric as fn(char) -> bool)
+            .expecting("letter or digit")
+    }
+
+    /// Create a parser for a run of at least one digit in base `radix`, where letters stand for digits above 9 in
+    /// either case. Panics if `radix` isn't between 2 and 36.
+    pub fn digits_radix(radix: u32) -> ParseTakeWhile<impl Fn(char) -> bool> {
+        assert!(
+            (2..=36).contains(&radix),
+            "radix must be between 2 and 36, but was {}",
+            radix
+        );
+        ParseTakeWhile::new(1, move |c: char| c.is_digit(radix)).expecting(digit_name(radix))
+    }
+
+    /// Describe a digit in base `radix`.
+    fn digit_name(radix: u32) -> &'static str {
+        match radix {
+            2 => "binary digit",
+            8 => "octal digit",
+            16 => "hex digit",
+            _ => "digit",
+        }
+    }
+
+    /// Parses a decimal integer with an optional `+` or `-` sign, such as `-42`, and converts it to any type that
+    /// implements [`FromStr`], like `i64` or `u64`. Numbers that don't fit in the type are reported as
+    /// [`ParseErrorKind::Invalid`] errors spanning the number rather than panicking.
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+    pub struct ParseInt<IntType: FromStr> {
+        /// Whether a sign is allowed before the digits.
+        signed: bool,
+
+        /* Phantom */
+        _phantom: PhantomData<IntType>,
+    }
+
+    impl<IntType: FromStr> ParseInt<IntType> {
+        /// Create a new parser for integers with an optional sign.
+        pub const fn new() -> Self {
+            Self {
+                signed: true,
+                _phantom: PhantomData,
+            }
+        }
+
+        /// Don't allow a sign before the digits, so that only the digits are matched.
+        pub const fn unsigned(mut self) -> Self {
+            self.signed = false;
+            self
+        }
+    }
+
+    impl<IntType: FromStr> Default for ParseInt<IntType> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<IntType: FromStr> Parser<IntType, ParseError> for ParseInt<IntType>
+    where
+        IntType::Err: Display,
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, IntType> {
+            let start = parser_state.index;
+            let mut state = parser_state.clone();
+            if self.signed && state.input.starts_with(['+', '-']) {
+                state = state.advance(1);
+            }
+            let (_, new_state) = crate::formats::take_while(state, 1, "digit", is_digit)?;
+
+            let text = &parser_state.input[..new_state.index - start];
+            match text.parse() {
+                Ok(number) => Ok((number, new_state)),
+                Err(e) => Err(ParseError::invalid(
+                    start,
+                    new_state.index,
+                    &if new_state.verbosity() == ErrorVerbosity::Rich {
+                        e.to_string()
+                    } else {
+                        String::new()
+                    },
+                )),
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            let digits = Grammar::repeat(1, usize::MAX, Grammar::Special(String::from("digit")));
+            if self.signed {
+                Grammar::sequence([
+                    Grammar::repeat(
+                        0,
+                        1,
+                        Grammar::choice([
+                            Grammar::Literal(String::from("+")),
+                            Grammar::Literal(String::from("-")),
+                        ]),
+                    ),
+                    digits,
+                ])
+            } else {
+                digits
+            }
+        }
+    }
+
+    /// Create a parser for a decimal integer with an optional sign, converted to `IntType`.
+    pub const fn integer<IntType: FromStr>() -> ParseInt<IntType> {
+        ParseInt::new()
+    }
+
+    /// Parses a decimal floating-point number like `-1.5e-3`, returning the number along with the text it was
+    /// parsed from. Either the whole or the fractional part may be left out, as in `.5` and `1.`, but not both. An
+    /// `e` that isn't followed by an exponent is left unconsumed, so `2em` parses as `2`. Numbers too large to
+    /// represent, like `1e400`, are reported as [`ParseErrorKind::Invalid`] errors spanning the number, unless
+    /// special values are accepted, in which case they round to infinity.
+    #[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+    pub struct ParseFloat {
+        /// Whether `inf`, `infinity`, and `nan` are accepted in any case.
+        special_values: bool,
+    }
+
+    impl ParseFloat {
+        /// Create a new parser for finite decimal numbers.
+        pub const fn new() -> Self {
+            Self {
+                special_values: false,
+            }
+        }
+
+        /// Also accept `inf`, `infinity`, and `nan` in any case, with an optional sign.
+        pub const fn special_values(mut self) -> Self {
+            self.special_values = true;
+            self
+        }
+    }
+
+    /// Consume an optional `+` or `-` sign.
+    fn skip_sign(parser_state: ParserState) -> ParserState {
+        if parser_state.input.starts_with(['+', '-']) {
+            parser_state.advance(1)
+        } else {
+            parser_state
+        }
+    }
+
+    impl Parser<(f64, String), ParseError> for ParseFloat {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, (f64, String)> {
+            let start = parser_state.index;
+            let number_state = skip_sign(parser_state.clone());
+
+            let (whole, mut state) =
+                crate::formats::take_while(number_state.clone(), 0, "digit", is_digit)?;
+            let mut fraction = String::new();
+            if state.input.starts_with('.') {
+                let (digits, fraction_state) =
+                    crate::formats::take_while(state.advance(1), 0, "digit", is_digit)?;
+                // A lone `.` isn't a number, so leave it for whatever follows.
+                if !whole.is_empty() || !digits.is_empty() {
+                    fraction = digits;
+                    state = fraction_state;
+                }
+            }
+
+            if whole.is_empty() && fraction.is_empty() {
+                // Check the longer name first so all of `infinity` is used.
+                let special = ["infinity", "inf", "nan"].iter().copied().find(|name| {
+                    self.special_values
+                        && number_state
+                            .input
+                            .get(..name.len())
+                            .is_some_and(|text| text.eq_ignore_ascii_case(name))
+                });
+                match special {
+                    Some(name) => state = number_state.advance(name.len()),
+                    None => return Err(ParseError::expected_at(&number_state, "number")),
+                }
+            } else if state.input.starts_with(['e', 'E']) {
+                // The exponent only counts if it has digits.
+                let exponent_state = skip_sign(state.advance(1));
+                if let Ok((_, exponent_state)) =
+                    crate::formats::take_while(exponent_state, 1, "digit", is_digit)
+                {
+                    state = exponent_state;
+                }
+            }
+
+            // Rust accepts all of the forms above, and rounds numbers that are
+            // too large to infinity.
+            let text = &parser_state.input[..state.index - start];
+            let number: f64 = text.parse().unwrap();
+            if number.is_infinite() && !self.special_values {
+                return Err(ParseError::invalid(
+                    start,
+                    state.index,
+                    "number is too large",
+                ));
+            }
+            Ok(((number, String::from(text)), state))
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::Special(String::from("floating-point number"))
+        }
+    }
+
+    /// Create a parser for finite decimal floating-point numbers.
+    pub const fn float() -> ParseFloat {
+        ParseFloat::new()
+    }
+
+    /// Parses an unsigned integer literal in any base from 2 to 36, such as `0xDEAD_BEEF`, and converts it to
+    /// `IntType`. The literal starts with one of a set of prefixes, if any are given, and may have separators
+    /// between its digits. Numbers that don't fit in the type are reported as [`ParseErrorKind::Invalid`] errors
+    /// spanning the literal.
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+    pub struct ParseRadixInt<IntType: TryFrom<u64>> {
+        /// The base of the digits.
+        radix: u32,
+
+        /// The prefixes, one of which must start the literal unless there are none.
+        prefixes: &'static [&'static str],
+
+        /// The character allowed between digits, if there is one.
+        separator: Option<char>,
+
+        /* Phantom */
+        _phantom: PhantomData<IntType>,
+    }
+
+    impl<IntType: TryFrom<u64>> ParseRadixInt<IntType> {
+        /// Create a new parser for literals in base `radix` that start with one of `prefixes`, or with a digit if
+        /// `prefixes` is empty. Panics if `radix` isn't between 2 and 36.
+        pub fn new(radix: u32, prefixes: &'static [&'static str]) -> Self {
+            assert!(
+                (2..=36).contains(&radix),
+                "radix must be between 2 and 36, but was {}",
+                radix
+            );
+            Self {
+                radix,
+                prefixes,
+                separator: None,
+                _phantom: PhantomData,
+            }
+        }
+
+        /// Allow `separator` between digits, such as `_` in `1_000`. Separators can't start or end the digits.
+        pub const fn separator(mut self, separator: char) -> Self {
+            self.separator = Some(separator);
+            self
+        }
+    }
+
+    impl<IntType: TryFrom<u64>> Parser<IntType, ParseError> for ParseRadixInt<IntType> {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, IntType> {
+            let start = parser_state.index;
+            let mut state = parser_state.clone();
+            if !self.prefixes.is_empty() {
+                let mut error = None::<ParseError>;
+                for prefix in self.prefixes {
+                    match crate::formats::tag(parser_state.clone(), prefix) {
+                        Ok(((), prefix_state)) => {
+                            state = prefix_state;
+                            error = None;
+                            break;
+                        }
+                        Err(e) => {
+                            error = Some(match error {
+                                Some(error) => error.merge(e),
+                                None => e,
+                            })
+                        }
+                    }
+                }
+                if let Some(error) = error {
+                    return Err(error);
+                }
+            }
+
+            // Keep reading digits after an overflow so that the error covers
+            // the whole literal.
+            let mut number = Some(0u64);
+            let mut any_digits = false;
+            loop {
+                match state.input.chars().next() {
+                    Some(c) if c.is_digit(self.radix) => {
+                        let digit = u64::from(c.to_digit(self.radix).unwrap());
+                        number = number
+                            .and_then(|n| n.checked_mul(u64::from(self.radix)))
+                            .and_then(|n| n.checked_add(digit));
+                        any_digits = true;
+                        state = state.advance(1);
+                    }
+                    Some(c) if Some(c) == self.separator && any_digits => {
+                        let next_state = state.advance(c.len_utf8());
+                        if !next_state
+                            .input
+                            .starts_with(|c: char| c.is_digit(self.radix))
+                        {
+                            return Err(ParseError::invalid(
+                                state.index,
+                                next_state.index,
+                                "digit separators must be between digits",
+                            ));
+                        }
+                        state = next_state;
+                    }
+                    _ => break,
+                }
+            }
+            if !any_digits {
+                return Err(ParseError::expected_at(&state, digit_name(self.radix)));
+            }
+
+            match number.and_then(|n| IntType::try_from(n).ok()) {
+                Some(number) => Ok((number, state)),
+                None => Err(ParseError::invalid(
+                    start,
+                    state.index,
+                    if state.verbosity() == ErrorVerbosity::Rich {
+                        "number is too large"
+                    } else {
+                        ""
+                    },
+                )),
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            let digits = Grammar::repeat(
+                1,
+                usize::MAX,
+                Grammar::Special(String::from(digit_name(self.radix))),
+            );
+            if self.prefixes.is_empty() {
+                digits
+            } else {
+                let prefixes = self
+                    .prefixes
+                    .iter()
+                    .map(|prefix| Grammar::Literal(String::from(*prefix)));
+                Grammar::sequence([Grammar::choice(prefixes), digits])
+            }
+        }
+    }
+
+    /// Create a parser for hexadecimal literals like `0xDEAD_BEEF`, with `_` between digits allowed.
+    pub fn hex<IntType: TryFrom<u64>>() -> ParseRadixInt<IntType> {
+        ParseRadixInt::new(16, &["0x", "0X"]).separator('_')
+    }
+
+    /// Create a parser for octal literals like `0o755`, with `_` between digits allowed.
+    pub fn octal<IntType: TryFrom<u64>>() -> ParseRadixInt<IntType> {
+        ParseRadixInt::new(8, &["0o", "0O"]).separator('_')
+    }
+
+    /// Create a parser for binary literals like `0b1010_0101`, with `_` between digits allowed.
+    pub fn binary<IntType: TryFrom<u64>>() -> ParseRadixInt<IntType> {
+        ParseRadixInt::new(2, &["0b", "0B"]).separator('_')
+    }
+
+    /// Parses everything up to the first place a terminator matches, such as the body of a comment, returning the
+    /// text before the terminator. The terminator itself is left unconsumed.
+    pub struct ParseTakeUntil<
+        TerminatorOutputType,
+        TerminatorParserType: Parser<TerminatorOutputType, ParseError>,
+    > {
+        /// The parser for the terminator.
+        terminator: TerminatorParserType,
+
+        /// How the input has to start for the terminator to match, worked out from its grammar the first time the
+        /// parser runs. `None` when the grammar doesn't say, in which case the terminator is tried everywhere.
+        starts: OnceLock<Option<Vec<TerminatorStart>>>,
+
+        /* Phantom */
+        _phantom: PhantomData<TerminatorOutputType>,
+    }
+
+    /// A way the input can start at a position where a terminator might match.
+    enum TerminatorStart {
+        /// The input starts with this text.
+        Text(String),
+
+        /// The input starts with a character in this inclusive range.
+        Range(char, char),
+    }
+
+    impl TerminatorStart {
+        /// Get the ways the input has to start for something matching `grammar` to be there, or `None` if the grammar
+        /// could match at any position (including by matching nothing).
+        fn of(grammar: &Grammar) -> Option<Vec<Self>> {
+            match grammar {
+                Grammar::Literal(text) if !text.is_empty() => Some(vec![Self::Text(text.clone())]),
+                Grammar::Class(spec) => match char_class(spec) {
+                    Ok(class) if !class.negated => Some(
+                        class
+                            .ranges
+                            .into_iter()
+                            .map(|(start, end)| Self::Range(start, end))
+                            .collect(),
+                    ),
+                    _ => None,
+                },
+                Grammar::Sequence(elements) => elements.first().and_then(Self::of),
+                Grammar::Choice(alternatives) => alternatives
+                    .iter()
+                    .map(Self::of)
+                    .collect::<Option<Vec<_>>>()
+                    .map(|starts| starts.into_iter().flatten().collect()),
+                Grammar::Repeat { min, element, .. } if *min > 0 => Self::of(element),
+                Grammar::Rule { body, .. } => Self::of(body),
+                _ => None,
+            }
+        }
+
+        /// Check whether `input` starts this way.
+        fn matches(&self, input: &str) -> bool {
+            match self {
+                Self::Text(text) => input.starts_with(text.as_str()),
+                Self::Range(start, end) => input
+                    .chars()
+                    .next()
+                    .is_some_and(|c| (*start..=*end).contains(&c)),
+            }
+        }
+    }
+
+    impl<TerminatorOutputType, TerminatorParserType: Parser<TerminatorOutputType, ParseError>>
+        ParseTakeUntil<TerminatorOutputType, TerminatorParserType>
+    {
+        /// Create a new parser that consumes input until `terminator` would match.
+        pub const fn new(terminator: TerminatorParserType) -> Self {
+            Self {
+                terminator,
+                starts: OnceLock::new(),
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<TerminatorOutputType, TerminatorParserType: Parser<TerminatorOutputType, ParseError>>
+        Parser<String, ParseError> for ParseTakeUntil<TerminatorOutputType, TerminatorParserType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, String> {
+            // Try the terminator at the start of each character, and at the
+            // end of the input in case it matches there (like an anchor).
+            // Building a state copies the rest of the input, so skip the
+            // positions where the terminator's grammar says it can't start.
+            let starts = self
+                .starts
+                .get_or_init(|| TerminatorStart::of(&self.terminator.describe()));
+            let input = &parser_state.input;
+            let boundaries = input
+                .char_indices()
+                .map(|(index, _)| index)
+                .filter(|&index| match starts {
+                    Some(starts) => starts.iter().any(|start| start.matches(&input[index..])),
+                    None => true,
+                })
+                .chain([input.len()]);
+            let mut last_error = None;
+            for length in boundaries {
+                let state = parser_state.advance(length);
+                match self.terminator.parse(state.clone()) {
+                    Ok(_) => return Ok((String::from(&parser_state.input[..length]), state)),
+                    Err(e) if e.is_committed() => return Err(e),
+                    Err(e) => last_error = Some(e),
+                }
+            }
+
+            // The terminator never matched, so report why it failed at the
+            // end of the input.
+            Err(last_error.unwrap())
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::repeat(
+                0,
+                usize::MAX,
+                Grammar::Special(format!(
+                    "anything but {}",
+                    ebnf::expression_text(&self.terminator.describe())
+                )),
+            )
+        }
+    }
+
+    /// Create a parser that consumes input until the text `terminator` is found, leaving the terminator unconsumed.
+    pub fn take_until(terminator: &str) -> ParseTakeUntil<String, ParseLiteralSet> {
+        ParseTakeUntil::new(literal_set(&[terminator]))
+    }
+
+    /// Parses all of the remaining input, such as a message body that runs to the end. This always succeeds, and
+    /// returns an empty string when there is no input left.
+    #[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+    pub struct ParseRest;
+
+    impl Parser<String, ParseError> for ParseRest {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, String> {
+            let length = parser_state.input.len();
+            Ok((parser_state.input.clone(), parser_state.advance(length)))
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::Special(String::from("rest of input"))
+        }
+    }
+
+    /// Create a parser that consumes and returns all of the remaining input.
+    pub const fn rest() -> ParseRest {
+        ParseRest
+    }
+
+    /// Parses an exact piece of text, such as a keyword like `let` or an operator like `=>`, returning the text as
+    /// it appeared in the input.
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+    pub struct ParseStr<'s> {
+        /// The text to match.
+        literal: &'s str,
+
+        /// Whether letters match regardless of case.
+        ignore_case: bool,
+    }
+
+    impl<'s> ParseStr<'s> {
+        /// Create a new parser that matches exactly `literal`.
+        pub const fn new(literal: &'s str) -> Self {
+            Self {
+                literal,
+                ignore_case: false,
+            }
+        }
+
+        /// Match letters regardless of case, so that `select` also matches `SELECT` and `Select`.
+        pub const fn ignore_case(mut self) -> Self {
+            self.ignore_case = true;
+            self
+        }
+    }
+
+    impl Parser<String, ParseError> for ParseStr<'_> {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, String> {
+            if !self.ignore_case {
+                let ((), new_state) = crate::formats::tag(parser_state, self.literal)?;
+                return Ok((String::from(self.literal), new_state));
+            }
+
+            // Compare as many characters as the literal has, since changing
+            // case can change how many bytes a character takes.
+            let length = parser_state
+                .input
+                .char_indices()
+                .nth(self.literal.chars().count())
+                .map_or(parser_state.input.len(), |(length, _)| length);
+            let candidate = &parser_state.input[..length];
+            if candidate.to_lowercase() == self.literal.to_lowercase() {
+                Ok((String::from(candidate), parser_state.advance(length)))
+            } else {
+                Err(ParseError::expected_at(&parser_state, self.literal))
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            if self.ignore_case {
+                Grammar::Special(format!("^{:?}", self.literal))
+            } else {
+                Grammar::Literal(String::from(self.literal))
+            }
+        }
+    }
+
+    /// Parses an identifier: a character that can start one followed by any number of characters that can continue
+    /// one, such as `[A-Za-z_][A-Za-z0-9_]*`. Use [`spanned`](crate::parsers::ParserExtensions::spanned) to also
+    /// get where the identifier was found.
+    #[derive(Clone)]
+    pub struct ParseIdentifier<StartType: Fn(char) -> bool, ContinueType: Fn(char) -> bool> {
+        /// The function that decides whether a character can start an identifier.
+        start: StartType,
+
+        /// The function that decides whether a character can continue an identifier.
+        continuation: ContinueType,
+
+        /// A description of identifiers, reported when there isn't one.
+        expected: &'static str,
+    }
+
+    impl<StartType: Fn(char) -> bool, ContinueType: Fn(char) -> bool>
+        ParseIdentifier<StartType, ContinueType>
+    {
+        /// Create a new parser for identifiers that start with a character matching `start` and continue with
+        /// characters matching `continuation`.
+        pub const fn new(start: StartType, continuation: ContinueType) -> Self {
+            Self {
+                start,
+                continuation,
+                expected: "identifier",
+            }
+        }
+
+        /// Describe the identifiers, for errors and grammar descriptions.
+        pub const fn expecting(mut self, expected: &'static str) -> Self {
+            self.expected = expected;
+            self
+        }
+    }
+
+    impl<StartType: Fn(char) -> bool, ContinueType: Fn(char) -> bool> Parser<String, ParseError>
+        for ParseIdentifier<StartType, ContinueType>
+    {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, String> {
+            match parser_state.input.chars().next() {
+                Some(c) if (self.start)(c) => {
+                    let (rest, new_state) = crate::formats::take_while(
+                        parser_state.advance(c.len_utf8()),
+                        0,
+                        self.expected,
+                        &self.continuation,
+                    )?;
+                    Ok((format!("{}{}", c, rest), new_state))
+                }
+                _ => Err(ParseError::expected_at(&parser_state, self.expected)),
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::Special(String::from(self.expected))
+        }
+    }
+
+    /// A plain function that decides whether a character matches.
+    type CharPredicate = fn(char) -> bool;
+
+    /// Check whether a character can start an ASCII identifier.
+    fn is_identifier_start(c: char) -> bool {
+        c.is_ascii_alphabetic() || c == '_'
+    }
+
+    /// Check whether a character can continue an ASCII identifier.
+    fn is_identifier_continue(c: char) -> bool {
+        c.is_ascii_alphanumeric() || c == '_'
+    }
+
+    /// Check whether a character can start an identifier written in any script.
+    fn is_unicode_identifier_start(c: char) -> bool {
+        c.is_alphabetic() || c == '_'
+    }
+
+    /// Check whether a character can continue an identifier written in any script.
+    fn is_unicode_identifier_continue(c: char) -> bool {
+        c.is_alphanumeric() || c == '_'
+    }
+
+    /// Create a parser for ASCII identifiers, `[A-Za-z_][A-Za-z0-9_]*`.
+    pub const fn identifier() -> ParseIdentifier<CharPredicate, CharPredicate> {
+        ParseIdentifier::new(is_identifier_start, is_identifier_continue)
+    }
+
+    /// Create a parser for identifiers written in any script, which start with a letter or `_` and continue with
+    /// letters, digits, and `_`. This is close to the Unicode `XID_Start` and `XID_Continue` classes, but also
+    /// allows a few letter-like symbols that those leave out.
+    pub const fn unicode_identifier() -> ParseIdentifier<CharPredicate, CharPredicate> {
+        ParseIdentifier::new(is_unicode_identifier_start, is_unicode_identifier_continue)
+    }
+
+    /// Parses the longest of a set of literal strings that matches the input, returning the literal. The literals
+    /// are compiled into a DFA so that matching takes one pass over the input no matter how many literals there are.
+    #[derive(Debug, Clone, Eq, PartialEq, Hash)]
+    pub struct ParseLiteralSet {
+        /// The literals, in the order they were given.
+        literals: Vec<String>,
+
+        /// The class of each byte. Bytes that don't appear in any literal share class 0.
+        classes: [u8; 256],
+
+        /// The number of byte classes.
+        class_count: usize,
+
+        /// The next state for each state and byte class, flattened. State 0 is the dead state and state 1 is the
+        /// start state.
+        transitions: Vec<u32>,
+
+        /// The index of the literal that ends at each state, if one does.
+        accepting: Vec<Option<usize>>,
+    }
+
+    impl ParseLiteralSet {
+        /// Create a parser that matches any of `literals`, preferring the longest match. Empty literals are ignored.
+        pub fn new<S: AsRef<str>>(literals: &[S]) -> Self {
+            let literals = literals
+                .iter()
+                .map(|l| String::from(l.as_ref()))
+                .collect::<Vec<String>>();
+
+            // Give each byte that's used a class of its own. Some bytes never
+            // appear in UTF-8, so there are always fewer than 256 classes.
+            let mut classes = [0; 256];
+            let mut class_count = 1;
+            for byte in literals.iter().flat_map(|l| l.bytes()) {
+                if classes[byte as usize] == 0 {
+                    classes[byte as usize] = class_count as u8;
+                    class_count += 1;
+                }
+            }
+
+            // Build the DFA as a trie, adding states as literals need them.
+            let mut transitions = vec![0; class_count * 2];
+            let mut accepting = vec![None; 2];
+            for (index, literal) in literals.iter().enumerate() {
+                if literal.is_empty() {
+                    continue;
+                }
+
+                let mut state = 1;
+                for byte in literal.bytes() {
+                    let slot = state * class_count + classes[byte as usize] as usize;
+                    if transitions[slot] == 0 {
+                        transitions[slot] = accepting.len() as u32;
+                        transitions.extend(std::iter::repeat_n(0, class_count));
+                        accepting.push(None);
+                    }
+                    state = transitions[slot] as usize;
+                }
+
+                // Keep the first of any duplicate literals.
+                accepting[state].get_or_insert(index);
+            }
+
+            Self {
+                literals,
+                classes,
+                class_count,
+                transitions,
+                accepting,
+            }
+        }
+
+        /// Find the index and length of the longest literal at the start of `input`.
+        pub fn longest_match(&self, input: &str) -> Option<(usize, usize)> {
+            let mut state = 1;
+            let mut longest = None;
+
+            for (offset, byte) in input.bytes().enumerate() {
+                state = self.transitions
+                    [state * self.class_count + self.classes[byte as usize] as usize]
+                    as usize;
+                if state == 0 {
+                    break;
+                }
+                if let Some(index) = self.accepting[state] {
+                    longest = Some((index, offset + 1));
+                }
+            }
+
+            longest
+        }
+
+        /// Get the literals in the set.
+        pub fn literals(&self) -> &[String] {
+            &self.literals
+        }
+    }
+
+    impl Parser<String, ParseError> for ParseLiteralSet {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, String> {
+            match self.longest_match(&parser_state.input) {
+                Some((index, length)) => {
+                    Ok((self.literals[index].clone(), parser_state.advance(length)))
+                }
+
+                // Only list the literals if the state asks for them.
+                None if parser_state.verbosity() == ErrorVerbosity::Rich => {
+                    let found = parser_state.input.chars().next().map(String::from);
+                    Err(ParseError::unexpected(
+                        parser_state.index,
+                        &self.literals,
+                        found.as_deref(),
+                    ))
+                }
+                None => Err(ParseError::expected_at(&parser_state, "")),
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::choice(self.literals.iter().cloned().map(Grammar::Literal))
+        }
+    }
+
+    /// Create a parser that matches the longest of `literals` that appears at the start of the input.
+    pub fn literal_set<S: AsRef<str>>(literals: &[S]) -> ParseLiteralSet {
+        ParseLiteralSet::new(literals)
+    }
+
+    /// A node of a [`KeywordSet`] trie.
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    struct KeywordNode<ValueType> {
+        children: BTreeMap<char, usize>,
+        value: Option<ValueType>,
+    }
+
+    impl<ValueType> KeywordNode<ValueType> {
+        fn new() -> Self {
+            Self {
+                children: BTreeMap::new(),
+                value: None,
+            }
+        }
+    }
+
+    /// Parses the longest of a set of keywords that matches the input, returning the value associated with it. The
+    /// keywords are stored in a prefix trie, so matching takes one pass over the input.
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    pub struct KeywordSet<ValueType: Clone> {
+        /// The trie, where the first node is the root.
+        nodes: Vec<KeywordNode<ValueType>>,
+
+        /// The keywords, in the order they were added.
+        keywords: Vec<String>,
+
+        /// Whether a keyword must not be followed by a letter, digit, or underscore.
+        whole_words: bool,
+    }
+
+    impl<ValueType: Clone> KeywordSet<ValueType> {
+        /// Create an empty keyword set.
+        pub fn new() -> Self {
+            Self {
+                nodes: vec![KeywordNode::new()],
+                keywords: Vec::new(),
+                whole_words: false,
+            }
+        }
+
+        /// Add a keyword that produces `value`, replacing the value if the keyword was already added.
+        pub fn keyword(mut self, keyword: &str, value: ValueType) -> Self {
+            let mut node = 0;
+            for c in keyword.chars() {
+                node = match self.nodes[node].children.get(&c) {
+                    Some(child) => *child,
+                    None => {
+                        self.nodes.push(KeywordNode::new());
+                        let child = self.nodes.len() - 1;
+                        self.nodes[node].children.insert(c, child);
+                        child
+                    }
+                };
+            }
+
+            if self.nodes[node].value.replace(value).is_none() {
+                self.keywords.push(String::from(keyword));
+            }
+            self
+        }
+
+        /// Only match keywords that aren't immediately followed by a letter, digit, or underscore, so that a keyword
+        /// like `in` doesn't match the start of `index`.
+        pub fn whole_words(mut self) -> Self {
+            self.whole_words = true;
+            self
+        }
+
+        /// Find the value and length of the longest keyword at the start of `input`. When only whole words match,
+        /// that's the longest keyword that isn't followed by a word character, so `a` still matches `a.bc` when
+        /// `a.b` is also a keyword.
+        pub fn longest_match(&self, input: &str) -> Option<(&ValueType, usize)> {
+            let mut node = 0;
+            let mut longest = None;
+
+            for (offset, c) in input.char_indices() {
+                node = match self.nodes[node].children.get(&c) {
+                    Some(child) => *child,
+                    None => break,
+                };
+                if let Some(value) = &self.nodes[node].value {
+                    let length = offset + c.len_utf8();
+                    let whole_word =
+                        !input[length..].starts_with(|c: char| c.is_alphanumeric() || c == '_');
+                    if !self.whole_words || whole_word {
+                        longest = Some((value, length));
+                    }
+                }
+            }
+
+            longest
+        }
+    }
+
+    impl KeywordSet<usize> {
+        /// Create a keyword set where each keyword produces its index in `keywords`.
+        pub fn from_keywords<S: AsRef<str>>(keywords: &[S]) -> Self {
+            keywords
+                .iter()
+                .enumerate()
+                .fold(Self::new(), |set, (index, keyword)| {
+                    set.keyword(keyword.as_ref(), index)
+                })
+        }
+    }
+
+    impl<ValueType: Clone> Default for KeywordSet<ValueType> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<ValueType: Clone> Parser<ValueType, ParseError> for KeywordSet<ValueType> {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, ValueType> {
+            match self.longest_match(&parser_state.input) {
+                Some((value, length)) => Ok((value.clone(), parser_state.advance(length))),
+
+                // Only list the keywords if the state asks for them.
+                None if parser_state.verbosity() == ErrorVerbosity::Rich => {
+                    let found = parser_state.input.chars().next().map(String::from);
+                    Err(ParseError::unexpected(
+                        parser_state.index,
+                        &self.keywords,
+                        found.as_deref(),
+                    ))
+                }
+                None => Err(ParseError::expected_at(&parser_state, "")),
+            }
+        }
+
+        fn describe(&self) -> Grammar {
+            Grammar::choice(self.keywords.iter().cloned().map(Grammar::Literal))
+        }
+    }
+}
+
+/// A trait to be added to other parsers that allows easier parser combining.
+pub trait ParserExtensions<OutputType, ErrorType>: Parser<OutputType, ErrorType> {
+    fn char(&self, character: char) -> ParseChar {
+        ParseChar::from_char(character)
+    }
+
+    fn char_range(&self, start: char, end: char) -> ParseChar {
+        ParseChar::from_range(start, end)
+    }
+
+    fn and<NextOutputType, NextErrorType, NextParserType: Parser<NextOutputType, NextErrorType>>(
+        self,
+        next: NextParserType,
+    ) -> ParseAnd<OutputType, ErrorType, Self, NextOutputType, NextErrorType, NextParserType>
+    where
+        Self: Sized,
+    {
+        ParseAnd::new(self, next)
+    }
+
+    fn then_keep_right<NextOutputType, NextParserType: Parser<NextOutputType, ErrorType>>(
+        self,
+        next: NextParserType,
+    ) -> ParsePreceded<OutputType, NextOutputType, ErrorType, Self, NextParserType>
+    where
+        Self: Sized,
+    {
+        ParsePreceded::new(self, next)
+    }
+
+    fn then_keep_left<NextOutputType, NextParserType: Parser<NextOutputType, ErrorType>>(
+        self,
+        next: NextParserType,
+    ) -> ParseTerminated<OutputType, NextOutputType, ErrorType, Self, NextParserType>
+    where
+        Self: Sized,
+    {
+        ParseTerminated::new(self, next)
+    }
+
+    fn delimited_by<
+        OpenOutputType,
+        CloseOutputType,
+        OpenParserType: Parser<OpenOutputType, ErrorType>,
+        CloseParserType: Parser<CloseOutputType, ErrorType>,
+    >(
+        self,
+        open: OpenParserType,
+        close: CloseParserType,
+    ) -> ParseDelimited<
+        OpenOutputType,
+        OutputType,
+        CloseOutputType,
+        ErrorType,
+        OpenParserType,
+        Self,
+        CloseParserType,
+    >
+    where
+        Self: Sized,
+    {
+        ParseDelimited::new(open, self, close)
+    }
+
+    fn xor<NextParserType: Parser<OutputType, ParseError>>(
+        self,
+        next: NextParserType,
+    ) -> ParseXor<OutputType, Self, NextParserType>
+    where
+        Self: Sized + Parser<OutputType, ParseError>,
+    {
+        ParseXor::new(self, next)
+    }
+
+    fn or<NextParserType: Parser<OutputType, ParseError>>(
+        self,
+        next: NextParserType,
+    ) -> ParseOr<OutputType, Self, NextParserType>
+    where
+        Self: Sized + Parser<OutputType, ParseError>,
+    {
+        ParseOr::new(self, next)
+    }
+
+    fn not(self) -> ParseNot<OutputType, Self>
+    where
+        Self: Sized + Parser<OutputType, ParseError>,
+    {
+        ParseNot::new(self)
+    }
+
+    fn peek(self) -> ParsePeek<OutputType, ErrorType, Self>
+    where
+        Self: Sized,
+    {
+        ParsePeek::new(self)
+    }
+
+    fn cut(self) -> ParseCut<OutputType, Self>
+    where
+        Self: Sized + Parser<OutputType, ParseError>,
+    {
+        ParseCut::new(self)
+    }
+
+    fn all_consuming(self) -> ParseAllConsuming<OutputType, Self>
+    where
+        Self: Sized + Parser<OutputType, ParseError>,
+    {
+        ParseAllConsuming::new(self)
+    }
+
+    fn or_value(self, value: OutputType) -> ParseOrValue<OutputType, ErrorType, Self>
+    where
+        Self: Sized,
+        OutputType: Clone,
+    {
+        ParseOrValue::new(self, value)
+    }
+
+    fn or_default(self) -> ParseOrValue<OutputType, ErrorType, Self>
+    where
+        Self: Sized,
+        OutputType: Clone + Default,
+    {
+        self.or_value(OutputType::default())
+    }
+
+    fn map<NextOutputType, MapperType: Fn(OutputType) -> NextOutputType>(
+        self,
+        mapper: MapperType,
+    ) -> ParseMap<OutputType, NextOutputType, ErrorType, Self, MapperType>
+    where
+        Self: Sized,
+    {
+        ParseMap::new(self, mapper)
+    }
+
+    fn to<NextOutputType: Clone>(
+        self,
+        value: NextOutputType,
+    ) -> ParseToValue<OutputType, NextOutputType, ErrorType, Self>
+    where
+        Self: Sized,
+    {
+        ParseToValue::new(self, value)
+    }
+
+    fn map_err<NextErrorType, MapperType: Fn(ErrorType) -> NextErrorType>(
+        self,
+        mapper: MapperType,
+    ) -> ParseMapErr<OutputType, ErrorType, NextErrorType, Self, MapperType>
+    where
+        Self: Sized,
+    {
+        ParseMapErr::new(self, mapper)
+    }
+
+    fn try_map<
+        NextOutputType,
+        ConversionErrorType: Display,
+        MapperType: Fn(OutputType) -> Result<NextOutputType, ConversionErrorType>,
+    >(
+        self,
+        mapper: MapperType,
+    ) -> ParseTryMap<OutputType, NextOutputType, ConversionErrorType, Self, MapperType>
+    where
+        Self: Sized + Parser<OutputType, ParseError>,
+    {
+        ParseTryMap::new(self, mapper)
+    }
+
+    fn verify<PredicateType: Fn(&OutputType) -> bool>(
+        self,
+        predicate: PredicateType,
+    ) -> ParseVerify<OutputType, Self, PredicateType>
+    where
+        Self: Sized + Parser<OutputType, ParseError>,
+    {
+        ParseVerify::new(self, predicate)
+    }
+
+    fn parse_to<NextOutputType: FromStr>(self) -> ParseTo<OutputType, NextOutputType, Self>
+    where
+        Self: Sized + Parser<OutputType, ParseError>,
+        NextOutputType::Err: Display,
+    {
+        ParseTo::new(self)
+    }
+
+    fn memoized(
+        self,
+    ) -> ParseMemoized<
+        OutputType,
+        ErrorType,
+        Self,
+        HashMap<MemoKey, ParseResult<ErrorType, OutputType>>,
+    >
+    where
+        Self: Sized,
+        OutputType: Clone,
+        ErrorType: Clone,
+    {
+        ParseMemoized::new(self)
+    }
+
+    fn memoized_with<CacheType: MemoCache<OutputType, ErrorType>>(
+        self,
+        cache: CacheType,
+    ) -> ParseMemoized<OutputType, ErrorType, Self, CacheType>
+    where
+        Self: Sized,
+    {
+        ParseMemoized::with_cache(self, cache)
+    }
+
+    fn and_then<
+        NextOutputType,
+        NextParserType: Parser<NextOutputType, ErrorType>,
+        BinderType: Fn(OutputType) -> NextParserType,
+    >(
+        self,
+        binder: BinderType,
+    ) -> ParseAndThen<OutputType, NextOutputType, ErrorType, Self, NextParserType, BinderType>
+    where
+        Self: Sized,
+    {
+        ParseAndThen::new(self, binder)
+    }
+
+    fn reparse<NextOutputType, NextParserType: Parser<NextOutputType, ErrorType>>(
+        self,
+        parser: NextParserType,
+    ) -> ParseReparse<NextOutputType, ErrorType, Self, NextParserType>
+    where
+        Self: Sized + Parser<Spanned<String>, ErrorType>,
+    {
+        ParseReparse::new(self, parser)
+    }
+
+    fn fold<
+        AccumulatorType: Clone,
+        FolderType: Fn(AccumulatorType, OutputType) -> AccumulatorType,
+    >(
+        self,
+        init: AccumulatorType,
+        folder: FolderType,
+    ) -> ParseFold<OutputType, ErrorType, Self, AccumulatorType, FolderType>
+    where
+        Self: Sized,
+    {
+        ParseFold::new(self, init, folder)
+    }
+
+    fn fold_while<
+        AccumulatorType: Clone,
+        FolderType: Fn(AccumulatorType, OutputType) -> ControlFlow<AccumulatorType, AccumulatorType>,
+    >(
+        self,
+        init: AccumulatorType,
+        folder: FolderType,
+    ) -> ParseFoldWhile<OutputType, ErrorType, Self, AccumulatorType, FolderType>
+    where
+        Self: Sized,
+    {
+        ParseFoldWhile::new(self, init, folder)
+    }
+
+    fn boxed(self) -> BoxedParser<OutputType, ErrorType>
+    where
+        Self: Sized + Send + Sync + 'static,
+    {
+        BoxedParser::new(self)
+    }
+
+    fn label(self, label: &str) -> ParseLabel<OutputType, Self>
+    where
+        Self: Sized + Parser<OutputType, ParseError>,
+    {
+        ParseLabel::new(self, label)
+    }
+
+    fn label_with_doc(self, label: &str, description: &str) -> ParseLabel<OutputType, Self>
+    where
+        Self: Sized + Parser<OutputType, ParseError>,
+    {
+        ParseLabel::new(self, label).doc(description)
+    }
+
+    fn parse_or_explain(&self, parser_state: ParserState) -> ParseResult<ParseError, OutputType>
+    where
+        Self: Parser<OutputType, ParseError>,
+    {
+        // Parse with cheap errors first, only building a detailed error by
+        // parsing again if the first attempt fails.
+        let verbosity = parser_state.verbosity();
+        let fast_state = parser_state.clone().with_verbosity(ErrorVerbosity::Fast);
+        match Parser::<OutputType, ParseError>::parse(self, fast_state) {
+            Ok((output, new_state)) => Ok((output, new_state.with_verbosity(verbosity))),
+            Err(_) => Parser::<OutputType, ParseError>::parse(
+                self,
+                parser_state.with_verbosity(ErrorVerbosity::Rich),
+            ),
+        }
+    }
+
+    fn parse_partial(&self, parser_state: ParserState) -> PartialParseResult<ParseError, OutputType>
+    where
+        Self: Parser<OutputType, ParseError>,
+    {
+        match Parser::<OutputType, ParseError>::parse(self, parser_state.clone()) {
+            Ok((output, new_state)) => (Ok(output), new_state),
+            Err(e) => {
+                let failed_state = e.state(&parser_state);
+                (Err(e), failed_state)
+            }
+        }
+    }
+
+    fn with_slice(self) -> ParseWithSlice<OutputType, ErrorType, Self>
+    where
+        Self: Sized,
+    {
+        ParseWithSlice::new(self)
+    }
+
+    fn recognize(self) -> ParseRecognize<OutputType, ErrorType, Self>
+    where
+        Self: Sized,
+    {
+        ParseRecognize::new(self)
+    }
+
+    fn spanned(self) -> ParseSpanned<OutputType, ErrorType, Self>
+    where
+        Self: Sized,
+    {
+        ParseSpanned::new(self)
+    }
+
+    fn count_of(self, min: usize, max: usize) -> ParseCountOf<OutputType, ErrorType, Self>
+    where
+        Self: Sized,
+    {
+        ParseCountOf::new(min, max, self)
+    }
+
+    fn skip_many(self) -> ParseCountOf<OutputType, ErrorType, Self>
+    where
+        Self: Sized,
+    {
+        self.count_of(0, usize::MAX)
+    }
+
+    fn between(self, min: usize, max: usize) -> ParseCount<OutputType, ErrorType, Self>
+    where
+        Self: Sized,
+    {
+        ParseCount::new(min, max, self)
+    }
+
+    fn exactly(self, count: usize) -> ParseCount<OutputType, ErrorType, Self>
+    where
+        Self: Sized,
+    {
+        self.between(count, count)
+    }
+
+    fn repeat<const COUNT: usize>(self) -> ParseArray<OutputType, ErrorType, Self, COUNT>
+    where
+        Self: Sized,
+    {
+        ParseArray::new(self)
+    }
+
+    fn at_least(self, min: usize) -> ParseCount<OutputType, ErrorType, Self>
+    where
+        Self: Sized,
+    {
+        self.between(min, usize::MAX)
+    }
+
+    fn no_more_than(self, max: usize) -> ParseCount<OutputType, ErrorType, Self>
+    where
+        Self: Sized,
+    {
         self.between(0, max)
     }
 
+    fn chainl1<
+        OperatorType: Fn(OutputType, OutputType) -> OutputType,
+        OperatorParserType: Parser<OperatorType, ErrorType>,
+    >(
+        self,
+        operator: OperatorParserType,
+    ) -> ParseChain<OutputType, OperatorType, ErrorType, Self, OperatorParserType>
+    where
+        Self: Sized,
+    {
+        ParseChain::new(self, operator, Associativity::Left)
+    }
+
+    fn chainr1<
+        OperatorType: Fn(OutputType, OutputType) -> OutputType,
+        OperatorParserType: Parser<OperatorType, ErrorType>,
+    >(
+        self,
+        operator: OperatorParserType,
+    ) -> ParseChain<OutputType, OperatorType, ErrorType, Self, OperatorParserType>
+    where
+        Self: Sized,
+    {
+        ParseChain::new(self, operator, Associativity::Right)
+    }
+
+    fn many_till<
+        TerminatorOutputType,
+        TerminatorParserType: Parser<TerminatorOutputType, ParseError>,
+    >(
+        self,
+        terminator: TerminatorParserType,
+    ) -> ParseManyTill<OutputType, TerminatorOutputType, Self, TerminatorParserType>
+    where
+        Self: Sized + Parser<OutputType, ParseError>,
+    {
+        ParseManyTill::new(self, terminator)
+    }
+
+    fn sep_by<SeparatorOutputType, SeparatorParserType: Parser<SeparatorOutputType, ErrorType>>(
+        self,
+        separator: SeparatorParserType,
+    ) -> ParseSepBy<OutputType, SeparatorOutputType, ErrorType, Self, SeparatorParserType>
+    where
+        Self: Sized,
+    {
+        ParseSepBy::new(self, separator, false)
+    }
+
+    fn sep_by1<SeparatorOutputType, SeparatorParserType: Parser<SeparatorOutputType, ErrorType>>(
+        self,
+        separator: SeparatorParserType,
+    ) -> ParseSepBy<OutputType, SeparatorOutputType, ErrorType, Self, SeparatorParserType>
+    where
+        Self: Sized,
+    {
+        ParseSepBy::new(self, separator, true)
+    }
+
+    fn when<ConditionType: Fn() -> bool>(
+        self,
+        condition: ConditionType,
+    ) -> ParseCond<OutputType, ErrorType, Self, ConditionType>
+    where
+        Self: Sized,
+    {
+        ParseCond::new(self, condition)
+    }
+
+    fn opt(self) -> ParseOpt<OutputType, ErrorType, Self>
+    where
+        Self: Sized,
+    {
+        ParseOpt::new(self)
+    }
+
     fn optional(self) -> ParseCount<OutputType, ErrorType, Self>
     where
         Self: Sized,
@@ -0,0 +1,282 @@
+use crate::grammar::Grammar;
+use crate::parsers::utf8::Associativity;
+use crate::{BoxedParser, Commit, ParseError, ParseResult, Parser, ParserState};
+
+/// A type-erased operator parser, which only needs to report where the operator ends.
+type OperatorParser = Box<dyn Fn(ParserState) -> Result<ParserState, ParseError> + Send + Sync>;
+
+/// An operator that comes before or after its operand.
+struct UnaryOperator<OutputType> {
+    /// The parser for the operator itself.
+    parser: OperatorParser,
+
+    /// A description of the operator.
+    grammar: Grammar,
+
+    /// How tightly the operator binds to its operand.
+    power: u32,
+
+    /// The function that builds the result from the operand.
+    build: Box<dyn Fn(OutputType) -> OutputType + Send + Sync>,
+}
+
+/// An operator that comes between its two operands.
+struct InfixOperator<OutputType> {
+    /// The parser for the operator itself.
+    parser: OperatorParser,
+
+    /// A description of the operator.
+    grammar: Grammar,
+
+    /// How tightly the operator binds to the operand on its left.
+    left_power: u32,
+
+    /// How tightly the operator binds to the operand on its right.
+    right_power: u32,
+
+    /// The function that builds the result from the two operands.
+    build: Box<dyn Fn(OutputType, OutputType) -> OutputType + Send + Sync>,
+}
+
+/// Erase the output type of an operator parser.
+fn operator_parser<OperatorOutputType>(
+    parser: impl Parser<OperatorOutputType, ParseError> + Send + Sync + 'static,
+) -> (OperatorParser, Grammar) {
+    let grammar = parser.describe();
+    let parser = Box::new(move |parser_state| parser.parse(parser_state).map(|(_, state)| state));
+    (parser, grammar)
+}
+
+/// Try to parse an operator, returning `None` if it isn't there and the error if it failed after committing.
+fn operator_at(
+    parser: &OperatorParser,
+    parser_state: &ParserState,
+) -> Result<Option<ParserState>, ParseError> {
+    match parser(parser_state.clone()) {
+        Ok(operator_state) => Ok(Some(operator_state)),
+        Err(e) if e.is_committed() => Err(e),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Parses expressions made of atoms and prefix, infix, and postfix operators, using precedence climbing (also
+/// known as Pratt parsing) to decide how operators group:
+///
+/// ```
+/// use parselib_rs::parsers::expr::PrattParser;
+/// use parselib_rs::parsers::utf8::{integer, Associativity, ParseChar};
+/// use parselib_rs::{Parser, ParserState};
+///
+/// fn factorial(n: i64) -> i64 {
+///     (1..=n).product()
+/// }
+///
+/// let expression = PrattParser::new(integer::<i64>().unsigned())
+///     .infix(ParseChar::from_char('+'), 1, Associativity::Left, |a, b| a + b)
+///     .infix(ParseChar::from_char('*'), 2, Associativity::Left, |a, b| a * b)
+///     .infix(ParseChar::from_char('^'), 3, Associativity::Right, |a, b| a.pow(b as u32))
+///     .prefix(ParseChar::from_char('-'), 4, |a| -a)
+///     .postfix(ParseChar::from_char('!'), 5, factorial);
+///
+/// let evaluate = |input| expression.parse(ParserState::new(input)).unwrap().0;
+/// assert_eq!(evaluate("1+2*3"), 7);
+/// assert_eq!(evaluate("2^3^2"), 512);
+/// assert_eq!(evaluate("-3!+1"), -5);
+/// ```
+///
+/// Operators with a higher precedence bind more tightly. Operators are tried in the order they were registered,
+/// so when one operator is a prefix of another (like `*` and `**`), register the longer one first. Once an infix
+/// or prefix operator is found, its operand is required. Parenthesized sub-expressions can be handled by making
+/// the atom parser refer back to the expression parser.
+pub struct PrattParser<OutputType> {
+    /// The parser for the operands.
+    atom: BoxedParser<OutputType, ParseError>,
+
+    /// The operators that come before their operand.
+    prefix: Vec<UnaryOperator<OutputType>>,
+
+    /// The operators that come between their operands.
+    infix: Vec<InfixOperator<OutputType>>,
+
+    /// The operators that come after their operand.
+    postfix: Vec<UnaryOperator<OutputType>>,
+}
+
+impl<OutputType> PrattParser<OutputType> {
+    /// Create an expression parser for operands parsed by `atom`, with no operators yet.
+    pub fn new(atom: impl Parser<OutputType, ParseError> + Send + Sync + 'static) -> Self {
+        Self {
+            atom: BoxedParser::new(atom),
+            prefix: Vec::new(),
+            infix: Vec::new(),
+            postfix: Vec::new(),
+        }
+    }
+
+    /// Add an operator that comes before its operand, such as negation.
+    pub fn prefix<OperatorOutputType>(
+        mut self,
+        operator: impl Parser<OperatorOutputType, ParseError> + Send + Sync + 'static,
+        precedence: u32,
+        build: impl Fn(OutputType) -> OutputType + Send + Sync + 'static,
+    ) -> Self {
+        let (parser, grammar) = operator_parser(operator);
+        self.prefix.push(UnaryOperator {
+            parser,
+            grammar,
+            power: precedence * 2 + 1,
+            build: Box::new(build),
+        });
+        self
+    }
+
+    /// Add an operator that comes between its operands, such as addition, grouped by `associativity` when it is
+    /// chained with itself or other operators of the same precedence.
+    pub fn infix<OperatorOutputType>(
+        mut self,
+        operator: impl Parser<OperatorOutputType, ParseError> + Send + Sync + 'static,
+        precedence: u32,
+        associativity: Associativity,
+        build: impl Fn(OutputType, OutputType) -> OutputType + Send + Sync + 'static,
+    ) -> Self {
+        // The side with the higher power wins ties between operators of the
+        // same precedence.
+        let (left_power, right_power) = match associativity {
+            Associativity::Left => (precedence * 2 + 1, precedence * 2 + 2),
+            Associativity::Right => (precedence * 2 + 2, precedence * 2 + 1),
+        };
+        let (parser, grammar) = operator_parser(operator);
+        self.infix.push(InfixOperator {
+            parser,
+            grammar,
+            left_power,
+            right_power,
+            build: Box::new(build),
+        });
+        self
+    }
+
+    /// Add an operator that comes after its operand, such as a factorial.
+    pub fn postfix<OperatorOutputType>(
+        mut self,
+        operator: impl Parser<OperatorOutputType, ParseError> + Send + Sync + 'static,
+        precedence: u32,
+        build: impl Fn(OutputType) -> OutputType + Send + Sync + 'static,
+    ) -> Self {
+        let (parser, grammar) = operator_parser(operator);
+        self.postfix.push(UnaryOperator {
+            parser,
+            grammar,
+            power: precedence * 2 + 1,
+            build: Box::new(build),
+        });
+        self
+    }
+
+    /// Parse an expression whose operators all bind at least as tightly as `min_power`.
+    fn expression(
+        &self,
+        parser_state: ParserState,
+        min_power: u32,
+    ) -> ParseResult<ParseError, OutputType> {
+        // Parse the left operand, which may start with prefix operators.
+        let mut error = None::<ParseError>;
+        let mut left = None;
+        for operator in &self.prefix {
+            match (operator.parser)(parser_state.clone()) {
+                Ok(operator_state) => {
+                    let (operand, new_state) = self.expression(operator_state, operator.power)?;
+                    left = Some(((operator.build)(operand), new_state));
+                    break;
+                }
+                Err(e) if e.is_committed() => return Err(e),
+                Err(e) => {
+                    error = Some(match error {
+                        Some(error) => error.merge(e),
+                        None => e,
+                    })
+                }
+            }
+        }
+        let (mut left, mut new_state) = match left {
+            Some(left) => left,
+            None => match self.atom.parse(parser_state) {
+                Ok(left) => left,
+                // If neither matched, report what both of them expected.
+                Err(e) => {
+                    return Err(match error {
+                        Some(error) => error.merge(e),
+                        None => e,
+                    })
+                }
+            },
+        };
+
+        'operators: loop {
+            for operator in &self.postfix {
+                if let Some(operator_state) = operator_at(&operator.parser, &new_state)? {
+                    if operator.power < min_power {
+                        break 'operators;
+                    }
+                    left = (operator.build)(left);
+                    new_state = operator_state;
+                    continue 'operators;
+                }
+            }
+
+            for operator in &self.infix {
+                if let Some(operator_state) = operator_at(&operator.parser, &new_state)? {
+                    if operator.left_power < min_power {
+                        break 'operators;
+                    }
+                    let (right, right_state) =
+                        self.expression(operator_state, operator.right_power)?;
+                    left = (operator.build)(left, right);
+                    new_state = right_state;
+                    continue 'operators;
+                }
+            }
+
+            break;
+        }
+
+        Ok((left, new_state))
+    }
+}
+
+impl<OutputType> Parser<OutputType, ParseError> for PrattParser<OutputType> {
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, OutputType> {
+        self.expression(parser_state, 0)
+    }
+
+    fn describe(&self) -> Grammar {
+        /// Describe any number of the operators in a list, or nothing if there aren't any.
+        fn any_of<'a>(operators: impl Iterator<Item = &'a Grammar>) -> Grammar {
+            let operators = operators.cloned().collect::<Vec<Grammar>>();
+            if operators.is_empty() {
+                Grammar::Empty
+            } else {
+                Grammar::repeat(0, usize::MAX, Grammar::choice(operators))
+            }
+        }
+
+        let operand = Grammar::sequence([
+            any_of(self.prefix.iter().map(|operator| &operator.grammar)),
+            self.atom.describe(),
+            any_of(self.postfix.iter().map(|operator| &operator.grammar)),
+        ]);
+        if self.infix.is_empty() {
+            operand
+        } else {
+            let infix = self.infix.iter().map(|operator| operator.grammar.clone());
+            Grammar::sequence([
+                operand.clone(),
+                Grammar::repeat(
+                    0,
+                    usize::MAX,
+                    Grammar::sequence([Grammar::choice(infix), operand]),
+                ),
+            ])
+        }
+    }
+}
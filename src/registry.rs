@@ -0,0 +1,191 @@
+use crate::grammar::Grammar;
+use crate::{BoxedParser, ErrorVerbosity, ParseError, ParseResult, Parser, ParserState};
+use std::collections::HashMap;
+use std::sync::{Arc, PoisonError, RwLock, Weak};
+
+/// The rules of a registry, shared with the references to them.
+type Rules<OutputType> = RwLock<HashMap<String, Arc<BoxedParser<OutputType, ParseError>>>>;
+
+/// A set of parsers registered under names at runtime, which can refer to each other by name. References are
+/// resolved each time they are parsed rather than when they are created, so rules can be referenced before they
+/// are registered, replaced later, and can be mutually recursive:
+///
+/// ```
+/// use parselib_rs::parsers::utf8::{digit1, ParseChar};
+/// use parselib_rs::parsers::ParserExtensions;
+/// use parselib_rs::registry::GrammarRegistry;
+/// use parselib_rs::{Parser, ParserState};
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Value {
+///     Number(u32),
+///     List(Vec<Value>),
+/// }
+///
+/// let registry = GrammarRegistry::new();
+/// registry.register("value", registry.rule("number").xor(registry.rule("list")));
+/// registry.register(
+///     "list",
+///     registry
+///         .rule("value")
+///         .sep_by(ParseChar::from_char(','))
+///         .delimited_by(ParseChar::from_char('['), ParseChar::from_char(']'))
+///         .map(Value::List),
+/// );
+/// registry.register("number", digit1().parse_to::<u32>().map(Value::Number));
+///
+/// let (value, _) = registry.rule("value").parse(ParserState::new("[1,[2]]")).unwrap();
+/// assert_eq!(
+///     value,
+///     Value::List(vec![Value::Number(1), Value::List(vec![Value::Number(2)])])
+/// );
+/// ```
+pub struct GrammarRegistry<OutputType> {
+    /// The registered rules.
+    rules: Arc<Rules<OutputType>>,
+}
+
+impl<OutputType: 'static> GrammarRegistry<OutputType> {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self {
+            rules: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Register `parser` under `name`, replacing any rule already registered under that name.
+    pub fn register(
+        &self,
+        name: &str,
+        parser: impl Parser<OutputType, ParseError> + Send + Sync + 'static,
+    ) {
+        self.rules
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(String::from(name), Arc::new(BoxedParser::new(parser)));
+    }
+
+    /// Remove the rule registered under `name`, returning whether there was one.
+    pub fn unregister(&self, name: &str) -> bool {
+        self.rules
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(name)
+            .is_some()
+    }
+
+    /// Check whether a rule is registered under `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.rules
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains_key(name)
+    }
+
+    /// Get the names of the registered rules, in sorted order.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = self
+            .rules
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .keys()
+            .cloned()
+            .collect::<Vec<String>>();
+        names.sort();
+        names
+    }
+
+    /// Create a parser that runs the rule registered under `name`. The rule doesn't need to be registered yet.
+    ///
+    /// The reference doesn't keep the registry alive, so registering rules that refer to each other doesn't leak
+    /// them. Parsing a reference after its registry has been dropped, or while its rule isn't registered, fails with
+    /// a committed error, so that alternatives don't hide the mistake by backtracking past it.
+    pub fn rule(&self, name: &str) -> ParseRule<OutputType> {
+        ParseRule {
+            name: String::from(name),
+            rules: Arc::downgrade(&self.rules),
+        }
+    }
+
+    /// Describe the rule registered under `name`, or `None` if there isn't one. References to other rules within it
+    /// are described as [`Grammar::Reference`]s, so describe each name to get the whole grammar.
+    pub fn describe(&self, name: &str) -> Option<Grammar> {
+        let parser = lookup(&self.rules, name)?;
+        Some(Grammar::rule(name, parser.describe()))
+    }
+}
+
+impl<OutputType: 'static> Default for GrammarRegistry<OutputType> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Find a rule, keeping the lock only long enough to take a reference to it so that the rule can refer to itself
+/// and other rules can be registered while it runs.
+fn lookup<OutputType>(
+    rules: &Rules<OutputType>,
+    name: &str,
+) -> Option<Arc<BoxedParser<OutputType, ParseError>>> {
+    rules
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(name)
+        .cloned()
+}
+
+/// Parses the rule registered under a name in a [`GrammarRegistry`], looking it up each time it is run.
+pub struct ParseRule<OutputType> {
+    /// The name of the rule.
+    name: String,
+
+    /// The rules of the registry the rule is in.
+    rules: Weak<Rules<OutputType>>,
+}
+
+impl<OutputType> ParseRule<OutputType> {
+    /// Get the name of the rule this parser runs.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<OutputType> Clone for ParseRule<OutputType> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            rules: Weak::clone(&self.rules),
+        }
+    }
+}
+
+impl<OutputType> ParseRule<OutputType> {
+    /// Create the error for a rule that can't be run. It's committed, since it's a mistake in how the grammar was put
+    /// together rather than in the input, and shouldn't be backtracked past like an ordinary mismatch.
+    fn missing(&self, parser_state: &ParserState, reason: &str) -> ParseError {
+        let message = if parser_state.verbosity() == ErrorVerbosity::Rich {
+            format!("rule `{}` {}", self.name, reason)
+        } else {
+            String::new()
+        };
+        ParseError::invalid(parser_state.index, parser_state.index, &message).commit()
+    }
+}
+
+impl<OutputType> Parser<OutputType, ParseError> for ParseRule<OutputType> {
+    fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, OutputType> {
+        let parser = match self.rules.upgrade() {
+            Some(rules) => lookup(&rules, &self.name),
+            None => return Err(self.missing(&parser_state, "is in a registry that was dropped")),
+        };
+
+        match parser {
+            Some(parser) => parser.parse(parser_state),
+            None => Err(self.missing(&parser_state, "is not defined")),
+        }
+    }
+
+    fn describe(&self) -> Grammar {
+        Grammar::Reference(self.name.clone())
+    }
+}
@@ -0,0 +1,102 @@
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut, Range};
+
+/// A region of the input, from the index `start` up to (but not including) the index `end`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct Span {
+    /// The index of the start of the region.
+    pub start: usize,
+
+    /// The index just past the end of the region.
+    pub end: usize,
+}
+
+impl Span {
+    /// Create a new span covering `start..end`.
+    pub const fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Get the length of the region.
+    pub fn len(&self) -> usize {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// Check whether the region is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl From<Range<usize>> for Span {
+    fn from(range: Range<usize>) -> Self {
+        Self::new(range.start, range.end)
+    }
+}
+
+impl From<Span> for Range<usize> {
+    fn from(span: Span) -> Self {
+        span.start..span.end
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/// A value along with the region of the input that it was parsed from.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct Spanned<ValueType> {
+    /// The parsed value.
+    pub value: ValueType,
+
+    /// The region of the input the value was parsed from.
+    pub span: Span,
+}
+
+impl<ValueType> Spanned<ValueType> {
+    /// Create a new spanned value.
+    pub const fn new(value: ValueType, span: Span) -> Self {
+        Self { value, span }
+    }
+
+    /// Transform the value, keeping the same span.
+    pub fn map<NextValueType>(
+        self,
+        mapper: impl FnOnce(ValueType) -> NextValueType,
+    ) -> Spanned<NextValueType> {
+        Spanned::new(mapper(self.value), self.span)
+    }
+
+    /// Borrow the value, keeping the same span.
+    pub fn as_ref(&self) -> Spanned<&ValueType> {
+        Spanned::new(&self.value, self.span)
+    }
+
+    /// Take the value, discarding the span.
+    pub fn into_inner(self) -> ValueType {
+        self.value
+    }
+}
+
+impl<ValueType> Deref for Spanned<ValueType> {
+    type Target = ValueType;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<ValueType> DerefMut for Spanned<ValueType> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<ValueType: Display> Display for Spanned<ValueType> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.value.fmt(f)
+    }
+}
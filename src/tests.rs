@@ -1,7 +1,6 @@
 mod chars {
     use crate::parsers::utf8::ParseChar;
-    use crate::parsers::ParserExtensions;
-    use crate::{ParseError, Parser, ParserState};
+    use crate::{ParseError, ParseErrorKind, Parser, ParserState};
 
     fn test_char_parser(
         char_parser: ParseChar,
@@ -16,7 +15,7 @@ mod chars {
         let val = char_parser.parse(parser_start_state);
 
         // Check if it matches the expected result.
-        if let Ok((c, ParserState { input, index })) = val {
+        if let Ok((c, ParserState { input, index, .. })) = val {
             assert_eq!(c, expected_c);
             assert_eq!(input, expected_input);
             assert_eq!(index, expected_index);
@@ -66,14 +65,21 @@ mod chars {
         // Check the values.
         match val {
             // Should be an error.
-            Err(ParseError::Unexpected {
-                expected: Some(expected),
-                found: Some(found),
+            Err(ParseError {
+                kind:
+                    ParseErrorKind::Unexpected {
+                        expected,
+                        found: Some(found),
+                    },
+                offset: 0,
+                ..
             }) => {
                 // The error should contain this info.
-                if &expected != "i..z" || &found != "h" {
+                if expected.iter().map(ToString::to_string).collect::<Vec<_>>() != ["i..z"]
+                    || found.to_string() != "h"
+                {
                     panic!(
-                        "parse failed but has incorrect error | found: \"{}\" | expected: {}",
+                        "parse failed but has incorrect error | found: \"{}\" | expected: {:?}",
                         found, expected
                     )
                 }
@@ -89,6 +95,146 @@ mod chars {
             ),
         }
     }
+
+    #[test]
+    fn state_char_a() {
+        // Characters are counted as characters, not bytes, so multi-byte
+        // characters can be read and stepped over.
+        let state = ParserState::new("é😀x");
+        let (new_state, c) = state.char(1).unwrap();
+        assert_eq!(c, '😀');
+        assert_eq!(new_state.input(), "x");
+        assert_eq!(new_state.index, 6);
+        assert_eq!(state.char(2).unwrap().1, 'x');
+        assert!(state.char(3).is_none());
+
+        // Only as many characters as there are can be taken, however many
+        // bytes they use.
+        assert_eq!(state.chars(3), Some(vec!['é', '😀', 'x']));
+        assert_eq!(state.chars(4), None);
+    }
+
+    #[test]
+    fn state_advance_a() {
+        // Advancing by bytes remembers the character stepped over.
+        let state = ParserState::new_offset("aé!", 10).advance(3);
+        assert_eq!(state.input(), "!");
+        assert_eq!(state.index, 13);
+        assert_eq!(state.previous, Some('é'));
+    }
+}
+
+mod statics {
+    use crate::bytes::{be_u16, tag, ByteParser, ByteState, ParseTag, ParseUnsigned};
+    use crate::parsers::utf8::{
+        end_of_input, ParseAnchor, ParseAnd, ParseChar, ParseCount, ParseOr,
+    };
+    use crate::{ParseError, Parser, ParserState};
+
+    /// A lowercase hex digit.
+    type HexDigit = ParseOr<char, ParseChar, ParseChar>;
+
+    /// A hex byte followed by the end of the input, built without any runtime setup.
+    static HEX_BYTE: ParseAnd<
+        Vec<char>,
+        ParseError,
+        ParseCount<char, ParseError, HexDigit>,
+        (),
+        ParseError,
+        ParseAnchor,
+    > = ParseAnd::new(
+        ParseCount::new(
+            2,
+            2,
+            ParseOr::new(
+                ParseChar::from_range('0', '9'),
+                ParseChar::from_range('a', 'f'),
+            ),
+        ),
+        end_of_input(),
+    );
+
+    static MAGIC: ParseTag<'static> = tag(b"GIF");
+    static WIDTH: ParseUnsigned<u16> = be_u16();
+
+    #[test]
+    fn statics_a() {
+        // Parsers in statics can be used directly, and simple ones copied.
+        let ((digits, _), _) = HEX_BYTE.parse(ParserState::new("7e")).unwrap();
+        assert_eq!(digits, vec!['7', 'e']);
+        assert!(HEX_BYTE.parse(ParserState::new("7e0")).is_err());
+        assert!(HEX_BYTE.parse(ParserState::new("7:")).is_err());
+
+        let letter = ParseChar::from_range('a', 'z');
+        let copy = letter;
+        assert_eq!(
+            letter.parse(ParserState::new("q")),
+            copy.parse(ParserState::new("q"))
+        );
+
+        let (_, state) = MAGIC.parse(ByteState::new(b"GIF\x01\x02")).unwrap();
+        assert_eq!(WIDTH.parse(state).unwrap().0, 0x0102);
+    }
+}
+
+mod threads {
+    use crate::diagnostics::Diagnostic;
+    use crate::lines::LineIndex;
+    use crate::parsers::utf8::*;
+    use crate::parsers::ParserExtensions;
+    use crate::{ParseError, Parser, ParserState};
+    use std::sync::Arc;
+    use std::thread;
+
+    /// Fails to compile unless the value can be shared between threads.
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    #[test]
+    fn send_sync_a() {
+        // Built-in parsers, combinators, and errors can all be shared.
+        let digit = ParseChar::from_range('0', '9');
+        assert_send_sync(&digit);
+        assert_send_sync(&digit.one_or_more().and(end_of_input()).all_consuming());
+        assert_send_sync(&digit.xor(ParseChar::from_char('x')).label("digit"));
+        assert_send_sync(&digit.or_value('0').memoized());
+        assert_send_sync(&digit.try_map(|c| c.to_digit(10).ok_or("not a digit")));
+        assert_send_sync(&digit.with_slice().spanned().count_of(0, 3));
+        assert_send_sync(
+            &digit
+                .parse_to::<u8>()
+                .fold_while(0, |sum, n| std::ops::ControlFlow::Continue(sum + n)),
+        );
+        assert_send_sync(&ParseSequence::<char, ParseError>::new(vec![digit.boxed()]));
+        assert_send_sync(&ParseDispatch::new().on('a', digit).otherwise(digit));
+        assert_send_sync(&char_class("[a-z]").unwrap());
+        assert_send_sync(&literal_set(&["a", "b"]));
+        assert_send_sync(&KeywordSet::from_keywords(&["if"]));
+        assert_send_sync(&pure(1));
+        assert_send_sync(&fail("no"));
+
+        let error = digit.parse(ParserState::new("x")).unwrap_err();
+        assert_send_sync(&error);
+        assert_send_sync(&Diagnostic::new(&error, "x"));
+        assert_send_sync(&LineIndex::new("x"));
+    }
+
+    #[test]
+    fn send_sync_b() {
+        // One memoized grammar shared by several threads.
+        let number = Arc::new(ParseChar::from_range('0', '9').one_or_more().memoized());
+        let workers = (0..4)
+            .map(|n| {
+                let number = Arc::clone(&number);
+                thread::spawn(move || number.parse(ParserState::new(&n.to_string())).unwrap().0)
+            })
+            .collect::<Vec<_>>();
+        for (n, worker) in workers.into_iter().enumerate() {
+            assert_eq!(
+                worker.join().unwrap(),
+                n.to_string().chars().collect::<Vec<char>>()
+            );
+        }
+    }
 }
 
 mod counts {
@@ -120,3 +266,4414 @@ mod counts {
         }
     }
 }
+
+mod exact_counts {
+    use crate::parsers::utf8::char_class;
+    use crate::parsers::ParserExtensions;
+    use crate::{ParseErrorKind, Parser, ParserState};
+
+    #[test]
+    fn exactly_a() {
+        // Exactly two digits, no more and no fewer.
+        let digits_parser = char_class("[0-9]").unwrap().exactly(2);
+        let (digits, new_state) = digits_parser.parse(ParserState::new("123")).unwrap();
+        assert_eq!(digits, vec!['1', '2']);
+        assert_eq!(new_state.input(), "3");
+        assert!(digits_parser.parse(ParserState::new("1")).is_err());
+    }
+
+    #[test]
+    fn repeat_a() {
+        // The four hex digits of a unicode escape, as an array.
+        let escape_parser = char_class("[0-9a-fA-F]")
+            .unwrap()
+            .repeat::<4>()
+            .map(|digits| {
+                let digits = digits.iter().collect::<String>();
+                char::from_u32(u32::from_str_radix(&digits, 16).unwrap()).unwrap()
+            });
+        let (c, new_state) = escape_parser.parse(ParserState::new("00e9!")).unwrap();
+        assert_eq!(c, 'é');
+        assert_eq!(new_state.input(), "!");
+
+        // Too few digits is reported with how many were found.
+        let error = escape_parser.parse(ParserState::new("0eg")).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ParseErrorKind::WrongCount {
+                min: 4,
+                max: 4,
+                found: 2
+            }
+        ));
+        assert_eq!(error.offset, 2);
+    }
+}
+
+mod xors {
+    use crate::parsers::utf8::ParseChar;
+    use crate::parsers::ParserExtensions;
+    use crate::{ParseError, ParseErrorKind, Parser, ParserState};
+
+    #[test]
+    fn xor_parser_a() {
+        // Create a parser that accepts either a lowercase letter or a digit,
+        // which never overlap.
+        let xor_parser = ParseChar::from_range('a', 'z').xor(ParseChar::from_range('0', '9'));
+
+        // Only the second alternative should match.
+        match xor_parser.parse(ParserState::new("1a")) {
+            Ok((c, new_state)) => {
+                assert_eq!(c, '1');
+                assert_eq!(new_state.input, String::from("a"));
+            }
+            Err(e) => panic!("unexpected parsing error: {}", e),
+        }
+    }
+
+    #[test]
+    fn xor_parser_b() {
+        // Create a parser with overlapping alternatives and a third
+        // alternative that doesn't match at all.
+        let xor_parser = ParseChar::from_range('a', 'm')
+            .xor(ParseChar::from_range('h', 'z'))
+            .xor(ParseChar::from_char('!'));
+
+        // The 'h' matches both of the first two alternatives, which must be
+        // reported even though the last one fails.
+        match xor_parser.parse(ParserState::new("hello")) {
+            Err(ParseError {
+                kind: ParseErrorKind::Ambiguous,
+                offset: 0,
+                ..
+            }) => {}
+            Err(e) => panic!("wrong parsing error: {}", e),
+            Ok((c, _)) => panic!("parse succeeded but meant to fail: found char: '{}'", c),
+        }
+    }
+}
+
+mod ors {
+    use crate::parsers::utf8::ParseChar;
+    use crate::parsers::ParserExtensions;
+    use crate::{Expected, ParseErrorKind, Parser, ParserState};
+
+    #[test]
+    fn or_parser_a() {
+        // Overlapping alternatives are fine, since the first match wins.
+        let or_parser = ParseChar::from_range('a', 'm').or(ParseChar::from_range('h', 'z'));
+        let (c, new_state) = or_parser.parse(ParserState::new("hi")).unwrap();
+        assert_eq!(c, 'h');
+        assert_eq!(new_state.input(), "i");
+
+        // The second alternative is tried from the same position.
+        let (c, new_state) = or_parser.parse(ParserState::new("xy")).unwrap();
+        assert_eq!(c, 'x');
+        assert_eq!(new_state.input(), "y");
+    }
+
+    #[test]
+    fn or_parser_b() {
+        // When neither alternative matches, both are reported as expected.
+        let or_parser = ParseChar::from_char('a')
+            .label("first")
+            .or(ParseChar::from_char('b').label("second"));
+        match or_parser.parse(ParserState::new("c")).unwrap_err().kind() {
+            ParseErrorKind::Unexpected { expected, .. } => {
+                assert!(expected.contains(&Expected::from("first")));
+                assert!(expected.contains(&Expected::from("second")));
+            }
+            kind => panic!("wrong parsing error: {}", kind),
+        }
+    }
+}
+
+mod all_consuming {
+    use crate::parsers::utf8::ParseChar;
+    use crate::parsers::ParserExtensions;
+    use crate::{ParseError, Parser, ParserState};
+
+    #[test]
+    fn all_consuming_parser_a() {
+        // Create a parser that must consume a whole lowercase word.
+        let word_parser = ParseChar::from_range('a', 'z')
+            .one_or_more()
+            .all_consuming();
+
+        // The entire input is a word, so this should succeed.
+        match word_parser.parse(ParserState::new("hello")) {
+            Ok((chars, new_state)) => {
+                assert_eq!(chars, "hello".chars().collect::<Vec<char>>());
+                assert_eq!(new_state.input, String::new());
+            }
+            Err(e) => panic!("unexpected parsing error: {}", e),
+        }
+    }
+
+    #[test]
+    fn all_consuming_parser_b() {
+        // Create a parser that must consume a whole lowercase word.
+        let word_parser = ParseChar::from_range('a', 'z')
+            .one_or_more()
+            .all_consuming();
+
+        // The trailing text should be reported along with its position.
+        assert_eq!(
+            word_parser
+                .parse(ParserState::new("hello world, this is a long string"))
+                .unwrap_err(),
+            ParseError::trailing_input(5, " world, this is ...")
+        );
+    }
+}
+
+mod or_values {
+    use crate::parsers::utf8::{cond, ParseChar, ParseStr};
+    use crate::parsers::ParserExtensions;
+    use crate::{Parser, ParserState};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn or_value_parser_a() {
+        // Create a parser for an optional sign that defaults to '+'.
+        let sign_parser = ParseChar::from_char('-').or_value('+');
+
+        // The sign is present, so it should be returned and consumed.
+        let (sign, new_state) = sign_parser.parse(ParserState::new("-5")).unwrap();
+        assert_eq!(sign, '-');
+        assert_eq!(new_state.index, 1);
+
+        // The sign is missing, so the fallback should be returned and
+        // nothing should be consumed.
+        let (sign, new_state) = sign_parser.parse(ParserState::new("5")).unwrap();
+        assert_eq!(sign, '+');
+        assert_eq!(new_state.input, String::from("5"));
+        assert_eq!(new_state.index, 0);
+    }
+
+    #[test]
+    fn or_value_parser_b() {
+        // A bracketed name that defaults when there are no brackets.
+        let name_parser = (ParseStr::new("["), ParseStr::new("]"))
+            .map(|_| "given")
+            .or_value("default");
+
+        // The element is missing entirely, so the fallback is used.
+        let (name, new_state) = name_parser.parse(ParserState::new("x")).unwrap();
+        assert_eq!(name, "default");
+        assert_eq!(new_state.index, 0);
+
+        // The element started but is malformed, so the error is reported
+        // where it went wrong instead of being hidden by the fallback.
+        let error = name_parser.parse(ParserState::new("[x")).unwrap_err();
+        assert_eq!(error.offset(), 1);
+    }
+
+    #[test]
+    fn or_default_parser_a() {
+        // Create a parser for digits that defaults to an empty list.
+        let digits_parser = ParseChar::from_range('0', '9').one_or_more().or_default();
+
+        // There are no digits, so the default should be returned.
+        let (digits, new_state) = digits_parser.parse(ParserState::new("abc")).unwrap();
+        assert!(digits.is_empty());
+        assert_eq!(new_state.index, 0);
+    }
+
+    #[test]
+    fn opt_parser_a() {
+        // An optional sign is an `Option` rather than a list.
+        let sign_parser = ParseChar::from_char('-').opt();
+        let (sign, new_state) = sign_parser.parse(ParserState::new("-5")).unwrap();
+        assert_eq!(sign, Some('-'));
+        assert_eq!(new_state.index, 1);
+
+        let (sign, new_state) = sign_parser.parse(ParserState::new("5")).unwrap();
+        assert_eq!(sign, None);
+        assert_eq!(new_state.index, 0);
+    }
+
+    #[test]
+    fn cond_parser_a() {
+        // Only accept a trailing unit suffix when the dialect allows it.
+        let units = AtomicBool::new(false);
+        let suffix_parser = ParseChar::from_char('%').when(|| units.load(Ordering::Relaxed));
+
+        // The flag is off, so nothing is parsed even though the suffix is there.
+        let (suffix, new_state) = suffix_parser.parse(ParserState::new("%")).unwrap();
+        assert_eq!(suffix, None);
+        assert_eq!(new_state.index, 0);
+
+        // The flag is on, so the suffix is parsed and is now required.
+        units.store(true, Ordering::Relaxed);
+        let (suffix, new_state) = suffix_parser.parse(ParserState::new("%")).unwrap();
+        assert_eq!(suffix, Some('%'));
+        assert_eq!(new_state.index, 1);
+        assert!(suffix_parser.parse(ParserState::new("x")).is_err());
+
+        // A fixed flag can be given directly.
+        let (suffix, _) = cond(false, ParseChar::from_char('%'))
+            .parse(ParserState::new("%"))
+            .unwrap();
+        assert_eq!(suffix, None);
+    }
+}
+
+mod try_maps {
+    use crate::parsers::utf8::{char_class, literal_set, ParseChar};
+    use crate::parsers::ParserExtensions;
+    use crate::span::Span;
+    use crate::{ParseError, ParseErrorKind, Parser, ParserState};
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn try_map_parser_a() {
+        // Create a parser that reads a run of digits as a byte.
+        let byte_parser = ParseChar::from_range('0', '9')
+            .one_or_more()
+            .try_map(|digits| digits.into_iter().collect::<String>().parse::<u8>());
+
+        // The number fits, so it should be converted.
+        let (byte, new_state) = byte_parser.parse(ParserState::new("42;")).unwrap();
+        assert_eq!(byte, 42);
+        assert_eq!(new_state.input, String::from(";"));
+
+        // The number is too large, so the error should point at the digits.
+        match byte_parser.parse(ParserState::new_offset("300;", 4)) {
+            Err(ParseError {
+                kind: ParseErrorKind::Invalid { end, .. },
+                offset,
+                ..
+            }) => {
+                assert_eq!(offset, 4);
+                assert_eq!(end, 7);
+            }
+            Err(e) => panic!("wrong parsing error: {}", e),
+            Ok((byte, _)) => panic!("parse succeeded but meant to fail: {}", byte),
+        }
+    }
+
+    #[test]
+    fn verify_parser_a() {
+        // Create a parser for identifiers that aren't reserved words.
+        let identifier_parser = ParseChar::from_range('a', 'z')
+            .one_or_more()
+            .map(|letters| letters.into_iter().collect::<String>())
+            .verify(|name| name != "let" && name != "fn")
+            .expecting("identifier that is not a keyword");
+
+        // An ordinary name should pass the check.
+        let (name, new_state) = identifier_parser.parse(ParserState::new("total=")).unwrap();
+        assert_eq!(name, "total");
+        assert_eq!(new_state.input, String::from("="));
+
+        // A reserved word should be rejected at its start, reporting the whole word.
+        assert_eq!(
+            identifier_parser.parse(ParserState::new_offset("let x", 2)),
+            Err(ParseError::unexpected(
+                2,
+                ["identifier that is not a keyword"],
+                Some("let")
+            ))
+        );
+    }
+
+    #[test]
+    fn map_parser_a() {
+        // Convert a pair of characters into a value without failing.
+        let pair_parser = ParseChar::from_range('a', 'z')
+            .and(ParseChar::from_range('0', '9'))
+            .map(|(letter, digit)| format!("{}{}", digit, letter));
+        let (pair, new_state) = pair_parser.parse(ParserState::new("b7!")).unwrap();
+        assert_eq!(pair, "7b");
+        assert_eq!(new_state.input(), "!");
+
+        // Errors pass through unchanged.
+        assert_eq!(
+            pair_parser
+                .parse(ParserState::new("bb"))
+                .unwrap_err()
+                .offset,
+            1
+        );
+    }
+
+    #[test]
+    fn to_value_a() {
+        // Keywords become literal values.
+        #[derive(Debug, Clone, PartialEq)]
+        enum Literal {
+            Bool(bool),
+        }
+
+        let bool_parser = literal_set(&["true"])
+            .to(Literal::Bool(true))
+            .or(literal_set(&["false"]).to(Literal::Bool(false)));
+        let (value, new_state) = bool_parser.parse(ParserState::new("false;")).unwrap();
+        assert_eq!(value, Literal::Bool(false));
+        assert_eq!(new_state.input(), ";");
+        assert!(bool_parser.parse(ParserState::new("maybe")).is_err());
+    }
+
+    #[test]
+    fn map_err_parser_a() {
+        // Lift a built-in parser into a pipeline with its own error type.
+        #[derive(Debug, PartialEq)]
+        enum ConfigError {
+            Syntax(usize),
+            MissingValue,
+        }
+
+        let key_parser = ParseChar::from_range('a', 'z')
+            .one_or_more()
+            .map_err(|e| ConfigError::Syntax(e.offset));
+        let value_parser = ParseChar::from_range('0', '9')
+            .one_or_more()
+            .map_err(|_| ConfigError::MissingValue);
+        let entry_parser = key_parser.and(value_parser);
+
+        let ((key, value), _) = entry_parser.parse(ParserState::new("abc12")).unwrap();
+        assert_eq!((key.len(), value.len()), (3, 2));
+        assert_eq!(
+            entry_parser
+                .parse(ParserState::new_offset("9", 5))
+                .unwrap_err(),
+            ConfigError::Syntax(5)
+        );
+        assert_eq!(
+            entry_parser.parse(ParserState::new("abc")).unwrap_err(),
+            ConfigError::MissingValue
+        );
+    }
+
+    #[test]
+    fn parse_to_a() {
+        // Convert the text matched by a parser with `FromStr`.
+        let address_parser = char_class("[0-9.]")
+            .unwrap()
+            .one_or_more()
+            .parse_to::<Ipv4Addr>();
+        let (address, new_state) = address_parser
+            .parse(ParserState::new("10.0.0.1 up"))
+            .unwrap();
+        assert_eq!(address, Ipv4Addr::new(10, 0, 0, 1));
+        assert_eq!(new_state.input(), " up");
+
+        // A failed conversion covers all of the matched text.
+        let error = address_parser
+            .parse(ParserState::new_offset("10.0.300.1 up", 2))
+            .unwrap_err();
+        assert_eq!(error.span(), Span::new(2, 12));
+        assert_eq!(
+            error.to_string(),
+            "invalid input: invalid IPv4 address syntax\n"
+        );
+    }
+}
+
+mod memoized {
+    use crate::parsers::utf8::ParseChar;
+    use crate::parsers::ParserExtensions;
+    use crate::{ErrorVerbosity, ParseError, ParseResult, Parser, ParserState};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// A character parser that counts how many times it has been run.
+    struct CountingParser {
+        calls: Rc<Cell<usize>>,
+    }
+
+    impl Parser<char, ParseError> for CountingParser {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, char> {
+            self.calls.set(self.calls.get() + 1);
+            ParseChar::from_any().parse(parser_state)
+        }
+    }
+
+    #[test]
+    fn memoized_parser_a() {
+        // Create a memoized parser around the counting parser.
+        let calls = Rc::new(Cell::new(0));
+        let memo_parser = CountingParser {
+            calls: calls.clone(),
+        }
+        .memoized();
+
+        // Parse from the same state several times.
+        let state = ParserState::new("abc");
+        for _ in 0..3 {
+            let (c, new_state) = memo_parser.parse(state.clone()).unwrap();
+            assert_eq!(c, 'a');
+            assert_eq!(new_state.index, 1);
+        }
+
+        // A different state must not reuse the result.
+        let (c, _) = memo_parser.parse(ParserState::new("xyz")).unwrap();
+        assert_eq!(c, 'x');
+
+        // The inner parser should only have run once per distinct state.
+        assert_eq!(calls.get(), 2);
+
+        // Results from earlier runs are kept while other runs use the parser,
+        // such as threads sharing it.
+        memo_parser.parse(state.clone()).unwrap();
+        assert_eq!(calls.get(), 2);
+
+        // After clearing, the inner parser runs again.
+        memo_parser.clear();
+        memo_parser.parse(state).unwrap();
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn memoized_parser_b() {
+        // Results are kept by position, but never reused for another input
+        // that reaches the same position.
+        let memo_parser = ParseChar::from_range('a', 'z').memoized();
+        let (c, _) = memo_parser
+            .parse(ParserState::new("ab").at(1).unwrap())
+            .unwrap();
+        assert_eq!(c, 'b');
+        let (c, _) = memo_parser
+            .parse(ParserState::new("ac").at(1).unwrap())
+            .unwrap();
+        assert_eq!(c, 'c');
+
+        // Fast errors aren't reused when rich ones are wanted.
+        let state = ParserState::new("1");
+        let error = memo_parser
+            .parse(state.clone().with_verbosity(ErrorVerbosity::Fast))
+            .unwrap_err();
+        assert!(error.expected().is_empty());
+        let error = memo_parser.parse(state).unwrap_err();
+        assert!(!error.expected().is_empty());
+    }
+}
+
+mod and_then {
+    use crate::grammar::Grammar;
+    use crate::parsers::utf8::ParseChar;
+    use crate::parsers::ParserExtensions;
+    use crate::{ParseErrorKind, Parser, ParserState};
+
+    #[test]
+    fn and_then_parser_a() {
+        // A digit gives the length of the payload that follows it.
+        let payload_parser = ParseChar::from_range('0', '9')
+            .map(|digit| digit.to_digit(10).unwrap() as usize)
+            .and_then(|length| ParseChar::from_any().between(length, length));
+
+        let (payload, new_state) = payload_parser.parse(ParserState::new("3abcd")).unwrap();
+        assert_eq!(payload, vec!['a', 'b', 'c']);
+        assert_eq!(new_state.input(), "d");
+        assert_eq!(new_state.index, 4);
+
+        // A payload that is too short is reported by the chosen parser.
+        let error = payload_parser.parse(ParserState::new("4ab")).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ParseErrorKind::WrongCount {
+                min: 4,
+                max: 4,
+                found: 2
+            }
+        ));
+
+        // Only the length prefix can be described.
+        assert_eq!(
+            payload_parser.describe(),
+            Grammar::Sequence(vec![Grammar::Class(String::from("[0-9]")), Grammar::Opaque])
+        );
+    }
+}
+
+mod reparse {
+    use crate::parsers::utf8::ParseChar;
+    use crate::parsers::ParserExtensions;
+    use crate::{ParseError, ParseErrorKind, Parser, ParserState};
+
+    #[test]
+    fn reparse_parser_a() {
+        // Create a parser that captures the text between square brackets
+        // and then requires it to be a single digit.
+        let bracket_parser = ParseChar::from_char('[')
+            .and(
+                ParseChar::from_range('a', 'z')
+                    .xor(ParseChar::from_range('0', '9'))
+                    .at_least(0)
+                    .recognize()
+                    .spanned(),
+            )
+            .and(ParseChar::from_char(']'))
+            .map(|((_, body), _)| body)
+            .reparse(ParseChar::from_range('0', '9').all_consuming());
+
+        // A single digit should be parsed and the brackets consumed.
+        let (digit, new_state) = bracket_parser.parse(ParserState::new("[7]!")).unwrap();
+        assert_eq!(digit, '7');
+        assert_eq!(new_state.input, String::from("!"));
+        assert_eq!(new_state.index, 3);
+
+        // Trailing text inside the brackets should be reported at its
+        // position in the original input.
+        match bracket_parser.parse(ParserState::new("[7ab]")) {
+            Err(ParseError {
+                kind: ParseErrorKind::TrailingInput { found },
+                offset,
+                ..
+            }) => {
+                assert_eq!(offset, 2);
+                assert_eq!(found, "ab");
+            }
+            Err(e) => panic!("wrong parsing error: {}", e),
+            Ok((digit, _)) => panic!("parse succeeded but meant to fail: {}", digit),
+        }
+    }
+
+    #[test]
+    fn reparse_parser_b() {
+        // A length-prefixed field whose body also appears in the length, so
+        // the body can't be found by searching for it.
+        let field_parser = ParseChar::from_range('0', '9')
+            .map(|digit| digit.to_digit(10).unwrap() as usize)
+            .then_keep_left(ParseChar::from_char(':'))
+            .and_then(|length| {
+                ParseChar::from_any()
+                    .between(length, length)
+                    .recognize()
+                    .spanned()
+            })
+            .reparse(ParseChar::from_range('0', '9').at_least(0).all_consuming());
+        let error = field_parser.parse(ParserState::new("3:33x!")).unwrap_err();
+        assert_eq!(error.offset(), 4);
+    }
+}
+
+mod anchors {
+    use crate::parsers::utf8::{
+        end_of_input, end_of_line, start_of_input, start_of_line, ParseChar,
+    };
+    use crate::parsers::ParserExtensions;
+    use crate::{ParseError, Parser, ParserState};
+
+    #[test]
+    fn anchor_parser_a() {
+        // Create a parser for a '#' that must begin a line and a word that
+        // must end one.
+        let heading_parser = start_of_line()
+            .and(ParseChar::from_char('#'))
+            .and(ParseChar::from_range('a', 'z').one_or_more())
+            .and(end_of_line());
+
+        // The heading is on its own line, so it should parse.
+        let state = ParseChar::from_char('x')
+            .and(ParseChar::from_char('\n'))
+            .parse(ParserState::new("x\n#title\r\nrest"))
+            .unwrap()
+            .1;
+        let (_, state) = heading_parser.parse(state).unwrap();
+        assert_eq!(state.input, String::from("\r\nrest"));
+
+        // The '#' is in the middle of a line, so the parse should fail.
+        let state = ParseChar::from_char('x')
+            .parse(ParserState::new("x#title"))
+            .unwrap()
+            .1;
+        assert!(heading_parser.parse(state).is_err());
+    }
+
+    #[test]
+    fn anchor_parser_b() {
+        // The start of input only matches before anything is consumed.
+        let state = ParserState::new("ab");
+        assert!(start_of_input().parse(state.clone()).is_ok());
+        let (_, state) = ParseChar::from_char('a').parse(state).unwrap();
+        assert!(start_of_input().parse(state).is_err());
+    }
+
+    #[test]
+    fn anchor_parser_c() {
+        // The end of input only matches once everything is consumed.
+        let (_, state) = ParseChar::from_char('a')
+            .parse(ParserState::new("a"))
+            .unwrap();
+        assert!(end_of_input().parse(state).is_ok());
+
+        // Anything left over is reported in full.
+        let (_, state) = ParseChar::from_char('a')
+            .parse(ParserState::new("a;b"))
+            .unwrap();
+        assert_eq!(
+            end_of_input().parse(state),
+            Err(ParseError::unexpected(1, ["end of input"], Some(";b")))
+        );
+    }
+}
+
+mod lookahead {
+    use crate::parsers::utf8::ParseChar;
+    use crate::parsers::ParserExtensions;
+    use crate::{ParseErrorKind, Parser, ParserState};
+
+    #[test]
+    fn not_parser_a() {
+        // A name that isn't followed by an opening parenthesis.
+        let variable_parser = ParseChar::from_range('a', 'z')
+            .one_or_more()
+            .and(ParseChar::from_char('(').not());
+
+        let (_, new_state) = variable_parser.parse(ParserState::new("abc+1")).unwrap();
+        assert_eq!(new_state.input(), "+1");
+
+        // The lookahead doesn't consume anything, even at the end of input.
+        let state = ParserState::new("x");
+        let (_, new_state) = ParseChar::from_char('(')
+            .not()
+            .parse(state.clone())
+            .unwrap();
+        assert_eq!(new_state, state);
+
+        // The error covers the text that shouldn't have matched.
+        let error = variable_parser
+            .parse(ParserState::new("abc(1)"))
+            .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ParseErrorKind::Invalid { end: 4, message } if message == "\"(\" is not allowed here"
+        ));
+        assert_eq!(error.offset, 3);
+    }
+
+    #[test]
+    fn peek_parser_a() {
+        // Look at the sign of a number before deciding how to parse it.
+        let sign_parser = ParseChar::from_char('-').peek();
+        let state = ParserState::new("-12");
+        let (sign, new_state) = sign_parser.parse(state.clone()).unwrap();
+        assert_eq!(sign, '-');
+        assert_eq!(new_state, state);
+
+        // A missing match is still an error, reported where it started.
+        let error = sign_parser.parse(ParserState::new("12")).unwrap_err();
+        assert_eq!(error.offset, 0);
+
+        // The peeked text can then be chosen between without ambiguity.
+        let number_parser = sign_parser
+            .and(ParseChar::from_char('-'))
+            .map(|_| -1)
+            .or(ParseChar::from_range('0', '9').map(|_| 1));
+        assert_eq!(number_parser.parse(ParserState::new("-1")).unwrap().0, -1);
+        assert_eq!(number_parser.parse(ParserState::new("1")).unwrap().0, 1);
+    }
+}
+
+mod cuts {
+    use crate::parsers::utf8::{literal_set, ParseChar};
+    use crate::parsers::ParserExtensions;
+    use crate::{Commit, ParseErrorKind, Parser, ParserState};
+
+    #[test]
+    fn cut_parser_a() {
+        // Statements made of a `let` followed by a name, where the name is
+        // required once the keyword has been seen.
+        let name = || ParseChar::from_range('a', 'z').one_or_more();
+        let uncut_parser = literal_set(&["let "])
+            .and(name())
+            .sep_by(ParseChar::from_char(';'))
+            .all_consuming();
+        let cut_parser = literal_set(&["let "])
+            .and(name().cut())
+            .sep_by(ParseChar::from_char(';'))
+            .all_consuming();
+
+        // Both accept valid input.
+        assert!(uncut_parser.parse(ParserState::new("let a;let b")).is_ok());
+        assert!(cut_parser.parse(ParserState::new("let a;let b")).is_ok());
+
+        // Without the cut, the list backtracks past the broken statement and
+        // the error blames the trailing input instead.
+        let error = uncut_parser
+            .parse(ParserState::new("let a;let 9"))
+            .unwrap_err();
+        assert!(matches!(error.kind(), ParseErrorKind::TrailingInput { .. }));
+        assert_eq!(error.offset(), 5);
+
+        // With the cut, the real problem is reported.
+        let error = cut_parser
+            .parse(ParserState::new("let a;let 9"))
+            .unwrap_err();
+        assert!(error.is_committed());
+        assert_eq!(error.offset(), 10);
+        assert!(matches!(
+            error.kind(),
+            ParseErrorKind::WrongCount {
+                min: 1,
+                found: 0,
+                ..
+            }
+        ));
+
+        // Failing before the cut still backtracks as usual.
+        let (_, new_state) = literal_set(&["let "])
+            .and(ParseChar::from_range('a', 'z').cut())
+            .or(literal_set(&["lex"]).map(|lex| (lex, 'x')))
+            .parse(ParserState::new("lex"))
+            .unwrap();
+        assert_eq!(new_state.index, 3);
+    }
+
+    /// An application's own error type, which knows nothing about committing.
+    #[derive(Debug)]
+    struct AppError;
+
+    #[test]
+    fn cut_parser_b() {
+        // Repetitions and optional elements work with any error type.
+        let digit = || ParseChar::from_range('0', '9').map_err(|_| AppError);
+        let (digits, new_state) = digit()
+            .between(1, 3)
+            .parse(ParserState::new("12x"))
+            .unwrap();
+        assert_eq!(digits, ['1', '2']);
+        assert_eq!(new_state.index, 2);
+        assert_eq!(
+            digit()
+                .count_of(0, 5)
+                .parse(ParserState::new("123"))
+                .unwrap()
+                .0,
+            3
+        );
+        assert!(digit().repeat::<3>().parse(ParserState::new("12")).is_err());
+        assert_eq!(digit().opt().parse(ParserState::new("x")).unwrap().0, None);
+        let (sum, _) = digit()
+            .fold(0, |sum, digit| sum + digit.to_digit(10).unwrap())
+            .parse(ParserState::new("123"))
+            .unwrap();
+        assert_eq!(sum, 6);
+    }
+}
+
+mod pure_fail {
+    use crate::parsers::utf8::{fail, pure, ParseChar, ParseFail};
+    use crate::parsers::ParserExtensions;
+    use crate::{ParseError, Parser, ParserState};
+
+    #[test]
+    fn pure_a() {
+        // A pure parser produces its value without consuming anything.
+        let (value, state) =
+            Parser::<_, ParseError>::parse(&pure(7), ParserState::new("abc")).unwrap();
+        assert_eq!(value, 7);
+        assert_eq!(state.input(), "abc");
+
+        // It works as a fallback at the end of a choice.
+        let sign = ParseChar::from_char('-').xor(pure('+'));
+        assert_eq!(sign.parse(ParserState::new("5")).unwrap().0, '+');
+    }
+
+    #[test]
+    fn fail_a() {
+        // A failing parser reports its error where it was tried.
+        let parser = ParseChar::from_char('a').and::<(), ParseError, _>(fail("not allowed here"));
+        let error = parser.parse(ParserState::new("ab")).unwrap_err();
+        assert_eq!(error.offset(), 1);
+        assert_eq!(error, ParseError::invalid(1, 1, "not allowed here"));
+
+        // Errors spanning some input keep their length when they're moved.
+        let parser = ParseChar::from_char('a')
+            .and::<(), ParseError, _>(ParseFail::new(ParseError::invalid(2, 5, "too long")));
+        let error = parser.parse(ParserState::new("abcd")).unwrap_err();
+        assert_eq!(error, ParseError::invalid(1, 4, "too long"));
+    }
+}
+
+mod folds {
+    use crate::parsers::utf8::ParseChar;
+    use crate::parsers::ParserExtensions;
+    use crate::{Parser, ParserState};
+    use std::ops::ControlFlow;
+
+    #[test]
+    fn fold_while_parser_a() {
+        // Create a parser that sums digits until the total exceeds 10.
+        let sum_parser = ParseChar::from_range('0', '9').fold_while(0, |sum, digit| {
+            let sum = sum + digit.to_digit(10).unwrap();
+            if sum > 10 {
+                ControlFlow::Break(sum)
+            } else {
+                ControlFlow::Continue(sum)
+            }
+        });
+
+        // The digit that pushes the total over the limit is the last one
+        // consumed.
+        let (sum, new_state) = sum_parser.parse(ParserState::new("45678")).unwrap();
+        assert_eq!(sum, 15);
+        assert_eq!(new_state.input, String::from("78"));
+
+        // Running out of digits also stops the fold.
+        let (sum, new_state) = sum_parser.parse(ParserState::new("12a")).unwrap();
+        assert_eq!(sum, 3);
+        assert_eq!(new_state.input, String::from("a"));
+    }
+
+    #[test]
+    fn fold_parser_a() {
+        // Build a number from its digits without collecting them.
+        let number_parser = ParseChar::from_range('0', '9').fold(0u32, |number, digit| {
+            number * 10 + digit.to_digit(10).unwrap()
+        });
+        let (number, new_state) = number_parser.parse(ParserState::new("1234+")).unwrap();
+        assert_eq!(number, 1234);
+        assert_eq!(new_state.input, String::from("+"));
+
+        // No elements leaves the initial value.
+        let (number, _) = number_parser.parse(ParserState::new("+")).unwrap();
+        assert_eq!(number, 0);
+    }
+}
+
+mod sep_by {
+    use crate::grammar::ebnf;
+    use crate::parsers::utf8::ParseChar;
+    use crate::parsers::ParserExtensions;
+    use crate::{Commit, ParseError, Parser, ParserState};
+
+    #[test]
+    fn sep_by_a() {
+        // A comma-separated list of digits, which may be empty.
+        let list_parser = ParseChar::from_range('0', '9').sep_by(ParseChar::from_char(','));
+
+        let (digits, new_state) = list_parser.parse(ParserState::new("1,2,3]")).unwrap();
+        assert_eq!(digits, vec!['1', '2', '3']);
+        assert_eq!(new_state.input(), "]");
+
+        let (digits, new_state) = list_parser.parse(ParserState::new("]")).unwrap();
+        assert!(digits.is_empty());
+        assert_eq!(new_state.input(), "]");
+
+        // A trailing separator is left for whatever comes next.
+        let (digits, new_state) = list_parser.parse(ParserState::new("1,2,]")).unwrap();
+        assert_eq!(digits, vec!['1', '2']);
+        assert_eq!(new_state.input(), ",]");
+
+        assert_eq!(
+            ebnf::text(&list_parser.describe()),
+            "root ::= ([0-9] (\",\" [0-9])*)?\n"
+        );
+    }
+
+    #[test]
+    fn sep_by_b() {
+        // At least one element is required, and a trailing separator is allowed.
+        let list_parser = ParseChar::from_range('a', 'z')
+            .sep_by1(ParseChar::from_char(';'))
+            .allow_trailing();
+
+        let (letters, new_state) = list_parser.parse(ParserState::new("a;b;.")).unwrap();
+        assert_eq!(letters, vec!['a', 'b']);
+        assert_eq!(new_state.input(), ".");
+
+        let error = list_parser.parse(ParserState::new(";")).unwrap_err();
+        assert_eq!(error.offset, 0);
+
+        assert_eq!(
+            ebnf::text(&list_parser.describe()),
+            "root ::= [a-z] (\";\" [a-z])* \";\"?\n"
+        );
+    }
+
+    /// An application's own error type, which records whether the list should stop backtracking.
+    #[derive(Debug)]
+    struct ListError {
+        committed: bool,
+    }
+
+    impl Commit for ListError {
+        fn is_committed(&self) -> bool {
+            self.committed
+        }
+    }
+
+    #[test]
+    fn sep_by_c() {
+        // Elements are `#` and a digit, where the digit is required once the
+        // `#` has been seen, all reported with the application's error type.
+        let to_list_error = |error: ParseError| ListError {
+            committed: error.is_committed(),
+        };
+        let list_parser = ParseChar::from_char('#')
+            .and(ParseChar::from_range('0', '9').cut())
+            .map(|(_, digit)| digit)
+            .map_err(to_list_error)
+            .sep_by(ParseChar::from_char(',').map_err(to_list_error));
+
+        // A separator that isn't followed by an element is left unconsumed.
+        let (digits, new_state) = list_parser.parse(ParserState::new("#1,#2,3")).unwrap();
+        assert_eq!(digits, vec!['1', '2']);
+        assert_eq!(new_state.input(), ",3");
+
+        // An element that went wrong after its start is reported.
+        let error = list_parser.parse(ParserState::new("#1,#x")).unwrap_err();
+        assert!(error.committed);
+    }
+
+    #[test]
+    fn delimited_a() {
+        // Only the contents of the brackets are returned.
+        let list_parser = ParseChar::from_range('0', '9')
+            .sep_by(ParseChar::from_char(','))
+            .delimited_by(ParseChar::from_char('['), ParseChar::from_char(']'));
+
+        let (digits, new_state) = list_parser.parse(ParserState::new("[4,2];")).unwrap();
+        assert_eq!(digits, vec!['4', '2']);
+        assert_eq!(new_state.input(), ";");
+
+        // A missing closing bracket is reported where it should have been.
+        let error = list_parser.parse(ParserState::new("[4,2;")).unwrap_err();
+        assert_eq!(error.offset, 4);
+
+        assert_eq!(
+            ebnf::text(&list_parser.describe()),
+            "root ::= \"[\" ([0-9] (\",\" [0-9])*)? \"]\"\n"
+        );
+    }
+}
+
+mod chains {
+    use crate::parsers::utf8::ParseChar;
+    use crate::parsers::ParserExtensions;
+    use crate::{Commit, ParseError, Parser, ParserState};
+
+    /// A parser for a single digit as a number.
+    fn digit() -> impl Parser<i64, ParseError> {
+        ParseChar::from_range('0', '9').map(|digit| digit.to_digit(10).unwrap() as i64)
+    }
+
+    #[test]
+    fn chain_a() {
+        // Subtraction groups from the left.
+        let subtract = ParseChar::from_char('-').map(|_| |a: i64, b: i64| a - b);
+        let difference_parser = digit().chainl1(subtract);
+        let (difference, new_state) = difference_parser.parse(ParserState::new("9-3-2!")).unwrap();
+        assert_eq!(difference, 4);
+        assert_eq!(new_state.input(), "!");
+
+        // A trailing operator without an operand is left unconsumed.
+        let (difference, new_state) = difference_parser.parse(ParserState::new("9-3-")).unwrap();
+        assert_eq!(difference, 6);
+        assert_eq!(new_state.input(), "-");
+    }
+
+    #[test]
+    fn chain_b() {
+        // Exponents group from the right, and operators can be chosen by the input.
+        let power = ParseChar::from_char('^').map(|_| |a: i64, b: i64| a.pow(b as u32));
+        let (value, _) = digit()
+            .chainr1(power)
+            .parse(ParserState::new("2^3^2"))
+            .unwrap();
+        assert_eq!(value, 512);
+
+        let operator = ParseChar::from_char('+')
+            .or(ParseChar::from_char('*'))
+            .map(|operator| match operator {
+                '+' => (|a, b| a + b) as fn(i64, i64) -> i64,
+                _ => |a, b| a * b,
+            });
+        let (value, _) = digit()
+            .chainl1(operator)
+            .parse(ParserState::new("1+2*3"))
+            .unwrap();
+        assert_eq!(value, 9);
+    }
+
+    /// An application's own error type, which records whether the chain should stop backtracking.
+    #[derive(Debug)]
+    struct ChainError {
+        committed: bool,
+    }
+
+    impl Commit for ChainError {
+        fn is_committed(&self) -> bool {
+            self.committed
+        }
+    }
+
+    #[test]
+    fn chain_c() {
+        // The operator is `**`, which must be finished once its first `*` has
+        // been seen, all reported with the application's error type.
+        let to_chain_error = |error: ParseError| ChainError {
+            committed: error.is_committed(),
+        };
+        let power = ParseChar::from_char('*')
+            .and(ParseChar::from_char('*').cut())
+            .map(|_| |a: i64, b: i64| a.pow(b as u32))
+            .map_err(to_chain_error);
+        let power_parser = digit().map_err(to_chain_error).chainr1(power);
+
+        let (value, new_state) = power_parser.parse(ParserState::new("2**3!")).unwrap();
+        assert_eq!(value, 8);
+        assert_eq!(new_state.input(), "!");
+
+        // A half-written operator is reported instead of ending the chain.
+        let error = power_parser.parse(ParserState::new("2*3")).unwrap_err();
+        assert!(error.committed);
+    }
+}
+
+mod pratt {
+    use crate::parsers::expr::PrattParser;
+    use crate::parsers::utf8::{literal_set, Associativity, ParseChar};
+    use crate::parsers::ParserExtensions;
+    use crate::{Parser, ParserState};
+
+    /// An arithmetic expression parser that writes out how it grouped everything.
+    fn expression() -> PrattParser<String> {
+        PrattParser::new(ParseChar::from_range('a', 'z').map(String::from))
+            .infix(ParseChar::from_char('+'), 1, Associativity::Left, |a, b| {
+                format!("({} + {})", a, b)
+            })
+            .infix(ParseChar::from_char('-'), 1, Associativity::Left, |a, b| {
+                format!("({} - {})", a, b)
+            })
+            .infix(literal_set(&["**"]), 3, Associativity::Right, |a, b| {
+                format!("({} ** {})", a, b)
+            })
+            .infix(ParseChar::from_char('*'), 2, Associativity::Left, |a, b| {
+                format!("({} * {})", a, b)
+            })
+            .prefix(ParseChar::from_char('-'), 4, |a| format!("(-{})", a))
+            .postfix(ParseChar::from_char('!'), 5, |a| format!("({}!)", a))
+    }
+
+    #[test]
+    fn pratt_a() {
+        // Operators group by precedence and associativity.
+        let parser = expression();
+        for (input, grouped) in [
+            ("a+b*c", "(a + (b * c))"),
+            ("a-b-c", "((a - b) - c)"),
+            ("a**b**c", "(a ** (b ** c))"),
+            ("-a*b", "((-a) * b)"),
+            ("-a**b", "((-a) ** b)"),
+            ("a*b!", "(a * (b!))"),
+            ("--a!", "(-(-(a!)))"),
+            ("a", "a"),
+        ] {
+            let (output, new_state) = parser.parse(ParserState::new(input)).unwrap();
+            assert_eq!(output, grouped, "grouping {}", input);
+            assert!(new_state.input().is_empty());
+        }
+    }
+
+    #[test]
+    fn pratt_b() {
+        // Parsing stops at anything that isn't an operator.
+        let parser = expression();
+        let (output, new_state) = parser.parse(ParserState::new("a+b)")).unwrap();
+        assert_eq!(output, "(a + b)");
+        assert_eq!(new_state.input(), ")");
+
+        // Operands after operators are required.
+        let error = parser.parse(ParserState::new("a+")).unwrap_err();
+        assert_eq!(error.offset, 2);
+        assert!(parser.parse(ParserState::new("+a")).is_err());
+    }
+}
+
+mod many_till {
+    use crate::parsers::utf8::{literal_set, ParseChar};
+    use crate::parsers::ParserExtensions;
+    use crate::{Expected, ParseErrorKind, Parser, ParserState};
+
+    #[test]
+    fn many_till_a() {
+        // Statements are single letters followed by `;`, until `end`.
+        let block_parser = ParseChar::from_range('a', 'z')
+            .then_keep_left(ParseChar::from_char(';'))
+            .many_till(literal_set(&["end"]));
+
+        let ((statements, terminator), new_state) =
+            block_parser.parse(ParserState::new("a;b;c;end.")).unwrap();
+        assert_eq!(statements, vec!['a', 'b', 'c']);
+        assert_eq!(terminator, "end");
+        assert_eq!(new_state.input(), ".");
+
+        // The terminator can come straight away.
+        let ((statements, _), _) = block_parser.parse(ParserState::new("end")).unwrap();
+        assert!(statements.is_empty());
+
+        // Neither an element nor the terminator is reported as both expected.
+        let error = block_parser.parse(ParserState::new("a;1")).unwrap_err();
+        assert_eq!(error.offset, 2);
+        match error.kind() {
+            ParseErrorKind::Unexpected { expected, .. } => {
+                assert!(expected.contains(&Expected::from("end")));
+                assert_eq!(expected.len(), 2);
+            }
+            kind => panic!("wrong parsing error: {}", kind),
+        }
+    }
+}
+
+mod sequences {
+    use crate::grammar::Grammar;
+    use crate::parsers::utf8::{ParseChar, ParseSequence};
+    use crate::parsers::ParserExtensions;
+    use crate::{BoxedParser, Parser, ParserState};
+
+    #[test]
+    fn sequence_parser_a() {
+        // Build a parser for a word known only at runtime.
+        let word = String::from("let");
+        let parsers = word
+            .chars()
+            .map(|c| BoxedParser::new(ParseChar::from_char(c)))
+            .collect();
+        let sequence_parser = ParseSequence::new(parsers);
+
+        // The word matches.
+        let (chars, new_state) = sequence_parser.parse(ParserState::new("let x")).unwrap();
+        assert_eq!(chars, vec!['l', 'e', 't']);
+        assert_eq!(new_state.input, String::from(" x"));
+
+        // The word doesn't match.
+        assert!(sequence_parser.parse(ParserState::new("lex")).is_err());
+    }
+
+    #[test]
+    fn tuple_parser_a() {
+        // A tuple of parsers runs them in order and produces a flat tuple.
+        let date_parser = (
+            ParseChar::from_range('0', '9'),
+            ParseChar::from_char('-'),
+            ParseChar::from_range('0', '9'),
+        );
+        let ((first, _, second), new_state) = date_parser.parse(ParserState::new("3-4!")).unwrap();
+        assert_eq!((first, second), ('3', '4'));
+        assert_eq!(new_state.input, String::from("!"));
+
+        // Any element failing fails the whole tuple.
+        assert!(date_parser.parse(ParserState::new("3+4")).is_err());
+        assert_eq!(
+            date_parser.describe(),
+            Grammar::sequence([
+                ParseChar::from_range('0', '9').describe(),
+                ParseChar::from_char('-').describe(),
+                ParseChar::from_range('0', '9').describe(),
+            ])
+        );
+    }
+
+    #[test]
+    fn keep_sides_a() {
+        // Drop the punctuation around an assignment.
+        let assignment_parser = ParseChar::from_range('a', 'z')
+            .then_keep_left(ParseChar::from_char('='))
+            .and(
+                ParseChar::from_char(' ')
+                    .then_keep_right(ParseChar::from_range('0', '9'))
+                    .then_keep_left(ParseChar::from_char(';')),
+            );
+
+        let ((name, value), new_state) = assignment_parser
+            .parse(ParserState::new("x= 4;\n"))
+            .unwrap();
+        assert_eq!((name, value), ('x', '4'));
+        assert_eq!(new_state.input, String::from("\n"));
+
+        // Both sides must still match.
+        assert_eq!(
+            assignment_parser
+                .parse(ParserState::new("x= 4\n"))
+                .unwrap_err()
+                .offset,
+            4
+        );
+    }
+}
+
+mod choice {
+    use crate::parsers::utf8::{literal_set, ParseChar, ParseChoice};
+    use crate::parsers::ParserExtensions;
+    use crate::{Expected, ParseErrorKind, Parser, ParserState};
+
+    #[test]
+    fn choice_parser_a() {
+        // Try a list of keywords built at runtime, in order.
+        let keywords = ["if", "in", "i"];
+        let choice_parser = ParseChoice::new(
+            keywords
+                .iter()
+                .map(|keyword| literal_set(&[keyword]).boxed())
+                .collect(),
+        )
+        .alternative(ParseChar::from_range('0', '9').map(String::from));
+
+        let (keyword, new_state) = choice_parser.parse(ParserState::new("in x")).unwrap();
+        assert_eq!(keyword, "in");
+        assert_eq!(new_state.input(), " x");
+        let (digit, _) = choice_parser.parse(ParserState::new("7")).unwrap();
+        assert_eq!(digit, "7");
+
+        // Everything that was expected is reported.
+        match choice_parser
+            .parse(ParserState::new("x"))
+            .unwrap_err()
+            .kind()
+        {
+            ParseErrorKind::Unexpected { expected, .. } => {
+                assert!(expected.contains(&Expected::from("if")));
+                assert!(expected.contains(&Expected::CharRange(Some('0'), Some('9'))));
+                assert_eq!(expected.len(), 4);
+            }
+            kind => panic!("wrong parsing error: {}", kind),
+        }
+
+        // An empty choice never matches.
+        assert!(ParseChoice::<char>::new(Vec::new())
+            .parse(ParserState::new("a"))
+            .is_err());
+    }
+}
+
+mod recursive {
+    use crate::grammar::ebnf;
+    use crate::parsers::utf8::{left_recursive, recursive, ParseChar, ParseRecursive};
+    use crate::parsers::ParserExtensions;
+    use crate::{ParseError, Parser, ParserState};
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn recursive_a() {
+        // Count how deeply a letter is nested in parentheses.
+        let nested = recursive("nested", |nested| {
+            ParseChar::from_range('a', 'z').map(|_| 0).or(nested
+                .delimited_by(ParseChar::from_char('('), ParseChar::from_char(')'))
+                .map(|depth: usize| depth + 1))
+        });
+
+        let (depth, new_state) = nested.parse(ParserState::new("(((x)))!")).unwrap();
+        assert_eq!(depth, 3);
+        assert_eq!(new_state.input(), "!");
+        assert!(nested.parse(ParserState::new("((x)")).is_err());
+
+        // The rule refers to itself by name.
+        assert_eq!(
+            ebnf::text(&nested.describe()),
+            "nested ::= [a-z] | \"(\" nested \")\"\n"
+        );
+    }
+
+    #[test]
+    fn recursive_b() {
+        // Lists contain values, and values can be lists.
+        let value = ParseRecursive::<usize, ParseError>::declare("value");
+        let list = ParseRecursive::<usize, ParseError>::declare("list");
+        value.define(
+            ParseChar::from_range('0', '9')
+                .map(|_| 1)
+                .or(list.reference()),
+        );
+        list.define(
+            value
+                .reference()
+                .sep_by(ParseChar::from_char(','))
+                .delimited_by(ParseChar::from_char('['), ParseChar::from_char(']'))
+                .map(|counts| counts.into_iter().sum()),
+        );
+
+        let (count, _) = value.parse(ParserState::new("[1,[2,3],[]]")).unwrap();
+        assert_eq!(count, 3);
+
+        // References don't keep their parsers alive.
+        let reference = list.reference();
+        drop(list);
+        let result =
+            panic::catch_unwind(AssertUnwindSafe(|| reference.parse(ParserState::new("[]"))));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn left_recursive_a() {
+        // Subtraction written the natural way, which starts with itself.
+        let number = || {
+            ParseChar::from_range('0', '9').one_or_more().map(|digits| {
+                digits
+                    .into_iter()
+                    .collect::<String>()
+                    .parse::<i32>()
+                    .unwrap()
+            })
+        };
+        let difference = left_recursive("difference", |difference| {
+            difference
+                .then_keep_left(ParseChar::from_char('-'))
+                .and(number())
+                .map(|(a, b)| a - b)
+                .or(number())
+        });
+
+        // The operators group to the left.
+        let (value, new_state) = difference.parse(ParserState::new("10-4-3;")).unwrap();
+        assert_eq!(value, 3);
+        assert_eq!(new_state.input(), ";");
+        let (value, _) = difference.parse(ParserState::new("7")).unwrap();
+        assert_eq!(value, 7);
+
+        // Failures still report what was expected.
+        let error = difference.parse(ParserState::new("x")).unwrap_err();
+        assert_eq!(error.offset(), 0);
+        assert!(difference.parse(ParserState::new("")).is_err());
+    }
+
+    #[test]
+    fn left_recursive_b() {
+        // A definition that panics while its result is being grown.
+        static PANIC: AtomicBool = AtomicBool::new(true);
+        let list = left_recursive("list", |list| {
+            list.then_keep_left(ParseChar::from_char(','))
+                .and(ParseChar::from_char('x'))
+                .map(|(n, _)| {
+                    assert!(!PANIC.load(Ordering::Relaxed), "definition panicked");
+                    n + 1
+                })
+                .or(ParseChar::from_char('x').map(|_| 1))
+        });
+        let state = ParserState::new("x,x,x");
+        let result = panic::catch_unwind(AssertUnwindSafe(|| list.parse(state.clone())));
+        assert!(result.is_err());
+
+        // The seed from the panicked parse doesn't affect the next one.
+        PANIC.store(false, Ordering::Relaxed);
+        assert_eq!(list.parse(state).unwrap().0, 3);
+    }
+}
+
+mod lazy {
+    use crate::parsers::utf8::{lazy, ParseChar};
+    use crate::parsers::ParserExtensions;
+    use crate::{Parser, ParserState};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn lazy_parser_a() {
+        // Count how many times the parser gets built.
+        let builds = AtomicUsize::new(0);
+        let build = || {
+            builds.fetch_add(1, Ordering::Relaxed);
+            ParseChar::from_range('0', '9').one_or_more()
+        };
+
+        // Nothing is built until the parser is used, and then only once.
+        let digits_parser = lazy(build);
+        assert_eq!(builds.load(Ordering::Relaxed), 0);
+        let (digits, _) = digits_parser.parse(ParserState::new("42")).unwrap();
+        assert_eq!(digits, vec!['4', '2']);
+        assert!(digits_parser.parse(ParserState::new("x")).is_err());
+        assert_eq!(builds.load(Ordering::Relaxed), 1);
+
+        // Without caching, it's built for every parse.
+        let digits_parser = lazy(build).uncached();
+        digits_parser.parse(ParserState::new("1")).unwrap();
+        digits_parser.parse(ParserState::new("2")).unwrap();
+        assert_eq!(builds.load(Ordering::Relaxed), 3);
+    }
+}
+
+mod longest_of {
+    use crate::parsers::utf8::{literal_set, ParseChoice, ParseLongestOf};
+    use crate::parsers::ParserExtensions;
+    use crate::{Parser, ParserState};
+
+    #[test]
+    fn longest_of_parser_a() {
+        // Operators listed with the shorter one first.
+        let operators = || vec![literal_set(&[">"]).boxed(), literal_set(&[">="]).boxed()];
+        let longest_parser = ParseLongestOf::new(operators());
+
+        // The longest match wins no matter the order, where a first-match
+        // choice would stop at the shorter operator.
+        let (operator, new_state) = longest_parser.parse(ParserState::new(">=1")).unwrap();
+        assert_eq!(operator, ">=");
+        assert_eq!(new_state.input(), "1");
+        let (operator, _) = ParseChoice::new(operators())
+            .parse(ParserState::new(">=1"))
+            .unwrap();
+        assert_eq!(operator, ">");
+
+        // Ties go to the alternative added first.
+        let tie_parser = longest_parser
+            .alternative(literal_set(&[">"]).map(|_| String::from("second")))
+            .alternative(literal_set(&["<"]));
+        let (operator, _) = tie_parser.parse(ParserState::new(">1")).unwrap();
+        assert_eq!(operator, ">");
+        assert!(tie_parser.parse(ParserState::new("=")).is_err());
+    }
+}
+
+mod all_of {
+    use crate::parsers::utf8::{literal_set, ParseAllOf, ParseChar};
+    use crate::parsers::ParserExtensions;
+    use crate::{ParseErrorKind, Parser, ParserState};
+
+    #[test]
+    fn all_of_parser_a() {
+        // "ab" can be read as one word or as a letter followed by more.
+        let reading_parser = ParseAllOf::new(vec![
+            literal_set(&["ab"]).boxed(),
+            ParseChar::from_char('a').map(String::from).boxed(),
+        ])
+        .alternative(literal_set(&["x"]));
+
+        // Every interpretation is returned with where it stopped.
+        let readings = reading_parser.parse_all(ParserState::new("abc")).unwrap();
+        let readings = readings
+            .iter()
+            .map(|(output, state)| (output.as_str(), state.index))
+            .collect::<Vec<_>>();
+        assert_eq!(readings, vec![("ab", 2), ("a", 1)]);
+
+        // As an ordinary parser, one interpretation is fine and more than one
+        // is ambiguous.
+        let (output, _) = reading_parser.parse(ParserState::new("x")).unwrap();
+        assert_eq!(output, "x");
+        let error = reading_parser.parse(ParserState::new("abc")).unwrap_err();
+        assert_eq!(error.kind(), &ParseErrorKind::Ambiguous);
+        assert!(reading_parser.parse_all(ParserState::new("z")).is_err());
+    }
+
+    #[test]
+    fn all_of_parser_b() {
+        // "ab" followed by the rest, where the split isn't decided until the
+        // whole input has been read.
+        let word = || {
+            ParseAllOf::new(vec![
+                literal_set(&["ab"]).boxed(),
+                ParseChar::from_char('a').map(String::from).boxed(),
+            ])
+        };
+        let nested_parser = word().and(literal_set(&["c", "bc"]));
+
+        // As an ordinary parser, the first word is already ambiguous.
+        let error = nested_parser.parse(ParserState::new("abc")).unwrap_err();
+        assert_eq!(error.kind(), &ParseErrorKind::Ambiguous);
+
+        // Listing every interpretation lists every split.
+        let readings = nested_parser.parse_all(ParserState::new("abc")).unwrap();
+        let readings = readings
+            .iter()
+            .map(|((a, b), state)| (a.as_str(), b.as_str(), state.index))
+            .collect::<Vec<_>>();
+        assert_eq!(readings, vec![("ab", "c", 3), ("a", "bc", 3)]);
+
+        // Interpretations pass through `map` and sequences too, and only the
+        // ones that the rest of the sequence accepts are kept.
+        let joined_parser =
+            (word(), word(), ParseChar::from_char('!')).map(|(a, b, _)| format!("{}+{}", a, b));
+        let readings = joined_parser.parse_all(ParserState::new("aab!")).unwrap();
+        let readings = readings
+            .into_iter()
+            .map(|(output, _)| output)
+            .collect::<Vec<_>>();
+        assert_eq!(readings, vec![String::from("a+ab")]);
+        assert!(joined_parser.parse_all(ParserState::new("ab")).is_err());
+    }
+}
+
+mod permutation {
+    use crate::parsers::utf8::{literal_set, ParsePermutation, ParseStr};
+    use crate::{Expected, ParseErrorKind, Parser, ParserState};
+
+    #[test]
+    fn permutation_parser_a() {
+        // Flags that may be written in any order, where `b` may be left out.
+        let flags_parser = ParsePermutation::new()
+            .required(literal_set(&["a"]))
+            .optional(literal_set(&["b"]))
+            .required(literal_set(&["c"]));
+
+        // The values come back in the order the elements were added.
+        let (flags, new_state) = flags_parser.parse(ParserState::new("cba!")).unwrap();
+        assert_eq!(
+            flags,
+            vec![
+                Some(String::from("a")),
+                Some(String::from("b")),
+                Some(String::from("c"))
+            ]
+        );
+        assert_eq!(new_state.input(), "!");
+
+        // Optional elements can be left out, and elements aren't repeated.
+        let (flags, new_state) = flags_parser.parse(ParserState::new("aca")).unwrap();
+        assert_eq!(
+            flags,
+            vec![Some(String::from("a")), None, Some(String::from("c"))]
+        );
+        assert_eq!(new_state.input(), "a");
+
+        // A missing required element is reported where it should have been.
+        let error = flags_parser.parse(ParserState::new("ba!")).unwrap_err();
+        assert_eq!(error.offset(), 2);
+        match error.kind() {
+            ParseErrorKind::Unexpected { expected, .. } => {
+                assert_eq!(
+                    expected.iter().collect::<Vec<_>>(),
+                    vec![&Expected::from("c")]
+                );
+            }
+            kind => panic!("wrong parsing error: {:?}", kind),
+        }
+        assert_eq!(error.notes(), [String::from("missing required `\"c\"`")]);
+    }
+
+    #[test]
+    fn permutation_parser_b() {
+        // Elements where taking the first match leaves nothing for the rest.
+        let words_parser = ParsePermutation::new()
+            .required(ParseStr::new("a"))
+            .required(ParseStr::new("ab"));
+
+        // Taking `a` first leaves `ba`, which neither element matches, so the
+        // permutation backtracks and takes `ab` and then `a`.
+        let (words, new_state) = words_parser.parse(ParserState::new("aba")).unwrap();
+        assert_eq!(
+            words,
+            vec![Some(String::from("a")), Some(String::from("ab"))]
+        );
+        assert!(new_state.input().is_empty());
+
+        // No order works, so the error comes from the one that got furthest.
+        let error = words_parser.parse(ParserState::new("abb")).unwrap_err();
+        assert_eq!(error.offset(), 2);
+    }
+}
+
+mod dispatch {
+    use crate::parsers::utf8::{ParseChar, ParseDispatch};
+    use crate::parsers::ParserExtensions;
+    use crate::{ParseError, Parser, ParserState};
+    use std::convert::Infallible;
+
+    /// Create a parser that classifies a value by its first character.
+    fn value_parser() -> ParseDispatch<&'static str> {
+        ParseDispatch::new()
+            .on(
+                '"',
+                ParseChar::from_char('"').try_map(|_| Ok::<_, Infallible>("string")),
+            )
+            .on(
+                '[',
+                ParseChar::from_char('[').try_map(|_| Ok::<_, Infallible>("array")),
+            )
+    }
+
+    #[test]
+    fn dispatch_parser_a() {
+        // Each registered character jumps to its own branch.
+        let (kind, _) = value_parser().parse(ParserState::new("\"hi\"")).unwrap();
+        assert_eq!(kind, "string");
+        let (kind, new_state) = value_parser().parse(ParserState::new("[1]")).unwrap();
+        assert_eq!(kind, "array");
+        assert_eq!(new_state.input, String::from("1]"));
+
+        // Without a default, other characters are rejected.
+        assert_eq!(
+            value_parser().parse(ParserState::new("1")).unwrap_err(),
+            ParseError::unexpected(0, ['"', '['], Some("1"))
+        );
+    }
+
+    #[test]
+    fn dispatch_parser_b() {
+        // With a default, other characters fall through to it.
+        let dispatch_parser = value_parser()
+            .otherwise(ParseChar::from_range('0', '9').try_map(|_| Ok::<_, Infallible>("number")));
+        let (kind, _) = dispatch_parser.parse(ParserState::new("1")).unwrap();
+        assert_eq!(kind, "number");
+    }
+}
+
+mod diagnostics {
+    use crate::diagnostics::{ColorChoice, Diagnostic};
+    use crate::lines::LineIndex;
+    use crate::parsers::utf8::ParseChar;
+    use crate::parsers::ParserExtensions;
+    use crate::{Parser, ParserState};
+
+    #[test]
+    fn label_parser_a() {
+        // Create a parser for a quoted word with a label and help.
+        let string_parser = ParseChar::from_char('"')
+            .and(ParseChar::from_range('a', 'z').at_least(0))
+            .and(ParseChar::from_char('"'))
+            .label("string")
+            .help("strings must be terminated with a closing quote");
+
+        // A missing string is reported by name.
+        let error = string_parser.parse(ParserState::new("x")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "expected string found x\nhelp: strings must be terminated with a closing quote\n"
+        );
+
+        // An unterminated string keeps the specific error but still gets the
+        // help attached.
+        let error = string_parser.parse(ParserState::new("\"abc")).unwrap_err();
+        assert_eq!(error.offset(), 4);
+        assert_eq!(error.help().len(), 1);
+    }
+
+    #[test]
+    fn diagnostic_a() {
+        // Parse the second line of some input and fail partway through it.
+        let source = "first line\nlet\tx = 1;\n";
+        let number_parser = ParseChar::from_range('0', '9')
+            .label("number")
+            .note("values must be numeric");
+        let error = number_parser
+            .parse(ParserState::new_offset(&source[15..], 15))
+            .unwrap_err();
+
+        // The report should point at the 'x' on the second line.
+        assert_eq!(
+            Diagnostic::new(&error, source)
+                .with_name("main.txt")
+                .to_string(),
+            "error: expected number found x\n \
+             --> main.txt:2:5\n  \
+             |\n\
+             2 | let\tx = 1;\n  \
+             |    \t^\n  \
+             = note: values must be numeric\n"
+        );
+
+        // Reports reusing a line index are the same.
+        let lines = LineIndex::new(source);
+        assert_eq!(
+            Diagnostic::with_lines(&error, &lines).to_string(),
+            Diagnostic::new(&error, source).to_string()
+        );
+    }
+
+    #[test]
+    fn diagnostic_b() {
+        // Fail to parse a digit.
+        let source = "x";
+        let error = ParseChar::from_range('0', '9')
+            .parse(ParserState::new(source))
+            .unwrap_err();
+
+        // Without colors, the report is plain text.
+        let plain = Diagnostic::new(&error, source).with_color(ColorChoice::Never);
+        assert_eq!(
+            plain.to_string(),
+            Diagnostic::new(&error, source).to_string()
+        );
+        assert!(!plain.to_string().contains('\x1b'));
+
+        // With colors, the error is red and the gutter is dimmed.
+        let colored = Diagnostic::new(&error, source)
+            .with_color(ColorChoice::Always)
+            .to_string();
+        assert!(colored.starts_with("\x1b[1;31merror\x1b[0m"));
+        assert!(colored.contains("\x1b[2m1 |\x1b[0m x\n"));
+        assert!(colored.contains("\x1b[31m^\x1b[0m"));
+    }
+}
+
+mod messages {
+    use crate::diagnostics::Diagnostic;
+    use crate::messages::{MessageProvider, ReportLabel};
+    use crate::{Expected, Found, ParseError};
+    use std::collections::BTreeSet;
+
+    /// Replaces some of the messages, leaving the rest as they are.
+    #[derive(Debug)]
+    struct Spanish;
+
+    impl MessageProvider for Spanish {
+        fn unexpected(&self, expected: &BTreeSet<Expected>, found: Option<&Found>) -> String {
+            let expected = expected
+                .iter()
+                .map(|item| self.expected(item))
+                .collect::<Vec<String>>()
+                .join(" o ");
+            match found {
+                Some(found) => format!("se esperaba {} pero se encontró {}", expected, found),
+                None => format!("se esperaba {}", expected),
+            }
+        }
+
+        fn expected(&self, expected: &Expected) -> String {
+            match expected {
+                Expected::CharRange(Some('0'), Some('9')) => String::from("un dígito"),
+                other => format!("«{}»", other),
+            }
+        }
+
+        fn label(&self, label: ReportLabel) -> String {
+            String::from(match label {
+                ReportLabel::Error => "error",
+                ReportLabel::Note => "nota",
+                ReportLabel::Help => "ayuda",
+            })
+        }
+    }
+
+    #[test]
+    fn messages_a() {
+        let error = ParseError::unexpected(
+            0,
+            [
+                Expected::CharRange(Some('0'), Some('9')),
+                Expected::from('-'),
+            ],
+            Some("x"),
+        )
+        .with_help("los números pueden empezar con un signo");
+
+        // The standard messages are unchanged.
+        assert_eq!(
+            error.to_string(),
+            "expected one of `-` or `0..9` found x\nhelp: los números pueden empezar con un signo\n"
+        );
+
+        // Errors and reports can use other wording.
+        assert_eq!(
+            error.display_with(&Spanish).to_string(),
+            "se esperaba «-» o un dígito pero se encontró x\n\
+             ayuda: los números pueden empezar con un signo\n"
+        );
+        assert_eq!(
+            Diagnostic::new(&error, "x")
+                .with_messages(&Spanish)
+                .to_string(),
+            "error: se esperaba «-» o un dígito pero se encontró x\n \
+             --> input:1:1\n  \
+             |\n\
+             1 | x\n  \
+             | ^\n  \
+             = ayuda: los números pueden empezar con un signo\n"
+        );
+    }
+}
+
+mod expected_sets {
+    use crate::parsers::utf8::ParseChar;
+    use crate::parsers::ParserExtensions;
+    use crate::{Expected, Parser, ParserState};
+
+    #[test]
+    fn expected_set_a() {
+        // Create an alternation where two alternatives expect the same
+        // thing.
+        let punctuation_parser = ParseChar::from_char(',')
+            .label("comma")
+            .xor(ParseChar::from_char(')').label("closing parenthesis"))
+            .xor(ParseChar::from_char(',').label("comma"));
+
+        // The expected items are deduplicated and sorted.
+        let error = punctuation_parser.parse(ParserState::new("x")).unwrap_err();
+        assert_eq!(
+            error.expected().iter().collect::<Vec<_>>(),
+            [
+                &Expected::from("closing parenthesis"),
+                &Expected::from("comma")
+            ]
+        );
+        assert_eq!(
+            error.to_string(),
+            "expected one of `closing parenthesis` or `comma` found x\n"
+        );
+    }
+
+    #[test]
+    fn expected_set_b() {
+        // Create a large alternation.
+        let letter_parser = "abcdefghij"
+            .chars()
+            .map(|c| ParseChar::from_char(c).label(&c.to_string()))
+            .map(|p| p.boxed())
+            .reduce(|a, b| a.xor(b).boxed())
+            .unwrap();
+
+        // The message is capped but the full set is still available.
+        let error = letter_parser.parse(ParserState::new("z")).unwrap_err();
+        assert_eq!(error.expected().len(), 10);
+        assert_eq!(
+            error.to_string(),
+            "expected one of `a`, `b`, `c`, `d`, `e`, or 5 more found z\n"
+        );
+    }
+}
+
+mod errors {
+    use crate::parsers::utf8::ParseChar;
+    use crate::parsers::ParserExtensions;
+    use crate::{Expected, ParseError, Parser, ParserState};
+
+    #[test]
+    fn error_accessors_a() {
+        // Errors can be compared and inspected directly.
+        let error = ParseChar::from_range('a', 'z')
+            .parse(ParserState::new_offset("1", 3))
+            .unwrap_err();
+        let range = Expected::CharRange(Some('a'), Some('z'));
+        assert_eq!(error, ParseError::unexpected(3, [range.clone()], Some("1")));
+        assert_eq!(error.offset(), 3);
+        assert_eq!(error.found().as_deref(), Some("1"));
+        assert!(error.expected().contains(&range));
+        assert_ne!(error, ParseError::unexpected(3, [range], None));
+        assert_eq!(error.to_string(), "expected a..z found 1\n");
+    }
+
+    #[test]
+    fn partial_a() {
+        // A failed parse reports the state where it stopped, from which
+        // parsing can carry on.
+        let digits = ParseChar::from_range('0', '9').at_least(1);
+        let (result, failed_state) = (&digits)
+            .and(ParseChar::from_char(';'))
+            .parse_partial(ParserState::new_offset("12x;34", 10));
+        assert_eq!(result.unwrap_err().offset(), 12);
+        assert_eq!(failed_state.index, 12);
+        assert_eq!(failed_state.input(), "x;34");
+
+        let resumed = failed_state.at(14).unwrap();
+        assert_eq!(digits.parse(resumed).unwrap().0, vec!['3', '4']);
+
+        // States can't move backwards or into the middle of a character.
+        let state = ParserState::new("\u{e9}a");
+        assert_eq!(state.at(1), None);
+        assert_eq!(state.at(2).unwrap().input(), "a");
+        assert_eq!(state.at(4), None);
+        assert_eq!(state.at(3).unwrap().at(2), None);
+    }
+}
+
+mod verbosity {
+    use crate::parsers::utf8::ParseChar;
+    use crate::parsers::ParserExtensions;
+    use crate::{ErrorVerbosity, ParseErrorKind, Parser, ParserState};
+
+    #[test]
+    fn fast_errors_a() {
+        // Create a labelled digit parser.
+        let digit_parser = ParseChar::from_range('0', '9').label("digit");
+
+        // Fast errors only record the kind and position.
+        let state = ParserState::new("x").with_verbosity(ErrorVerbosity::Fast);
+        let error = digit_parser.parse(state).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ParseErrorKind::Unexpected { expected, found: None } if expected.is_empty()
+        ));
+        assert_eq!(error.offset(), 0);
+
+        // Explaining the failure parses again with rich errors.
+        let error = digit_parser
+            .parse_or_explain(ParserState::new("x"))
+            .unwrap_err();
+        assert_eq!(error.to_string(), "expected digit found x\n");
+
+        // A successful parse keeps the original verbosity.
+        let (_, new_state) = digit_parser
+            .parse_or_explain(ParserState::new("1"))
+            .unwrap();
+        assert_eq!(new_state.verbosity(), ErrorVerbosity::Rich);
+    }
+}
+
+mod slices {
+    use crate::parsers::utf8::ParseChar;
+    use crate::parsers::ParserExtensions;
+    use crate::{Parser, ParserState};
+    use std::convert::Infallible;
+
+    #[test]
+    fn with_slice_parser_a() {
+        // Create a parser for a number that keeps its source text.
+        let number_parser = ParseChar::from_char('-')
+            .optional()
+            .and(ParseChar::from_range('0', '9').one_or_more())
+            .try_map(|(sign, digits)| {
+                let value = digits.into_iter().fold(0, |value, digit| {
+                    value * 10 + digit.to_digit(10).unwrap() as i32
+                });
+                Ok::<_, Infallible>(if sign.is_empty() { value } else { -value })
+            })
+            .with_slice();
+
+        // Both the value and the exact text are returned.
+        let ((value, text), new_state) =
+            number_parser.parse(ParserState::new("-042 rest")).unwrap();
+        assert_eq!(value, -42);
+        assert_eq!(text, "-042");
+        assert_eq!(new_state.input, String::from(" rest"));
+    }
+
+    #[test]
+    fn recognize_parser_a() {
+        // Capture an identifier as a string without collecting its characters.
+        let identifier_parser = ParseChar::from_range('a', 'z')
+            .and(
+                ParseChar::from_range('a', 'z')
+                    .or(ParseChar::from_range('0', '9'))
+                    .at_least(0),
+            )
+            .recognize();
+
+        let (name, new_state) = identifier_parser
+            .parse(ParserState::new_offset("x2y = 1", 10))
+            .unwrap();
+        assert_eq!(name, "x2y");
+        assert_eq!(new_state.input, String::from(" = 1"));
+        assert_eq!(new_state.index, 13);
+        assert!(identifier_parser.parse(ParserState::new("2x")).is_err());
+    }
+}
+
+mod spans {
+    use crate::parsers::utf8::ParseChar;
+    use crate::parsers::ParserExtensions;
+    use crate::span::{Span, Spanned};
+    use crate::{ParseError, Parser, ParserState};
+
+    #[test]
+    fn spanned_parser_a() {
+        // Create a parser for a word that records where it was found.
+        let word_parser = ParseChar::from_range('a', 'z').one_or_more().spanned();
+
+        // Skip the leading space and parse the word.
+        let (word, _) = word_parser
+            .parse(ParserState::new_offset("word!", 1))
+            .unwrap();
+        assert_eq!(word.span, Span::new(1, 5));
+        assert_eq!(word.len(), 4);
+
+        // The helpers keep the span.
+        let text = word.map(|chars| chars.into_iter().collect::<String>());
+        assert_eq!(text.as_ref().value, "word");
+        assert_eq!(text.to_string(), "word");
+        assert_eq!(text, Spanned::new(String::from("word"), Span::new(1, 5)));
+    }
+
+    #[test]
+    fn error_span_a() {
+        // Invalid values cover the whole value, other errors a single point.
+        assert_eq!(ParseError::invalid(2, 6, "too big").span(), Span::new(2, 6));
+        assert_eq!(ParseError::ambiguous(3).span(), Span::new(3, 3));
+    }
+}
+
+mod lines {
+    use crate::lines::{parse_lines, LineIndex, LineOptions};
+    use crate::parsers::utf8::ParseChar;
+    use crate::parsers::ParserExtensions;
+    use crate::ParseError;
+
+    #[test]
+    fn lines_a() {
+        // Create a parser for a line of digits.
+        let digits_parser = ParseChar::from_range('0', '9').one_or_more();
+        let input = "12\r\n# comment\n\n3x\n45";
+
+        // Parse each line, skipping comments and blank lines.
+        let results = parse_lines(
+            &digits_parser,
+            input,
+            LineOptions::new().skip_blank().skip_comments("#"),
+        )
+        .collect::<Vec<_>>();
+
+        // The bad line is reported with its line number and an offset into
+        // the whole input.
+        assert_eq!(
+            results,
+            vec![
+                Ok(vec!['1', '2']),
+                Err((4, ParseError::trailing_input(16, "x"))),
+                Ok(vec!['4', '5']),
+            ]
+        );
+    }
+
+    #[test]
+    fn line_index_a() {
+        // Columns count characters, and line endings aren't part of lines.
+        let lines = LineIndex::new("ab\r\n\u{e9}x\n\nend");
+        assert_eq!(lines.line_count(), 4);
+        assert_eq!(lines.line(1), Some("ab"));
+        assert_eq!(lines.line(2), Some("\u{e9}x"));
+        assert_eq!(lines.line(3), Some(""));
+        assert_eq!(lines.line(5), None);
+        assert_eq!(lines.line_column(0), (1, 1));
+        assert_eq!(lines.line_column(2), (1, 3));
+        assert_eq!(lines.line_column(6), (2, 2));
+        assert_eq!(lines.line_column(7), (2, 3));
+        assert_eq!(lines.line_column(8), (3, 1));
+        assert_eq!(lines.line_column(9), (4, 1));
+        assert_eq!(lines.line_column(100), (4, 4));
+
+        // Indices inside a character move back to its start.
+        assert_eq!(lines.line_column(5), (2, 1));
+
+        // Line and column numbers convert back to the same indices.
+        for index in [0, 1, 2, 4, 6, 7, 8, 9, 12] {
+            let (line, column) = lines.line_column(index);
+            assert_eq!(lines.index(line, column), Some(index));
+        }
+        assert_eq!(lines.index(2, 4), None);
+        assert_eq!(lines.index(0, 1), None);
+    }
+
+    #[test]
+    fn line_index_b() {
+        // Columns on long lines mixing ASCII and other characters agree with
+        // counting the characters directly.
+        let source = format!("first\n{}\nlast", "ab\u{e9}\u{1f600}c".repeat(100));
+        let lines = LineIndex::new(&source);
+        for (index, _) in source
+            .char_indices()
+            .skip(6)
+            .take_while(|&(index, _)| index < 906)
+        {
+            let column = source[6..index].chars().count() + 1;
+            assert_eq!(lines.line_column(index), (2, column));
+            assert_eq!(lines.index(2, column), Some(index));
+        }
+        assert_eq!(lines.line_column(906), (2, 501));
+        assert_eq!(lines.index(2, 501), Some(906));
+        assert_eq!(lines.index(2, 502), None);
+        assert_eq!(lines.line_column(source.len()), (3, 5));
+        assert_eq!(lines.index(3, 5), Some(source.len()));
+        assert_eq!(lines.index(3, 6), None);
+    }
+}
+
+mod lsp {
+    use crate::lsp::{self, Position, Range};
+    use crate::span::Span;
+
+    #[test]
+    fn lsp_a() {
+        // Characters outside the BMP count as two UTF-16 code units, and all
+        // three kinds of line ending start a new line.
+        let source = "a\u{1F600}b\r\nc\u{e9}d\re\nf";
+        assert_eq!(lsp::position(source, 0), Position::new(0, 0));
+        assert_eq!(lsp::position(source, 5), Position::new(0, 3));
+        assert_eq!(lsp::position(source, 6), Position::new(0, 4));
+        assert_eq!(lsp::position(source, 8), Position::new(1, 0));
+        assert_eq!(lsp::position(source, 11), Position::new(1, 2));
+        assert_eq!(lsp::position(source, 13), Position::new(2, 0));
+        assert_eq!(lsp::position(source, 15), Position::new(3, 0));
+
+        // Indices inside a character move back to its start, and indices past
+        // the end are the end.
+        assert_eq!(lsp::position(source, 3), Position::new(0, 1));
+        assert_eq!(lsp::position(source, 100), Position::new(3, 1));
+    }
+
+    #[test]
+    fn lsp_b() {
+        // Positions convert back to the same indices.
+        let source = "a\u{1F600}b\r\nc\u{e9}d\re\nf";
+        for index in [0, 1, 5, 6, 8, 9, 11, 12, 13, 15, 16] {
+            assert_eq!(lsp::index(source, lsp::position(source, index)), index);
+        }
+
+        // Positions inside a surrogate pair move back, and positions past the
+        // end of a line or the source are clamped.
+        assert_eq!(lsp::index(source, Position::new(0, 2)), 1);
+        assert_eq!(lsp::index(source, Position::new(1, 10)), 12);
+        assert_eq!(lsp::index(source, Position::new(9, 0)), source.len());
+
+        let range = Range::new(Position::new(0, 1), Position::new(1, 1));
+        assert_eq!(lsp::range(source, Span::new(1, 9)), range);
+        assert_eq!(lsp::span(source, range), Span::new(1, 9));
+    }
+
+    #[cfg(feature = "lsp")]
+    #[test]
+    fn lsp_c() {
+        use crate::lsp::LspDiagnostic;
+        use crate::parsers::utf8::ParseChar;
+        use crate::parsers::ParserExtensions;
+        use crate::{Parser, ParserState};
+
+        // An error after an emoji on the second line.
+        let source = "\n\u{1F600}x";
+        let error = ParseChar::from_range('0', '9')
+            .label("number")
+            .help("try \"1\"")
+            .parse(ParserState::new_offset(&source[5..], 5))
+            .unwrap_err();
+
+        assert_eq!(
+            LspDiagnostic::new(&error, source)
+                .with_source("numbers")
+                .to_json(),
+            "{\"range\":{\"start\":{\"line\":1,\"character\":2},\"end\":{\"line\":1,\"character\":2}},\
+             \"severity\":1,\"source\":\"numbers\",\"message\":\"expected number found x\\nhelp: try \\\"1\\\"\"}"
+        );
+    }
+}
+
+mod http {
+    use crate::formats::http::*;
+    use crate::{ParseError, Parser, ParserState};
+
+    #[test]
+    fn request_line_a() {
+        // Parse a typical request line.
+        let (request, new_state) = ParseRequestLine
+            .parse(ParserState::new(
+                "GET /index.html?q=1 HTTP/1.1\r\nHost: x\r\n",
+            ))
+            .unwrap();
+        assert_eq!(
+            request,
+            RequestLine {
+                method: String::from("GET"),
+                target: String::from("/index.html?q=1"),
+                version: HttpVersion { major: 1, minor: 1 },
+            }
+        );
+        assert_eq!(new_state.input, String::from("Host: x\r\n"));
+
+        // A malformed version is reported where it goes wrong.
+        assert_eq!(
+            ParseRequestLine
+                .parse(ParserState::new("GET / HTTP/x.1\r\n"))
+                .unwrap_err(),
+            ParseError::unexpected(11, ["digit"], Some("x"))
+        );
+    }
+
+    #[test]
+    fn status_line_a() {
+        // Parse a status line with a non-ASCII reason phrase.
+        let (status, _) = ParseStatusLine
+            .parse(ParserState::new("HTTP/1.0 404 Não Encontrado\n"))
+            .unwrap();
+        assert_eq!(status.status, 404);
+        assert_eq!(status.reason, "Não Encontrado");
+    }
+
+    #[test]
+    fn headers_a() {
+        // Parse a header block with folding and padding.
+        let (headers, new_state) = ParseHeaders
+            .parse(ParserState::new(
+                "Content-Type:  text/html; charset=\"utf-8\"  \r\nX-Long: a\r\n \t b\r\n\r\nbody",
+            ))
+            .unwrap();
+        assert_eq!(
+            headers,
+            vec![
+                Header {
+                    name: String::from("Content-Type"),
+                    value: String::from("text/html; charset=\"utf-8\""),
+                },
+                Header {
+                    name: String::from("X-Long"),
+                    value: String::from("a b"),
+                },
+            ]
+        );
+        assert_eq!(new_state.input, String::from("body"));
+    }
+
+    #[test]
+    fn parameters_a() {
+        // Parse parameters with both tokens and quoted strings.
+        let (parameters, new_state) = ParseParameters
+            .parse(ParserState::new("; charset=\"a \\\"b\\\"\" ;q=0.5,"))
+            .unwrap();
+        assert_eq!(
+            parameters,
+            vec![
+                (String::from("charset"), String::from("a \"b\"")),
+                (String::from("q"), String::from("0.5")),
+            ]
+        );
+        assert_eq!(new_state.input, String::from(","));
+    }
+}
+
+mod colors {
+    use crate::formats::color::*;
+    use crate::{ParseError, Parser, ParserState};
+
+    fn color(input: &str) -> Result<Rgba, ParseError> {
+        ParseColor.parse(ParserState::new(input)).map(|(c, _)| c)
+    }
+
+    #[test]
+    fn color_a() {
+        // Hex colors in each length.
+        assert_eq!(color("#f80"), Ok(Rgba::rgb(255, 136, 0)));
+        assert_eq!(color("#F808"), Ok(Rgba::new(255, 136, 0, 136)));
+        assert_eq!(color("#ff8800"), Ok(Rgba::rgb(255, 136, 0)));
+        assert_eq!(color("#ff880080"), Ok(Rgba::new(255, 136, 0, 128)));
+        assert_eq!(color("#ff88"), Ok(Rgba::new(255, 255, 136, 136)),);
+        assert_eq!(
+            color("#ff88000"),
+            Err(ParseError::invalid(
+                0,
+                8,
+                "hex colors must have 3, 4, 6, or 8 digits"
+            ))
+        );
+    }
+
+    #[test]
+    fn color_b() {
+        // Functional notation, with percentages and alpha.
+        assert_eq!(color("rgb(255, 136, 0)"), Ok(Rgba::rgb(255, 136, 0)));
+        assert_eq!(
+            color("RGBA( 100%,0%,0% , 0.5 )"),
+            Ok(Rgba::new(255, 0, 0, 128))
+        );
+        assert_eq!(color("hsl(120, 100%, 50%)"), Ok(Rgba::rgb(0, 255, 0)));
+        assert_eq!(
+            color("hsla(240, 100%, 25%, 50%)"),
+            Ok(Rgba::new(0, 0, 128, 128))
+        );
+
+        // Out of range values are reported on the argument.
+        assert_eq!(
+            color("rgb(0, 300, 0)"),
+            Err(ParseError::invalid(7, 10, "green is out of range"))
+        );
+        assert_eq!(
+            color("hsl(50%, 1%, 1%)"),
+            Err(ParseError::invalid(4, 7, "hue must be a number of degrees"))
+        );
+    }
+}
+
+mod cron {
+    use crate::formats::cron::*;
+    use crate::{ParseError, Parser, ParserState};
+    use std::collections::BTreeSet;
+
+    fn set(values: &[u8]) -> BTreeSet<u8> {
+        values.iter().copied().collect()
+    }
+
+    #[test]
+    fn cron_a() {
+        // A 5 field schedule with lists, ranges, steps, and names.
+        let (schedule, _) = ParseCron
+            .parse(ParserState::new("*/15 9-17/4 1,15 jan-MAR MON-FRI"))
+            .unwrap();
+        assert_eq!(schedule.seconds, None);
+        assert_eq!(schedule.minutes, set(&[0, 15, 30, 45]));
+        assert_eq!(schedule.hours, set(&[9, 13, 17]));
+        assert_eq!(schedule.days_of_month, set(&[1, 15]));
+        assert_eq!(schedule.months, set(&[1, 2, 3]));
+        assert_eq!(schedule.days_of_week, set(&[1, 2, 3, 4, 5]));
+
+        // A 6 field schedule, with Sunday written as 7.
+        let (schedule, _) = ParseCron
+            .parse(ParserState::new("30 0 12 ? * 5/2"))
+            .unwrap();
+        assert_eq!(schedule.seconds, Some(set(&[30])));
+        assert_eq!(schedule.days_of_month.len(), 31);
+        assert_eq!(schedule.days_of_week, set(&[0, 5]));
+    }
+
+    #[test]
+    fn cron_b() {
+        // Errors point at the offending part of the field.
+        let error = |input| ParseCron.parse(ParserState::new(input)).unwrap_err();
+        assert_eq!(
+            error("0 24 * * *"),
+            ParseError::invalid(2, 4, "hour must be between 0 and 23")
+        );
+        assert_eq!(
+            error("0 0 * FOO *"),
+            ParseError::unexpected(
+                6,
+                [
+                    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV",
+                    "DEC"
+                ],
+                Some("FOO")
+            )
+        );
+        assert_eq!(
+            error("5-1 * * * *"),
+            ParseError::invalid(0, 3, "minute range starts after it ends")
+        );
+        assert_eq!(
+            error("* * *").kind(),
+            ParseError::wrong_count(0, 5, 6, 3).kind()
+        );
+    }
+}
+
+mod args {
+    use crate::formats::args::*;
+    use crate::{ParseError, Parser, ParserState};
+
+    fn split(style: ArgumentStyle, input: &str) -> Result<Vec<String>, ParseError> {
+        ParseArguments::new(style)
+            .parse(ParserState::new(input))
+            .map(|(args, _)| args)
+    }
+
+    #[test]
+    fn args_a() {
+        // POSIX quoting and escaping.
+        assert_eq!(
+            split(
+                ArgumentStyle::Posix,
+                r#"  cp 'a b'\ c "d \"e\" \n" '' f\\g "#
+            ),
+            Ok(vec![
+                String::from("cp"),
+                String::from("a b c"),
+                String::from("d \"e\" \\n"),
+                String::from(""),
+                String::from("f\\g"),
+            ])
+        );
+
+        // Unterminated quotes are errors at the end of the input.
+        assert_eq!(
+            split(ArgumentStyle::Posix, "echo 'oops"),
+            Err(ParseError::unexpected(10, ["'"], None)
+                .with_note("the quoted argument starts at offset 5"))
+        );
+    }
+
+    #[test]
+    fn args_b() {
+        // Windows backslash and quote rules.
+        assert_eq!(
+            split(
+                ArgumentStyle::Windows,
+                r#"C:\dir\app.exe "a b" a\\\"b "c\\" "say ""hi""" """#
+            ),
+            Ok(vec![
+                String::from(r"C:\dir\app.exe"),
+                String::from("a b"),
+                String::from(r#"a\"b"#),
+                String::from(r"c\"),
+                String::from(r#"say "hi""#),
+                String::from(""),
+            ])
+        );
+    }
+}
+
+mod numbers {
+    use crate::formats::numbers::*;
+    use crate::{ParseError, Parser, ParserState};
+
+    #[test]
+    fn numbers_a() {
+        // Underscores are allowed by default.
+        let (number, new_state) = ParseLocaleInt::new(NumberFormat::new())
+            .parse(ParserState::new("-1_000_000.5"))
+            .unwrap();
+        assert_eq!(number, -1_000_000);
+        assert_eq!(new_state.input, String::from(".5"));
+        assert_eq!(
+            ParseLocaleInt::new(NumberFormat::new())
+                .parse(ParserState::new("1__0"))
+                .unwrap_err(),
+            ParseError::invalid(1, 2, "digit separators must be between digits")
+        );
+
+        // European grouping and decimal separators.
+        let european = ParseLocaleFloat::new(NumberFormat::new().grouping('.').decimal(','));
+        let (number, _) = european.parse(ParserState::new("1.234.567,25")).unwrap();
+        assert_eq!(number, 1_234_567.25);
+    }
+
+    #[test]
+    fn numbers_b() {
+        let english = ParseLocaleFloat::new(NumberFormat::new().grouping(','));
+
+        // A grouping separator that isn't followed by a digit ends the
+        // number.
+        let (number, new_state) = english.parse(ParserState::new("12,345, 6")).unwrap();
+        assert_eq!(number, 12345.0);
+        assert_eq!(new_state.input, String::from(", 6"));
+
+        // Groups must have three digits.
+        assert_eq!(
+            english.parse(ParserState::new("12,34")).unwrap_err().kind(),
+            ParseError::invalid(0, 5, "digit groups must have three digits").kind()
+        );
+        assert!(english.parse(ParserState::new("1234,567")).is_err());
+    }
+}
+
+mod durations {
+    use crate::formats::duration::*;
+    use crate::{ParseError, Parser, ParserState};
+    use std::time::Duration;
+
+    #[test]
+    fn duration_a() {
+        let duration = |input| {
+            ParseDuration::default()
+                .parse(ParserState::new(input))
+                .map(|(d, _)| d)
+        };
+
+        // Units combine, and longer unit names win.
+        assert_eq!(duration("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(duration("2d4h"), Ok(Duration::from_secs(187_200)));
+        assert_eq!(duration("1500ms"), Ok(Duration::from_millis(1500)));
+        assert_eq!(duration("1.5s"), Ok(Duration::from_millis(1500)));
+
+        // A missing unit lists the possible units.
+        assert_eq!(
+            duration("5").unwrap_err(),
+            ParseError::unexpected(1, ["ns", "us", "µs", "ms", "s", "m", "h", "d", "w"], None)
+                .with_help("every number in a duration needs a unit, like `30s`")
+        );
+
+        // Durations too long to add up are reported rather than overflowing,
+        // including when only the fraction pushes them over.
+        let input = "340282366920938463463374607431768211455.9ns";
+        assert_eq!(
+            duration(input).unwrap_err(),
+            ParseError::invalid(0, input.len(), "duration is too long")
+        );
+    }
+
+    #[test]
+    fn duration_b() {
+        // Custom unit tables.
+        let units = DurationUnits::new()
+            .unit("min", Duration::from_secs(60))
+            .unit("sec", Duration::from_secs(1));
+        let (duration, new_state) = ParseDuration::new(units)
+            .parse(ParserState::new("2min5sec rest"))
+            .unwrap();
+        assert_eq!(duration, Duration::from_secs(125));
+        assert_eq!(new_state.input, String::from(" rest"));
+    }
+}
+
+mod char_classes {
+    use crate::parsers::utf8::*;
+    use crate::{ParseError, Parser, ParserState};
+
+    #[test]
+    fn char_class_a() {
+        // Ranges and single characters.
+        let identifier = char_class("[a-zA-Z0-9_]").unwrap();
+        assert_eq!(
+            identifier.ranges,
+            vec![('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')]
+        );
+        assert!(identifier.contains('q') && identifier.contains('_'));
+        assert!(!identifier.contains('-'));
+
+        // Negation and escapes.
+        let unquoted = char_class(r#"[^"\\]"#).unwrap();
+        assert!(unquoted.negated);
+        assert_eq!(unquoted.parse(ParserState::new("a")).unwrap().0, 'a');
+        assert_eq!(
+            unquoted.parse(ParserState::new("\\")).unwrap_err(),
+            ParseError::unexpected(0, [r#"[^"\\]"#], Some("\\"))
+        );
+
+        // Dashes at the ends and shorthand classes.
+        let class = char_class(r"[-\d.]").unwrap();
+        assert!(class.contains('-') && class.contains('7') && class.contains('.'));
+        assert_eq!(class.to_string(), r"[\-0-9.]");
+    }
+
+    #[test]
+    fn char_class_b() {
+        // Errors are reported within the specification.
+        assert_eq!(
+            char_class("[z-a]").unwrap_err(),
+            ParseError::invalid(1, 4, "character range starts after it ends")
+        );
+        assert_eq!(
+            char_class("[abc").unwrap_err(),
+            ParseError::unexpected(4, ["]"], None)
+        );
+        assert_eq!(
+            char_class("[a]b").unwrap_err(),
+            ParseError::trailing_input(3, "b")
+        );
+    }
+}
+
+mod templates {
+    use crate::formats::template::*;
+    use crate::{ParseError, Parser, ParserState};
+
+    #[test]
+    fn template_a() {
+        // Both placeholder forms and escapes.
+        let (segments, _) = ParseTemplate
+            .parse(ParserState::new("Hi ${user_name}, you owe $$5 to $bank1!"))
+            .unwrap();
+        assert_eq!(
+            segments,
+            vec![
+                Segment::Literal(String::from("Hi ")),
+                Segment::Placeholder(String::from("user_name")),
+                Segment::Literal(String::from(", you owe $5 to ")),
+                Segment::Placeholder(String::from("bank1")),
+                Segment::Literal(String::from("!")),
+            ]
+        );
+    }
+
+    #[test]
+    fn template_b() {
+        // Unterminated placeholders point back at the start.
+        assert_eq!(
+            ParseTemplate
+                .parse(ParserState::new("a ${b c"))
+                .unwrap_err(),
+            ParseError::unexpected(5, ["}"], Some(" "))
+                .with_note("the placeholder starts at offset 2")
+        );
+
+        // A lone `$` suggests escaping it.
+        assert_eq!(
+            ParseTemplate.parse(ParserState::new("$5")).unwrap_err(),
+            ParseError::unexpected(1, ["placeholder name"], Some("5"))
+                .with_help("write `$$` for a literal `$`")
+        );
+    }
+}
+
+#[cfg(feature = "yaml")]
+mod yaml {
+    use crate::formats::yaml::*;
+    use crate::{ParseError, Parser, ParserState};
+
+    fn yaml(input: &str) -> Result<YamlValue, ParseError> {
+        ParseYaml.parse(ParserState::new(input)).map(|(v, _)| v)
+    }
+
+    #[test]
+    fn yaml_a() {
+        // Block mappings and sequences, including sequences of mappings.
+        let value = yaml(
+            "# A config file\n\
+             name: parselib # the name\n\
+             version: 1.5\n\
+             tags:\n\
+             - parser\n\
+             - 'it''s fast'\n\
+             servers:\n  \
+               - host: \"a#1\"\n    \
+                 port: 80\n  \
+               - host: b\n\
+             empty:\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            value.get("name"),
+            Some(&YamlValue::String(String::from("parselib")))
+        );
+        assert_eq!(value.get("version"), Some(&YamlValue::Float(1.5)));
+        assert_eq!(
+            value.get("tags"),
+            Some(&YamlValue::Sequence(vec![
+                YamlValue::String(String::from("parser")),
+                YamlValue::String(String::from("it's fast")),
+            ]))
+        );
+        assert_eq!(
+            value.get("servers"),
+            Some(&YamlValue::Sequence(vec![
+                YamlValue::Mapping(vec![
+                    (String::from("host"), YamlValue::String(String::from("a#1"))),
+                    (String::from("port"), YamlValue::Int(80)),
+                ]),
+                YamlValue::Mapping(vec![(
+                    String::from("host"),
+                    YamlValue::String(String::from("b"))
+                )]),
+            ]))
+        );
+        assert_eq!(value.get("empty"), Some(&YamlValue::Null));
+    }
+
+    #[test]
+    fn yaml_b() {
+        // Flow collections.
+        assert_eq!(
+            yaml("point: {x: 1, \"y\": -2}\nlist: [true, ~, [a b]]").unwrap(),
+            YamlValue::Mapping(vec![
+                (
+                    String::from("point"),
+                    YamlValue::Mapping(vec![
+                        (String::from("x"), YamlValue::Int(1)),
+                        (String::from("y"), YamlValue::Int(-2)),
+                    ])
+                ),
+                (
+                    String::from("list"),
+                    YamlValue::Sequence(vec![
+                        YamlValue::Bool(true),
+                        YamlValue::Null,
+                        YamlValue::Sequence(vec![YamlValue::String(String::from("a b"))]),
+                    ])
+                ),
+            ])
+        );
+
+        // Errors point at the offending text.
+        assert_eq!(
+            yaml("a: 1\n   b: 2").unwrap_err(),
+            ParseError::invalid(8, 12, "unexpected indentation")
+        );
+        assert_eq!(
+            yaml("a: [1, 2").unwrap_err(),
+            ParseError::unexpected(8, ["`,`", "`]`"], None)
+        );
+    }
+
+    #[test]
+    fn yaml_c() {
+        // An apostrophe inside plain text doesn't start a quoted string, so
+        // the comment after it is still removed.
+        assert_eq!(
+            yaml(
+                "note: it's fine # comment
+list: [a, 'b # c']"
+            )
+            .unwrap(),
+            YamlValue::Mapping(vec![
+                (
+                    String::from("note"),
+                    YamlValue::String(String::from("it's fine"))
+                ),
+                (
+                    String::from("list"),
+                    YamlValue::Sequence(vec![
+                        YamlValue::String(String::from("a")),
+                        YamlValue::String(String::from("b # c")),
+                    ])
+                ),
+            ])
+        );
+    }
+}
+
+mod bytes {
+    use crate::bytes::*;
+    use crate::ParseError;
+
+    #[test]
+    fn bytes_a() {
+        let input = b"\x7fELF\x02\x01rest";
+
+        // Tags and takes return slices of the original input.
+        let (magic, state) = tag(b"\x7fELF").parse(ByteState::new(input)).unwrap();
+        let (class, state) = take(2).parse(state).unwrap();
+        assert_eq!(magic.as_ptr(), input.as_ptr());
+        assert_eq!(class, &[2, 1]);
+        assert_eq!(class.as_ptr(), input[4..].as_ptr());
+        assert_eq!(state.index(), 6);
+        assert_eq!(state.input(), b"rest");
+
+        // Recognize returns everything its parser consumed.
+        let (header, _) = tag(b"\x7fELF")
+            .recognize()
+            .parse(ByteState::new(input))
+            .unwrap();
+        assert_eq!(header, &input[..4]);
+    }
+
+    #[test]
+    fn bytes_b() {
+        // Mismatches are shown as hex.
+        assert_eq!(
+            tag(b"\x7fELF").parse(ByteState::new(b"MZ")).unwrap_err(),
+            ParseError::unexpected(0, ["7f 45 4c 46"], Some("4d 5a"))
+        );
+        assert_eq!(
+            take(4).parse(ByteState::new_offset(b"ab", 10)).unwrap_err(),
+            ParseError::wrong_count(12, 4, 4, 2)
+        );
+    }
+}
+
+mod flags {
+    use crate::bytes::*;
+    use crate::ParseError;
+
+    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    enum TcpFlag {
+        Fin,
+        Syn,
+        Ack,
+    }
+
+    #[test]
+    fn flags_a() {
+        let table = vec![
+            (0x01, TcpFlag::Fin),
+            (0x02, TcpFlag::Syn),
+            (0x10, TcpFlag::Ack),
+        ];
+
+        // Set bits are decoded in table order.
+        let (flags, state) = u8()
+            .flags(table.clone())
+            .parse(ByteState::new(&[0x12, 0xff]))
+            .unwrap();
+        assert_eq!(flags, vec![TcpFlag::Syn, TcpFlag::Ack]);
+        assert_eq!(state.index(), 1);
+
+        // Strict mode rejects bits that aren't in the table.
+        assert_eq!(
+            be_u16()
+                .flags(table)
+                .strict()
+                .parse(ByteState::new(&[0x80, 0x01]))
+                .unwrap_err(),
+            ParseError::invalid(0, 2, "unknown flag bits 0x8000")
+        );
+    }
+
+    #[test]
+    fn unsigned_a() {
+        // Byte order matters for wider integers.
+        let input = [0x12, 0x34, 0x56, 0x78];
+        assert_eq!(
+            be_u32().parse(ByteState::new(&input)).unwrap().0,
+            0x1234_5678
+        );
+        assert_eq!(le_u16().parse(ByteState::new(&input)).unwrap().0, 0x3412);
+        assert!(be_u64().parse(ByteState::new(&input)).is_err());
+    }
+}
+
+mod protobuf {
+    use crate::bytes::{ByteParser, ByteState};
+    use crate::formats::protobuf::*;
+    use crate::ParseError;
+
+    #[test]
+    fn varint_a() {
+        // Multi-byte varints and zigzag decoding.
+        assert_eq!(
+            ParseVarint.parse(ByteState::new(&[0xac, 0x02])).unwrap().0,
+            300
+        );
+        assert_eq!(zigzag(3), -2);
+        assert_eq!(zigzag(4), 2);
+
+        // Varints that don't end or are too large.
+        assert_eq!(
+            ParseVarint.parse(ByteState::new(&[0x80])).unwrap_err(),
+            ParseError::unexpected(1, ["varint byte"], None)
+        );
+        assert!(ParseVarint.parse(ByteState::new(&[0xff; 10])).is_err());
+    }
+
+    #[test]
+    fn fields_a() {
+        // Field 1 = 150, field 2 = "hi", field 3 = fixed32 1.
+        let message = [0x08, 0x96, 0x01, 0x12, 0x02, b'h', b'i', 0x1d, 1, 0, 0, 0];
+        let decoded = fields(&message).collect::<Result<Vec<Field>, ParseError>>();
+        assert_eq!(
+            decoded,
+            Ok(vec![
+                Field {
+                    number: 1,
+                    value: FieldValue::Varint(150),
+                },
+                Field {
+                    number: 2,
+                    value: FieldValue::LengthDelimited(b"hi"),
+                },
+                Field {
+                    number: 3,
+                    value: FieldValue::Fixed32(1),
+                },
+            ])
+        );
+
+        // Invalid wire types are reported on the key.
+        assert_eq!(
+            fields(&[0x08, 0x01, 0x0f]).nth(1),
+            Some(Err(ParseError::invalid(2, 3, "unknown wire type 7")))
+        );
+    }
+}
+
+mod net {
+    use crate::bytes::{ByteParser, ByteState};
+    use crate::formats::net::*;
+    use crate::ParseError;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn ethernet_ipv4_a() {
+        // An Ethernet frame with a VLAN tag, carrying an IPv4 header.
+        let frame = [
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x81, 0x00,
+            0x00, 0x2a, 0x08, 0x00, 0x45, 0x00, 0x00, 0x73, 0x00, 0x00, 0x40, 0x00, 0x40, 0x11,
+            0xb8, 0x61, 0xc0, 0xa8, 0x00, 0x01, 0xc0, 0xa8, 0x00, 0xc7,
+        ];
+        let (ethernet, state) = ParseEthernet.parse(ByteState::new(&frame)).unwrap();
+        assert_eq!(ethernet.vlan, Some(42));
+        assert_eq!(ethernet.ether_type, 0x0800);
+
+        let (ip, state) = ParseIpv4.parse(state).unwrap();
+        assert_eq!(ip.total_length, 0x73);
+        assert!(ip.dont_fragment && !ip.more_fragments);
+        assert_eq!(ip.protocol, 17);
+        assert!(ip.checksum_valid);
+        assert_eq!(ip.source, Ipv4Addr::new(192, 168, 0, 1));
+        assert_eq!(ip.destination, Ipv4Addr::new(192, 168, 0, 199));
+        assert!(ip.options.is_empty());
+        assert_eq!(state.index(), frame.len());
+
+        // A corrupted header fails its checksum, and other versions are
+        // rejected.
+        let mut corrupted = frame[18..].to_vec();
+        corrupted[8] = 0x3f;
+        assert!(
+            !ParseIpv4
+                .parse(ByteState::new(&corrupted))
+                .unwrap()
+                .0
+                .checksum_valid
+        );
+        assert_eq!(
+            ParseIpv4.parse(ByteState::new(&[0x60])).unwrap_err(),
+            ParseError::invalid(0, 1, "expected IP version 4 but found 6")
+        );
+    }
+
+    #[test]
+    fn checksum_a() {
+        // The example from RFC 1071.
+        let data = [0x00, 0x01, 0xf2, 0x03, 0xf4, 0xf5, 0xf6, 0xf7];
+        assert_eq!(internet_checksum(&data), !0xddf2);
+
+        // Long inputs of large words don't overflow the sum.
+        assert_eq!(internet_checksum(&vec![0xff; 256 * 1024]), 0);
+    }
+
+    #[test]
+    fn tcp_udp_a() {
+        // A SYN segment with MSS, SACK permitted, and window scale options.
+        let segment = [
+            0x30, 0x39, 0x00, 0x50, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x80, 0x02,
+            0xff, 0xff, 0x12, 0x34, 0x00, 0x00, 0x02, 0x04, 0x05, 0xb4, 0x04, 0x02, 0x01, 0x03,
+            0x03, 0x07, 0x00, 0x00, b'd', b'a', b't', b'a',
+        ];
+        let (tcp, state) = ParseTcp.parse(ByteState::new(&segment)).unwrap();
+        assert_eq!((tcp.source_port, tcp.destination_port), (12345, 80));
+        assert!(tcp.has_flag(TcpHeader::SYN) && !tcp.has_flag(TcpHeader::ACK));
+        assert_eq!(tcp.checksum, 0x1234);
+        assert_eq!(
+            tcp.options,
+            vec![
+                TcpOption::MaximumSegmentSize(1460),
+                TcpOption::SackPermitted,
+                TcpOption::WindowScale(7),
+            ]
+        );
+        assert_eq!(state.input(), b"data");
+
+        let (udp, _) = ParseUdp
+            .parse(ByteState::new(&[
+                0x00, 0x35, 0xd4, 0x31, 0x00, 0x20, 0xab, 0xcd,
+            ]))
+            .unwrap();
+        assert_eq!(
+            udp,
+            UdpHeader {
+                source_port: 53,
+                destination_port: 54321,
+                length: 32,
+                checksum: 0xabcd,
+            }
+        );
+    }
+}
+
+mod encodings {
+    use crate::encoding::*;
+    use crate::parsers::utf8::ParseChar;
+    use crate::parsers::ParserExtensions;
+    use crate::{ParseError, ParseErrorKind};
+
+    fn utf16_le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    #[test]
+    fn decode_a() {
+        // Surrogate pairs decode, and a byte order mark is skipped.
+        let mut bytes = vec![0xff, 0xfe];
+        bytes.extend(utf16_le("a😀b"));
+        assert_eq!(Encoding::detect(&bytes), Some(Encoding::Utf16Le));
+        let decoded = DecodedInput::decode(&bytes, Encoding::Utf16Le).unwrap();
+        assert_eq!(decoded.text(), "a😀b");
+
+        // Offsets map back to the original bytes.
+        assert_eq!(decoded.source_offset(0), 2);
+        assert_eq!(decoded.source_offset(1), 4);
+        assert_eq!(decoded.source_offset(5), 8);
+        assert_eq!(decoded.source_offset(6), 10);
+
+        // So do errors from parsing the decoded text.
+        let error = decoded
+            .parse(ParseChar::from_char('a').and(ParseChar::from_char('b')))
+            .unwrap_err();
+        assert_eq!(error.offset(), 4);
+    }
+
+    #[test]
+    fn decode_b() {
+        // Unpaired surrogates are reported in the original bytes.
+        assert_eq!(
+            DecodedInput::decode(&[0x61, 0x00, 0x3d, 0xd8, 0x62, 0x00], Encoding::Utf16Le)
+                .unwrap_err(),
+            ParseError::invalid(2, 4, "unpaired high surrogate")
+        );
+        assert_eq!(
+            DecodedInput::decode(&[0xdc, 0x00], Encoding::Utf16Be).unwrap_err(),
+            ParseError::invalid(0, 2, "unpaired low surrogate")
+        );
+
+        // UTF-32 rejects values outside of Unicode.
+        let decoded =
+            DecodedInput::decode(&[0, 0, 0, 0x41, 0, 0x01, 0xf6, 0x00], Encoding::Utf32Be).unwrap();
+        assert_eq!(decoded.text(), "A😀");
+        assert!(DecodedInput::decode(&[0, 0x11, 0, 0], Encoding::Utf32Be).is_err());
+    }
+
+    #[test]
+    fn encoded_state_a() {
+        // Parse `key=value` straight from UTF-16, with offsets in the bytes.
+        let mut bytes = vec![0xfe, 0xff];
+        bytes.extend("größe=😀".encode_utf16().flat_map(u16::to_be_bytes));
+        fn pair(state: EncodedState<'_>) -> EncodedResult<'_, ParseError, (String, String)> {
+            let (key, state) = state.take_while(char::is_alphabetic)?;
+            let state = state.tag("=")?;
+            let (value, state) = state.take_while(|c| !c.is_whitespace())?;
+            Ok(((key, value), state))
+        }
+        let ((key, value), new_state) = pair
+            .parse(EncodedState::new(&bytes, Encoding::Utf16Be))
+            .unwrap();
+        assert_eq!((key.as_str(), value.as_str()), ("größe", "😀"));
+        assert!(new_state.is_empty());
+        assert_eq!(new_state.index(), 18);
+
+        // Errors point at the encoded bytes, and invalid code units are only
+        // reported once they are reached.
+        let error = pair
+            .parse(EncodedState::new(&utf16_le("ab:c"), Encoding::Utf16Le))
+            .unwrap_err();
+        assert_eq!(error.offset(), 4);
+        let mut bytes = utf16_le("a b");
+        bytes.extend([0x00, 0xdc]);
+        let state = EncodedState::new(&bytes, Encoding::Utf16Le);
+        let (word, state) = state.take_while(char::is_alphabetic).unwrap();
+        assert_eq!((word.as_str(), state.index()), ("a", 2));
+        assert_eq!(
+            state.tag(" b").unwrap().next_char().unwrap_err(),
+            ParseError::invalid(6, 8, "unpaired low surrogate")
+        );
+    }
+
+    #[test]
+    fn encoded_parser_a() {
+        // A comma-separated list of answers, built from combinators that read
+        // UTF-16 directly.
+        fn yes(state: EncodedState<'_>) -> EncodedResult<'_, ParseError, bool> {
+            Ok((true, state.tag("yes")?))
+        }
+        fn no(state: EncodedState<'_>) -> EncodedResult<'_, ParseError, bool> {
+            Ok((false, state.tag("no")?))
+        }
+        fn comma(state: EncodedState<'_>) -> EncodedResult<'_, ParseError, ()> {
+            Ok(((), state.tag(",")?))
+        }
+        let answers_parser = yes
+            .or(no)
+            .and(
+                comma
+                    .and(yes.or(no))
+                    .map(|(_, answer)| answer)
+                    .between(0, 2),
+            )
+            .map(|(first, mut rest)| {
+                rest.insert(0, first);
+                rest
+            });
+
+        let bytes = utf16_le("no,yes,yes,no");
+        let (answers, new_state) = answers_parser
+            .parse(EncodedState::new(&bytes, Encoding::Utf16Le))
+            .unwrap();
+        assert_eq!(answers, vec![false, true, true]);
+        assert_eq!(new_state.index(), 20);
+
+        // If neither alternative matches, the error lists both.
+        let bytes = utf16_le("maybe");
+        let error = answers_parser
+            .parse(EncodedState::new(&bytes, Encoding::Utf16Le))
+            .unwrap_err();
+        assert_eq!(error.offset(), 0);
+        match error.kind() {
+            ParseErrorKind::Unexpected { expected, .. } => assert_eq!(expected.len(), 2),
+            kind => panic!("wrong parsing error: {:?}", kind),
+        }
+
+        // Too few repetitions are reported where the next one should be.
+        let error = yes
+            .one_or_more()
+            .parse(EncodedState::new(&bytes, Encoding::Utf16Le))
+            .unwrap_err();
+        assert!(matches!(error.kind(), ParseErrorKind::WrongCount { .. }));
+    }
+}
+
+mod take_while {
+    use crate::parsers::utf8::{
+        alpha, alpha1, alphanumeric, alphanumeric1, digit, digit0, digit1, digits_radix,
+        end_of_line, multispace0, multispace1, rest, space0, space1, take_until, take_while,
+        take_while1, ParseChar, ParseStr, ParseTakeUntil,
+    };
+    use crate::parsers::ParserExtensions;
+    use crate::{Expected, Parser, ParserState};
+
+    #[test]
+    fn take_while_a() {
+        // Take an identifier made of any alphanumeric characters, including
+        // ones outside ASCII.
+        let identifier_parser = take_while1(|c: char| c.is_alphanumeric() || c == '_');
+        let (name, new_state) = identifier_parser
+            .parse(ParserState::new("größe_2 = 1"))
+            .unwrap();
+        assert_eq!(name, "größe_2");
+        assert_eq!(new_state.input(), " = 1");
+        assert_eq!(new_state.index, 9);
+
+        // Empty runs are only allowed by `take_while`.
+        assert!(identifier_parser.parse(ParserState::new(" x")).is_err());
+        let (blank, new_state) = take_while(char::is_whitespace)
+            .parse(ParserState::new("x"))
+            .unwrap();
+        assert_eq!(blank, "");
+        assert_eq!(new_state.input(), "x");
+    }
+
+    #[test]
+    fn take_while_b() {
+        // The description is used in errors.
+        let hex_parser = take_while1(|c: char| c.is_ascii_hexdigit())
+            .expecting("hex digit")
+            .all_consuming();
+        let error = hex_parser.parse(ParserState::new("g")).unwrap_err();
+        assert_eq!(error.to_string(), "expected hex digit found g\n");
+    }
+
+    #[test]
+    fn whitespace_a() {
+        // Spaces stop at the end of the line, but multispace carries on.
+        let (spaces, new_state) = space0().parse(ParserState::new(" \t\n x")).unwrap();
+        assert_eq!(spaces, " \t");
+        assert_eq!(new_state.input(), "\n x");
+        let (spaces, new_state) = multispace1().parse(ParserState::new(" \t\r\n x")).unwrap();
+        assert_eq!(spaces, " \t\r\n ");
+        assert_eq!(new_state.input(), "x");
+
+        // The optional versions accept nothing, and the required ones don't.
+        assert_eq!(space0().parse(ParserState::new("x")).unwrap().0, "");
+        assert_eq!(multispace0().parse(ParserState::new("")).unwrap().0, "");
+        let error = space1().parse(ParserState::new("\nx")).unwrap_err();
+        assert!(error.expected().contains(&Expected::from("space or tab")));
+        assert!(multispace1().parse(ParserState::new("x")).is_err());
+    }
+
+    #[test]
+    fn digits_a() {
+        // A single digit, which is required by `digit`.
+        let (first, new_state) = digit().parse(ParserState::new("42px")).unwrap();
+        assert_eq!(first, '4');
+        assert_eq!(new_state.input(), "2px");
+        assert!(digit().parse(ParserState::new("abc")).is_err());
+
+        // A run of digits, which is only required by `digit1`.
+        assert_eq!(digit0().parse(ParserState::new("px")).unwrap().0, "");
+        let (digits, new_state) = digit1().parse(ParserState::new("42px")).unwrap();
+        assert_eq!(digits, "42");
+        assert_eq!(new_state.input(), "px");
+        assert!(digit1().parse(ParserState::new("px")).is_err());
+
+        // Other bases accept letters in either case.
+        let (digits, _) = digits_radix(16).parse(ParserState::new("dEaDg")).unwrap();
+        assert_eq!(digits, "dEaD");
+        let (digits, _) = digits_radix(2).parse(ParserState::new("1012")).unwrap();
+        assert_eq!(digits, "101");
+        let error = digits_radix(8).parse(ParserState::new("9")).unwrap_err();
+        assert!(error.expected().contains(&Expected::from("octal digit")));
+    }
+
+    #[test]
+    fn alpha_a() {
+        // Letters from any script count, not just ASCII.
+        let (word, new_state) = alpha1().parse(ParserState::new("straße1")).unwrap();
+        assert_eq!(word, "straße");
+        assert_eq!(new_state.input(), "1");
+        let (word, _) = alphanumeric1().parse(ParserState::new("日本2 x")).unwrap();
+        assert_eq!(word, "日本2");
+
+        // Only the `1` versions require anything.
+        assert_eq!(alpha().parse(ParserState::new("42")).unwrap().0, "");
+        assert_eq!(alphanumeric().parse(ParserState::new("_")).unwrap().0, "");
+        assert!(alpha1().parse(ParserState::new("42")).is_err());
+        assert!(alphanumeric1().parse(ParserState::new("_")).is_err());
+    }
+
+    #[test]
+    fn take_until_a() {
+        // Take the body of a block comment, leaving the terminator.
+        let comment_parser = take_until("*/");
+        let (body, new_state) = comment_parser
+            .parse(ParserState::new(" a * b */ rest"))
+            .unwrap();
+        assert_eq!(body, " a * b ");
+        assert_eq!(new_state.input(), "*/ rest");
+
+        // A missing terminator is reported at the end of the input.
+        let error = comment_parser
+            .parse(ParserState::new(" unfinished"))
+            .unwrap_err();
+        assert_eq!(error.offset, 11);
+
+        // Any parser can be the terminator, including ones that match at
+        // the end of the input.
+        let line_parser = ParseTakeUntil::new(end_of_line());
+        let (line, _) = line_parser.parse(ParserState::new("last line")).unwrap();
+        assert_eq!(line, "last line");
+        let digits_parser = ParseTakeUntil::new(ParseChar::from_range('0', '9'));
+        let (text, new_state) = digits_parser.parse(ParserState::new("abc123")).unwrap();
+        assert_eq!(text, "abc");
+        assert_eq!(new_state.index, 3);
+    }
+
+    #[test]
+    fn take_until_b() {
+        // A long comment body is scanned without trying the terminator at
+        // every character.
+        let body = "x".repeat(200_000);
+        let (taken, new_state) = take_until("*/")
+            .parse(ParserState::new(&format!("{}*/", body)))
+            .unwrap();
+        assert_eq!(taken, body);
+        assert_eq!(new_state.input(), "*/");
+
+        // Terminators that start the same way can overlap.
+        let overlap_parser =
+            ParseTakeUntil::new(ParseStr::new("aa").and(ParseChar::from_char('b')));
+        let (taken, _) = overlap_parser.parse(ParserState::new("xaaab")).unwrap();
+        assert_eq!(taken, "xa");
+
+        // Terminators whose grammar doesn't say how they start are still
+        // tried everywhere.
+        let end_parser = ParseTakeUntil::new(ParseStr::new("end").ignore_case());
+        let (taken, new_state) = end_parser.parse(ParserState::new("body END")).unwrap();
+        assert_eq!(taken, "body ");
+        assert_eq!(new_state.index, 5);
+    }
+
+    #[test]
+    fn rest_a() {
+        // Everything after the colon is the message body.
+        let message_parser = take_until(":")
+            .then_keep_left(ParseChar::from_char(':'))
+            .and(rest());
+        let ((level, body), new_state) = message_parser
+            .parse(ParserState::new("warn: disk is full"))
+            .unwrap();
+        assert_eq!(level, "warn");
+        assert_eq!(body, " disk is full");
+        assert_eq!(new_state.input(), "");
+        assert_eq!(new_state.index, 18);
+
+        // There may be nothing left at all.
+        let (body, _) = rest().parse(ParserState::new("")).unwrap();
+        assert_eq!(body, "");
+    }
+}
+
+mod strs {
+    use crate::grammar::Grammar;
+    use crate::parsers::utf8::ParseStr;
+    use crate::{ParseError, Parser, ParserState};
+
+    /// A literal that can be built without any runtime setup.
+    static ARROW: ParseStr<'static> = ParseStr::new("=>");
+
+    #[test]
+    fn str_parser_a() {
+        // The literal is matched and returned.
+        let (arrow, new_state) = ARROW.parse(ParserState::new("=> x")).unwrap();
+        assert_eq!(arrow, "=>");
+        assert_eq!(new_state.input(), " x");
+        assert_eq!(new_state.index, 2);
+
+        // Multi-byte characters advance by their length in bytes.
+        let (word, new_state) = ParseStr::new("héllo")
+            .parse(ParserState::new("héllo!"))
+            .unwrap();
+        assert_eq!(word, "héllo");
+        assert_eq!(new_state.input(), "!");
+        assert_eq!(new_state.index, 6);
+
+        // A partial match fails where it started.
+        assert_eq!(
+            ARROW.parse(ParserState::new("=<")),
+            Err(ParseError::unexpected(0, ["=>"], Some("=")))
+        );
+        assert_eq!(ARROW.describe(), Grammar::Literal(String::from("=>")));
+    }
+
+    #[test]
+    fn str_parser_b() {
+        // Keywords can match in any case, returning the text as written.
+        let select_parser = ParseStr::new("select").ignore_case();
+        for input in ["select *", "SELECT *", "Select *"] {
+            let (keyword, new_state) = select_parser.parse(ParserState::new(input)).unwrap();
+            assert_eq!(keyword, &input[..6]);
+            assert_eq!(new_state.input(), " *");
+        }
+        assert!(select_parser.parse(ParserState::new("selec")).is_err());
+        assert!(select_parser.parse(ParserState::new("insert")).is_err());
+    }
+}
+
+mod identifiers {
+    use crate::parsers::utf8::{identifier, unicode_identifier, ParseIdentifier};
+    use crate::parsers::ParserExtensions;
+    use crate::span::Span;
+    use crate::{Expected, Parser, ParserState};
+
+    #[test]
+    fn identifier_parser_a() {
+        // ASCII identifiers can't start with a digit.
+        let (name, new_state) = identifier().parse(ParserState::new("_tmp2 = 1")).unwrap();
+        assert_eq!(name, "_tmp2");
+        assert_eq!(new_state.input(), " = 1");
+        let error = identifier().parse(ParserState::new("2x")).unwrap_err();
+        assert!(error.expected().contains(&Expected::from("identifier")));
+        assert!(identifier().parse(ParserState::new("é")).is_err());
+
+        // Identifiers in other scripts, along with where they were found.
+        let (name, _) = unicode_identifier()
+            .spanned()
+            .parse(ParserState::new_offset("größe1+", 4))
+            .unwrap();
+        assert_eq!(name.value, "größe1");
+        assert_eq!(name.span, Span::new(4, 12));
+
+        // Custom classes, such as names with dashes.
+        let kebab = ParseIdentifier::new(
+            |c: char| c.is_ascii_lowercase(),
+            |c: char| c.is_ascii_lowercase() || c == '-',
+        )
+        .expecting("property name");
+        let (name, _) = kebab.parse(ParserState::new("font-size:")).unwrap();
+        assert_eq!(name, "font-size");
+        let error = kebab.parse(ParserState::new("-x")).unwrap_err();
+        assert!(error.expected().contains(&Expected::from("property name")));
+    }
+}
+
+mod integers {
+    use crate::parsers::utf8::{
+        binary, float, hex, integer, octal, ParseFloat, ParseInt, ParseRadixInt,
+    };
+    use crate::{Expected, ParseError, ParseErrorKind, Parser, ParserState};
+
+    #[test]
+    fn int_parser_a() {
+        // Signed numbers, stopping at the first character that isn't a digit.
+        let (number, new_state) = integer::<i64>().parse(ParserState::new("-42px")).unwrap();
+        assert_eq!(number, -42);
+        assert_eq!(new_state.input(), "px");
+        assert_eq!(integer::<i64>().parse(ParserState::new("+7")).unwrap().0, 7);
+        assert!(integer::<i64>().parse(ParserState::new("-")).is_err());
+
+        // Numbers that don't fit are errors spanning the whole number.
+        let error = integer::<u8>().parse(ParserState::new("256,")).unwrap_err();
+        match error.kind() {
+            ParseErrorKind::Invalid { end, message } => {
+                assert_eq!(*end, 3);
+                assert!(message.contains("too large"));
+            }
+            kind => panic!("unexpected error kind {:?}", kind),
+        }
+        assert_eq!(
+            integer::<i64>()
+                .parse(ParserState::new("-9223372036854775808"))
+                .unwrap()
+                .0,
+            i64::MIN
+        );
+
+        // Unsigned parsers leave the sign alone.
+        let unsigned = ParseInt::<u64>::new().unsigned();
+        assert!(unsigned.parse(ParserState::new("-1")).is_err());
+        assert_eq!(
+            unsigned
+                .parse(ParserState::new("18446744073709551615"))
+                .unwrap()
+                .0,
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn float_parser_a() {
+        // Numbers with every part, returned with the text they came from.
+        let ((number, text), new_state) = float().parse(ParserState::new("-1.5e-3 m")).unwrap();
+        assert_eq!(number, -0.0015);
+        assert_eq!(text, "-1.5e-3");
+        assert_eq!(new_state.input(), " m");
+
+        // Either side of the point can be left out, but not both.
+        assert_eq!(float().parse(ParserState::new(".5")).unwrap().0 .0, 0.5);
+        assert_eq!(float().parse(ParserState::new("1.")).unwrap().0 .0, 1.0);
+        assert!(float().parse(ParserState::new(".")).is_err());
+        assert!(float().parse(ParserState::new("-x")).is_err());
+
+        // An `e` without an exponent belongs to whatever comes next.
+        let ((number, _), new_state) = float().parse(ParserState::new("2em")).unwrap();
+        assert_eq!(number, 2.0);
+        assert_eq!(new_state.input(), "em");
+
+        // Infinity and NaN are only accepted when asked for, including by
+        // numbers too large to represent.
+        assert!(float().parse(ParserState::new("inf")).is_err());
+        assert_eq!(
+            float().parse(ParserState::new("-1e400")).unwrap_err(),
+            ParseError::invalid(0, 6, "number is too large")
+        );
+        let special = ParseFloat::new().special_values();
+        let ((number, text), _) = special.parse(ParserState::new("-Infinity")).unwrap();
+        assert_eq!(number, f64::NEG_INFINITY);
+        assert_eq!(text, "-Infinity");
+        assert!(special
+            .parse(ParserState::new("NaN"))
+            .unwrap()
+            .0
+             .0
+            .is_nan());
+    }
+
+    #[test]
+    fn radix_int_parser_a() {
+        // Each base has its own prefix, in either case.
+        let (number, new_state) = hex::<u32>()
+            .parse(ParserState::new("0xDEAD_BEEF;"))
+            .unwrap();
+        assert_eq!(number, 0xDEAD_BEEF);
+        assert_eq!(new_state.input(), ";");
+        assert_eq!(
+            octal::<u16>().parse(ParserState::new("0O755")).unwrap().0,
+            0o755
+        );
+        assert_eq!(
+            binary::<u8>()
+                .parse(ParserState::new("0b1010_0101"))
+                .unwrap()
+                .0,
+            0b1010_0101
+        );
+        let error = hex::<u32>().parse(ParserState::new("0b1")).unwrap_err();
+        assert!(error.expected().contains(&Expected::from("0x")));
+        assert!(octal::<u8>().parse(ParserState::new("0o8")).is_err());
+
+        // Separators have to be between digits.
+        assert!(binary::<u8>().parse(ParserState::new("0b1_")).is_err());
+        assert!(binary::<u8>().parse(ParserState::new("0b_1")).is_err());
+
+        // Literals that don't fit are errors spanning the whole literal.
+        let error = hex::<u8>().parse(ParserState::new("0x1_00")).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ParseErrorKind::Invalid { end: 6, .. }
+        ));
+        assert!(hex::<u64>()
+            .parse(ParserState::new("0x1_0000_0000_0000_0000"))
+            .is_err());
+
+        // Custom prefixes and separators, or none at all.
+        let decimal = ParseRadixInt::<u32>::new(10, &[]).separator('\'');
+        assert_eq!(decimal.parse(ParserState::new("1'000")).unwrap().0, 1000);
+        let pascal = ParseRadixInt::<u16>::new(16, &["$"]);
+        assert_eq!(pascal.parse(ParserState::new("$ff")).unwrap().0, 255);
+    }
+}
+
+mod literal_sets {
+    use crate::parsers::utf8::*;
+    use crate::{ParseError, Parser, ParserState};
+
+    #[test]
+    fn literal_set_a() {
+        let operators = literal_set(&["=", "==", "=>", "!=", "<", "<=", "<<="]);
+
+        // The longest matching literal wins.
+        let (operator, new_state) = operators.parse(ParserState::new("<<=1")).unwrap();
+        assert_eq!(operator, "<<=");
+        assert_eq!(new_state.input, String::from("1"));
+        assert_eq!(operators.parse(ParserState::new("<x")).unwrap().0, "<");
+        assert_eq!(operators.parse(ParserState::new("==>")).unwrap().0, "==");
+
+        // A partial match of a longer literal falls back to a shorter one.
+        assert_eq!(operators.longest_match("<<"), Some((4, 1)));
+
+        // Nothing matching lists the literals.
+        assert_eq!(
+            literal_set(&["let", "fn"])
+                .parse(ParserState::new("mut"))
+                .unwrap_err(),
+            ParseError::unexpected(0, ["fn", "let"], Some("m"))
+        );
+    }
+
+    #[test]
+    fn literal_set_b() {
+        // Non-ASCII literals match on character boundaries.
+        let arrows = literal_set(&["→", "⇒", "->"]);
+        let (arrow, new_state) = arrows.parse(ParserState::new("⇒x")).unwrap();
+        assert_eq!(arrow, "⇒");
+        assert_eq!(new_state.index, "⇒".len());
+        assert!(arrows.parse(ParserState::new("⇐")).is_err());
+    }
+}
+
+mod keyword_sets {
+    use crate::parsers::utf8::*;
+    use crate::{ParseError, Parser, ParserState};
+
+    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    enum Token {
+        In,
+        Int,
+        Interface,
+    }
+
+    #[test]
+    fn keyword_set_a() {
+        let keywords = KeywordSet::new()
+            .keyword("in", Token::In)
+            .keyword("int", Token::Int)
+            .keyword("interface", Token::Interface);
+
+        // The longest keyword wins, and the value comes back.
+        let (token, new_state) = keywords.parse(ParserState::new("interface X")).unwrap();
+        assert_eq!(token, Token::Interface);
+        assert_eq!(new_state.input, String::from(" X"));
+        assert_eq!(
+            keywords.parse(ParserState::new("inter")).unwrap().0,
+            Token::Int
+        );
+
+        // Whole words don't match the start of identifiers.
+        let keywords = keywords.whole_words();
+        assert_eq!(
+            keywords.parse(ParserState::new("int x")).unwrap().0,
+            Token::Int
+        );
+        assert_eq!(
+            keywords.parse(ParserState::new("integer")).unwrap_err(),
+            ParseError::unexpected(0, ["in", "int", "interface"], Some("i"))
+        );
+    }
+
+    #[test]
+    fn keyword_set_b() {
+        // Keywords can also produce their index.
+        let keywords = KeywordSet::from_keywords(&["+", "+=", "++"]);
+        assert_eq!(keywords.parse(ParserState::new("+=1")).unwrap().0, 1);
+        assert_eq!(keywords.longest_match("+-"), Some((&0, 1)));
+
+        // A longer keyword that isn't a whole word falls back to a shorter
+        // one that is.
+        let keywords = KeywordSet::from_keywords(&["a", "a.b"]).whole_words();
+        assert_eq!(keywords.longest_match("a.bc"), Some((&0, 1)));
+        assert_eq!(keywords.longest_match("a.b c"), Some((&1, 3)));
+    }
+}
+
+mod count_of {
+    use crate::parsers::utf8::*;
+    use crate::parsers::ParserExtensions;
+    use crate::{ParseError, Parser, ParserState};
+
+    #[test]
+    fn count_of_parser_a() {
+        // Count whitespace without collecting it.
+        let (count, new_state) = ParseChar::from_char(' ')
+            .skip_many()
+            .parse(ParserState::new(&format!("{}x", " ".repeat(10_000))))
+            .unwrap();
+        assert_eq!(count, 10_000);
+        assert_eq!(new_state.input, String::from("x"));
+
+        // The bounds work like other repetition.
+        let digits = ParseChar::from_range('0', '9').count_of(2, 3);
+        assert_eq!(digits.parse(ParserState::new("12345")).unwrap().0, 3);
+        assert_eq!(
+            digits.parse(ParserState::new("1a")).unwrap_err(),
+            ParseError::wrong_count(1, 2, 3, 1)
+        );
+    }
+}
+
+mod registry {
+    use crate::grammar::{ebnf, Grammar};
+    use crate::parsers::utf8::ParseChar;
+    use crate::parsers::ParserExtensions;
+    use crate::registry::GrammarRegistry;
+    use crate::{Commit, ErrorVerbosity, ParseErrorKind, Parser, ParserState};
+    use std::convert::Infallible;
+
+    #[test]
+    fn registry_a() {
+        // Two rules that refer to each other, registered in any order, count
+        // how deeply an `x` is nested in parentheses.
+        let registry = GrammarRegistry::<usize>::new();
+        let expression = registry.rule("expression");
+        registry.register(
+            "group",
+            ParseChar::from_char('(')
+                .and(registry.rule("expression"))
+                .and(ParseChar::from_char(')'))
+                .try_map(|((_, depth), _)| Ok::<_, Infallible>(depth + 1)),
+        );
+        registry.register(
+            "expression",
+            ParseChar::from_char('x')
+                .try_map(|_| Ok::<_, Infallible>(0))
+                .xor(registry.rule("group")),
+        );
+
+        assert_eq!(expression.parse(ParserState::new("((x))")).unwrap().0, 2);
+        assert!(expression.parse(ParserState::new("((x)")).is_err());
+        assert_eq!(registry.names(), vec!["expression", "group"]);
+
+        // Rules can be replaced after references to them are made.
+        registry.register(
+            "group",
+            ParseChar::from_char('y').try_map(|_| Ok::<_, Infallible>(9)),
+        );
+        assert_eq!(expression.parse(ParserState::new("y")).unwrap().0, 9);
+
+        // Missing rules and dropped registries are errors.
+        assert!(registry.unregister("group"));
+        assert!(!registry.contains("group"));
+        let error = registry
+            .rule("group")
+            .parse(ParserState::new("y"))
+            .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ParseErrorKind::Invalid { message, .. } if message == "rule `group` is not defined"
+        ));
+        drop(registry);
+        assert!(expression.parse(ParserState::new("x")).is_err());
+    }
+
+    #[test]
+    fn registry_c() {
+        // A misspelt rule isn't backtracked past like a mismatch, since it's
+        // a mistake in the grammar rather than the input.
+        let registry = GrammarRegistry::<char>::new();
+        registry.register("letter", ParseChar::from_range('a', 'z'));
+        let value = registry.rule("leter").or(ParseChar::from_char('0'));
+        let error = value.parse(ParserState::new("0")).unwrap_err();
+        assert!(error.is_committed());
+
+        // Fast errors leave out the message.
+        let error = value
+            .parse(ParserState::new("0").with_verbosity(ErrorVerbosity::Fast))
+            .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ParseErrorKind::Invalid { message, .. } if message.is_empty()
+        ));
+    }
+
+    #[test]
+    fn registry_b() {
+        // Rules are described with references to each other.
+        let registry = GrammarRegistry::<char>::new();
+        registry.register(
+            "pair",
+            ParseChar::from_char('<')
+                .and(registry.rule("item"))
+                .try_map(|(_, item)| Ok::<_, Infallible>(item)),
+        );
+        registry.register("item", ParseChar::from_range('a', 'z'));
+
+        let description = registry.describe("pair").unwrap();
+        assert!(matches!(&description, Grammar::Rule { name, .. } if name == "pair"));
+        assert_eq!(ebnf::text(&description), "pair ::= \"<\" item\n");
+        assert!(registry.describe("missing").is_none());
+    }
+}
+
+mod railroad {
+    use crate::grammar::{railroad, Grammar};
+    use crate::parsers::utf8::*;
+    use crate::parsers::ParserExtensions;
+    use crate::Parser;
+
+    #[test]
+    fn describe_a() {
+        // Describe a signed number made of labelled digits.
+        let digit = ParseChar::from_range('0', '9').label("digit");
+        let number = ParseChar::from_char('-')
+            .optional()
+            .and(digit.one_or_more())
+            .label("number");
+
+        assert_eq!(
+            number.describe(),
+            Grammar::rule(
+                "number",
+                Grammar::Sequence(vec![
+                    Grammar::repeat(0, 1, Grammar::Literal(String::from("-"))),
+                    Grammar::repeat(
+                        1,
+                        usize::MAX,
+                        Grammar::rule("digit", Grammar::Class(String::from("[0-9]")))
+                    ),
+                ])
+            )
+        );
+
+        // Both rules can be found, outermost first.
+        let described = number.describe();
+        let names = described
+            .rules()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<&str>>();
+        assert_eq!(names, vec!["number", "digit"]);
+    }
+
+    #[test]
+    fn describe_b() {
+        // Alternatives are flattened, and parsers written by hand are opaque.
+        let keyword = literal_set(&["if", "else"]).xor(literal_set(&["while"]));
+        assert_eq!(
+            keyword.describe(),
+            Grammar::Choice(vec![
+                Grammar::Literal(String::from("if")),
+                Grammar::Literal(String::from("else")),
+                Grammar::Literal(String::from("while")),
+            ])
+        );
+        assert_eq!(crate::formats::cron::ParseCron.describe(), Grammar::Opaque);
+    }
+
+    #[test]
+    fn railroad_a() {
+        let digit = ParseChar::from_range('0', '9').label("digit");
+        let number = ParseChar::from_char('-')
+            .optional()
+            .and(digit.between(1, 3))
+            .label("number")
+            .describe();
+
+        // The rule body is drawn, with terminals and references to other rules in boxes.
+        let svg = railroad::svg(&number);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>"));
+        assert!(svg.contains("<g class=\"terminal\">"));
+        assert!(svg.contains("&quot;-&quot;"));
+        assert!(svg.contains("<g class=\"nonterminal\">"));
+        assert!(svg.contains(">digit</text>"));
+        assert!(svg.contains(">at most 3 times</text>"));
+
+        // The page has a diagram for each rule.
+        let html = railroad::html(&number);
+        assert!(html.contains("<h2>number</h2>"));
+        assert!(html.contains("<h2>digit</h2>"));
+        assert!(html.contains(">[0-9]</text>"));
+        assert_eq!(html.matches("<svg").count(), 2);
+    }
+}
+
+mod docs {
+    use crate::grammar::{docs, Grammar, RuleDoc};
+    use crate::parsers::utf8::*;
+    use crate::parsers::ParserExtensions;
+    use crate::Parser;
+
+    #[test]
+    fn docs_a() {
+        // Documented rules carry their description and examples.
+        let digit = ParseChar::from_range('0', '9').label("digit");
+        let number = ParseChar::from_char('-')
+            .optional()
+            .and(digit.one_or_more())
+            .label_with_doc("number", "A whole number, which may be negative.")
+            .example("42")
+            .example("-7");
+
+        let description = number.describe();
+        assert!(matches!(
+            &description,
+            Grammar::Rule { doc: Some(RuleDoc { description: Some(text), examples }), .. }
+                if text == "A whole number, which may be negative." && examples.len() == 2
+        ));
+        assert!(matches!(
+            description.documented_rules()[1],
+            ("digit", None, _)
+        ));
+
+        assert_eq!(
+            docs::markdown(&description),
+            "# Grammar\n\
+             \n\
+             ## number\n\
+             \n\
+             A whole number, which may be negative.\n\
+             \n\
+             ```ebnf\n\
+             number ::= \"-\"? digit+\n\
+             ```\n\
+             \n\
+             Examples:\n\
+             \n\
+             - `42`\n\
+             - `-7`\n\
+             \n\
+             ## digit\n\
+             \n\
+             ```ebnf\n\
+             digit ::= [0-9]\n\
+             ```\n"
+        );
+    }
+
+    #[test]
+    fn docs_b() {
+        // Unnamed grammars are written first, and text is escaped for each format.
+        let tag = ParseChar::from_char('<')
+            .label("open")
+            .doc("Starts a <tag>.")
+            .example("<");
+        let grammar = (&tag).and(ParseChar::from_char('`').label("tick").example("`"));
+
+        let markdown = docs::markdown(&grammar.describe());
+        assert!(markdown.starts_with("# Grammar\n\n```ebnf\nroot ::= open tick\n```\n"));
+        assert!(markdown.contains("- `` ` ``\n"));
+
+        let html = docs::html(&grammar.describe());
+        assert!(html.contains("<h2 id=\"open\">open</h2>\n<p>Starts a &lt;tag&gt;.</p>\n<svg"));
+        assert!(html.contains("<pre><code>open ::= &quot;&lt;&quot;</code></pre>"));
+        assert!(html.contains("<li><code>&lt;</code></li>"));
+        assert!(html.ends_with("</body>\n</html>\n"));
+    }
+}
+
+mod ebnf {
+    use crate::grammar::ebnf;
+    use crate::parsers::utf8::*;
+    use crate::parsers::ParserExtensions;
+    use crate::Parser;
+
+    #[test]
+    fn ebnf_a() {
+        // A list of signed numbers separated by commas.
+        let digit = ParseChar::from_range('0', '9').label("digit");
+        let number = ParseChar::from_char('-')
+            .optional()
+            .and(digit.one_or_more())
+            .label("signed number");
+        let list = (&number)
+            .and(ParseChar::from_char(',').and(&number).at_least(0))
+            .all_consuming()
+            .label("list");
+
+        assert_eq!(
+            ebnf::text(&list.describe()),
+            "list          ::= signed_number (\",\" signed_number)* ? end of input ?\n\
+             signed_number ::= \"-\"? digit+\n\
+             digit         ::= [0-9]\n"
+        );
+    }
+
+    #[test]
+    fn ebnf_b() {
+        // Unnamed grammars become the root rule, and choices in sequences are grouped.
+        let grammar = literal_set(&["a", "b"])
+            .and(ParseChar::from_char('"').between(2, 4))
+            .and(char_class("[xy]").unwrap().between(3, 3));
+        assert_eq!(
+            ebnf::text(&grammar.describe()),
+            "root ::= (\"a\" | \"b\") '\"'{2,4} [xy]{3}\n"
+        );
+    }
+}
+
+mod tree_sitter {
+    use crate::grammar::tree_sitter;
+    use crate::parsers::utf8::*;
+    use crate::parsers::ParserExtensions;
+    use crate::Parser;
+
+    #[test]
+    fn tree_sitter_a() {
+        // A list of signed numbers separated by commas, where the first rule is the root.
+        let digit = ParseChar::from_range('0', '9').label("digit");
+        let number = ParseChar::from_char('-')
+            .optional()
+            .and(digit.one_or_more())
+            .label("signed number");
+        let list = (&number)
+            .and(ParseChar::from_char(',').and(&number).at_least(0))
+            .all_consuming()
+            .label("list");
+
+        assert_eq!(
+            tree_sitter::grammar_js("numbers", &list.describe()),
+            "module.exports = grammar({\n  \
+               name: 'numbers',\n\n  \
+               rules: {\n    \
+                 list: $ => seq($.signed_number, repeat(seq(\",\", $.signed_number)), blank() /* end of input */),\n\n    \
+                 signed_number: $ => seq(optional(\"-\"), repeat1($.digit)),\n\n    \
+                 digit: $ => /[0-9]/,\n  \
+               }\n\
+             });\n"
+        );
+    }
+
+    #[test]
+    fn tree_sitter_b() {
+        // Unnamed grammars become the root rule, bounded repetitions are written out, and classes are escaped.
+        let grammar = literal_set(&["a", "b"])
+            .and(ParseChar::from_char('"').between(2, 4))
+            .and(char_class("[^-/\u{e9}]").unwrap());
+        assert_eq!(
+            tree_sitter::grammar_js("letters", &grammar.describe()),
+            "module.exports = grammar({\n  \
+               name: 'letters',\n\n  \
+               rules: {\n    \
+                 root: $ => seq(choice(\"a\", \"b\"), seq(\"\\\"\", \"\\\"\", optional(\"\\\"\"), optional(\"\\\"\")), /[^\\-\\/\\u00E9]/),\n  \
+               }\n\
+             });\n"
+        );
+    }
+}
+
+mod fuzzing {
+    use crate::formats::args::{ArgumentStyle, ParseArguments};
+    use crate::formats::color::ParseColor;
+    use crate::formats::cron::ParseCron;
+    use crate::formats::duration::ParseDuration;
+    use crate::formats::http::{ParseHeaders, ParseQuotedString, ParseRequestLine};
+    use crate::formats::numbers::{NumberFormat, ParseLocaleFloat};
+    use crate::formats::template::ParseTemplate;
+    use crate::fuzzing::{check_parser, fuzz_parser, DEFAULT_TIME_LIMIT};
+    use crate::parsers::utf8::*;
+    use crate::parsers::ParserExtensions;
+    use crate::{ParseError, ParseResult, Parser, ParserState};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Awkward inputs, including multi-byte characters right where parsers look for ASCII.
+    const INPUTS: &[&str] = &[
+        "",
+        "\n",
+        "é",
+        "#ééé",
+        "rgb(1,2,3)é",
+        "1h30mé",
+        "*/5 * * * é",
+        "a 'b\" c\\",
+        "${é}$",
+        "GET / HTTP/1.1\r\nHost: é\r\n\r\n",
+        "\"\\é",
+        "1,234.5é",
+        "\u{10FFFF}\u{FFFD}",
+    ];
+
+    #[test]
+    fn check_parser_a() {
+        // The built-in parsers follow the rules for every input.
+        for input in INPUTS {
+            let check = |result: Result<(), String>| {
+                if let Err(message) = result {
+                    panic!("{:?}: {}", input, message);
+                }
+            };
+            check(check_parser(
+                &Arc::new(ParseColor),
+                input,
+                DEFAULT_TIME_LIMIT,
+            ));
+            check(check_parser(
+                &Arc::new(ParseCron),
+                input,
+                DEFAULT_TIME_LIMIT,
+            ));
+            check(check_parser(
+                &Arc::new(ParseDuration::default()),
+                input,
+                DEFAULT_TIME_LIMIT,
+            ));
+            check(check_parser(
+                &Arc::new(ParseTemplate),
+                input,
+                DEFAULT_TIME_LIMIT,
+            ));
+            check(check_parser(
+                &Arc::new(ParseQuotedString),
+                input,
+                DEFAULT_TIME_LIMIT,
+            ));
+            check(check_parser(
+                &Arc::new(ParseRequestLine.and(ParseHeaders)),
+                input,
+                DEFAULT_TIME_LIMIT,
+            ));
+            check(check_parser(
+                &Arc::new(ParseLocaleFloat::new(NumberFormat::new().grouping(','))),
+                input,
+                DEFAULT_TIME_LIMIT,
+            ));
+            for style in [ArgumentStyle::Posix, ArgumentStyle::Windows] {
+                check(check_parser(
+                    &Arc::new(ParseArguments::new(style)),
+                    input,
+                    DEFAULT_TIME_LIMIT,
+                ));
+            }
+            check(check_parser(
+                &Arc::new(ParseChar::from_any().between(2, 3).label("pair")),
+                input,
+                DEFAULT_TIME_LIMIT,
+            ));
+        }
+
+        // Invalid UTF-8 is replaced rather than rejected.
+        fuzz_parser(&ParseColor, b"#\xff\xfe");
+    }
+
+    /// A parser that claims to have consumed more input than there was.
+    struct Overrun;
+
+    impl Parser<(), ParseError> for Overrun {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, ()> {
+            Err(ParseError::invalid(
+                parser_state.index,
+                parser_state.index + 10,
+                "too far",
+            ))
+        }
+    }
+
+    #[test]
+    fn check_parser_b() {
+        // Errors outside the input are reported.
+        assert_eq!(
+            check_parser(&Arc::new(Overrun), "abc", DEFAULT_TIME_LIMIT),
+            Err(String::from(
+                "error spans 0..10, outside the 3 byte input: invalid input: too far\n"
+            ))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "error spans 0..10, outside the 3 byte input")]
+    fn fuzz_parser_a() {
+        // Fuzzing checks the same rules, and panics so the fuzzer sees it.
+        fuzz_parser(&Overrun, b"abc");
+    }
+
+    /// A parser that never finishes until it is told to stop.
+    struct Hang(Arc<AtomicBool>);
+
+    impl Parser<(), ParseError> for Hang {
+        fn parse(&self, parser_state: ParserState) -> ParseResult<ParseError, ()> {
+            while !self.0.load(Ordering::Relaxed) {
+                std::thread::yield_now();
+            }
+            Ok(((), parser_state))
+        }
+    }
+
+    #[test]
+    fn check_parser_c() {
+        // A parser that hangs is reported once the time limit passes.
+        let stop = Arc::new(AtomicBool::new(false));
+        let result = check_parser(
+            &Arc::new(Hang(Arc::clone(&stop))),
+            "abc",
+            Duration::from_millis(50),
+        );
+        stop.store(true, Ordering::Relaxed);
+        assert_eq!(
+            result,
+            Err(String::from(
+                "parser didn't finish within the limit of 50ms with Rich errors"
+            ))
+        );
+    }
+}
+
+mod ambiguity {
+    use crate::grammar::ambiguity::{ambiguities, AmbiguityKind};
+    use crate::parsers::utf8::*;
+    use crate::parsers::ParserExtensions;
+    use crate::Parser;
+
+    #[test]
+    fn ambiguities_a() {
+        // Identifiers and keywords can both start with a letter.
+        let identifier = char_class("[a-z_]")
+            .unwrap()
+            .count_of(1, usize::MAX)
+            .label("identifier");
+        let keyword = KeywordSet::from_keywords(&["if", "while"]).label("keyword");
+        let number = ParseChar::from_range('0', '9')
+            .count_of(1, usize::MAX)
+            .label("number");
+        let token = identifier.xor(keyword).xor(number).label("token");
+
+        let found = ambiguities(&token.describe());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].rule.as_deref(), Some("token"));
+        assert_eq!((found[0].first, found[0].second), (0, 1));
+        assert_eq!(
+            found[0].kind,
+            AmbiguityKind::SharedStart(vec![('i', 'i'), ('w', 'w')])
+        );
+        assert_eq!(
+            found[0].to_string(),
+            "in token: `identifier` and `keyword` can both start with [iw]"
+        );
+    }
+
+    #[test]
+    fn ambiguities_b() {
+        // An optional sign before a number means the number is never tried on its own.
+        let signed = ParseChar::from_char('-').optional();
+        let digits = ParseChar::from_range('0', '9').one_or_more();
+        let found = ambiguities(&signed.xor(digits).describe());
+        assert_eq!(found[0].kind, AmbiguityKind::Unreachable);
+        assert_eq!(
+            found[0].to_string(),
+            "`\"-\"?` can match nothing, so `[0-9]+` is never tried"
+        );
+
+        // Negated classes don't overlap with what they exclude.
+        let quoted = ParseChar::from_char('"').xor(char_class("[^\"]").unwrap());
+        assert!(ambiguities(&quoted.describe()).is_empty());
+    }
+}
+
+mod corpus {
+    use crate::corpus::Corpus;
+    use crate::formats::color::ParseColor;
+    use std::fs;
+
+    #[test]
+    fn corpus_a() {
+        let directory =
+            std::env::temp_dir().join(format!("parselib-corpus-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("bad.txt"), "#12").unwrap();
+        fs::write(directory.join("red.txt"), "#f00").unwrap();
+
+        // Without expectations, every input fails.
+        let corpus = Corpus::new(&directory);
+        let report = corpus.run(&ParseColor).unwrap();
+        assert_eq!(report.failed.len(), 2);
+        assert_eq!(report.failed[0].expected, None);
+
+        // Updating writes the expectations, which then pass.
+        let report = corpus.clone().update(true).run(&ParseColor).unwrap();
+        assert_eq!(report.updated.len(), 2);
+        assert!(fs::read_to_string(directory.join("red.txt.expected"))
+            .unwrap()
+            .starts_with("ok\nRgba {\n    r: 255,"));
+        assert!(fs::read_to_string(directory.join("bad.txt.expected"))
+            .unwrap()
+            .starts_with("error\nerror: invalid input"));
+        corpus.run(&ParseColor).unwrap().assert_passed();
+
+        // Changing an input shows how its result differs.
+        fs::write(directory.join("red.txt"), "#f01").unwrap();
+        let report = corpus.run(&ParseColor).unwrap();
+        assert_eq!(report.passed.len(), 1);
+        assert!(report.failed[0]
+            .to_string()
+            .contains("-     b: 0,\n+     b: 17,\n"));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
+
+#[cfg(feature = "pest")]
+mod pest {
+    use crate::grammar::ebnf;
+    use crate::grammar::pest::*;
+    use crate::span::Span;
+    use crate::{Expected, ParseError, Parser, ParserState};
+
+    const CALCULATOR: &str = r#"
+        // Whitespace is skipped between the parts of non-atomic rules.
+        WHITESPACE = _{ " " | "\t" }
+
+        number = @{ "-"? ~ ASCII_DIGIT+ }
+        op     =  { "+" | "-" | ^"times" }
+        expr   =  { number ~ (op ~ number)* }
+        calc   = _{ SOI ~ expr ~ EOI }
+    "#;
+
+    #[test]
+    fn pest_a() {
+        let grammar = PestGrammar::new(CALCULATOR).unwrap();
+        assert_eq!(
+            grammar.rule_names().collect::<Vec<&str>>(),
+            vec!["WHITESPACE", "number", "op", "expr", "calc"]
+        );
+        assert_eq!(grammar.rule_kind("number"), Some(RuleKind::Atomic));
+
+        // Silent rules only produce the nodes inside them, and atomic rules
+        // produce no nodes inside them.
+        let (nodes, _) = grammar
+            .rule("calc")
+            .unwrap()
+            .parse(ParserState::new("1 + 22 TIMES -3"))
+            .unwrap();
+        let leaf = |rule: &str, start, end| PestNode {
+            rule: String::from(rule),
+            span: Span::new(start, end),
+            children: Vec::new(),
+        };
+        assert_eq!(
+            nodes,
+            vec![PestNode {
+                rule: String::from("expr"),
+                span: Span::new(0, 15),
+                children: vec![
+                    leaf("number", 0, 1),
+                    leaf("op", 2, 3),
+                    leaf("number", 4, 6),
+                    leaf("op", 7, 12),
+                    leaf("number", 13, 15),
+                ],
+            }]
+        );
+
+        // Atomic rules don't skip whitespace, so the sign can't be separated
+        // from the digits.
+        assert_eq!(
+            grammar
+                .rule("calc")
+                .unwrap()
+                .parse(ParserState::new("1 + - 3"))
+                .unwrap_err(),
+            ParseError::unexpected(5, [Expected::CharRange(Some('0'), Some('9'))], Some(" "))
+        );
+    }
+
+    #[test]
+    fn pest_b() {
+        // Rules describe themselves, so they can be exported.
+        let grammar = PestGrammar::new(CALCULATOR).unwrap();
+        assert_eq!(
+            ebnf::text(&grammar.rule("expr").unwrap().describe()),
+            "expr   ::= number (op number)*\n\
+             number ::= \"-\"? [0-9]+\n\
+             op     ::= \"+\" | \"-\" | ? ^\"times\" ?\n"
+        );
+
+        // Lookahead, character ranges, and bounded repetition.
+        let grammar =
+            PestGrammar::new("ident = @{ !(\"if\" ~ !ASCII_ALPHA) ~ ('a'..'z'){1, 3} }").unwrap();
+        let ident = grammar.rule("ident").unwrap();
+        assert_eq!(ident.parse(ParserState::new("ifs")).unwrap().1.input(), "");
+        assert_eq!(
+            ident.parse(ParserState::new("abcd")).unwrap().1.input(),
+            "d"
+        );
+        assert!(ident.parse(ParserState::new("if")).is_err());
+    }
+
+    #[test]
+    fn pest_c() {
+        // Mistakes in the grammar are reported where they are.
+        assert_eq!(
+            PestGrammar::new("a = { b ~ \"x\" }").unwrap_err(),
+            ParseError::invalid(6, 7, "rule `b` is not defined")
+        );
+        assert_eq!(
+            PestGrammar::new("a = { PUSH(\"x\") }").unwrap_err(),
+            ParseError::invalid(6, 10, "stack operations aren't supported")
+        );
+        assert_eq!(
+            PestGrammar::new("a = { \"x\" ").unwrap_err(),
+            ParseError::unexpected(10, ["}"], None)
+        );
+    }
+
+    #[test]
+    fn pest_d() {
+        // Left-recursive rules are rejected instead of overflowing the stack
+        // when they're parsed.
+        assert_eq!(
+            PestGrammar::new("expr = { expr ~ \"+\" ~ term | term }\nterm = { ASCII_DIGIT }")
+                .unwrap_err(),
+            ParseError::invalid(0, 4, "rule `expr` is left-recursive (expr -> expr)")
+        );
+
+        // That includes cycles through other rules and through parts that
+        // can match nothing.
+        let source = "a = { \"x\"? ~ b }\nb = { !\"y\" ~ a ~ \"z\" }";
+        assert_eq!(
+            PestGrammar::new(source).unwrap_err(),
+            ParseError::invalid(0, 1, "rule `a` is left-recursive (a -> b -> a)")
+        );
+
+        // Recursion after consuming something is fine.
+        let grammar = PestGrammar::new("list = { \"(\" ~ list? ~ \")\" }").unwrap();
+        let list = grammar.rule("list").unwrap();
+        assert_eq!(list.parse(ParserState::new("(())")).unwrap().1.input(), "");
+    }
+}
+
+#[cfg(feature = "serde")]
+mod deserialize {
+    use crate::deserialize::{self, DeError, Text};
+    use crate::parsers::utf8::*;
+    use crate::parsers::ParserExtensions;
+    use crate::{ParseError, Parser};
+    use serde::Deserialize;
+    use std::collections::BTreeMap;
+    use std::convert::Infallible;
+
+    #[derive(Debug, Deserialize, Eq, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Level {
+        Low,
+        High,
+    }
+
+    #[derive(Debug, Deserialize, Eq, PartialEq)]
+    struct Settings {
+        name: String,
+        port: u16,
+        verbose: Option<bool>,
+        level: Level,
+    }
+
+    /// Parse comma separated `key=value` pairs into a map.
+    fn pairs() -> impl Parser<BTreeMap<String, Text>, ParseError> {
+        let word = || {
+            char_class("[a-z0-9]")
+                .unwrap()
+                .at_least(0)
+                .try_map(|chars| Ok::<_, Infallible>(chars.into_iter().collect::<String>()))
+        };
+        let pair = || word().and(ParseChar::from_char('=')).and(word());
+        pair()
+            .and(ParseChar::from_char(',').and(pair()).at_least(0))
+            .try_map(|(first, rest)| {
+                Ok::<_, Infallible>(
+                    std::iter::once(first)
+                        .chain(rest.into_iter().map(|(_, pair)| pair))
+                        .map(|((key, _), value)| (key, Text(value)))
+                        .collect(),
+                )
+            })
+    }
+
+    #[test]
+    fn deserialize_a() {
+        // Text values become whatever type each field needs.
+        let settings: Settings =
+            deserialize::from_str(&pairs(), "name=server,port=8080,level=high").unwrap();
+        assert_eq!(
+            settings,
+            Settings {
+                name: String::from("server"),
+                port: 8080,
+                verbose: None,
+                level: Level::High,
+            }
+        );
+
+        let settings: Settings =
+            deserialize::from_str(&pairs(), "verbose=true,level=low,name=x,port=1").unwrap();
+        assert_eq!(settings.verbose, Some(true));
+    }
+
+    #[test]
+    fn deserialize_b() {
+        // Input that doesn't match the grammar is a parse error.
+        let result = deserialize::from_str::<Settings, _, _>(&pairs(), "name=x;");
+        assert!(matches!(result, Err(DeError::Parse(e)) if e.offset() == 6));
+
+        // Values of the wrong type and missing fields are reported by serde.
+        let result = deserialize::from_str::<Settings, _, _>(&pairs(), "name=x,port=big,level=low");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "invalid value: string \"big\", expected u16\n"
+        );
+        let result = deserialize::from_str::<Settings, _, _>(&pairs(), "name=x,level=low");
+        assert_eq!(result.unwrap_err().to_string(), "missing field `port`\n");
+    }
+
+    #[test]
+    fn deserialize_entries_a() {
+        // Entries are handed to serde as they are parsed.
+        let word = || {
+            char_class("[a-z0-9]")
+                .unwrap()
+                .at_least(0)
+                .try_map(|chars| Ok::<_, Infallible>(chars.into_iter().collect::<String>()))
+        };
+        let entry = word()
+            .and(ParseChar::from_char('='))
+            .and(word())
+            .try_map(|((key, _), value)| Ok::<_, Infallible>((key, Text(value))));
+        let separator = ParseChar::from_char(',');
+        let settings: Settings =
+            deserialize::from_entries(&entry, &separator, "port=1,level=low,name=x").unwrap();
+        assert_eq!(settings.port, 1);
+        assert_eq!(settings.level, Level::Low);
+
+        // A bad separator is reported where it was found.
+        let result = deserialize::from_entries::<Settings, _, _, _>(&entry, &separator, "name=x;");
+        assert!(matches!(result, Err(DeError::Parse(e)) if e.offset() == 6));
+    }
+}